@@ -43,6 +43,7 @@ fn main() {
     .define("_USE_MATH_DEFINES", None)
     .define("kiss_fft_scalar", Some("double"))
     .file(qm_root.join("qm_key_wrapper.cpp"))
+    .file(qm_root.join("qm_chroma_wrapper.cpp"))
     .file(qm_root.join("dsp/keydetection/GetKeyMode.cpp"))
     .file(qm_root.join("dsp/chromagram/Chromagram.cpp"))
     .file(qm_root.join("dsp/chromagram/ConstantQ.cpp"))
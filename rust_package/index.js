@@ -310,7 +310,7 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { horizontalBrowseTransportReset, horizontalBrowseTransportSetDeckState, horizontalBrowseTransportSetState, horizontalBrowseTransportSetPlaybackRate, horizontalBrowseTransportSetTempoNudgePlaybackRate, horizontalBrowseTransportSetMasterTempoEnabled, horizontalBrowseTransportSetBeatGrid, horizontalBrowseTransportSetSyncEnabled, horizontalBrowseTransportBeatsync, horizontalBrowseTransportAlignToLeader, horizontalBrowseTransportSetLeader, horizontalBrowseTransportSetBandState, horizontalBrowseTransportSetCueMonitorEnabled, horizontalBrowseTransportSetPlaying, horizontalBrowseTransportPreparePlayhead, horizontalBrowseTransportSeek, horizontalBrowseTransportSetScrubPreview, horizontalBrowseTransportSetMetronome, horizontalBrowseTransportToggleLoop, horizontalBrowseTransportStepLoopBeats, horizontalBrowseTransportSetLoopFromRange, horizontalBrowseTransportClearLoop, horizontalBrowseTransportSetGain, horizontalBrowseTransportSetAutoGainEnabled, horizontalBrowseTransportSetOutputState, horizontalBrowseTransportStartRecording, horizontalBrowseTransportStopRecording, horizontalBrowseTransportRecordingSnapshot, horizontalBrowseTransportSnapshot, horizontalBrowseTransportVisualizerSnapshot, horizontalBrowseTransportDrainDecodeDiagnostics, readPioneerCues, dumpPioneerExportDebug, readPioneerPlaylistTree, readPioneerPreviewWaveform, readPioneerBeatGrid, readPioneerDetailWaveform, readPioneerPlaylistTracks, calculateAudioHashes, calculateAudioHashesWithProgress, calculateFileHashes, calculateFileHashesWithProgress, decodeAudioFile, decodeAudioFileNativePcm, processSoundtouchPcm, generateChromaprintFingerprint, computeMixxxWaveform, computeMixxxWaveformWithRate, analyzeKeyFromPcm } = nativeBinding
+const { horizontalBrowseTransportReset, horizontalBrowseTransportSetDeckState, horizontalBrowseTransportSetState, horizontalBrowseTransportSetPlaybackRate, horizontalBrowseTransportSetTempoNudgePlaybackRate, horizontalBrowseTransportSetMasterTempoEnabled, horizontalBrowseTransportSetBeatGrid, horizontalBrowseTransportSetSyncEnabled, horizontalBrowseTransportBeatsync, horizontalBrowseTransportAlignToLeader, horizontalBrowseTransportSetLeader, horizontalBrowseTransportSetBandState, horizontalBrowseTransportSetDeckStems, horizontalBrowseTransportSetStemState, horizontalBrowseTransportSetCueMonitorEnabled, horizontalBrowseTransportSetPlaying, horizontalBrowseTransportPreparePlayhead, horizontalBrowseTransportSeek, horizontalBrowseTransportBrake, horizontalBrowseTransportSpinback, horizontalBrowseTransportSetReverse, horizontalBrowseTransportSetCensor, horizontalBrowseTransportSetScrubPreview, horizontalBrowseTransportSetMetronome, horizontalBrowseTransportToggleLoop, horizontalBrowseTransportStepLoopBeats, horizontalBrowseTransportSetLoopFromRange, horizontalBrowseTransportClearLoop, horizontalBrowseTransportSetGain, horizontalBrowseTransportSetAutoGainEnabled, horizontalBrowseTransportSetOutputState, horizontalBrowseTransportSetChannelFader, horizontalBrowseTransportSetCrossfaderCurve, horizontalBrowseTransportSetResampleQuality, horizontalBrowseTransportGetPcmCacheStatus, horizontalBrowseTransportConfigurePcmCache, horizontalBrowseTransportClearPcmCache, horizontalBrowseTransportStartRecording, horizontalBrowseTransportStopRecording, horizontalBrowseTransportRecordingSnapshot, horizontalBrowseTransportSnapshot, horizontalBrowseTransportVisualizerSnapshot, horizontalBrowseTransportMeterSnapshot, horizontalBrowseTransportDrainDecodeDiagnostics, horizontalBrowseTransportMidiListPorts, horizontalBrowseTransportMidiListOutputPorts, horizontalBrowseTransportMidiLoadMapping, horizontalBrowseTransportMidiConnect, horizontalBrowseTransportMidiDisconnect, horizontalBrowseTransportMidiStatus, horizontalBrowseTransportMidiDrainEvents, readPioneerCues, dumpPioneerExportDebug, readPioneerPlaylistTree, readPioneerPreviewWaveform, readPioneerBeatGrid, readPioneerDetailWaveform, generatePioneerWaveforms, generatePioneerWaveformsFromPcm, renderPioneerDetailWaveform, readPioneerPlaylistTracks, calculateAudioHashes, calculateAudioHashesWithProgress, calculateFileHashes, calculateFileHashesWithProgress, decodeAudioFile, decodeAudioFileNativePcm, openAudioDecodeStream, seekAudioDecodeStream, readAudioDecodeStream, closeAudioDecodeStream, listAudioTracks, decodeAudioSubTrack, transcodeAudioFile, cancelAudioTranscode, analyzeTracks, cancelTrackAnalysis, analyzeAudioAudit, analyzeAudioAuditFromPcm, analyzeAudioSilence, analyzeAudioSilenceFromPcm, analyzeSongStructure, analyzeSongStructureFromPcm, audioDescriptorsVersion, analyzeAudioDescriptors, analyzeAudioDescriptorsFromPcm, audioEmbeddingVersion, audioEmbeddingDimension, analyzeAudioEmbedding, analyzeAudioEmbeddingFromPcm, createSimilarityIndex, loadSimilarityIndex, saveSimilarityIndex, addSimilarityIndexItem, removeSimilarityIndexItem, similarityIndexSize, querySimilarityIndex, querySimilarityIndexById, closeSimilarityIndex, computeWaveformPyramid, saveWaveformPyramid, readWaveformPyramidInfo, readWaveformPyramidLevel, loadWaveformPyramid, analyzeSpectralQuality, analyzeSpectralQualityFromPcm, verifyAudioFiles, probeTimeBasisOffset, resamplePcm, processSoundtouchPcm, generateChromaprintFingerprint, computeMixxxWaveform, computeMixxxWaveformWithRate, computeMixxxWaveformFromFile, renderMixxxWaveform, analyzeKeyFromPcm } = nativeBinding
 
 module.exports.horizontalBrowseTransportReset = horizontalBrowseTransportReset
 module.exports.horizontalBrowseTransportSetDeckState = horizontalBrowseTransportSetDeckState
@@ -324,10 +324,16 @@ module.exports.horizontalBrowseTransportBeatsync = horizontalBrowseTransportBeat
 module.exports.horizontalBrowseTransportAlignToLeader = horizontalBrowseTransportAlignToLeader
 module.exports.horizontalBrowseTransportSetLeader = horizontalBrowseTransportSetLeader
 module.exports.horizontalBrowseTransportSetBandState = horizontalBrowseTransportSetBandState
+module.exports.horizontalBrowseTransportSetDeckStems = horizontalBrowseTransportSetDeckStems
+module.exports.horizontalBrowseTransportSetStemState = horizontalBrowseTransportSetStemState
 module.exports.horizontalBrowseTransportSetCueMonitorEnabled = horizontalBrowseTransportSetCueMonitorEnabled
 module.exports.horizontalBrowseTransportSetPlaying = horizontalBrowseTransportSetPlaying
 module.exports.horizontalBrowseTransportPreparePlayhead = horizontalBrowseTransportPreparePlayhead
 module.exports.horizontalBrowseTransportSeek = horizontalBrowseTransportSeek
+module.exports.horizontalBrowseTransportBrake = horizontalBrowseTransportBrake
+module.exports.horizontalBrowseTransportSpinback = horizontalBrowseTransportSpinback
+module.exports.horizontalBrowseTransportSetReverse = horizontalBrowseTransportSetReverse
+module.exports.horizontalBrowseTransportSetCensor = horizontalBrowseTransportSetCensor
 module.exports.horizontalBrowseTransportSetScrubPreview = horizontalBrowseTransportSetScrubPreview
 module.exports.horizontalBrowseTransportSetMetronome = horizontalBrowseTransportSetMetronome
 module.exports.horizontalBrowseTransportToggleLoop = horizontalBrowseTransportToggleLoop
@@ -337,18 +343,35 @@ module.exports.horizontalBrowseTransportClearLoop = horizontalBrowseTransportCle
 module.exports.horizontalBrowseTransportSetGain = horizontalBrowseTransportSetGain
 module.exports.horizontalBrowseTransportSetAutoGainEnabled = horizontalBrowseTransportSetAutoGainEnabled
 module.exports.horizontalBrowseTransportSetOutputState = horizontalBrowseTransportSetOutputState
+module.exports.horizontalBrowseTransportSetChannelFader = horizontalBrowseTransportSetChannelFader
+module.exports.horizontalBrowseTransportSetCrossfaderCurve = horizontalBrowseTransportSetCrossfaderCurve
+module.exports.horizontalBrowseTransportSetResampleQuality = horizontalBrowseTransportSetResampleQuality
+module.exports.horizontalBrowseTransportGetPcmCacheStatus = horizontalBrowseTransportGetPcmCacheStatus
+module.exports.horizontalBrowseTransportConfigurePcmCache = horizontalBrowseTransportConfigurePcmCache
+module.exports.horizontalBrowseTransportClearPcmCache = horizontalBrowseTransportClearPcmCache
 module.exports.horizontalBrowseTransportStartRecording = horizontalBrowseTransportStartRecording
 module.exports.horizontalBrowseTransportStopRecording = horizontalBrowseTransportStopRecording
 module.exports.horizontalBrowseTransportRecordingSnapshot = horizontalBrowseTransportRecordingSnapshot
 module.exports.horizontalBrowseTransportSnapshot = horizontalBrowseTransportSnapshot
 module.exports.horizontalBrowseTransportVisualizerSnapshot = horizontalBrowseTransportVisualizerSnapshot
+module.exports.horizontalBrowseTransportMeterSnapshot = horizontalBrowseTransportMeterSnapshot
 module.exports.horizontalBrowseTransportDrainDecodeDiagnostics = horizontalBrowseTransportDrainDecodeDiagnostics
+module.exports.horizontalBrowseTransportMidiListPorts = horizontalBrowseTransportMidiListPorts
+module.exports.horizontalBrowseTransportMidiListOutputPorts = horizontalBrowseTransportMidiListOutputPorts
+module.exports.horizontalBrowseTransportMidiLoadMapping = horizontalBrowseTransportMidiLoadMapping
+module.exports.horizontalBrowseTransportMidiConnect = horizontalBrowseTransportMidiConnect
+module.exports.horizontalBrowseTransportMidiDisconnect = horizontalBrowseTransportMidiDisconnect
+module.exports.horizontalBrowseTransportMidiStatus = horizontalBrowseTransportMidiStatus
+module.exports.horizontalBrowseTransportMidiDrainEvents = horizontalBrowseTransportMidiDrainEvents
 module.exports.readPioneerCues = readPioneerCues
 module.exports.dumpPioneerExportDebug = dumpPioneerExportDebug
 module.exports.readPioneerPlaylistTree = readPioneerPlaylistTree
 module.exports.readPioneerPreviewWaveform = readPioneerPreviewWaveform
 module.exports.readPioneerBeatGrid = readPioneerBeatGrid
 module.exports.readPioneerDetailWaveform = readPioneerDetailWaveform
+module.exports.generatePioneerWaveforms = generatePioneerWaveforms
+module.exports.generatePioneerWaveformsFromPcm = generatePioneerWaveformsFromPcm
+module.exports.renderPioneerDetailWaveform = renderPioneerDetailWaveform
 module.exports.readPioneerPlaylistTracks = readPioneerPlaylistTracks
 module.exports.calculateAudioHashes = calculateAudioHashes
 module.exports.calculateAudioHashesWithProgress = calculateAudioHashesWithProgress
@@ -356,8 +379,52 @@ module.exports.calculateFileHashes = calculateFileHashes
 module.exports.calculateFileHashesWithProgress = calculateFileHashesWithProgress
 module.exports.decodeAudioFile = decodeAudioFile
 module.exports.decodeAudioFileNativePcm = decodeAudioFileNativePcm
+module.exports.openAudioDecodeStream = openAudioDecodeStream
+module.exports.seekAudioDecodeStream = seekAudioDecodeStream
+module.exports.readAudioDecodeStream = readAudioDecodeStream
+module.exports.closeAudioDecodeStream = closeAudioDecodeStream
+module.exports.listAudioTracks = listAudioTracks
+module.exports.decodeAudioSubTrack = decodeAudioSubTrack
+module.exports.transcodeAudioFile = transcodeAudioFile
+module.exports.cancelAudioTranscode = cancelAudioTranscode
+module.exports.analyzeTracks = analyzeTracks
+module.exports.cancelTrackAnalysis = cancelTrackAnalysis
+module.exports.analyzeAudioAudit = analyzeAudioAudit
+module.exports.analyzeAudioAuditFromPcm = analyzeAudioAuditFromPcm
+module.exports.analyzeAudioSilence = analyzeAudioSilence
+module.exports.analyzeAudioSilenceFromPcm = analyzeAudioSilenceFromPcm
+module.exports.analyzeSongStructure = analyzeSongStructure
+module.exports.analyzeSongStructureFromPcm = analyzeSongStructureFromPcm
+module.exports.audioDescriptorsVersion = audioDescriptorsVersion
+module.exports.analyzeAudioDescriptors = analyzeAudioDescriptors
+module.exports.analyzeAudioDescriptorsFromPcm = analyzeAudioDescriptorsFromPcm
+module.exports.audioEmbeddingVersion = audioEmbeddingVersion
+module.exports.audioEmbeddingDimension = audioEmbeddingDimension
+module.exports.analyzeAudioEmbedding = analyzeAudioEmbedding
+module.exports.analyzeAudioEmbeddingFromPcm = analyzeAudioEmbeddingFromPcm
+module.exports.createSimilarityIndex = createSimilarityIndex
+module.exports.loadSimilarityIndex = loadSimilarityIndex
+module.exports.saveSimilarityIndex = saveSimilarityIndex
+module.exports.addSimilarityIndexItem = addSimilarityIndexItem
+module.exports.removeSimilarityIndexItem = removeSimilarityIndexItem
+module.exports.similarityIndexSize = similarityIndexSize
+module.exports.querySimilarityIndex = querySimilarityIndex
+module.exports.querySimilarityIndexById = querySimilarityIndexById
+module.exports.closeSimilarityIndex = closeSimilarityIndex
+module.exports.computeWaveformPyramid = computeWaveformPyramid
+module.exports.saveWaveformPyramid = saveWaveformPyramid
+module.exports.readWaveformPyramidInfo = readWaveformPyramidInfo
+module.exports.readWaveformPyramidLevel = readWaveformPyramidLevel
+module.exports.loadWaveformPyramid = loadWaveformPyramid
+module.exports.analyzeSpectralQuality = analyzeSpectralQuality
+module.exports.analyzeSpectralQualityFromPcm = analyzeSpectralQualityFromPcm
+module.exports.verifyAudioFiles = verifyAudioFiles
+module.exports.probeTimeBasisOffset = probeTimeBasisOffset
+module.exports.resamplePcm = resamplePcm
 module.exports.processSoundtouchPcm = processSoundtouchPcm
 module.exports.generateChromaprintFingerprint = generateChromaprintFingerprint
 module.exports.computeMixxxWaveform = computeMixxxWaveform
 module.exports.computeMixxxWaveformWithRate = computeMixxxWaveformWithRate
+module.exports.computeMixxxWaveformFromFile = computeMixxxWaveformFromFile
+module.exports.renderMixxxWaveform = renderMixxxWaveform
 module.exports.analyzeKeyFromPcm = analyzeKeyFromPcm
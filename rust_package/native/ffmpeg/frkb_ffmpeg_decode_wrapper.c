@@ -11,8 +11,11 @@
 #include <libavformat/avformat.h>
 #include <libavutil/mathematics.h>
 #include <libavutil/avutil.h>
+#include <libavutil/avstring.h>
+#include <libavutil/audio_fifo.h>
 #include <libavutil/opt.h>
 #include <libavutil/channel_layout.h>
+#include <libavutil/intreadwrite.h>
 #include <libswresample/swresample.h>
 
 #include <stdlib.h>
@@ -31,6 +34,8 @@
 #define FRKB_ERR_DECODE         9
 #define FRKB_ERR_INVALID_ARG    10
 #define FRKB_ERR_CANCELLED      11
+#define FRKB_ERR_DRM            12
+#define FRKB_ERR_OUTPUT         13
 
 /* Target output format: interleaved s16, stereo */
 #define TARGET_SAMPLE_FMT AV_SAMPLE_FMT_S16
@@ -46,6 +51,9 @@ typedef struct {
     int              audio_stream_idx;
     int              out_sample_rate;
     int              out_channels;
+    int              last_av_error;
+    double           lead_trim_sec;     /* 编码器前导时长，时间线零点相对包时间戳的偏移 */
+    int              manual_lead_trim;  /* 前导未由 libavcodec 自动裁掉，需按时间戳丢弃 */
 } DecodeContext;
 
 static void decode_ctx_free(DecodeContext *ctx) {
@@ -54,42 +62,125 @@ static void decode_ctx_free(DecodeContext *ctx) {
     if (ctx->fmt_ctx)   avformat_close_input(&ctx->fmt_ctx);
 }
 
+static int find_audio_stream_by_ordinal(AVFormatContext *fmt_ctx, int ordinal)
+{
+    int seen = 0;
+    for (unsigned int i = 0; i < fmt_ctx->nb_streams; i++) {
+        if (fmt_ctx->streams[i]->codecpar->codec_type != AVMEDIA_TYPE_AUDIO) continue;
+        if (seen == ordinal) return (int)i;
+        seen++;
+    }
+    return AVERROR_STREAM_NOT_FOUND;
+}
+
+/* 重新打开输入并回到原音频流，用于无法 seek 回开头的输入 */
+static int reopen_input(DecodeContext *ctx, const char *file_path)
+{
+    avformat_close_input(&ctx->fmt_ctx);
+    int ret = avformat_open_input(&ctx->fmt_ctx, file_path, NULL, NULL);
+    if (ret < 0) { ctx->last_av_error = ret; return FRKB_ERR_OPEN_INPUT; }
+    ret = avformat_find_stream_info(ctx->fmt_ctx, NULL);
+    if (ret < 0) { ctx->last_av_error = ret; return FRKB_ERR_STREAM_INFO; }
+    if ((unsigned int)ctx->audio_stream_idx >= ctx->fmt_ctx->nb_streams) return FRKB_ERR_NO_AUDIO;
+    return 0;
+}
+
+/**
+ * 读取首个音频包的 AV_PKT_DATA_SKIP_SAMPLES 作为编码器前导（libavcodec 会在开头自动裁掉）；
+ * 容器未提供时退回 codecpar->initial_padding，由调用方按时间戳丢弃。
+ * 尽力而为：读完后 seek 回开头，seek 失败则重新打开输入；探测不到前导时按 0 处理。
+ */
+static int probe_lead_trim(DecodeContext *ctx, const char *file_path)
+{
+    AVStream *stream = ctx->fmt_ctx->streams[ctx->audio_stream_idx];
+    AVPacket *pkt = av_packet_alloc();
+    if (!pkt) return FRKB_ERR_ALLOC;
+
+    int64_t skip_start = 0;
+    int consumed = 0;
+    while (av_read_frame(ctx->fmt_ctx, pkt) >= 0) {
+        consumed = 1;
+        if (pkt->stream_index != ctx->audio_stream_idx) {
+            av_packet_unref(pkt);
+            continue;
+        }
+        size_t side_size = 0;
+        const uint8_t *side = av_packet_get_side_data(pkt, AV_PKT_DATA_SKIP_SAMPLES, &side_size);
+        if (side && side_size >= 4) skip_start = AV_RL32(side);
+        av_packet_unref(pkt);
+        break;
+    }
+    av_packet_free(&pkt);
+
+    int sample_rate = ctx->dec_ctx->sample_rate > 0 ? ctx->dec_ctx->sample_rate : 1;
+    if (skip_start > 0) {
+        ctx->lead_trim_sec = (double)skip_start / sample_rate;
+    } else if (stream->codecpar->initial_padding > 0) {
+        ctx->lead_trim_sec = (double)stream->codecpar->initial_padding / sample_rate;
+        ctx->manual_lead_trim = 1;
+    }
+
+    if (!consumed) return 0;
+    int64_t start_ts = stream->start_time != AV_NOPTS_VALUE ? stream->start_time : 0;
+    if (av_seek_frame(ctx->fmt_ctx, ctx->audio_stream_idx, start_ts, AVSEEK_FLAG_BACKWARD) >= 0) {
+        return 0;
+    }
+    return reopen_input(ctx, file_path);
+}
+
+/* 裁掉前导后的时间线位置换算为包时间戳秒数；0 且前导已自动裁掉时无需 seek */
+static double timeline_to_packet_sec(const DecodeContext *ctx, double timeline_sec)
+{
+    if (timeline_sec <= 0.0 && !ctx->manual_lead_trim) return 0.0;
+    return timeline_sec + ctx->lead_trim_sec;
+}
+
 /**
  * Initialize the decode context: open file, find audio stream, open codec, set up resampler.
+ * trim_lead 非 0 时探测编码器前导，只给需要按裁剪后时间线定位的调用方（transport / 流式解码）。
  * Returns 0 on success.
  */
 static int decode_ctx_init(
     DecodeContext *ctx,
     const char *file_path,
     int target_sample_rate,
-    int target_channels)
+    int target_channels,
+    int audio_stream_ordinal,
+    int trim_lead)
 {
     memset(ctx, 0, sizeof(*ctx));
     av_log_set_level(AV_LOG_ERROR);
 
     int ret = avformat_open_input(&ctx->fmt_ctx, file_path, NULL, NULL);
-    if (ret < 0) return FRKB_ERR_OPEN_INPUT;
+    if (ret < 0) { ctx->last_av_error = ret; return FRKB_ERR_OPEN_INPUT; }
 
     ret = avformat_find_stream_info(ctx->fmt_ctx, NULL);
-    if (ret < 0) return FRKB_ERR_STREAM_INFO;
+    if (ret < 0) { ctx->last_av_error = ret; return FRKB_ERR_STREAM_INFO; }
 
-    /* Find best audio stream */
-    ret = av_find_best_stream(ctx->fmt_ctx, AVMEDIA_TYPE_AUDIO, -1, -1, NULL, 0);
-    if (ret < 0) return FRKB_ERR_NO_AUDIO;
+    /* 未指定序号时取最佳音轨，否则按音频流出现顺序选第 N 条 */
+    ret = audio_stream_ordinal < 0
+        ? av_find_best_stream(ctx->fmt_ctx, AVMEDIA_TYPE_AUDIO, -1, -1, NULL, 0)
+        : find_audio_stream_by_ordinal(ctx->fmt_ctx, audio_stream_ordinal);
+    if (ret < 0) { ctx->last_av_error = ret; return FRKB_ERR_NO_AUDIO; }
     ctx->audio_stream_idx = ret;
 
     AVStream *stream = ctx->fmt_ctx->streams[ctx->audio_stream_idx];
+    /* FairPlay（drms）与 CENC（enca）加密音轨无法解码，单独报告 */
+    uint32_t codec_tag = stream->codecpar->codec_tag;
+    if (codec_tag == MKTAG('d', 'r', 'm', 's') || codec_tag == MKTAG('e', 'n', 'c', 'a')) {
+        return FRKB_ERR_DRM;
+    }
     const AVCodec *codec = avcodec_find_decoder(stream->codecpar->codec_id);
-    if (!codec) return FRKB_ERR_NO_AUDIO;
+    if (!codec) { ctx->last_av_error = AVERROR_DECODER_NOT_FOUND; return FRKB_ERR_NO_AUDIO; }
 
     ctx->dec_ctx = avcodec_alloc_context3(codec);
     if (!ctx->dec_ctx) return FRKB_ERR_ALLOC;
 
     ret = avcodec_parameters_to_context(ctx->dec_ctx, stream->codecpar);
-    if (ret < 0) return FRKB_ERR_OPEN_CODEC;
+    if (ret < 0) { ctx->last_av_error = ret; return FRKB_ERR_OPEN_CODEC; }
 
     ret = avcodec_open2(ctx->dec_ctx, codec, NULL);
-    if (ret < 0) return FRKB_ERR_OPEN_CODEC;
+    if (ret < 0) { ctx->last_av_error = ret; return FRKB_ERR_OPEN_CODEC; }
 
     /* transport 调用方会传入固定输出格式，用来对齐现有 FFmpeg CLI 路径。 */
     int src_channels = ctx->dec_ctx->ch_layout.nb_channels;
@@ -112,7 +203,7 @@ static int decode_ctx_init(
     ret = swr_init(ctx->swr_ctx);
     if (ret < 0) return FRKB_ERR_SWR_INIT;
 
-    return 0;
+    return trim_lead ? probe_lead_trim(ctx, file_path) : 0;
 }
 
 static int append_i16_samples(
@@ -180,7 +271,7 @@ int frkb_ffmpeg_chromaprint_generate(
     if (max_duration_sec <= 0) max_duration_sec = 120;
 
     DecodeContext ctx;
-    int ret = decode_ctx_init(&ctx, file_path, 0, 0);
+    int ret = decode_ctx_init(&ctx, file_path, 0, 0, -1, 0);
     if (ret != 0) return ret;
 
     /* Create Chromaprint context */
@@ -312,17 +403,24 @@ finish:
 
 int frkb_ffmpeg_transport_decode(
     const char *file_path,
+    int audio_stream_ordinal,
     double start_sec,
     double max_duration_sec,
     int target_sample_rate,
     int target_channels,
+    int trim_lead,
     int16_t **samples_out,
     size_t *sample_count_out,
     int *sample_rate_out,
     int *channels_out,
     void *cancel_opaque,
-    int (*should_cancel)(void *cancel_opaque))
+    int (*should_cancel)(void *cancel_opaque),
+    FrkbDecodeErrorInfo *error_out)
 {
+    if (error_out) {
+        error_out->av_error = 0;
+        error_out->position_sec = -1.0;
+    }
     if (!file_path || !samples_out || !sample_count_out || !sample_rate_out || !channels_out) {
         return FRKB_ERR_INVALID_ARG;
     }
@@ -334,8 +432,13 @@ int frkb_ffmpeg_transport_decode(
     if (start_sec < 0.0) start_sec = 0.0;
 
     DecodeContext ctx;
-    int ret = decode_ctx_init(&ctx, file_path, target_sample_rate, target_channels);
-    if (ret != 0) return ret;
+    int ret = decode_ctx_init(
+        &ctx, file_path, target_sample_rate, target_channels, audio_stream_ordinal, trim_lead);
+    if (ret != 0) {
+        if (error_out) error_out->av_error = ctx.last_av_error;
+        decode_ctx_free(&ctx);
+        return ret;
+    }
 
     if (decode_should_cancel(cancel_opaque, should_cancel)) {
         decode_ctx_free(&ctx);
@@ -343,13 +446,19 @@ int frkb_ffmpeg_transport_decode(
     }
 
     AVStream *stream = ctx.fmt_ctx->streams[ctx.audio_stream_idx];
+    /* 前导裁剪后的时间线与 decode/transport/stream 三条路径一致；从头解码时只丢帧不 seek */
+    double packet_start_sec = timeline_to_packet_sec(&ctx, start_sec);
     if (start_sec > 0.0) {
         int64_t seek_ts = av_rescale_q(
-            (int64_t)(start_sec * AV_TIME_BASE),
+            (int64_t)(packet_start_sec * AV_TIME_BASE),
             AV_TIME_BASE_Q,
             stream->time_base);
         ret = av_seek_frame(ctx.fmt_ctx, ctx.audio_stream_idx, seek_ts, AVSEEK_FLAG_BACKWARD);
         if (ret < 0) {
+            if (error_out) {
+                error_out->av_error = ret;
+                error_out->position_sec = start_sec;
+            }
             decode_ctx_free(&ctx);
             return FRKB_ERR_SEEK;
         }
@@ -366,6 +475,7 @@ int frkb_ffmpeg_transport_decode(
         : INT64_MAX;
     int64_t written_frames = 0;
     int reached_limit = 0;
+    double packet_position_sec = start_sec;
 
     AVPacket *pkt = av_packet_alloc();
     AVFrame *frame = av_frame_alloc();
@@ -398,6 +508,9 @@ int frkb_ffmpeg_transport_decode(
             av_packet_unref(pkt);
             continue;
         }
+        if (pkt->pts != AV_NOPTS_VALUE) {
+            packet_position_sec = (double)pkt->pts * av_q2d(stream->time_base);
+        }
 
         ret = avcodec_send_packet(ctx.dec_ctx, pkt);
         av_packet_unref(pkt);
@@ -405,6 +518,7 @@ int frkb_ffmpeg_transport_decode(
             if (is_recoverable_decode_error(ret)) {
                 continue;
             }
+            if (error_out) error_out->av_error = ret;
             ret = FRKB_ERR_DECODE;
             goto finish_transport;
         }
@@ -451,10 +565,10 @@ int frkb_ffmpeg_transport_decode(
             }
 
             int skip_frames = 0;
-            if (start_sec > 0.0) {
+            if (packet_start_sec > 0.0) {
                 double current_frame_start_sec = 0.0;
                 if (frame_start_seconds(frame, stream, &current_frame_start_sec)) {
-                    double delta_sec = start_sec - current_frame_start_sec;
+                    double delta_sec = packet_start_sec - current_frame_start_sec;
                     if (delta_sec > 0.0) {
                         skip_frames = (int)(delta_sec * ctx.out_sample_rate + 0.5);
                         if (skip_frames >= converted) {
@@ -487,6 +601,7 @@ int frkb_ffmpeg_transport_decode(
 
         if (ret == AVERROR_EOF) break;
         if (ret != AVERROR(EAGAIN)) {
+            if (error_out) error_out->av_error = ret;
             ret = FRKB_ERR_DECODE;
             goto finish_transport;
         }
@@ -563,6 +678,7 @@ finish_transport:
     decode_ctx_free(&ctx);
 
     if (ret != 0) {
+        if (error_out && ret != FRKB_ERR_CANCELLED) error_out->position_sec = packet_position_sec;
         free(samples);
         return ret;
     }
@@ -578,3 +694,928 @@ void frkb_ffmpeg_transport_free_samples(int16_t *ptr)
 {
     free(ptr);
 }
+
+/* ===================== Streaming decode ===================== */
+
+struct FrkbFfmpegStream {
+    DecodeContext ctx;
+    AVPacket *pkt;
+    AVFrame *frame;
+    int16_t *buf;
+    int buf_capacity_frames;
+    double skip_until_sec;   /* seek 目标（包时间戳秒）；此前的帧丢弃，<= 0 表示不丢弃 */
+    double packet_position_sec;
+    int draining;            /* 已向解码器送入 NULL 包 */
+    int finished;
+};
+
+int frkb_ffmpeg_stream_open(
+    const char *file_path,
+    int audio_stream_ordinal,
+    FrkbFfmpegStream **stream_out,
+    int *sample_rate_out,
+    int *channels_out,
+    FrkbDecodeErrorInfo *error_out)
+{
+    if (error_out) {
+        error_out->av_error = 0;
+        error_out->position_sec = -1.0;
+    }
+    if (!file_path || !stream_out || !sample_rate_out || !channels_out) {
+        return FRKB_ERR_INVALID_ARG;
+    }
+    *stream_out = NULL;
+
+    FrkbFfmpegStream *stream = (FrkbFfmpegStream *)calloc(1, sizeof(FrkbFfmpegStream));
+    if (!stream) return FRKB_ERR_ALLOC;
+
+    int ret = decode_ctx_init(&stream->ctx, file_path, 0, 0, audio_stream_ordinal, 1);
+    if (ret != 0) {
+        if (error_out) error_out->av_error = stream->ctx.last_av_error;
+        frkb_ffmpeg_stream_close(stream);
+        return ret;
+    }
+
+    stream->pkt = av_packet_alloc();
+    stream->frame = av_frame_alloc();
+    stream->buf_capacity_frames = 4096;
+    stream->buf = (int16_t *)malloc(
+        (size_t)stream->buf_capacity_frames * stream->ctx.out_channels * sizeof(int16_t));
+    if (!stream->pkt || !stream->frame || !stream->buf) {
+        frkb_ffmpeg_stream_close(stream);
+        return FRKB_ERR_ALLOC;
+    }
+
+    stream->skip_until_sec = timeline_to_packet_sec(&stream->ctx, 0.0);
+    *stream_out = stream;
+    *sample_rate_out = stream->ctx.out_sample_rate;
+    *channels_out = stream->ctx.out_channels;
+    return 0;
+}
+
+/* 把当前帧转换到 stream->buf，按 seek 目标裁掉开头；返回可用帧数或负的错误码 */
+static int stream_convert_frame(FrkbFfmpegStream *stream, int *skip_frames_out)
+{
+    DecodeContext *ctx = &stream->ctx;
+    AVFrame *frame = stream->frame;
+    *skip_frames_out = 0;
+
+    int out_count = (int)av_rescale_rnd(
+        swr_get_delay(ctx->swr_ctx, ctx->dec_ctx->sample_rate) + frame->nb_samples,
+        ctx->out_sample_rate,
+        ctx->dec_ctx->sample_rate,
+        AV_ROUND_UP);
+    if (out_count < frame->nb_samples) out_count = frame->nb_samples;
+    if (out_count > stream->buf_capacity_frames) {
+        int next_capacity = out_count * 2;
+        int16_t *next_buf = (int16_t *)realloc(
+            stream->buf,
+            (size_t)next_capacity * ctx->out_channels * sizeof(int16_t));
+        if (!next_buf) return -FRKB_ERR_ALLOC;
+        stream->buf = next_buf;
+        stream->buf_capacity_frames = next_capacity;
+    }
+
+    uint8_t *out_planes[1] = { (uint8_t *)stream->buf };
+    int converted = swr_convert(
+        ctx->swr_ctx,
+        out_planes,
+        out_count,
+        (const uint8_t **)frame->extended_data,
+        frame->nb_samples);
+    if (converted < 0) return -FRKB_ERR_DECODE;
+
+    if (converted > 0 && stream->skip_until_sec > 0.0) {
+        double frame_start_sec = 0.0;
+        AVStream *av_stream = ctx->fmt_ctx->streams[ctx->audio_stream_idx];
+        if (frame_start_seconds(frame, av_stream, &frame_start_sec)) {
+            double delta_sec = stream->skip_until_sec - frame_start_sec;
+            if (delta_sec > 0.0) {
+                int skip_frames = (int)(delta_sec * ctx->out_sample_rate + 0.5);
+                if (skip_frames >= converted) return 0;
+                *skip_frames_out = skip_frames;
+            }
+        }
+        /* 到达目标后的帧不再比较时间戳 */
+        stream->skip_until_sec = 0.0;
+    }
+    return converted - *skip_frames_out;
+}
+
+int frkb_ffmpeg_stream_next(
+    FrkbFfmpegStream *stream,
+    const int16_t **samples_out,
+    int *frames_out,
+    FrkbDecodeErrorInfo *error_out)
+{
+    if (error_out) {
+        error_out->av_error = 0;
+        error_out->position_sec = -1.0;
+    }
+    if (!stream || !samples_out || !frames_out) return FRKB_ERR_INVALID_ARG;
+    *samples_out = NULL;
+    *frames_out = 0;
+
+    DecodeContext *ctx = &stream->ctx;
+    AVStream *av_stream = ctx->fmt_ctx->streams[ctx->audio_stream_idx];
+    while (!stream->finished) {
+        int ret = avcodec_receive_frame(ctx->dec_ctx, stream->frame);
+        if (ret == 0) {
+            int skip_frames = 0;
+            int frames = stream_convert_frame(stream, &skip_frames);
+            av_frame_unref(stream->frame);
+            if (frames < 0) {
+                if (error_out) error_out->position_sec = stream->packet_position_sec;
+                return -frames;
+            }
+            if (frames == 0) continue;
+            *samples_out = stream->buf + (size_t)skip_frames * ctx->out_channels;
+            *frames_out = frames;
+            return 0;
+        }
+        if (ret == AVERROR_EOF) {
+            stream->finished = 1;
+            break;
+        }
+        if (ret != AVERROR(EAGAIN)) {
+            if (error_out) {
+                error_out->av_error = ret;
+                error_out->position_sec = stream->packet_position_sec;
+            }
+            return FRKB_ERR_DECODE;
+        }
+        if (stream->draining) {
+            stream->finished = 1;
+            break;
+        }
+
+        /* 解码器需要更多输入：读下一个音频包，读完后送 NULL 包排空 */
+        if (av_read_frame(ctx->fmt_ctx, stream->pkt) < 0) {
+            avcodec_send_packet(ctx->dec_ctx, NULL);
+            stream->draining = 1;
+            continue;
+        }
+        if (stream->pkt->stream_index != ctx->audio_stream_idx) {
+            av_packet_unref(stream->pkt);
+            continue;
+        }
+        if (stream->pkt->pts != AV_NOPTS_VALUE) {
+            stream->packet_position_sec =
+                (double)stream->pkt->pts * av_q2d(av_stream->time_base);
+        }
+        ret = avcodec_send_packet(ctx->dec_ctx, stream->pkt);
+        av_packet_unref(stream->pkt);
+        if (ret < 0 && !is_recoverable_decode_error(ret)) {
+            if (error_out) {
+                error_out->av_error = ret;
+                error_out->position_sec = stream->packet_position_sec;
+            }
+            return FRKB_ERR_DECODE;
+        }
+    }
+    return 0;
+}
+
+int frkb_ffmpeg_stream_seek(
+    FrkbFfmpegStream *stream,
+    double start_sec,
+    FrkbDecodeErrorInfo *error_out)
+{
+    if (error_out) {
+        error_out->av_error = 0;
+        error_out->position_sec = -1.0;
+    }
+    if (!stream) return FRKB_ERR_INVALID_ARG;
+    if (!(start_sec > 0.0)) start_sec = 0.0;
+
+    DecodeContext *ctx = &stream->ctx;
+    AVStream *av_stream = ctx->fmt_ctx->streams[ctx->audio_stream_idx];
+    double packet_start_sec = timeline_to_packet_sec(ctx, start_sec);
+    int64_t seek_ts = av_rescale_q(
+        (int64_t)(packet_start_sec * AV_TIME_BASE),
+        AV_TIME_BASE_Q,
+        av_stream->time_base);
+    int ret = av_seek_frame(ctx->fmt_ctx, ctx->audio_stream_idx, seek_ts, AVSEEK_FLAG_BACKWARD);
+    if (ret < 0) {
+        if (error_out) {
+            error_out->av_error = ret;
+            error_out->position_sec = start_sec;
+        }
+        return FRKB_ERR_SEEK;
+    }
+    avcodec_flush_buffers(ctx->dec_ctx);
+    /* 重新初始化即清空重采样器内部缓存 */
+    if (swr_init(ctx->swr_ctx) < 0) return FRKB_ERR_SWR_INIT;
+
+    stream->skip_until_sec = packet_start_sec;
+    stream->packet_position_sec = packet_start_sec;
+    stream->draining = 0;
+    stream->finished = 0;
+    return 0;
+}
+
+void frkb_ffmpeg_stream_close(FrkbFfmpegStream *stream)
+{
+    if (!stream) return;
+    free(stream->buf);
+    av_frame_free(&stream->frame);
+    av_packet_free(&stream->pkt);
+    decode_ctx_free(&stream->ctx);
+    free(stream);
+}
+
+/* ===================== Verify / salvage ===================== */
+
+typedef struct {
+    AVFormatContext *fmt_ctx;
+    AVStream        *stream;
+    int              header_written;
+} SalvageOutput;
+
+static void salvage_output_free(SalvageOutput *out) {
+    if (!out->fmt_ctx) return;
+    if (!(out->fmt_ctx->oformat->flags & AVFMT_NOFILE) && out->fmt_ctx->pb) {
+        avio_closep(&out->fmt_ctx->pb);
+    }
+    avformat_free_context(out->fmt_ctx);
+    out->fmt_ctx = NULL;
+}
+
+/**
+ * 打开修复副本输出：只复制音频流参数，包数据原样写入（不重新编码）。
+ */
+static int salvage_output_open(
+    SalvageOutput *out,
+    const char *salvage_path,
+    const AVStream *in_stream,
+    int *av_error)
+{
+    memset(out, 0, sizeof(*out));
+    int ret = avformat_alloc_output_context2(&out->fmt_ctx, NULL, NULL, salvage_path);
+    if (ret < 0 || !out->fmt_ctx) { *av_error = ret; return FRKB_ERR_OUTPUT; }
+
+    out->stream = avformat_new_stream(out->fmt_ctx, NULL);
+    if (!out->stream) return FRKB_ERR_ALLOC;
+    ret = avcodec_parameters_copy(out->stream->codecpar, in_stream->codecpar);
+    if (ret < 0) { *av_error = ret; return FRKB_ERR_OUTPUT; }
+    /* 不同容器的 codec tag 不通用，交给 muxer 重新选择 */
+    out->stream->codecpar->codec_tag = 0;
+    out->stream->time_base = in_stream->time_base;
+
+    if (!(out->fmt_ctx->oformat->flags & AVFMT_NOFILE)) {
+        ret = avio_open(&out->fmt_ctx->pb, salvage_path, AVIO_FLAG_WRITE);
+        if (ret < 0) { *av_error = ret; return FRKB_ERR_OUTPUT; }
+    }
+    ret = avformat_write_header(out->fmt_ctx, NULL);
+    if (ret < 0) { *av_error = ret; return FRKB_ERR_OUTPUT; }
+    out->header_written = 1;
+    return 0;
+}
+
+static double packet_position_seconds(const AVPacket *pkt, const AVStream *stream)
+{
+    int64_t ts = pkt->pts != AV_NOPTS_VALUE ? pkt->pts : pkt->dts;
+    if (ts == AV_NOPTS_VALUE) return -1.0;
+    return (double)ts * av_q2d(stream->time_base);
+}
+
+int frkb_ffmpeg_verify_audio(
+    const char *file_path,
+    const char *salvage_path,
+    FrkbVerifyReport *report_out,
+    FrkbBadPacket *bad_packets,
+    int bad_packet_capacity,
+    int *bad_packet_written_out,
+    int *av_error_out)
+{
+    if (av_error_out) *av_error_out = 0;
+    if (!file_path || !report_out || !bad_packet_written_out) return FRKB_ERR_INVALID_ARG;
+    memset(report_out, 0, sizeof(*report_out));
+    report_out->declared_duration_sec = -1.0;
+    report_out->last_position_sec = -1.0;
+    *bad_packet_written_out = 0;
+    if (!bad_packets) bad_packet_capacity = 0;
+
+    DecodeContext ctx;
+    int ret = decode_ctx_init(&ctx, file_path, 0, 0, -1, 0);
+    if (ret != 0) {
+        if (av_error_out) *av_error_out = ctx.last_av_error;
+        decode_ctx_free(&ctx);
+        return ret;
+    }
+
+    AVStream *stream = ctx.fmt_ctx->streams[ctx.audio_stream_idx];
+    report_out->sample_rate = ctx.dec_ctx->sample_rate;
+    if (stream->duration != AV_NOPTS_VALUE && stream->duration > 0) {
+        report_out->declared_duration_sec = (double)stream->duration * av_q2d(stream->time_base);
+    } else if (ctx.fmt_ctx->duration != AV_NOPTS_VALUE && ctx.fmt_ctx->duration > 0) {
+        report_out->declared_duration_sec = (double)ctx.fmt_ctx->duration / AV_TIME_BASE;
+    }
+    report_out->declared_duration_estimated =
+        ctx.fmt_ctx->duration_estimation_method == AVFMT_DURATION_FROM_BITRATE;
+
+    SalvageOutput out;
+    memset(&out, 0, sizeof(out));
+    if (salvage_path) {
+        int out_av_error = 0;
+        ret = salvage_output_open(&out, salvage_path, stream, &out_av_error);
+        if (ret != 0) {
+            if (av_error_out) *av_error_out = out_av_error;
+            salvage_output_free(&out);
+            decode_ctx_free(&ctx);
+            return ret;
+        }
+    }
+
+    AVPacket *pkt = av_packet_alloc();
+    AVFrame *frame = av_frame_alloc();
+    if (!pkt || !frame) {
+        ret = FRKB_ERR_ALLOC;
+        goto finish_verify;
+    }
+
+    ret = 0;
+    for (;;) {
+        int read_ret = av_read_frame(ctx.fmt_ctx, pkt);
+        if (read_ret < 0) {
+            if (read_ret != AVERROR_EOF) report_out->read_error = read_ret;
+            break;
+        }
+        if (pkt->stream_index != ctx.audio_stream_idx) {
+            av_packet_unref(pkt);
+            continue;
+        }
+
+        report_out->packet_count++;
+        double position_sec = packet_position_seconds(pkt, stream);
+        if (position_sec >= 0.0) report_out->last_position_sec = position_sec;
+
+        /* 坏包判定：送包或取帧返回非 EAGAIN/EOF 错误 */
+        int packet_error = 0;
+        if (pkt->flags & AVPKT_FLAG_CORRUPT) packet_error = AVERROR_INVALIDDATA;
+        int send_ret = avcodec_send_packet(ctx.dec_ctx, pkt);
+        if (send_ret < 0 && send_ret != AVERROR(EAGAIN)) packet_error = send_ret;
+        for (;;) {
+            int recv_ret = avcodec_receive_frame(ctx.dec_ctx, frame);
+            if (recv_ret == AVERROR(EAGAIN) || recv_ret == AVERROR_EOF) break;
+            if (recv_ret < 0) {
+                if (!packet_error) packet_error = recv_ret;
+                break;
+            }
+            report_out->decoded_frames += frame->nb_samples;
+            av_frame_unref(frame);
+        }
+
+        if (packet_error) {
+            if (report_out->bad_packet_count < bad_packet_capacity) {
+                FrkbBadPacket *bad = &bad_packets[report_out->bad_packet_count];
+                bad->position_sec = position_sec;
+                bad->byte_offset = pkt->pos;
+                bad->av_error = packet_error;
+                *bad_packet_written_out = (int)(report_out->bad_packet_count + 1);
+            }
+            report_out->bad_packet_count++;
+            av_packet_unref(pkt);
+            continue;
+        }
+
+        if (out.header_written) {
+            av_packet_rescale_ts(pkt, stream->time_base, out.stream->time_base);
+            pkt->stream_index = out.stream->index;
+            pkt->pos = -1;
+            int write_ret = av_interleaved_write_frame(out.fmt_ctx, pkt);
+            if (write_ret < 0) {
+                if (av_error_out) *av_error_out = write_ret;
+                ret = FRKB_ERR_OUTPUT;
+                goto finish_verify;
+            }
+            report_out->salvaged_packet_count++;
+        }
+        av_packet_unref(pkt);
+    }
+
+    /* 冲刷解码器尾部缓存帧 */
+    if (avcodec_send_packet(ctx.dec_ctx, NULL) >= 0) {
+        while (avcodec_receive_frame(ctx.dec_ctx, frame) >= 0) {
+            report_out->decoded_frames += frame->nb_samples;
+            av_frame_unref(frame);
+        }
+    }
+
+    if (out.header_written) {
+        int trailer_ret = av_write_trailer(out.fmt_ctx);
+        if (trailer_ret < 0) {
+            if (av_error_out) *av_error_out = trailer_ret;
+            ret = FRKB_ERR_OUTPUT;
+        }
+    }
+
+finish_verify:
+    av_frame_free(&frame);
+    av_packet_free(&pkt);
+    salvage_output_free(&out);
+    decode_ctx_free(&ctx);
+    return ret;
+}
+
+/* ===================== Layout probe ===================== */
+
+static void copy_dict_value(char *dest, size_t dest_size, AVDictionary *dict, const char *key)
+{
+    dest[0] = '\0';
+    AVDictionaryEntry *entry = av_dict_get(dict, key, NULL, 0);
+    if (!entry || !entry->value) return;
+    av_strlcpy(dest, entry->value, dest_size);
+}
+
+int frkb_ffmpeg_probe_layout(
+    const char *file_path,
+    FrkbAudioStreamInfo *streams,
+    int stream_capacity,
+    int *stream_count_out,
+    FrkbChapterInfo *chapters,
+    int chapter_capacity,
+    int *chapter_count_out,
+    char **cuesheet_out,
+    int *av_error_out)
+{
+    if (av_error_out) *av_error_out = 0;
+    if (!file_path || !stream_count_out || !chapter_count_out || !cuesheet_out) {
+        return FRKB_ERR_INVALID_ARG;
+    }
+    *stream_count_out = 0;
+    *chapter_count_out = 0;
+    *cuesheet_out = NULL;
+    if (!streams) stream_capacity = 0;
+    if (!chapters) chapter_capacity = 0;
+    av_log_set_level(AV_LOG_ERROR);
+
+    AVFormatContext *fmt_ctx = NULL;
+    int ret = avformat_open_input(&fmt_ctx, file_path, NULL, NULL);
+    if (ret < 0) {
+        if (av_error_out) *av_error_out = ret;
+        return FRKB_ERR_OPEN_INPUT;
+    }
+    ret = avformat_find_stream_info(fmt_ctx, NULL);
+    if (ret < 0) {
+        if (av_error_out) *av_error_out = ret;
+        avformat_close_input(&fmt_ctx);
+        return FRKB_ERR_STREAM_INFO;
+    }
+
+    int best_stream = av_find_best_stream(fmt_ctx, AVMEDIA_TYPE_AUDIO, -1, -1, NULL, 0);
+    int stream_count = 0;
+    for (unsigned int i = 0; i < fmt_ctx->nb_streams; i++) {
+        AVStream *stream = fmt_ctx->streams[i];
+        if (stream->codecpar->codec_type != AVMEDIA_TYPE_AUDIO) continue;
+        if (stream_count < stream_capacity) {
+            FrkbAudioStreamInfo *info = &streams[stream_count];
+            memset(info, 0, sizeof(*info));
+            info->sample_rate = stream->codecpar->sample_rate;
+            info->channels = stream->codecpar->ch_layout.nb_channels;
+            info->duration_sec = stream->duration != AV_NOPTS_VALUE && stream->duration > 0
+                ? (double)stream->duration * av_q2d(stream->time_base)
+                : (fmt_ctx->duration != AV_NOPTS_VALUE && fmt_ctx->duration > 0
+                    ? (double)fmt_ctx->duration / AV_TIME_BASE
+                    : -1.0);
+            info->is_default = (int)i == best_stream;
+            av_strlcpy(info->codec_name, avcodec_get_name(stream->codecpar->codec_id),
+                sizeof(info->codec_name));
+            copy_dict_value(info->language, sizeof(info->language), stream->metadata, "language");
+            copy_dict_value(info->title, sizeof(info->title), stream->metadata, "title");
+        }
+        stream_count++;
+    }
+    *stream_count_out = stream_count < stream_capacity ? stream_count : stream_capacity;
+
+    /* FLAC CUESHEET 块、MKA/MP4 章节都由 demuxer 转成 chapters */
+    int chapter_count = 0;
+    for (unsigned int i = 0; i < fmt_ctx->nb_chapters && chapter_count < chapter_capacity; i++) {
+        AVChapter *chapter = fmt_ctx->chapters[i];
+        FrkbChapterInfo *info = &chapters[chapter_count++];
+        info->start_sec = (double)chapter->start * av_q2d(chapter->time_base);
+        info->end_sec = chapter->end > chapter->start
+            ? (double)chapter->end * av_q2d(chapter->time_base)
+            : -1.0;
+        copy_dict_value(info->title, sizeof(info->title), chapter->metadata, "title");
+    }
+    *chapter_count_out = chapter_count;
+
+    /* APE/WavPack/FLAC 的内嵌 cue sheet 文本（Vorbis comment CUESHEET / APEv2 Cuesheet） */
+    AVDictionaryEntry *cuesheet = av_dict_get(fmt_ctx->metadata, "cuesheet", NULL, 0);
+    if (!cuesheet && best_stream >= 0) {
+        cuesheet = av_dict_get(fmt_ctx->streams[best_stream]->metadata, "cuesheet", NULL, 0);
+    }
+    if (cuesheet && cuesheet->value) {
+        *cuesheet_out = av_strdup(cuesheet->value);
+    }
+
+    avformat_close_input(&fmt_ctx);
+    return 0;
+}
+
+void frkb_ffmpeg_free_string(char *ptr)
+{
+    av_free(ptr);
+}
+
+/* ===================== Transcode ===================== */
+
+typedef struct {
+    const char *muxer;
+    enum AVCodecID codec_id;
+    enum AVSampleFormat sample_fmt;   /* AV_SAMPLE_FMT_NONE 表示取编码器首选 */
+    int bits_per_raw_sample;
+    int default_bitrate_kbps;
+    int max_channels;
+    int supports_cover;
+} TranscodeTarget;
+
+typedef struct {
+    AVFormatContext *out_ctx;
+    AVCodecContext  *enc_ctx;
+    AVStream        *out_stream;
+    SwrContext      *swr_ctx;
+    AVAudioFifo     *fifo;
+    AVFrame         *enc_frame;
+    AVPacket        *out_pkt;
+    int              frame_size;
+    int64_t          next_pts;
+} TranscodeState;
+
+static int resolve_transcode_target(const FrkbTranscodeOptions *options, TranscodeTarget *target)
+{
+    int depth = options->bit_depth;
+    if (depth != 24 && depth != 32) depth = 16;
+    memset(target, 0, sizeof(*target));
+    target->sample_fmt = AV_SAMPLE_FMT_NONE;
+    switch (options->format) {
+    case FRKB_TRANSCODE_WAV:
+        target->muxer = "wav";
+        target->codec_id = depth == 16 ? AV_CODEC_ID_PCM_S16LE
+            : (depth == 24 ? AV_CODEC_ID_PCM_S24LE : AV_CODEC_ID_PCM_S32LE);
+        break;
+    case FRKB_TRANSCODE_AIFF:
+        target->muxer = "aiff";
+        target->codec_id = depth == 16 ? AV_CODEC_ID_PCM_S16BE
+            : (depth == 24 ? AV_CODEC_ID_PCM_S24BE : AV_CODEC_ID_PCM_S32BE);
+        target->supports_cover = 1;
+        break;
+    case FRKB_TRANSCODE_FLAC:
+        /* FLAC 编码器最高 24 bit，32 bit 请求按 24 bit 输出 */
+        target->muxer = "flac";
+        target->codec_id = AV_CODEC_ID_FLAC;
+        target->sample_fmt = depth == 16 ? AV_SAMPLE_FMT_S16 : AV_SAMPLE_FMT_S32;
+        target->bits_per_raw_sample = depth == 16 ? 16 : 24;
+        target->supports_cover = 1;
+        break;
+    case FRKB_TRANSCODE_MP3:
+        target->muxer = "mp3";
+        target->codec_id = AV_CODEC_ID_MP3;
+        target->default_bitrate_kbps = 320;
+        target->max_channels = 2;
+        target->supports_cover = 1;
+        break;
+    case FRKB_TRANSCODE_AAC:
+        target->muxer = "ipod";
+        target->codec_id = AV_CODEC_ID_AAC;
+        target->default_bitrate_kbps = 256;
+        target->supports_cover = 1;
+        break;
+    default:
+        return FRKB_ERR_INVALID_ARG;
+    }
+    return 0;
+}
+
+/* 编码器只接受固定采样率集合时（如 MP3），取不高于请求值的最大支持采样率 */
+static int pick_encoder_sample_rate(const AVCodec *codec, int requested)
+{
+    if (!codec->supported_samplerates) return requested;
+    int best = 0;
+    int lowest = 0;
+    for (const int *rate = codec->supported_samplerates; *rate; rate++) {
+        if (*rate == requested) return requested;
+        if (*rate < requested && *rate > best) best = *rate;
+        if (!lowest || *rate < lowest) lowest = *rate;
+    }
+    return best ? best : lowest;
+}
+
+static void transcode_state_free(TranscodeState *st)
+{
+    av_packet_free(&st->out_pkt);
+    av_frame_free(&st->enc_frame);
+    if (st->fifo) av_audio_fifo_free(st->fifo);
+    st->fifo = NULL;
+    if (st->swr_ctx) swr_free(&st->swr_ctx);
+    if (st->enc_ctx) avcodec_free_context(&st->enc_ctx);
+    if (st->out_ctx) {
+        if (!(st->out_ctx->oformat->flags & AVFMT_NOFILE) && st->out_ctx->pb) {
+            avio_closep(&st->out_ctx->pb);
+        }
+        avformat_free_context(st->out_ctx);
+        st->out_ctx = NULL;
+    }
+}
+
+static int transcode_write_packets(TranscodeState *st, const AVFrame *frame)
+{
+    int ret = avcodec_send_frame(st->enc_ctx, frame);
+    if (ret < 0) return ret;
+    for (;;) {
+        ret = avcodec_receive_packet(st->enc_ctx, st->out_pkt);
+        if (ret == AVERROR(EAGAIN) || ret == AVERROR_EOF) return 0;
+        if (ret < 0) return ret;
+        av_packet_rescale_ts(st->out_pkt, st->enc_ctx->time_base, st->out_stream->time_base);
+        st->out_pkt->stream_index = st->out_stream->index;
+        ret = av_interleaved_write_frame(st->out_ctx, st->out_pkt);
+        if (ret < 0) return ret;
+    }
+}
+
+/* 按编码器帧长从 FIFO 取样本编码；flush 时把不足一帧的尾部也送出 */
+static int transcode_drain_fifo(TranscodeState *st, int flush)
+{
+    while (av_audio_fifo_size(st->fifo) >= st->frame_size
+        || (flush && av_audio_fifo_size(st->fifo) > 0)) {
+        int nb_samples = FFMIN(av_audio_fifo_size(st->fifo), st->frame_size);
+        AVFrame *frame = st->enc_frame;
+        av_frame_unref(frame);
+        frame->nb_samples = nb_samples;
+        frame->format = st->enc_ctx->sample_fmt;
+        frame->sample_rate = st->enc_ctx->sample_rate;
+        int ret = av_channel_layout_copy(&frame->ch_layout, &st->enc_ctx->ch_layout);
+        if (ret < 0) return ret;
+        ret = av_frame_get_buffer(frame, 0);
+        if (ret < 0) return ret;
+        if (av_audio_fifo_read(st->fifo, (void **)frame->data, nb_samples) < nb_samples) {
+            return AVERROR(EIO);
+        }
+        frame->pts = st->next_pts;
+        st->next_pts += nb_samples;
+        ret = transcode_write_packets(st, frame);
+        if (ret < 0) return ret;
+    }
+    return 0;
+}
+
+/* 重采样到编码器格式后写入 FIFO；frame 为 NULL 时冲刷 swr 缓存 */
+static int transcode_resample_into_fifo(TranscodeState *st, const AVFrame *frame)
+{
+    int in_samples = frame ? frame->nb_samples : 0;
+    int out_capacity = swr_get_out_samples(st->swr_ctx, in_samples);
+    if (out_capacity <= 0) return 0;
+
+    uint8_t **converted = NULL;
+    int ret = av_samples_alloc_array_and_samples(
+        &converted, NULL, st->enc_ctx->ch_layout.nb_channels,
+        out_capacity, st->enc_ctx->sample_fmt, 0);
+    if (ret < 0) return ret;
+
+    int out_samples = swr_convert(
+        st->swr_ctx, converted, out_capacity,
+        frame ? (const uint8_t **)frame->extended_data : NULL, in_samples);
+    ret = out_samples;
+    if (out_samples > 0 && av_audio_fifo_write(st->fifo, (void **)converted, out_samples) < out_samples) {
+        ret = AVERROR(ENOMEM);
+    }
+    av_freep(&converted[0]);
+    av_freep(&converted);
+    return ret < 0 ? ret : 0;
+}
+
+static AVStream *find_cover_stream(AVFormatContext *fmt_ctx)
+{
+    for (unsigned int i = 0; i < fmt_ctx->nb_streams; i++) {
+        AVStream *stream = fmt_ctx->streams[i];
+        if (!(stream->disposition & AV_DISPOSITION_ATTACHED_PIC)) continue;
+        enum AVCodecID codec_id = stream->codecpar->codec_id;
+        if (codec_id == AV_CODEC_ID_MJPEG || codec_id == AV_CODEC_ID_PNG) return stream;
+    }
+    return NULL;
+}
+
+int frkb_ffmpeg_transcode(
+    const char *input_path,
+    const char *output_path,
+    const FrkbTranscodeOptions *options,
+    void *opaque,
+    int (*should_cancel)(void *opaque),
+    void (*on_progress)(void *opaque, double processed_sec, double total_sec),
+    int *av_error_out)
+{
+    if (av_error_out) *av_error_out = 0;
+    if (!input_path || !output_path || !options) return FRKB_ERR_INVALID_ARG;
+
+    TranscodeTarget target;
+    int ret = resolve_transcode_target(options, &target);
+    if (ret != 0) return ret;
+
+    DecodeContext ctx;
+    ret = decode_ctx_init(&ctx, input_path, 0, 0, -1, 0);
+    if (ret != 0) {
+        if (av_error_out) *av_error_out = ctx.last_av_error;
+        decode_ctx_free(&ctx);
+        return ret;
+    }
+    /* 解码上下文自带的 s16 重采样不适用于编码，按编码器格式重建 */
+    swr_free(&ctx.swr_ctx);
+
+    TranscodeState st;
+    memset(&st, 0, sizeof(st));
+    AVPacket *pkt = av_packet_alloc();
+    AVFrame *frame = av_frame_alloc();
+    st.enc_frame = av_frame_alloc();
+    st.out_pkt = av_packet_alloc();
+    int av_error = 0;
+    if (!pkt || !frame || !st.enc_frame || !st.out_pkt) {
+        ret = FRKB_ERR_ALLOC;
+        goto finish_transcode;
+    }
+
+    AVStream *in_stream = ctx.fmt_ctx->streams[ctx.audio_stream_idx];
+    const AVCodec *encoder = avcodec_find_encoder(target.codec_id);
+    if (!encoder) {
+        av_error = AVERROR_ENCODER_NOT_FOUND;
+        ret = FRKB_ERR_OPEN_CODEC;
+        goto finish_transcode;
+    }
+
+    av_error = avformat_alloc_output_context2(&st.out_ctx, NULL, target.muxer, output_path);
+    if (av_error < 0 || !st.out_ctx) { ret = FRKB_ERR_OUTPUT; goto finish_transcode; }
+    av_error = 0;
+
+    st.enc_ctx = avcodec_alloc_context3(encoder);
+    if (!st.enc_ctx) { ret = FRKB_ERR_ALLOC; goto finish_transcode; }
+
+    int channels = options->channels > 0 ? options->channels : ctx.dec_ctx->ch_layout.nb_channels;
+    if (target.max_channels > 0 && channels > target.max_channels) channels = target.max_channels;
+    if (channels <= 0) channels = TARGET_CHANNELS;
+    av_channel_layout_default(&st.enc_ctx->ch_layout, channels);
+    int requested_rate = options->sample_rate > 0 ? options->sample_rate : ctx.dec_ctx->sample_rate;
+    st.enc_ctx->sample_rate = pick_encoder_sample_rate(encoder, requested_rate);
+    st.enc_ctx->sample_fmt = target.sample_fmt != AV_SAMPLE_FMT_NONE
+        ? target.sample_fmt
+        : (encoder->sample_fmts ? encoder->sample_fmts[0] : AV_SAMPLE_FMT_S16);
+    if (target.bits_per_raw_sample > 0) st.enc_ctx->bits_per_raw_sample = target.bits_per_raw_sample;
+    int bitrate_kbps = options->bitrate_kbps > 0 ? options->bitrate_kbps : target.default_bitrate_kbps;
+    if (bitrate_kbps > 0) st.enc_ctx->bit_rate = (int64_t)bitrate_kbps * 1000;
+    st.enc_ctx->time_base = (AVRational){1, st.enc_ctx->sample_rate};
+    if (st.out_ctx->oformat->flags & AVFMT_GLOBALHEADER) {
+        st.enc_ctx->flags |= AV_CODEC_FLAG_GLOBAL_HEADER;
+    }
+    av_error = avcodec_open2(st.enc_ctx, encoder, NULL);
+    if (av_error < 0) { ret = FRKB_ERR_OPEN_CODEC; goto finish_transcode; }
+    av_error = 0;
+
+    st.out_stream = avformat_new_stream(st.out_ctx, NULL);
+    if (!st.out_stream) { ret = FRKB_ERR_ALLOC; goto finish_transcode; }
+    st.out_stream->time_base = st.enc_ctx->time_base;
+    av_error = avcodec_parameters_from_context(st.out_stream->codecpar, st.enc_ctx);
+    if (av_error < 0) { ret = FRKB_ERR_OUTPUT; goto finish_transcode; }
+    av_error = 0;
+
+    /* 标签：容器级与音轨级（FLAC/Ogg 写在流上）合并到输出容器 */
+    if (options->preserve_metadata) {
+        av_dict_copy(&st.out_ctx->metadata, ctx.fmt_ctx->metadata, 0);
+        av_dict_copy(&st.out_ctx->metadata, in_stream->metadata, AV_DICT_DONT_OVERWRITE);
+        av_dict_set(&st.out_ctx->metadata, "encoder", NULL, 0);
+    }
+
+    AVStream *in_cover = options->preserve_cover_art && target.supports_cover
+        ? find_cover_stream(ctx.fmt_ctx)
+        : NULL;
+    AVStream *out_cover = NULL;
+    if (in_cover) {
+        out_cover = avformat_new_stream(st.out_ctx, NULL);
+        if (!out_cover) { ret = FRKB_ERR_ALLOC; goto finish_transcode; }
+        av_error = avcodec_parameters_copy(out_cover->codecpar, in_cover->codecpar);
+        if (av_error < 0) { ret = FRKB_ERR_OUTPUT; goto finish_transcode; }
+        av_error = 0;
+        out_cover->codecpar->codec_tag = 0;
+        out_cover->disposition = AV_DISPOSITION_ATTACHED_PIC;
+        out_cover->time_base = (AVRational){1, 90000};
+        av_dict_copy(&out_cover->metadata, in_cover->metadata, 0);
+    }
+
+    av_error = swr_alloc_set_opts2(&st.swr_ctx,
+        &st.enc_ctx->ch_layout, st.enc_ctx->sample_fmt, st.enc_ctx->sample_rate,
+        &ctx.dec_ctx->ch_layout, ctx.dec_ctx->sample_fmt, ctx.dec_ctx->sample_rate,
+        0, NULL);
+    if (av_error < 0 || !st.swr_ctx || swr_init(st.swr_ctx) < 0) {
+        ret = FRKB_ERR_SWR_INIT;
+        goto finish_transcode;
+    }
+    av_error = 0;
+
+    st.frame_size = (st.enc_ctx->frame_size > 0
+        && !(encoder->capabilities & AV_CODEC_CAP_VARIABLE_FRAME_SIZE))
+        ? st.enc_ctx->frame_size
+        : 4096;
+    st.fifo = av_audio_fifo_alloc(st.enc_ctx->sample_fmt, channels, st.frame_size * 2);
+    if (!st.fifo) { ret = FRKB_ERR_ALLOC; goto finish_transcode; }
+
+    if (!(st.out_ctx->oformat->flags & AVFMT_NOFILE)) {
+        av_error = avio_open(&st.out_ctx->pb, output_path, AVIO_FLAG_WRITE);
+        if (av_error < 0) { ret = FRKB_ERR_OUTPUT; goto finish_transcode; }
+        av_error = 0;
+    }
+    AVDictionary *mux_opts = NULL;
+    if (target.codec_id == AV_CODEC_ID_MP3 || options->format == FRKB_TRANSCODE_AIFF) {
+        /* 播放器对 ID3v2.4 支持不一，与旧 CLI 参数保持一致写 v2.3 */
+        av_dict_set(&mux_opts, "id3v2_version", "3", 0);
+    }
+    if (options->format == FRKB_TRANSCODE_AIFF && options->preserve_metadata) {
+        av_dict_set(&mux_opts, "write_id3v2", "1", 0);
+    }
+    av_error = avformat_write_header(st.out_ctx, &mux_opts);
+    av_dict_free(&mux_opts);
+    if (av_error < 0) { ret = FRKB_ERR_OUTPUT; goto finish_transcode; }
+    av_error = 0;
+
+    if (in_cover && out_cover) {
+        AVPacket *cover_pkt = av_packet_clone(&in_cover->attached_pic);
+        if (cover_pkt) {
+            cover_pkt->stream_index = out_cover->index;
+            av_error = av_interleaved_write_frame(st.out_ctx, cover_pkt);
+            av_packet_free(&cover_pkt);
+            if (av_error < 0) { ret = FRKB_ERR_OUTPUT; goto finish_transcode; }
+            av_error = 0;
+        }
+    }
+
+    double total_sec = in_stream->duration != AV_NOPTS_VALUE && in_stream->duration > 0
+        ? (double)in_stream->duration * av_q2d(in_stream->time_base)
+        : (ctx.fmt_ctx->duration != AV_NOPTS_VALUE && ctx.fmt_ctx->duration > 0
+            ? (double)ctx.fmt_ctx->duration / AV_TIME_BASE
+            : -1.0);
+    double processed_sec = 0.0;
+
+    ret = 0;
+    while (av_read_frame(ctx.fmt_ctx, pkt) >= 0) {
+        if (decode_should_cancel(opaque, should_cancel)) {
+            av_packet_unref(pkt);
+            ret = FRKB_ERR_CANCELLED;
+            goto finish_transcode;
+        }
+        if (pkt->stream_index != ctx.audio_stream_idx) {
+            av_packet_unref(pkt);
+            continue;
+        }
+        int send_ret = avcodec_send_packet(ctx.dec_ctx, pkt);
+        av_packet_unref(pkt);
+        if (send_ret < 0 && !is_recoverable_decode_error(send_ret)) {
+            av_error = send_ret;
+            ret = FRKB_ERR_DECODE;
+            goto finish_transcode;
+        }
+        for (;;) {
+            int recv_ret = avcodec_receive_frame(ctx.dec_ctx, frame);
+            if (recv_ret == AVERROR(EAGAIN) || recv_ret == AVERROR_EOF) break;
+            if (recv_ret < 0) {
+                if (is_recoverable_decode_error(recv_ret)) continue;
+                av_error = recv_ret;
+                ret = FRKB_ERR_DECODE;
+                goto finish_transcode;
+            }
+            double frame_start_sec = 0.0;
+            if (frame_start_seconds(frame, in_stream, &frame_start_sec)) {
+                processed_sec = frame_start_sec + (double)frame->nb_samples / frame->sample_rate;
+            } else if (frame->sample_rate > 0) {
+                processed_sec += (double)frame->nb_samples / frame->sample_rate;
+            }
+            av_error = transcode_resample_into_fifo(&st, frame);
+            av_frame_unref(frame);
+            if (av_error < 0) { ret = FRKB_ERR_SWR_INIT; goto finish_transcode; }
+            av_error = transcode_drain_fifo(&st, 0);
+            if (av_error < 0) { ret = FRKB_ERR_OUTPUT; goto finish_transcode; }
+        }
+        if (on_progress) on_progress(opaque, processed_sec, total_sec);
+    }
+
+    /* 冲刷解码器、重采样器、FIFO 与编码器 */
+    if (avcodec_send_packet(ctx.dec_ctx, NULL) >= 0) {
+        while (avcodec_receive_frame(ctx.dec_ctx, frame) >= 0) {
+            av_error = transcode_resample_into_fifo(&st, frame);
+            av_frame_unref(frame);
+            if (av_error < 0) { ret = FRKB_ERR_SWR_INIT; goto finish_transcode; }
+        }
+    }
+    av_error = transcode_resample_into_fifo(&st, NULL);
+    if (av_error < 0) { ret = FRKB_ERR_SWR_INIT; goto finish_transcode; }
+    av_error = transcode_drain_fifo(&st, 1);
+    if (av_error >= 0) av_error = transcode_write_packets(&st, NULL);
+    if (av_error >= 0) av_error = av_write_trailer(st.out_ctx);
+    if (av_error < 0) { ret = FRKB_ERR_OUTPUT; goto finish_transcode; }
+    av_error = 0;
+    if (on_progress) on_progress(opaque, total_sec > 0.0 ? total_sec : processed_sec, total_sec);
+
+finish_transcode:
+    if (av_error_out) *av_error_out = av_error;
+    av_frame_free(&frame);
+    av_packet_free(&pkt);
+    transcode_state_free(&st);
+    decode_ctx_free(&ctx);
+    return ret;
+}
@@ -25,33 +25,48 @@ int frkb_ffmpeg_chromaprint_generate(
     double *duration_out
 );
 
+/**
+ * 失败时的补充信息，供 Rust 侧映射为结构化错误。
+ */
+typedef struct {
+    int av_error;          /* 最近一次失败的 libav 返回值（AVERROR），0 表示无 */
+    double position_sec;   /* 出错时的流位置（秒），未知为 -1 */
+} FrkbDecodeErrorInfo;
+
 /**
  * 使用 FFmpeg libav* 直接解码横向浏览播放器所需的音频片段。
  * 输出格式刻意对齐现有 CLI 路径：交错 s16le、target_sample_rate Hz、target_channels 声道。
  *
  * @param file_path             音频文件路径。
+ * @param audio_stream_ordinal  音频流序号（只计音频流，从 0 开始），-1 表示最佳音轨。
  * @param start_sec             开始位置，单位秒。
  * @param max_duration_sec      最大解码时长，0 表示不限。
  * @param target_sample_rate    输出采样率，0 表示保留源采样率；transport 通常为 44100。
  * @param target_channels       输出声道数，0 表示按源声道保留 mono/stereo；transport 通常为 2。
+ * @param trim_lead             非 0 时时间线从有效首帧起算（探测并裁掉编码器前导）；
+ *                              0 保持 libavcodec 原始输出，内容哈希与指纹依赖其逐字节稳定。
  * @param samples_out           接收分配后的交错 i16 样本。
  * @param sample_count_out      接收 i16 样本数，不是帧数。
  * @param sample_rate_out       接收输出采样率。
  * @param channels_out          接收输出声道数。
+ * @param error_out             可为 NULL；失败时接收 AVERROR 与出错位置。
  * @return 成功返回 0，失败返回非 0 错误码。
  */
 int frkb_ffmpeg_transport_decode(
     const char *file_path,
+    int audio_stream_ordinal,
     double start_sec,
     double max_duration_sec,
     int target_sample_rate,
     int target_channels,
+    int trim_lead,
     int16_t **samples_out,
     size_t *sample_count_out,
     int *sample_rate_out,
     int *channels_out,
     void *cancel_opaque,
-    int (*should_cancel)(void *cancel_opaque)
+    int (*should_cancel)(void *cancel_opaque),
+    FrkbDecodeErrorInfo *error_out
 );
 
 /**
@@ -59,6 +74,182 @@ int frkb_ffmpeg_transport_decode(
  */
 void frkb_ffmpeg_transport_free_samples(int16_t *ptr);
 
+/**
+ * 持久解码句柄：整轨只打开一次 libav 上下文，按帧增量取出 PCM。
+ */
+typedef struct FrkbFfmpegStream FrkbFfmpegStream;
+
+/**
+ * 打开流式解码句柄，输出为交错 s16、源采样率，声道按源保留 mono/stereo。
+ *
+ * @param stream_out       接收句柄，需用 frkb_ffmpeg_stream_close 释放。
+ * @param sample_rate_out  接收输出采样率。
+ * @param channels_out     接收输出声道数。
+ * @param error_out        可为 NULL；失败时接收 AVERROR。
+ * @return 成功返回 0，失败返回非 0 错误码。
+ */
+int frkb_ffmpeg_stream_open(
+    const char *file_path,
+    int audio_stream_ordinal,
+    FrkbFfmpegStream **stream_out,
+    int *sample_rate_out,
+    int *channels_out,
+    FrkbDecodeErrorInfo *error_out
+);
+
+/**
+ * 解码下一批帧。samples_out 指向句柄内部缓冲，下次调用 next/seek/close 前有效。
+ *
+ * @param frames_out  接收本批帧数；返回 0 且帧数为 0 表示已到结尾。
+ * @return 成功返回 0，失败返回非 0 错误码。
+ */
+int frkb_ffmpeg_stream_next(
+    FrkbFfmpegStream *stream,
+    const int16_t **samples_out,
+    int *frames_out,
+    FrkbDecodeErrorInfo *error_out
+);
+
+/**
+ * 跳转到 start_sec；此后 next 丢弃该位置之前的帧，保证与整段解码对齐。
+ */
+int frkb_ffmpeg_stream_seek(
+    FrkbFfmpegStream *stream,
+    double start_sec,
+    FrkbDecodeErrorInfo *error_out
+);
+
+/**
+ * 关闭句柄并释放 libav 上下文，可传 NULL。
+ */
+void frkb_ffmpeg_stream_close(FrkbFfmpegStream *stream);
+
+/**
+ * 校验时记录的坏包。
+ */
+typedef struct {
+    double position_sec;   /* 包时间戳（秒），未知为 -1 */
+    int64_t byte_offset;   /* 包在文件中的字节偏移，未知为 -1 */
+    int av_error;          /* 解码该包时 libav 返回的 AVERROR */
+} FrkbBadPacket;
+
+/**
+ * 整轨校验结果。
+ */
+typedef struct {
+    int64_t packet_count;           /* 音频包总数 */
+    int64_t bad_packet_count;       /* 解码失败的包数 */
+    int64_t decoded_frames;         /* 成功解码的 PCM 帧数（源采样率） */
+    int sample_rate;                /* 源采样率 */
+    double declared_duration_sec;   /* 容器声明的时长，未知为 -1 */
+    int declared_duration_estimated;/* 声明时长由码率估算（无 Xing/VBRI 等头） */
+    double last_position_sec;       /* 最后一个音频包的时间戳，未知为 -1 */
+    int read_error;                 /* 读包以非 EOF 错误结束时的 AVERROR，正常结束为 0 */
+    int64_t salvaged_packet_count;  /* 写入修复副本的包数 */
+} FrkbVerifyReport;
+
+/**
+ * 完整解码音轨并统计坏包；salvage_path 非 NULL 时把可解码的包原样 remux 到该路径
+ * （仅保留音频流，容器格式按扩展名推断）。
+ *
+ * @param bad_packets             接收坏包明细，可为 NULL。
+ * @param bad_packet_capacity     bad_packets 容量，超出部分只计数不记录。
+ * @param bad_packet_written_out  接收实际写入 bad_packets 的条数。
+ * @param av_error_out            失败时接收 AVERROR，可为 NULL。
+ * @return 成功返回 0（即便存在坏包），失败返回非 0 错误码。
+ */
+int frkb_ffmpeg_verify_audio(
+    const char *file_path,
+    const char *salvage_path,
+    FrkbVerifyReport *report_out,
+    FrkbBadPacket *bad_packets,
+    int bad_packet_capacity,
+    int *bad_packet_written_out,
+    int *av_error_out
+);
+
+/**
+ * 探测到的音频流。
+ */
+typedef struct {
+    int sample_rate;
+    int channels;
+    double duration_sec;   /* 未知为 -1 */
+    int is_default;        /* 是否为 av_find_best_stream 选中的音轨 */
+    char codec_name[32];
+    char language[16];
+    char title[128];
+} FrkbAudioStreamInfo;
+
+/**
+ * 容器章节（FLAC CUESHEET 块、MKA/MP4 章节）。
+ */
+typedef struct {
+    double start_sec;
+    double end_sec;        /* 未知为 -1 */
+    char title[256];
+} FrkbChapterInfo;
+
+/**
+ * 枚举音频流、章节与内嵌 cue sheet 文本。
+ *
+ * @param cuesheet_out  接收 cue sheet 文本（无则为 NULL），需用 frkb_ffmpeg_free_string 释放。
+ * @return 成功返回 0，失败返回非 0 错误码。
+ */
+int frkb_ffmpeg_probe_layout(
+    const char *file_path,
+    FrkbAudioStreamInfo *streams,
+    int stream_capacity,
+    int *stream_count_out,
+    FrkbChapterInfo *chapters,
+    int chapter_capacity,
+    int *chapter_count_out,
+    char **cuesheet_out,
+    int *av_error_out
+);
+
+/**
+ * 释放 frkb_ffmpeg_probe_layout 返回的字符串。
+ */
+void frkb_ffmpeg_free_string(char *ptr);
+
+/* 转码目标格式 */
+#define FRKB_TRANSCODE_WAV   0
+#define FRKB_TRANSCODE_AIFF  1
+#define FRKB_TRANSCODE_FLAC  2
+#define FRKB_TRANSCODE_MP3   3
+#define FRKB_TRANSCODE_AAC   4
+
+/**
+ * 转码参数。
+ */
+typedef struct {
+    int format;              /* FRKB_TRANSCODE_* */
+    int sample_rate;         /* 0 表示保留源采样率（编码器不支持时取最接近的较低值） */
+    int channels;            /* 0 表示保留源声道数（MP3 最多 2 声道） */
+    int bit_depth;           /* 16/24/32，仅 WAV/AIFF/FLAC 有效 */
+    int bitrate_kbps;        /* 仅 MP3/AAC 有效，0 使用默认（320/256） */
+    int preserve_metadata;   /* 复制容器与音轨标签 */
+    int preserve_cover_art;  /* 复制内嵌封面（AIFF/FLAC/MP3/AAC） */
+} FrkbTranscodeOptions;
+
+/**
+ * 将任意可解码音频转码为 WAV/AIFF/FLAC/MP3/AAC(M4A)。
+ *
+ * @param on_progress   可为 NULL；每处理一个音频包回调一次已处理秒数与总时长（未知为 -1）。
+ * @param av_error_out  失败时接收 AVERROR，可为 NULL。
+ * @return 成功返回 0，被取消返回 FRKB_ERR_CANCELLED(11)，失败返回其他错误码；失败时输出文件可能残留。
+ */
+int frkb_ffmpeg_transcode(
+    const char *input_path,
+    const char *output_path,
+    const FrkbTranscodeOptions *options,
+    void *opaque,
+    int (*should_cancel)(void *opaque),
+    void (*on_progress)(void *opaque, double processed_sec, double total_sec),
+    int *av_error_out
+);
+
 #ifdef __cplusplus
 }
 #endif
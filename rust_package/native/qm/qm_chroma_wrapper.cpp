@@ -0,0 +1,133 @@
+#include <array>
+#include <cstddef>
+#include <memory>
+#include <vector>
+
+#include "base/Pitch.h"
+#include "dsp/chromagram/Chromagram.h"
+#include "dsp/rateconversion/Decimator.h"
+#include "qm_downmix_overlap.h"
+
+namespace {
+
+constexpr int kBinsPerOctave = 36;
+constexpr int kBinsPerSemitone = kBinsPerOctave / 12;
+constexpr int kDecimationFactor = 8;
+constexpr float kTuningFrequencyHertz = 440.0f;
+
+// Same chromagram front end as GetKeyMode: C3..C7, 3 bins per semitone, 8x decimation.
+class QmChromaAccumulator {
+  public:
+    explicit QmChromaAccumulator(double sampleRate)
+            : m_windows(0) {
+        ChromaConfig config;
+        config.normalise = MathUtilities::NormaliseUnitMax;
+        config.FS = sampleRate / static_cast<double>(kDecimationFactor);
+        if (config.FS < 1) {
+            config.FS = 1;
+        }
+        const float centsOffset = -12.0f / kBinsPerOctave * 100;
+        config.min = Pitch::getFrequencyForPitch(48, centsOffset, kTuningFrequencyHertz);
+        config.max = Pitch::getFrequencyForPitch(96, centsOffset, kTuningFrequencyHertz);
+        config.BPO = kBinsPerOctave;
+        config.CQThresh = 0.0054;
+        m_chroma = std::make_unique<Chromagram>(config);
+
+        const int frameSize = m_chroma->getFrameSize();
+        const std::size_t blockSize = static_cast<std::size_t>(frameSize * kDecimationFactor);
+        m_decimated.assign(static_cast<std::size_t>(frameSize), 0.0);
+        m_decimator = std::make_unique<Decimator>(frameSize * kDecimationFactor, kDecimationFactor);
+        m_ready = m_helper.initialize(blockSize, blockSize,
+                [this](double* pWindow, std::size_t) {
+                    return this->handleWindow(pWindow);
+                });
+    }
+
+    bool isReady() const {
+        return m_ready;
+    }
+
+    bool process(const float* interleaved, std::size_t frames, int channels) {
+        if (!m_ready || !interleaved || channels != 2) {
+            return false;
+        }
+        return m_helper.processStereoSamples(interleaved, frames);
+    }
+
+    int finalize(double* out) {
+        if (!m_ready || !out) {
+            return 0;
+        }
+        m_helper.finalize();
+        for (std::size_t pitchClass = 0; pitchClass < m_sum.size(); ++pitchClass) {
+            out[pitchClass] = m_windows > 0 ? m_sum[pitchClass] / m_windows : 0.0;
+        }
+        return m_windows;
+    }
+
+  private:
+    bool handleWindow(double* pWindow) {
+        m_decimator->process(pWindow, m_decimated.data());
+        const double* chroma = m_chroma->process(m_decimated.data());
+        for (int bin = 0; bin < kBinsPerOctave; ++bin) {
+            m_sum[static_cast<std::size_t>(bin / kBinsPerSemitone)] += chroma[bin];
+        }
+        ++m_windows;
+        return true;
+    }
+
+    bool m_ready = false;
+    std::unique_ptr<Chromagram> m_chroma;
+    std::unique_ptr<Decimator> m_decimator;
+    std::vector<double> m_decimated;
+    DownmixAndOverlapHelper m_helper;
+    std::array<double, 12> m_sum{};
+    int m_windows;
+};
+
+} // namespace
+
+extern "C" {
+
+struct QmChromaHandle {
+    QmChromaAccumulator* accumulator;
+};
+
+QmChromaHandle* qm_chroma_create(double sampleRate) {
+    auto* handle = new QmChromaHandle{ nullptr };
+    handle->accumulator = new QmChromaAccumulator(sampleRate);
+    if (!handle->accumulator->isReady()) {
+        delete handle->accumulator;
+        delete handle;
+        return nullptr;
+    }
+    return handle;
+}
+
+void qm_chroma_destroy(QmChromaHandle* handle) {
+    if (!handle) {
+        return;
+    }
+    delete handle->accumulator;
+    delete handle;
+}
+
+int qm_chroma_process(QmChromaHandle* handle,
+        const float* interleaved,
+        std::size_t frames,
+        int channels) {
+    if (!handle || !handle->accumulator) {
+        return 0;
+    }
+    return handle->accumulator->process(interleaved, frames, channels) ? 1 : 0;
+}
+
+// Writes the mean 12-bin chroma (index 0 = C) and returns the number of analysed windows.
+int qm_chroma_finalize(QmChromaHandle* handle, double* out12) {
+    if (!handle || !handle->accumulator) {
+        return 0;
+    }
+    return handle->accumulator->finalize(out12);
+}
+
+} // extern "C"
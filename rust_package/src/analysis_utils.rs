@@ -1,4 +1,10 @@
 use std::borrow::Cow;
+use std::path::Path;
+
+use napi::bindgen_prelude::Buffer;
+
+use crate::audio_decode_stream::AudioDecodeStream;
+use crate::mixxx_waveform::pcm_buffer_to_f32;
 
 pub const K_ANALYSIS_FRAMES_PER_CHUNK: usize = 4096;
 pub const K_FAST_ANALYSIS_SECONDS: usize = 60;
@@ -41,3 +47,173 @@ pub fn to_stereo(pcm: &[f32], channels: usize, frames: usize) -> Cow<'_, [f32]>
   }
   Cow::Owned(out)
 }
+
+pub fn hann_window(size: usize) -> Vec<f64> {
+  if size <= 1 {
+    return vec![1.0; size];
+  }
+  (0..size)
+    .map(|index| 0.5 - 0.5 * (std::f64::consts::TAU * index as f64 / (size - 1) as f64).cos())
+    .collect()
+}
+
+/// 原地 radix-2 复数 FFT，长度必须为 2 的幂
+pub fn fft_in_place(re: &mut [f64], im: &mut [f64]) {
+  let size = re.len();
+  if size <= 1 || im.len() != size || !size.is_power_of_two() {
+    return;
+  }
+  let mut j = 0usize;
+  for i in 1..size {
+    let mut bit = size >> 1;
+    while j & bit != 0 {
+      j ^= bit;
+      bit >>= 1;
+    }
+    j |= bit;
+    if i < j {
+      re.swap(i, j);
+      im.swap(i, j);
+    }
+  }
+  let mut len = 2usize;
+  while len <= size {
+    let angle = -std::f64::consts::TAU / len as f64;
+    let (w_im, w_re) = angle.sin_cos();
+    for start in (0..size).step_by(len) {
+      let mut cur_re = 1.0;
+      let mut cur_im = 0.0;
+      for offset in 0..len / 2 {
+        let a = start + offset;
+        let b = a + len / 2;
+        let t_re = re[b] * cur_re - im[b] * cur_im;
+        let t_im = re[b] * cur_im + im[b] * cur_re;
+        re[b] = re[a] - t_re;
+        im[b] = im[a] - t_im;
+        re[a] += t_re;
+        im[a] += t_im;
+        let next_re = cur_re * w_re - cur_im * w_im;
+        cur_im = cur_re * w_im + cur_im * w_re;
+        cur_re = next_re;
+      }
+    }
+    len <<= 1;
+  }
+}
+
+/// 对实数帧加窗后做 FFT，返回 0..=size/2 的功率谱（帧长须为 2 的幂）
+pub fn power_spectrum(frame: &[f64], window: &[f64]) -> Vec<f64> {
+  let size = frame.len();
+  if size == 0 {
+    return Vec::new();
+  }
+  let mut re: Vec<f64> = frame
+    .iter()
+    .zip(window.iter().chain(std::iter::repeat(&1.0)))
+    .map(|(sample, weight)| sample * weight)
+    .collect();
+  let mut im = vec![0.0; size];
+  fft_in_place(&mut re, &mut im);
+  (0..=size / 2)
+    .map(|bin| re[bin] * re[bin] + im[bin] * im[bin])
+    .collect()
+}
+
+const LOUDNESS_FLOOR_DB: f64 = -70.0;
+const LOUDNESS_EPSILON: f64 = 1e-9;
+
+/// 交错 PCM 的整体响度累加器：逐帧求各声道均方，最终给出积分响度与峰值（dBFS）
+#[derive(Clone, Copy, Default)]
+pub struct LoudnessAccumulator {
+  total_energy: f64,
+  peak: f64,
+  frames: u64,
+}
+
+impl LoudnessAccumulator {
+  pub fn push(&mut self, samples: &[f32], channels: u16) {
+    let channel_count = channels.max(1) as usize;
+    for frame in samples.chunks_exact(channel_count) {
+      let mut frame_energy = 0.0_f64;
+      for &sample in frame {
+        let sample = sample as f64;
+        frame_energy += sample * sample;
+        self.peak = self.peak.max(sample.abs());
+      }
+      self.total_energy += frame_energy / channel_count as f64;
+    }
+    self.frames += (samples.len() / channel_count) as u64;
+  }
+
+  /// 返回 (integrated_db, peak_db)；无有效帧时为 None
+  pub fn finish(&self) -> Option<(f64, f64)> {
+    if self.frames == 0 {
+      return None;
+    }
+    let mean_square = self.total_energy / self.frames as f64;
+    let integrated_db = (10.0 * mean_square.max(LOUDNESS_EPSILON).log10()).max(LOUDNESS_FLOOR_DB);
+    let peak_db = 20.0 * self.peak.max(LOUDNESS_EPSILON).log10();
+    if !integrated_db.is_finite() || !peak_db.is_finite() {
+      return None;
+    }
+    Some((integrated_db, peak_db))
+  }
+}
+
+/// 测试用单声道正弦信号
+#[cfg(test)]
+pub fn sine_wave(frequency: f64, amplitude: f32, sample_rate: f64, frames: usize) -> Vec<f32> {
+  (0..frames)
+    .map(|index| {
+      amplitude * (std::f64::consts::TAU * frequency * index as f64 / sample_rate).sin() as f32
+    })
+    .collect()
+}
+
+/// 分析结果的出错形态，供整轨 / PCM 两种分析入口共用
+pub trait AnalysisErrorResult {
+  fn error_result(file_path: Option<String>, error: String) -> Self;
+}
+
+/// 解码整轨，按块把交错 f32 PCM 交给分析器；打开、建分析器或读取失败时返回带路径的出错结果
+pub fn analyze_file_in_chunks<A, R: AnalysisErrorResult>(
+  file_path: String,
+  chunk_frames: usize,
+  create: impl FnOnce(u32, u16) -> Result<A, String>,
+  mut push: impl FnMut(&mut A, &[f32]),
+  finish: impl FnOnce(A, String, u32, u16) -> R,
+) -> R {
+  let mut stream = match AudioDecodeStream::open(Path::new(&file_path)) {
+    Ok(stream) => stream,
+    Err(error) => return R::error_result(Some(file_path), error),
+  };
+  let (sample_rate, channels) = (stream.sample_rate(), stream.channels());
+  let mut analyzer = match create(sample_rate, channels) {
+    Ok(analyzer) => analyzer,
+    Err(error) => return R::error_result(Some(file_path), error),
+  };
+  loop {
+    match stream.read_frames(chunk_frames) {
+      Ok(chunk) if chunk.is_empty() => break,
+      Ok(chunk) => push(&mut analyzer, &chunk),
+      Err(error) => return R::error_result(Some(file_path), error.into()),
+    }
+  }
+  finish(analyzer, file_path, sample_rate, channels)
+}
+
+/// 校验 napi 传入的交错 f32 PCM 与格式参数后交给 analyze
+pub fn analyze_pcm_buffer<R: AnalysisErrorResult>(
+  pcm_data: &Buffer,
+  sample_rate: u32,
+  channels: u8,
+  analyze: impl FnOnce(&[f32]) -> R,
+) -> R {
+  if !pcm_data.len().is_multiple_of(4) {
+    return R::error_result(None, "PCM buffer length is not aligned".to_string());
+  }
+  if sample_rate == 0 || channels == 0 {
+    return R::error_result(None, "sample_rate/channels must be positive".to_string());
+  }
+  analyze(&pcm_buffer_to_f32(pcm_data))
+}
@@ -0,0 +1,468 @@
+//! 技术审计：由解码 PCM 统计削波（连续满幅样本）、直流偏移、立体声相位相关与单声道兼容性，
+//! 并识别伪立体声（L==R）与极性反相
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::analysis_utils::{analyze_file_in_chunks, analyze_pcm_buffer, AnalysisErrorResult};
+
+/// 样本绝对值不低于该值视为满幅（覆盖 i16 的 32767/32768）
+const CLIP_THRESHOLD: f32 = 0.9999;
+/// 连续满幅样本达到该长度才计为一次削波（单个满幅样本可能是正常峰值）
+const MIN_CLIP_RUN: u32 = 3;
+/// 直流偏移超过该值（约 -54 dBFS）时标记
+const DC_OFFSET_THRESHOLD: f64 = 0.002;
+/// 侧信号能量低于中间信号该比例视为伪立体声
+const FAKE_STEREO_SIDE_RATIO: f64 = 1e-6;
+/// 相关系数低于该值视为一路极性反相
+const INVERTED_POLARITY_CORRELATION: f64 = -0.9;
+/// 单声道兼容性低于该值（折叠后能量损失超过 3 dB）时标记
+const POOR_MONO_COMPATIBILITY: f64 = 0.5;
+/// 低于该 RMS 的段落不参与相关性判断
+const SILENCE_RMS: f64 = 1e-4;
+/// 时间线原始分段长度与最大段数
+const SEGMENT_SEC: f64 = 1.0;
+const MAX_TIMELINE_SEGMENTS: usize = 64;
+const READ_CHUNK_FRAMES: usize = 65_536;
+
+/// 时间线分段
+#[napi(object)]
+pub struct AudioAuditSegment {
+  pub start_sec: f64,
+  pub end_sec: f64,
+  pub peak_dbfs: f64,
+  /// 段内削波样本数
+  pub clipped_samples: u32,
+  /// 段内相位相关（-1..1），单声道或静音段为空
+  pub correlation: Option<f64>,
+}
+
+/// 技术审计结果
+#[napi(object)]
+pub struct AudioAuditResult {
+  pub file_path: Option<String>,
+  pub sample_rate: u32,
+  pub channels: u32,
+  pub duration_sec: f64,
+  pub peak_dbfs: f64,
+  /// 削波次数（连续满幅样本段）
+  pub clip_run_count: u32,
+  /// 削波样本总数
+  pub clipped_samples: f64,
+  /// 最长削波段（样本数）
+  pub longest_clip_run: u32,
+  /// 各声道直流偏移（线性）
+  pub dc_offset: Vec<f64>,
+  /// 整轨相位相关（-1..1），单声道为空
+  pub phase_correlation: Option<f64>,
+  /// 单声道兼容性（0..1，折叠后与原立体声的能量比），单声道为空
+  pub mono_compatibility: Option<f64>,
+  /// 折叠为单声道后的能量损失（dB）
+  pub mono_loss_db: Option<f64>,
+  /// 左右声道完全相同
+  pub fake_stereo: bool,
+  /// 一路声道极性反相
+  pub inverted_polarity: bool,
+  /// clipping / dc-offset / fake-stereo / inverted-polarity / poor-mono-compatibility
+  pub flags: Vec<String>,
+  pub timeline: Vec<AudioAuditSegment>,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct StereoSums {
+  left: f64,
+  right: f64,
+  cross: f64,
+}
+
+impl StereoSums {
+  fn add(&mut self, other: &StereoSums) {
+    self.left += other.left;
+    self.right += other.right;
+    self.cross += other.cross;
+  }
+
+  fn correlation(&self, frames: u64) -> Option<f64> {
+    let frames = frames.max(1) as f64;
+    if (self.left / frames).sqrt() < SILENCE_RMS || (self.right / frames).sqrt() < SILENCE_RMS {
+      return None;
+    }
+    Some((self.cross / (self.left * self.right).sqrt()).clamp(-1.0, 1.0))
+  }
+
+  /// 中间信号 (L+R)/2 与侧信号 (L-R)/2 的能量
+  fn mid_side(&self) -> (f64, f64) {
+    (
+      (self.left + self.right + 2.0 * self.cross) / 4.0,
+      (self.left + self.right - 2.0 * self.cross) / 4.0,
+    )
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct SegmentStats {
+  frames: u64,
+  peak: f32,
+  clipped_samples: u32,
+  stereo: StereoSums,
+}
+
+#[derive(Debug)]
+pub(crate) struct AuditReport {
+  duration_sec: f64,
+  peak: f32,
+  clip_run_count: u32,
+  clipped_samples: u64,
+  longest_clip_run: u32,
+  dc_offset: Vec<f64>,
+  phase_correlation: Option<f64>,
+  mono_compatibility: Option<f64>,
+  fake_stereo: bool,
+  inverted_polarity: bool,
+  flags: Vec<&'static str>,
+  timeline: Vec<(f64, f64, SegmentStats)>,
+}
+
+/// 增量审计器：逐块喂入交错 f32 PCM
+pub(crate) struct AudioAuditor {
+  sample_rate: u32,
+  channels: usize,
+  segment_frames: u64,
+  frames: u64,
+  channel_sums: Vec<f64>,
+  clip_runs: Vec<u32>,
+  clip_run_count: u32,
+  clipped_samples: u64,
+  longest_clip_run: u32,
+  stereo: StereoSums,
+  current: SegmentStats,
+  segments: Vec<SegmentStats>,
+}
+
+fn to_dbfs(value: f64) -> f64 {
+  if value > 0.0 {
+    20.0 * value.log10()
+  } else {
+    f64::NEG_INFINITY
+  }
+}
+
+impl AudioAuditor {
+  pub(crate) fn new(sample_rate: u32, channels: u16) -> Self {
+    let channels = channels.max(1) as usize;
+    Self {
+      sample_rate,
+      channels,
+      segment_frames: ((sample_rate as f64 * SEGMENT_SEC) as u64).max(1),
+      frames: 0,
+      channel_sums: vec![0.0; channels],
+      clip_runs: vec![0; channels],
+      clip_run_count: 0,
+      clipped_samples: 0,
+      longest_clip_run: 0,
+      stereo: StereoSums::default(),
+      current: SegmentStats::default(),
+      segments: Vec::new(),
+    }
+  }
+
+  fn end_clip_run(&mut self, channel: usize) {
+    let run = std::mem::take(&mut self.clip_runs[channel]);
+    if run >= MIN_CLIP_RUN {
+      self.clip_run_count += 1;
+      self.clipped_samples += run as u64;
+      self.longest_clip_run = self.longest_clip_run.max(run);
+    }
+  }
+
+  pub(crate) fn push(&mut self, interleaved: &[f32]) {
+    for frame in interleaved.chunks_exact(self.channels) {
+      for (channel, sample) in frame.iter().enumerate() {
+        let magnitude = sample.abs();
+        self.channel_sums[channel] += *sample as f64;
+        self.current.peak = self.current.peak.max(magnitude);
+        if magnitude >= CLIP_THRESHOLD {
+          self.clip_runs[channel] += 1;
+          // 段内计数以达到最小长度为准，避免单个满幅峰值
+          match self.clip_runs[channel].cmp(&MIN_CLIP_RUN) {
+            std::cmp::Ordering::Less => {}
+            std::cmp::Ordering::Equal => self.current.clipped_samples += MIN_CLIP_RUN,
+            std::cmp::Ordering::Greater => self.current.clipped_samples += 1,
+          }
+        } else if self.clip_runs[channel] > 0 {
+          self.end_clip_run(channel);
+        }
+      }
+      if self.channels >= 2 {
+        let (left, right) = (frame[0] as f64, frame[1] as f64);
+        self.current.stereo.left += left * left;
+        self.current.stereo.right += right * right;
+        self.current.stereo.cross += left * right;
+      }
+      self.current.frames += 1;
+      self.frames += 1;
+      if self.current.frames >= self.segment_frames {
+        self.stereo.add(&self.current.stereo);
+        self.segments.push(std::mem::take(&mut self.current));
+      }
+    }
+  }
+
+  /// 把 1 秒分段合并为不超过 MAX_TIMELINE_SEGMENTS 段
+  fn merge_timeline(&self) -> Vec<(f64, f64, SegmentStats)> {
+    let count = self.segments.len();
+    if count == 0 {
+      return Vec::new();
+    }
+    let group = count.div_ceil(MAX_TIMELINE_SEGMENTS);
+    let mut start_frame = 0u64;
+    self
+      .segments
+      .chunks(group)
+      .map(|chunk| {
+        let mut merged = SegmentStats::default();
+        for segment in chunk {
+          merged.frames += segment.frames;
+          merged.peak = merged.peak.max(segment.peak);
+          merged.clipped_samples += segment.clipped_samples;
+          merged.stereo.add(&segment.stereo);
+        }
+        let start_sec = start_frame as f64 / self.sample_rate as f64;
+        start_frame += merged.frames;
+        (
+          start_sec,
+          start_frame as f64 / self.sample_rate as f64,
+          merged,
+        )
+      })
+      .collect()
+  }
+
+  pub(crate) fn finish(mut self) -> AuditReport {
+    for channel in 0..self.channels {
+      self.end_clip_run(channel);
+    }
+    if self.current.frames > 0 {
+      self.stereo.add(&self.current.stereo);
+      self.segments.push(std::mem::take(&mut self.current));
+    }
+
+    let frames = self.frames.max(1) as f64;
+    let dc_offset: Vec<f64> = self.channel_sums.iter().map(|sum| sum / frames).collect();
+    let stereo = self.channels >= 2 && self.frames > 0;
+    let (mid, side) = self.stereo.mid_side();
+    let stereo_energy = (self.stereo.left + self.stereo.right) / 2.0;
+    let audible = stereo && (stereo_energy / frames).sqrt() >= SILENCE_RMS;
+    let phase_correlation = if stereo {
+      self.stereo.correlation(self.frames)
+    } else {
+      None
+    };
+    let mono_compatibility = audible.then(|| (mid / stereo_energy).clamp(0.0, 1.0));
+    let fake_stereo = audible && side <= mid * FAKE_STEREO_SIDE_RATIO;
+    let inverted_polarity =
+      phase_correlation.is_some_and(|value| value <= INVERTED_POLARITY_CORRELATION);
+
+    let mut flags = Vec::new();
+    if self.clip_run_count > 0 {
+      flags.push("clipping");
+    }
+    if dc_offset
+      .iter()
+      .any(|offset| offset.abs() > DC_OFFSET_THRESHOLD)
+    {
+      flags.push("dc-offset");
+    }
+    if fake_stereo {
+      flags.push("fake-stereo");
+    }
+    if inverted_polarity {
+      flags.push("inverted-polarity");
+    }
+    if mono_compatibility.is_some_and(|value| value < POOR_MONO_COMPATIBILITY) {
+      flags.push("poor-mono-compatibility");
+    }
+
+    AuditReport {
+      duration_sec: self.frames as f64 / self.sample_rate.max(1) as f64,
+      peak: self
+        .segments
+        .iter()
+        .map(|segment| segment.peak)
+        .fold(0.0, f32::max),
+      clip_run_count: self.clip_run_count,
+      clipped_samples: self.clipped_samples,
+      longest_clip_run: self.longest_clip_run,
+      dc_offset,
+      phase_correlation,
+      mono_compatibility,
+      fake_stereo,
+      inverted_polarity,
+      flags,
+      timeline: self.merge_timeline(),
+    }
+  }
+}
+
+fn to_result(
+  file_path: Option<String>,
+  sample_rate: u32,
+  channels: u16,
+  report: AuditReport,
+) -> AudioAuditResult {
+  let stereo = channels >= 2;
+  AudioAuditResult {
+    file_path,
+    sample_rate,
+    channels: channels as u32,
+    duration_sec: report.duration_sec,
+    peak_dbfs: to_dbfs(report.peak as f64),
+    clip_run_count: report.clip_run_count,
+    clipped_samples: report.clipped_samples as f64,
+    longest_clip_run: report.longest_clip_run,
+    dc_offset: report.dc_offset,
+    phase_correlation: report.phase_correlation,
+    mono_compatibility: report.mono_compatibility,
+    mono_loss_db: report
+      .mono_compatibility
+      .map(|value| -10.0 * value.max(1e-12).log10()),
+    fake_stereo: report.fake_stereo,
+    inverted_polarity: report.inverted_polarity,
+    flags: report.flags.iter().map(|flag| flag.to_string()).collect(),
+    timeline: report
+      .timeline
+      .into_iter()
+      .map(|(start_sec, end_sec, segment)| AudioAuditSegment {
+        start_sec,
+        end_sec,
+        peak_dbfs: to_dbfs(segment.peak as f64),
+        clipped_samples: segment.clipped_samples,
+        correlation: if stereo {
+          segment.stereo.correlation(segment.frames)
+        } else {
+          None
+        },
+      })
+      .collect(),
+    error: None,
+  }
+}
+
+impl AnalysisErrorResult for AudioAuditResult {
+  fn error_result(file_path: Option<String>, error: String) -> Self {
+    AudioAuditResult {
+      file_path,
+      sample_rate: 0,
+      channels: 0,
+      duration_sec: 0.0,
+      peak_dbfs: f64::NEG_INFINITY,
+      clip_run_count: 0,
+      clipped_samples: 0.0,
+      longest_clip_run: 0,
+      dc_offset: Vec::new(),
+      phase_correlation: None,
+      mono_compatibility: None,
+      mono_loss_db: None,
+      fake_stereo: false,
+      inverted_polarity: false,
+      flags: Vec::new(),
+      timeline: Vec::new(),
+      error: Some(error),
+    }
+  }
+}
+
+/// 解码整轨做技术审计（削波 / 直流偏移 / 相位相关 / 单声道兼容性）
+#[napi]
+pub fn analyze_audio_audit(file_path: String) -> AudioAuditResult {
+  analyze_file_in_chunks(
+    file_path,
+    READ_CHUNK_FRAMES,
+    |sample_rate, channels| Ok(AudioAuditor::new(sample_rate, channels)),
+    AudioAuditor::push,
+    |auditor, file_path, sample_rate, channels| {
+      to_result(Some(file_path), sample_rate, channels, auditor.finish())
+    },
+  )
+}
+
+/// 对已解码的交错 f32 PCM 做技术审计
+#[napi]
+pub fn analyze_audio_audit_from_pcm(
+  pcm_data: Buffer,
+  sample_rate: u32,
+  channels: u8,
+) -> AudioAuditResult {
+  analyze_pcm_buffer(&pcm_data, sample_rate, channels, |samples| {
+    let mut auditor = AudioAuditor::new(sample_rate, channels as u16);
+    auditor.push(samples);
+    to_result(None, sample_rate, channels as u16, auditor.finish())
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn stereo_tone(seconds: f64, right: impl Fn(f32) -> f32) -> Vec<f32> {
+    let frames = (44_100.0 * seconds) as usize;
+    (0..frames)
+      .flat_map(|frame| {
+        let left = 0.5 * (std::f32::consts::TAU * 440.0 * frame as f32 / 44_100.0).sin();
+        [left, right(left)]
+      })
+      .collect()
+  }
+
+  #[test]
+  fn detects_clip_runs_and_dc_offset() {
+    let mut pcm = stereo_tone(2.0, |left| left * 0.8 + 0.01);
+    // 左声道一段 5 个样本满幅，一个孤立满幅样本不计
+    for frame in 1000..1005 {
+      pcm[frame * 2] = 1.0;
+    }
+    pcm[4000 * 2] = -1.0;
+    let mut auditor = AudioAuditor::new(44_100, 2);
+    auditor.push(&pcm);
+    let report = auditor.finish();
+
+    assert_eq!(report.clip_run_count, 1);
+    assert_eq!(report.clipped_samples, 5);
+    assert_eq!(report.longest_clip_run, 5);
+    assert!(report.dc_offset[0].abs() < DC_OFFSET_THRESHOLD);
+    assert!((report.dc_offset[1] - 0.01).abs() < 1e-3);
+    assert_eq!(report.flags, vec!["clipping", "dc-offset"]);
+    assert_eq!(report.timeline.len(), 2);
+    assert_eq!(report.timeline[0].2.clipped_samples, 5);
+    assert!(report.phase_correlation.unwrap() > 0.9);
+  }
+
+  #[test]
+  fn flags_fake_stereo_and_inverted_polarity() {
+    let mut auditor = AudioAuditor::new(44_100, 2);
+    auditor.push(&stereo_tone(1.0, |left| left));
+    let report = auditor.finish();
+    assert!(report.fake_stereo);
+    assert!(!report.inverted_polarity);
+    assert!((report.mono_compatibility.unwrap() - 1.0).abs() < 1e-9);
+
+    let mut auditor = AudioAuditor::new(44_100, 2);
+    auditor.push(&stereo_tone(1.0, |left| -left));
+    let report = auditor.finish();
+    assert!(report.inverted_polarity);
+    assert!(!report.fake_stereo);
+    assert!(report.mono_compatibility.unwrap() < 1e-9);
+    assert_eq!(
+      report.flags,
+      vec!["inverted-polarity", "poor-mono-compatibility"]
+    );
+
+    let mut auditor = AudioAuditor::new(44_100, 1);
+    auditor.push(&vec![0.25; 44_100 * 3]);
+    let report = auditor.finish();
+    assert!(report.phase_correlation.is_none());
+    assert!(report.mono_compatibility.is_none());
+    assert_eq!(report.flags, vec!["dc-offset"]);
+  }
+}
@@ -0,0 +1,534 @@
+//! 流式分块解码：open / seek / read N 帧 / close
+//! 避免整轨一次性解码为单个 f32 Buffer（2 小时 48k 立体声超过 1GB）
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use bytemuck::cast_slice;
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use parking_lot::Mutex;
+use symphonia::core::audio::{SampleBuffer, SignalSpec};
+use symphonia::core::codecs::{Decoder, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
+use symphonia::default::get_probe;
+
+use crate::decode_error::{join_decode_errors, DecodeBackend, DecodeError, DecodeErrorKind};
+use crate::ffmpeg_decode::FfmpegDecodeStream;
+use crate::gapless_info::{read_gapless_info, GaplessInfo, GaplessSource};
+
+const MAX_READ_FRAMES: u32 = 48_000 * 60;
+/// 容器未给出单包最大帧数时的转换缓冲初始容量
+const DEFAULT_PACKET_FRAMES: u64 = 8_192;
+
+/// 流式解码句柄信息
+#[napi(object)]
+pub struct AudioDecodeStreamInfo {
+  /// 句柄 ID，后续 seek/read/close 使用
+  pub handle: u32,
+  pub sample_rate: u32,
+  pub channels: u32,
+  /// 总帧数（容器未提供时为空）
+  pub total_frames: Option<f64>,
+  pub duration_sec: Option<f64>,
+  /// 解码后端（symphonia / ffmpeg / ffmpeg-fallback）
+  pub decoder_backend: String,
+  /// 已裁掉的编码器前导（priming）时长（毫秒）
+  pub priming_offset_ms: Option<f64>,
+}
+
+/// 单次读取结果
+#[napi(object)]
+pub struct AudioDecodeStreamChunk {
+  /// PCM 数据（Buffer，交错 f32 小端序）
+  pub pcm_data: Buffer,
+  pub frames: u32,
+  /// 本块第一帧的时间位置（秒）
+  pub start_sec: f64,
+  pub end_of_stream: bool,
+}
+
+struct SymphoniaStreamBackend {
+  format: Box<dyn FormatReader>,
+  decoder: Box<dyn Decoder>,
+  track_id: u32,
+  /// seek 后到达精确位置前需丢弃的帧数
+  skip_frames: u64,
+  /// 需手动裁掉的编码器前导帧（MP4 iTunSMPB；MP3 由 Symphonia 自行裁剪）
+  lead_frames: u64,
+  /// 整个流复用的交错转换缓冲，按单包最大帧数分配
+  sample_buf: SampleBuffer<f32>,
+}
+
+enum StreamBackend {
+  Symphonia(SymphoniaStreamBackend),
+  /// 整轨共用一个 libav 上下文，按帧增量解码
+  Ffmpeg(FfmpegDecodeStream),
+}
+
+pub(crate) struct AudioDecodeStream {
+  backend: StreamBackend,
+  backend_name: &'static str,
+  sample_rate: u32,
+  channels: u16,
+  total_frames: Option<u64>,
+  gapless: Option<GaplessInfo>,
+  /// gapless 有效帧数，超出部分为尾部补零
+  end_frame: Option<u64>,
+  /// 已解码但尚未读出的交错样本
+  pending: Vec<f32>,
+  pending_offset: usize,
+  position_frame: u64,
+  end_of_stream: bool,
+}
+
+impl AudioDecodeStream {
+  /// 与 decode_audio_file 相同的后端选择：FFmpeg 专属扩展名直接走 FFmpeg，其他优先 Symphonia；
+  /// 两个后端都失败时按 join_decode_errors 合并两条错误
+  pub(crate) fn open(path: &Path) -> StdResult<Self, String> {
+    if crate::is_ffmpeg_only_extension(path) {
+      return Ok(Self::open_ffmpeg(path, "ffmpeg")?);
+    }
+    match Self::open_symphonia(path) {
+      Ok(stream) => Ok(stream),
+      Err(symphonia_err) => Self::open_ffmpeg(path, "ffmpeg-fallback")
+        .map_err(|ffmpeg_err| join_decode_errors(&[symphonia_err, ffmpeg_err])),
+    }
+  }
+
+  pub(crate) fn open_symphonia(path: &Path) -> StdResult<Self, DecodeError> {
+    let file =
+      File::open(path).map_err(|error| DecodeError::from_io(DecodeBackend::Symphonia, &error))?;
+    let media_stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|os| os.to_str()) {
+      hint.with_extension(ext);
+    }
+    let probed = get_probe()
+      .format(
+        &hint,
+        media_stream,
+        &FormatOptions {
+          enable_gapless: true,
+          ..Default::default()
+        },
+        &MetadataOptions::default(),
+      )
+      .map_err(|e| DecodeError::from_symphonia(&e).context("探测音频格式失败"))?;
+    let format = probed.format;
+    let unsupported = |detail: &str| {
+      DecodeError::new(
+        DecodeErrorKind::UnsupportedCodec,
+        DecodeBackend::Symphonia,
+        detail,
+      )
+    };
+    let track = format
+      .tracks()
+      .iter()
+      .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+      .ok_or_else(|| unsupported("未找到可解码音轨"))?;
+    let codec_params = track.codec_params.clone();
+    let track_id = track.id;
+    let channel_layout = codec_params
+      .channels
+      .ok_or_else(|| unsupported("缺少声道信息"))?;
+    let channels = channel_layout.count() as u16;
+    let sample_rate = codec_params
+      .sample_rate
+      .ok_or_else(|| unsupported("缺少采样率信息"))?;
+    let decoder = crate::build_decoder(&codec_params).map_err(crate::symphonia_setup_error)?;
+    let sample_buf = SampleBuffer::new(
+      codec_params
+        .max_frames_per_packet
+        .unwrap_or(DEFAULT_PACKET_FRAMES)
+        .max(1),
+      SignalSpec::new(sample_rate, channel_layout),
+    );
+    let gapless = read_gapless_info(path);
+    let lead_frames = match gapless {
+      Some(info) if info.source != GaplessSource::Lame => info.delay_frames,
+      _ => 0,
+    };
+    let end_frame = gapless.and_then(|info| info.valid_frames_at(sample_rate));
+    Ok(Self {
+      backend: StreamBackend::Symphonia(SymphoniaStreamBackend {
+        format,
+        decoder,
+        track_id,
+        skip_frames: lead_frames,
+        lead_frames,
+        sample_buf,
+      }),
+      backend_name: "symphonia",
+      sample_rate,
+      channels,
+      total_frames: end_frame.or(codec_params.n_frames),
+      gapless,
+      end_frame,
+      pending: Vec::new(),
+      pending_offset: 0,
+      position_frame: 0,
+      end_of_stream: false,
+    })
+  }
+
+  pub(crate) fn open_ffmpeg(
+    path: &Path,
+    backend_name: &'static str,
+  ) -> StdResult<Self, DecodeError> {
+    let decoder = FfmpegDecodeStream::open(path, None)?;
+    let sample_rate = decoder.sample_rate();
+    let channels = decoder.channels();
+    let gapless = read_gapless_info(path);
+    // FFmpeg 已跳过前导帧，这里只截掉尾部补零
+    let end_frame = gapless.and_then(|info| info.valid_frames_at(sample_rate));
+    Ok(Self {
+      backend: StreamBackend::Ffmpeg(decoder),
+      backend_name,
+      sample_rate,
+      channels,
+      total_frames: end_frame,
+      gapless,
+      end_frame,
+      pending: Vec::new(),
+      pending_offset: 0,
+      position_frame: 0,
+      end_of_stream: false,
+    })
+  }
+
+  pub(crate) fn backend_name(&self) -> &'static str {
+    self.backend_name
+  }
+
+  pub(crate) fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  pub(crate) fn channels(&self) -> u16 {
+    self.channels
+  }
+
+  pub(crate) fn total_frames(&self) -> Option<u64> {
+    self.total_frames
+  }
+
+  pub(crate) fn priming_offset_ms(&self) -> Option<f64> {
+    self
+      .gapless
+      .and_then(|info| info.priming_offset_ms(self.sample_rate))
+  }
+
+  pub(crate) fn position_sec(&self) -> f64 {
+    self.position_frame as f64 / self.sample_rate.max(1) as f64
+  }
+
+  fn pending_frames(&self) -> usize {
+    (self.pending.len() - self.pending_offset) / self.channels.max(1) as usize
+  }
+
+  /// 解码下一段追加到 pending；返回 false 表示已到结尾。
+  /// 流中途采样率或声道数变化时报错，不做重配置
+  fn fill_pending(&mut self) -> StdResult<bool, DecodeError> {
+    if self.pending_offset > 0 {
+      self.pending.drain(..self.pending_offset);
+      self.pending_offset = 0;
+    }
+    match &mut self.backend {
+      StreamBackend::Symphonia(backend) => loop {
+        let packet = match backend.format.next_packet() {
+          Ok(packet) => packet,
+          Err(SymphoniaError::IoError(error)) if error.kind() == ErrorKind::UnexpectedEof => {
+            return Ok(false)
+          }
+          Err(e) => return Err(DecodeError::from_symphonia(&e).context("读取音频包失败")),
+        };
+        if packet.track_id() != backend.track_id {
+          continue;
+        }
+        let audio_buf = match backend.decoder.decode(&packet) {
+          Ok(audio_buf) => audio_buf,
+          Err(SymphoniaError::IoError(_)) | Err(SymphoniaError::DecodeError(_)) => continue,
+          Err(e) => return Err(DecodeError::from_symphonia(&e).context("解码错误")),
+        };
+        let spec = *audio_buf.spec();
+        if spec.rate != self.sample_rate || spec.channels.count() != self.channels as usize {
+          return Err(DecodeError::new(
+            DecodeErrorKind::UnsupportedCodec,
+            DecodeBackend::Symphonia,
+            format!(
+              "流中途格式变化: {} Hz / {} 声道 → {} Hz / {} 声道",
+              self.sample_rate,
+              self.channels,
+              spec.rate,
+              spec.channels.count()
+            ),
+          ));
+        }
+        let frame_count = audio_buf.frames() as u64;
+        if frame_count == 0 {
+          continue;
+        }
+        if backend.sample_buf.capacity() < frame_count as usize * self.channels as usize {
+          backend.sample_buf = SampleBuffer::new(frame_count, spec);
+        }
+        backend.sample_buf.copy_interleaved_ref(audio_buf);
+        let skip = backend.skip_frames.min(frame_count);
+        backend.skip_frames -= skip;
+        let skip_samples = skip as usize * self.channels as usize;
+        self
+          .pending
+          .extend_from_slice(&backend.sample_buf.samples()[skip_samples..]);
+        if skip < frame_count {
+          return Ok(true);
+        }
+      },
+      StreamBackend::Ffmpeg(decoder) => Ok(decoder.next_into(&mut self.pending)?),
+    }
+  }
+
+  /// 读取至多 frame_count 帧交错 PCM；返回空表示已到结尾
+  pub(crate) fn read_frames(&mut self, frame_count: usize) -> StdResult<Vec<f32>, DecodeError> {
+    while !self.end_of_stream && self.pending_frames() < frame_count {
+      if !self.fill_pending()? {
+        self.end_of_stream = true;
+      }
+    }
+    let mut frames = self.pending_frames().min(frame_count);
+    if let Some(end_frame) = self.end_frame {
+      frames = frames.min(end_frame.saturating_sub(self.position_frame) as usize);
+      if self.position_frame + frames as u64 >= end_frame {
+        self.end_of_stream = true;
+        self
+          .pending
+          .truncate(self.pending_offset + frames * self.channels as usize);
+      }
+    }
+    let sample_count = frames * self.channels as usize;
+    let samples = self.pending[self.pending_offset..self.pending_offset + sample_count].to_vec();
+    self.pending_offset += sample_count;
+    self.position_frame += frames as u64;
+    Ok(samples)
+  }
+
+  pub(crate) fn is_finished(&self) -> bool {
+    self.end_of_stream && self.pending_frames() == 0
+  }
+
+  /// 帧精确 seek；返回实际位置（秒）
+  pub(crate) fn seek(&mut self, sec: f64) -> StdResult<f64, DecodeError> {
+    let target_sec = if sec.is_finite() { sec.max(0.0) } else { 0.0 };
+    let mut target_frame = (target_sec * self.sample_rate as f64).round() as u64;
+    if let Some(total_frames) = self.total_frames {
+      target_frame = target_frame.min(total_frames);
+    }
+    self.pending.clear();
+    self.pending_offset = 0;
+    self.end_of_stream = false;
+    match &mut self.backend {
+      StreamBackend::Symphonia(backend) => {
+        let seeked = backend
+          .format
+          .seek(
+            SeekMode::Accurate,
+            SeekTo::Time {
+              time: Time::from(
+                (target_frame + backend.lead_frames) as f64 / self.sample_rate.max(1) as f64,
+              ),
+              track_id: Some(backend.track_id),
+            },
+          )
+          .map_err(|e| {
+            DecodeError::from_symphonia(&e)
+              .context("seek 失败")
+              .at(Some(target_sec))
+          })?;
+        backend.decoder.reset();
+        let time_base = backend
+          .format
+          .tracks()
+          .iter()
+          .find(|track| track.id == backend.track_id)
+          .and_then(|track| track.codec_params.time_base);
+        let actual_frame = match time_base {
+          Some(time_base) => {
+            let time = time_base.calc_time(seeked.actual_ts);
+            ((time.seconds as f64 + time.frac) * self.sample_rate as f64).round() as u64
+          }
+          None => seeked.actual_ts,
+        };
+        backend.skip_frames = (target_frame + backend.lead_frames).saturating_sub(actual_frame);
+      }
+      StreamBackend::Ffmpeg(decoder) => {
+        decoder.seek(target_frame as f64 / self.sample_rate.max(1) as f64)?;
+      }
+    }
+    self.position_frame = target_frame;
+    Ok(self.position_sec())
+  }
+}
+
+fn decode_streams() -> &'static Mutex<HashMap<u32, Arc<Mutex<AudioDecodeStream>>>> {
+  static DECODE_STREAMS: OnceLock<Mutex<HashMap<u32, Arc<Mutex<AudioDecodeStream>>>>> =
+    OnceLock::new();
+  DECODE_STREAMS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn next_stream_handle() -> u32 {
+  static NEXT_STREAM_HANDLE: AtomicU32 = AtomicU32::new(1);
+  NEXT_STREAM_HANDLE.fetch_add(1, Ordering::Relaxed)
+}
+
+fn stream_by_handle(handle: u32) -> napi::Result<Arc<Mutex<AudioDecodeStream>>> {
+  decode_streams()
+    .lock()
+    .get(&handle)
+    .cloned()
+    .ok_or_else(|| napi::Error::from_reason(format!("无效的解码句柄: {}", handle)))
+}
+
+/// 打开流式解码句柄
+#[napi]
+pub fn open_audio_decode_stream(file_path: String) -> napi::Result<AudioDecodeStreamInfo> {
+  let stream = AudioDecodeStream::open(Path::new(&file_path)).map_err(napi::Error::from_reason)?;
+  let handle = next_stream_handle();
+  let sample_rate = stream.sample_rate();
+  let info = AudioDecodeStreamInfo {
+    handle,
+    sample_rate,
+    channels: stream.channels() as u32,
+    total_frames: stream.total_frames().map(|frames| frames as f64),
+    duration_sec: stream
+      .total_frames()
+      .map(|frames| frames as f64 / sample_rate.max(1) as f64),
+    decoder_backend: stream.backend_name().to_string(),
+    priming_offset_ms: stream.priming_offset_ms(),
+  };
+  decode_streams()
+    .lock()
+    .insert(handle, Arc::new(Mutex::new(stream)));
+  Ok(info)
+}
+
+/// 跳转到指定秒数，返回实际位置
+#[napi]
+pub fn seek_audio_decode_stream(handle: u32, sec: f64) -> napi::Result<f64> {
+  let stream = stream_by_handle(handle)?;
+  let mut stream = stream.lock();
+  stream.seek(sec).map_err(napi::Error::from_reason)
+}
+
+/// 读取至多 frame_count 帧
+#[napi]
+pub fn read_audio_decode_stream(
+  handle: u32,
+  frame_count: u32,
+) -> napi::Result<AudioDecodeStreamChunk> {
+  let stream = stream_by_handle(handle)?;
+  let mut stream = stream.lock();
+  let start_sec = stream.position_sec();
+  let samples = stream
+    .read_frames(frame_count.min(MAX_READ_FRAMES) as usize)
+    .map_err(napi::Error::from_reason)?;
+  let frames = samples.len() / stream.channels().max(1) as usize;
+  Ok(AudioDecodeStreamChunk {
+    pcm_data: Buffer::from(cast_slice(&samples).to_vec()),
+    frames: frames as u32,
+    start_sec,
+    end_of_stream: stream.is_finished(),
+  })
+}
+
+/// 关闭句柄并释放解码器
+#[napi]
+pub fn close_audio_decode_stream(handle: u32) -> bool {
+  decode_streams().lock().remove(&handle).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn write_test_wav(path: &Path, sample_rate: u32, frames: u32) {
+    let channels = 2u16;
+    let data_len = frames * channels as u32 * 2;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+    bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for frame in 0..frames {
+      // 左声道为帧号的递增斜坡，便于校验位置
+      let left = (frame % 32_768) as i16;
+      bytes.extend_from_slice(&left.to_le_bytes());
+      bytes.extend_from_slice(&(-left).to_le_bytes());
+    }
+    std::fs::write(path, bytes).expect("write wav");
+  }
+
+  fn frame_value(frame: u32) -> f32 {
+    (frame % 32_768) as f32 / 32_768.0
+  }
+
+  #[test]
+  fn symphonia_stream_reads_in_chunks_and_seeks_frame_accurately() {
+    let path = std::env::temp_dir().join(format!("frkb-stream-{}.wav", std::process::id()));
+    write_test_wav(&path, 8_000, 20_000);
+
+    let mut stream = AudioDecodeStream::open(&path).expect("open stream");
+    assert_eq!(stream.backend_name(), "symphonia");
+    assert_eq!(stream.sample_rate(), 8_000);
+    assert_eq!(stream.channels(), 2);
+    assert_eq!(stream.total_frames(), Some(20_000));
+
+    let mut total = 0usize;
+    loop {
+      let chunk = stream.read_frames(3_001).expect("read chunk");
+      if chunk.is_empty() {
+        break;
+      }
+      assert_eq!(chunk[0], frame_value(total as u32));
+      total += chunk.len() / 2;
+    }
+    assert_eq!(total, 20_000);
+    assert!(stream.is_finished());
+
+    let actual_sec = stream.seek(1.5).expect("seek");
+    assert!((actual_sec - 1.5).abs() < 1e-9);
+    let chunk = stream.read_frames(4).expect("read after seek");
+    assert_eq!(chunk[0], frame_value(12_000));
+    assert_eq!(chunk[1], -frame_value(12_000));
+    assert!((stream.position_sec() - 12_004.0 / 8_000.0).abs() < 1e-9);
+
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn symphonia_open_reports_typed_decode_error() {
+    let path = std::env::temp_dir().join(format!("frkb-stream-missing-{}.wav", std::process::id()));
+    let error = AudioDecodeStream::open_symphonia(&path)
+      .err()
+      .expect("missing file");
+    assert_eq!(error.kind, DecodeErrorKind::NotFound);
+    assert_eq!(error.backend, DecodeBackend::Symphonia);
+  }
+}
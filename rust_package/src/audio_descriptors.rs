@@ -0,0 +1,686 @@
+//! 选曲描述符：能量等级（1-10）、可舞性、打击感、明亮度（频谱质心）与人声存在概率；
+//! 频段包络复用 Mixxx 波形的 Bessel 三分频，和声信息来自 QM 色度，结果带算法版本号供缓存失效
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::analysis_utils::{
+  analyze_file_in_chunks, analyze_pcm_buffer, hann_window, power_spectrum, to_stereo,
+  AnalysisErrorResult,
+};
+use crate::mixxx_waveform::MixxxBandSplitter;
+use crate::qm_key::ChromaAccumulator;
+
+/// 算法或参数变化时递增，调用方据此失效缓存
+pub const AUDIO_DESCRIPTORS_VERSION: u32 = 1;
+
+/// 包络块长（10 ms，100 块/秒）
+const BLOCK_SEC: f64 = 0.01;
+const FFT_SIZE: usize = 2048;
+const FLOOR_DB: f64 = -80.0;
+/// 低于该电平的块视为静音，不参与统计
+const ACTIVE_BLOCK_DB: f64 = -60.0;
+/// 单块 flux 超过该值且为局部峰值时记为一次起音
+const ONSET_FLUX_DB: f64 = 3.0;
+const ONSET_PEAK_RADIUS_BLOCKS: usize = 3;
+/// 频段能量先做约 50 ms 平滑，压掉低频拍频造成的块间抖动
+const ENVELOPE_SMOOTH_RADIUS_BLOCKS: usize = 2;
+/// 小于该值的电平上升视为包络纹波，不计入 flux
+const FLUX_DEADBAND_DB: f64 = 0.5;
+/// 可舞性只在 90–180 BPM 的周期上找节拍自相关
+const DANCE_MIN_BPM: f64 = 90.0;
+const DANCE_MAX_BPM: f64 = 180.0;
+/// 归一化尺度
+const LOUDNESS_RANGE_DB: (f64, f64) = (-30.0, -8.0);
+const PERCUSSIVE_FLUX_DB: f64 = 1.0;
+const MAX_ONSET_RATE: f64 = 6.0;
+const BRIGHTNESS_RANGE_HZ: (f64, f64) = (200.0, 8_000.0);
+const SYLLABIC_MODULATION_DB: f64 = 3.0;
+/// 人声频段（用于谐波度）
+const VOCAL_BAND_HZ: (f64, f64) = (300.0, 3_400.0);
+/// 无网格时能量曲线的窗长
+const ENERGY_CURVE_FALLBACK_SEC: f64 = 4.0;
+const DEFAULT_BEATS_PER_BAR: u32 = 4;
+const READ_CHUNK_FRAMES: usize = 65_536;
+
+/// 描述符选项
+#[napi(object)]
+#[derive(Default)]
+pub struct AudioDescriptorOptions {
+  /// 是否输出能量曲线
+  pub energy_curve: Option<bool>,
+  /// 节拍网格；提供时能量曲线按小节，否则按 4 秒窗
+  pub bpm: Option<f64>,
+  pub first_beat_ms: Option<f64>,
+  pub beats_per_bar: Option<u32>,
+}
+
+/// 能量曲线点
+#[napi(object)]
+pub struct AudioEnergyPoint {
+  pub start_sec: f64,
+  pub end_sec: f64,
+  /// 1..10（连续值）
+  pub energy: f64,
+}
+
+/// 描述符结果
+#[napi(object)]
+pub struct AudioDescriptorsResult {
+  pub file_path: Option<String>,
+  /// 算法版本，同 audioDescriptorsVersion()
+  pub version: u32,
+  /// 能量等级 1..10
+  pub energy_level: u32,
+  /// 能量得分 0..1
+  pub energy_score: f64,
+  /// 0..1
+  pub danceability: f64,
+  /// 0..1
+  pub percussiveness: f64,
+  /// 0..1（频谱质心的对数映射）
+  pub brightness: f64,
+  pub spectral_centroid_hz: f64,
+  /// 人声存在概率 0..1（启发式）
+  pub vocal_presence: f64,
+  /// 非静音部分的平均电平（dBFS）
+  pub loudness_dbfs: f64,
+  /// 每秒起音数
+  pub onset_rate: f64,
+  /// QM 平均色度（12 维，0 = C，最大值归一为 1）；采样率过低时为空
+  pub chroma: Vec<f64>,
+  pub energy_curve: Option<Vec<AudioEnergyPoint>>,
+  pub error: Option<String>,
+}
+
+/// 每块 [全频, 低, 中, 高] 的均方值
+type BlockEnergy = [f64; 4];
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct DescriptorReport {
+  energy_level: u32,
+  energy_score: f64,
+  danceability: f64,
+  percussiveness: f64,
+  brightness: f64,
+  spectral_centroid_hz: f64,
+  vocal_presence: f64,
+  loudness_dbfs: f64,
+  onset_rate: f64,
+  chroma: Vec<f64>,
+  energy_curve: Option<Vec<(f64, f64, f64)>>,
+}
+
+/// 增量分析器：逐块喂入交错 f32 PCM
+pub(crate) struct DescriptorAnalyzer {
+  sample_rate: u32,
+  channels: usize,
+  splitter: MixxxBandSplitter,
+  chroma: Option<ChromaAccumulator>,
+  block_frames: usize,
+  block: BlockEnergy,
+  block_filled: usize,
+  blocks: Vec<BlockEnergy>,
+  window: Vec<f64>,
+  pending: Vec<f64>,
+  centroid_sum: f64,
+  tonal_sum: f64,
+  spectral_weight: f64,
+}
+
+fn to_db(mean_square: f64) -> f64 {
+  if mean_square > 0.0 {
+    (10.0 * mean_square.log10()).max(FLOOR_DB)
+  } else {
+    FLOOR_DB
+  }
+}
+
+fn normalize(value: f64, range: (f64, f64)) -> f64 {
+  ((value - range.0) / (range.1 - range.0)).clamp(0.0, 1.0)
+}
+
+fn moving_average(values: &[f64], radius: usize) -> Vec<f64> {
+  let mut prefix = Vec::with_capacity(values.len() + 1);
+  prefix.push(0.0);
+  for value in values {
+    prefix.push(prefix.last().copied().unwrap_or(0.0) + value);
+  }
+  (0..values.len())
+    .map(|index| {
+      let start = index.saturating_sub(radius);
+      let end = (index + radius + 1).min(values.len());
+      (prefix[end] - prefix[start]) / (end - start) as f64
+    })
+    .collect()
+}
+
+/// 能量得分：响度、起音密度、打击感与明亮度的加权
+fn energy_score(loudness_db: f64, onset_rate: f64, percussiveness: f64, brightness: f64) -> f64 {
+  (0.4 * normalize(loudness_db, LOUDNESS_RANGE_DB)
+    + 0.25 * (onset_rate / MAX_ONSET_RATE).clamp(0.0, 1.0)
+    + 0.2 * percussiveness
+    + 0.15 * brightness)
+    .clamp(0.0, 1.0)
+}
+
+impl DescriptorAnalyzer {
+  pub(crate) fn new(sample_rate: u32, channels: u16) -> Result<Self, String> {
+    let splitter = MixxxBandSplitter::new(sample_rate as f64).map_err(|error| error.reason)?;
+    Ok(Self {
+      sample_rate,
+      channels: channels.max(1) as usize,
+      splitter,
+      // 采样率过低时 QM 前端无法构建，色度留空即可
+      chroma: ChromaAccumulator::new(sample_rate).ok(),
+      block_frames: ((sample_rate as f64 * BLOCK_SEC).round() as usize).max(1),
+      block: [0.0; 4],
+      block_filled: 0,
+      blocks: Vec::new(),
+      window: hann_window(FFT_SIZE),
+      pending: Vec::with_capacity(FFT_SIZE),
+      centroid_sum: 0.0,
+      tonal_sum: 0.0,
+      spectral_weight: 0.0,
+    })
+  }
+
+  pub(crate) fn push(&mut self, interleaved: &[f32]) {
+    let channels = self.channels;
+    let frames = interleaved.len() / channels;
+    if let Some(chroma) = self.chroma.as_mut() {
+      let stereo = to_stereo(&interleaved[..frames * channels], channels, frames);
+      if chroma.process(&stereo, frames).is_err() {
+        self.chroma = None;
+      }
+    }
+    for frame in interleaved.chunks_exact(channels) {
+      let mono = frame.iter().map(|sample| *sample as f64).sum::<f64>() / channels as f64;
+      let [low, mid, high] = self.splitter.process(mono);
+      self.block[0] += mono * mono;
+      self.block[1] += low * low;
+      self.block[2] += mid * mid;
+      self.block[3] += high * high;
+      self.block_filled += 1;
+      if self.block_filled == self.block_frames {
+        let filled = self.block_filled as f64;
+        self.blocks.push(self.block.map(|sum| sum / filled));
+        self.block = [0.0; 4];
+        self.block_filled = 0;
+      }
+      self.pending.push(mono);
+      if self.pending.len() == FFT_SIZE {
+        self.analyze_spectrum();
+        self.pending.clear();
+      }
+    }
+  }
+
+  /// 频谱质心与人声频段谐波度（1 - 频谱平坦度），按帧能量加权
+  fn analyze_spectrum(&mut self) {
+    let power = power_spectrum(&self.pending, &self.window);
+    let total: f64 = power.iter().sum();
+    if to_db(total / (FFT_SIZE * FFT_SIZE) as f64) < ACTIVE_BLOCK_DB {
+      return;
+    }
+    let bin_hz = self.sample_rate as f64 / FFT_SIZE as f64;
+    let centroid = power
+      .iter()
+      .enumerate()
+      .map(|(bin, value)| bin as f64 * bin_hz * value)
+      .sum::<f64>()
+      / total;
+    let first = (VOCAL_BAND_HZ.0 / bin_hz) as usize;
+    let last = ((VOCAL_BAND_HZ.1 / bin_hz) as usize).min(power.len() - 1);
+    let band = &power[first..=last];
+    let arithmetic = band.iter().sum::<f64>() / band.len() as f64;
+    let geometric =
+      (band.iter().map(|value| value.max(1e-20).ln()).sum::<f64>() / band.len() as f64).exp();
+    let flatness = if arithmetic > 0.0 {
+      (geometric / arithmetic).clamp(0.0, 1.0)
+    } else {
+      1.0
+    };
+    self.centroid_sum += centroid * total;
+    self.tonal_sum += (1.0 - flatness) * total;
+    self.spectral_weight += total;
+  }
+
+  fn block_sec(&self) -> f64 {
+    self.block_frames as f64 / self.sample_rate as f64
+  }
+
+  pub(crate) fn finish(mut self, options: &AudioDescriptorOptions) -> DescriptorReport {
+    let block_sec = self.block_sec();
+    let blocks = std::mem::take(&mut self.blocks);
+    let smoothed: Vec<Vec<f64>> = (0..4)
+      .map(|band| {
+        let values: Vec<f64> = blocks.iter().map(|block| block[band]).collect();
+        moving_average(&values, ENVELOPE_SMOOTH_RADIUS_BLOCKS)
+      })
+      .collect();
+    let band_db: Vec<[f64; 4]> = (0..blocks.len())
+      .map(|index| std::array::from_fn(|band| to_db(smoothed[band][index])))
+      .collect();
+    let active: Vec<bool> = band_db.iter().map(|db| db[0] >= ACTIVE_BLOCK_DB).collect();
+    let active_count = active.iter().filter(|value| **value).count();
+
+    // 起音包络：三个频段正向电平变化的平均
+    let flux: Vec<f64> = (0..band_db.len())
+      .map(|index| {
+        if index == 0 {
+          return 0.0;
+        }
+        (1..4)
+          .map(|band| (band_db[index][band] - band_db[index - 1][band] - FLUX_DEADBAND_DB).max(0.0))
+          .sum::<f64>()
+          / 3.0
+      })
+      .collect();
+    let onsets: Vec<usize> = (0..flux.len())
+      .filter(|index| {
+        let lo = index.saturating_sub(ONSET_PEAK_RADIUS_BLOCKS);
+        let hi = (index + ONSET_PEAK_RADIUS_BLOCKS).min(flux.len() - 1);
+        active[*index]
+          && flux[*index] >= ONSET_FLUX_DB
+          && (lo..=hi).all(|other| flux[other] < flux[*index] || other >= *index)
+      })
+      .collect();
+
+    let active_sec = active_count as f64 * block_sec;
+    let mean_over_active = |values: &dyn Fn(usize) -> f64| {
+      if active_count == 0 {
+        return 0.0;
+      }
+      (0..blocks.len())
+        .filter(|index| active[*index])
+        .map(values)
+        .sum::<f64>()
+        / active_count as f64
+    };
+    let loudness_dbfs = if active_count > 0 {
+      to_db(mean_over_active(&|index| blocks[index][0]))
+    } else {
+      FLOOR_DB
+    };
+    let percussiveness =
+      (mean_over_active(&|index| flux[index]) / PERCUSSIVE_FLUX_DB).clamp(0.0, 1.0);
+    let onset_rate = if active_sec > 0.0 {
+      onsets.len() as f64 / active_sec
+    } else {
+      0.0
+    };
+    // 规律但几乎听不到的起伏不算可舞：节拍规律性乘以脉冲强度
+    let danceability = self.beat_regularity(&flux) * percussiveness;
+
+    let spectral_centroid_hz = if self.spectral_weight > 0.0 {
+      self.centroid_sum / self.spectral_weight
+    } else {
+      0.0
+    };
+    let brightness = if spectral_centroid_hz > 0.0 {
+      (spectral_centroid_hz / BRIGHTNESS_RANGE_HZ.0).ln()
+        / (BRIGHTNESS_RANGE_HZ.1 / BRIGHTNESS_RANGE_HZ.0).ln()
+    } else {
+      0.0
+    }
+    .clamp(0.0, 1.0);
+
+    let chroma: Vec<f64> = self
+      .chroma
+      .as_mut()
+      .and_then(ChromaAccumulator::finalize)
+      .map(|values| {
+        let max = values.iter().copied().fold(0.0, f64::max);
+        values
+          .iter()
+          .map(|value| if max > 0.0 { value / max } else { 0.0 })
+          .collect()
+      })
+      .unwrap_or_default();
+    let vocal_presence = self.vocal_presence(&blocks, &band_db, &active, &chroma);
+
+    let score = energy_score(loudness_dbfs, onset_rate, percussiveness, brightness);
+    let energy_curve = options
+      .energy_curve
+      .unwrap_or(false)
+      .then(|| self.energy_curve(options, &blocks, &flux, &onsets, &active, brightness));
+    DescriptorReport {
+      energy_level: (1.0 + (score * 9.0).round()) as u32,
+      energy_score: score,
+      danceability,
+      percussiveness,
+      brightness,
+      spectral_centroid_hz,
+      vocal_presence,
+      loudness_dbfs,
+      onset_rate,
+      chroma,
+      energy_curve,
+    }
+  }
+
+  /// 起音包络在 90–180 BPM 周期（及其两倍）上的归一化自相关
+  fn beat_regularity(&self, flux: &[f64]) -> f64 {
+    let block_sec = self.block_sec();
+    let min_lag = (60.0 / DANCE_MAX_BPM / block_sec).round() as usize;
+    let max_lag = (60.0 / DANCE_MIN_BPM / block_sec).round() as usize;
+    if flux.len() < max_lag * 4 || min_lag == 0 {
+      return 0.0;
+    }
+    let mean = flux.iter().sum::<f64>() / flux.len() as f64;
+    let centered: Vec<f64> = flux.iter().map(|value| value - mean).collect();
+    let autocorrelation = |lag: usize| -> f64 {
+      centered
+        .iter()
+        .zip(centered[lag..].iter())
+        .map(|(a, b)| a * b)
+        .sum::<f64>()
+        / (centered.len() - lag) as f64
+    };
+    let energy = autocorrelation(0);
+    if energy <= 0.0 {
+      return 0.0;
+    }
+    (min_lag..=max_lag)
+      .map(|lag| (autocorrelation(lag) + autocorrelation(lag * 2)) / (2.0 * energy))
+      .fold(0.0, f64::max)
+      .clamp(0.0, 1.0)
+  }
+
+  /// 启发式人声概率：中频占比、音节速率（约 3–10 Hz）的中频包络起伏、人声频段谐波度与色度集中度
+  fn vocal_presence(
+    &self,
+    blocks: &[BlockEnergy],
+    band_db: &[[f64; 4]],
+    active: &[bool],
+    chroma: &[f64],
+  ) -> f64 {
+    let active_blocks: Vec<usize> = (0..blocks.len()).filter(|index| active[*index]).collect();
+    if active_blocks.is_empty() || self.spectral_weight <= 0.0 {
+      return 0.0;
+    }
+    let (mid_sum, band_sum) = active_blocks
+      .iter()
+      .fold((0.0, 0.0), |(mid, total), index| {
+        let block = blocks[*index];
+        (mid + block[2], total + block[1] + block[2] + block[3])
+      });
+    let mid_share = if band_sum > 0.0 {
+      mid_sum / band_sum
+    } else {
+      0.0
+    };
+    let mid_db: Vec<f64> = band_db.iter().map(|db| db[2]).collect();
+    let fast = moving_average(&mid_db, 2);
+    let slow = moving_average(&mid_db, 12);
+    let syllabic = active_blocks
+      .iter()
+      .map(|index| (fast[*index] - slow[*index]).abs())
+      .sum::<f64>()
+      / active_blocks.len() as f64;
+    let mut tonal = self.tonal_sum / self.spectral_weight;
+    if !chroma.is_empty() {
+      let mean = chroma.iter().sum::<f64>() / chroma.len() as f64;
+      tonal = (tonal + (1.0 - mean)) / 2.0;
+    }
+    let evidence = 0.35 * (mid_share / 0.5).clamp(0.0, 1.0)
+      + 0.35 * (syllabic / SYLLABIC_MODULATION_DB).clamp(0.0, 1.0)
+      + 0.3 * tonal.clamp(0.0, 1.0);
+    1.0 / (1.0 + (-8.0 * (evidence - 0.55)).exp())
+  }
+
+  fn energy_curve(
+    &self,
+    options: &AudioDescriptorOptions,
+    blocks: &[BlockEnergy],
+    flux: &[f64],
+    onsets: &[usize],
+    active: &[bool],
+    brightness: f64,
+  ) -> Vec<(f64, f64, f64)> {
+    let block_sec = self.block_sec();
+    let duration_sec = blocks.len() as f64 * block_sec;
+    let grid = options
+      .bpm
+      .filter(|bpm| bpm.is_finite() && *bpm > 0.0)
+      .map(|bpm| {
+        let beats_per_bar = options
+          .beats_per_bar
+          .filter(|value| *value > 0)
+          .unwrap_or(DEFAULT_BEATS_PER_BAR);
+        let bar_sec = 60.0 / bpm * beats_per_bar as f64;
+        let first_beat_sec = options
+          .first_beat_ms
+          .filter(|value| value.is_finite())
+          .unwrap_or(0.0)
+          / 1000.0;
+        (bar_sec, first_beat_sec.rem_euclid(bar_sec))
+      });
+    let (window_sec, origin_sec) = grid.unwrap_or((ENERGY_CURVE_FALLBACK_SEC, 0.0));
+    let mut edges = vec![0.0];
+    let mut edge = origin_sec;
+    while edge < duration_sec {
+      if edge > edges[edges.len() - 1] + block_sec {
+        edges.push(edge);
+      }
+      edge += window_sec;
+    }
+    edges.push(duration_sec);
+
+    edges
+      .windows(2)
+      .filter(|pair| pair[1] > pair[0])
+      .map(|pair| {
+        let start = (pair[0] / block_sec) as usize;
+        let end = ((pair[1] / block_sec).ceil() as usize).min(blocks.len());
+        let range: Vec<usize> = (start..end).filter(|index| active[*index]).collect();
+        if range.is_empty() {
+          return (pair[0], pair[1], 1.0);
+        }
+        let loudness =
+          to_db(range.iter().map(|index| blocks[*index][0]).sum::<f64>() / range.len() as f64);
+        let percussiveness = (range.iter().map(|index| flux[*index]).sum::<f64>()
+          / range.len() as f64
+          / PERCUSSIVE_FLUX_DB)
+          .clamp(0.0, 1.0);
+        let onset_rate = onsets
+          .iter()
+          .filter(|index| (start..end).contains(*index))
+          .count() as f64
+          / (range.len() as f64 * block_sec);
+        let score = energy_score(loudness, onset_rate, percussiveness, brightness);
+        (pair[0], pair[1], 1.0 + score * 9.0)
+      })
+      .collect()
+  }
+}
+
+fn to_result(file_path: Option<String>, report: DescriptorReport) -> AudioDescriptorsResult {
+  AudioDescriptorsResult {
+    file_path,
+    version: AUDIO_DESCRIPTORS_VERSION,
+    energy_level: report.energy_level,
+    energy_score: report.energy_score,
+    danceability: report.danceability,
+    percussiveness: report.percussiveness,
+    brightness: report.brightness,
+    spectral_centroid_hz: report.spectral_centroid_hz,
+    vocal_presence: report.vocal_presence,
+    loudness_dbfs: report.loudness_dbfs,
+    onset_rate: report.onset_rate,
+    chroma: report.chroma,
+    energy_curve: report.energy_curve.map(|points| {
+      points
+        .into_iter()
+        .map(|(start_sec, end_sec, energy)| AudioEnergyPoint {
+          start_sec,
+          end_sec,
+          energy,
+        })
+        .collect()
+    }),
+    error: None,
+  }
+}
+
+impl AnalysisErrorResult for AudioDescriptorsResult {
+  fn error_result(file_path: Option<String>, error: String) -> Self {
+    AudioDescriptorsResult {
+      file_path,
+      version: AUDIO_DESCRIPTORS_VERSION,
+      energy_level: 0,
+      energy_score: 0.0,
+      danceability: 0.0,
+      percussiveness: 0.0,
+      brightness: 0.0,
+      spectral_centroid_hz: 0.0,
+      vocal_presence: 0.0,
+      loudness_dbfs: FLOOR_DB,
+      onset_rate: 0.0,
+      chroma: Vec::new(),
+      energy_curve: None,
+      error: Some(error),
+    }
+  }
+}
+
+/// 当前描述符算法版本
+#[napi]
+pub fn audio_descriptors_version() -> u32 {
+  AUDIO_DESCRIPTORS_VERSION
+}
+
+/// 解码整轨计算选曲描述符
+#[napi]
+pub fn analyze_audio_descriptors(
+  file_path: String,
+  options: Option<AudioDescriptorOptions>,
+) -> AudioDescriptorsResult {
+  let options = options.unwrap_or_default();
+  analyze_file_in_chunks(
+    file_path,
+    READ_CHUNK_FRAMES,
+    DescriptorAnalyzer::new,
+    DescriptorAnalyzer::push,
+    |analyzer, file_path, _, _| to_result(Some(file_path), analyzer.finish(&options)),
+  )
+}
+
+/// 对已解码的交错 f32 PCM 计算选曲描述符
+#[napi]
+pub fn analyze_audio_descriptors_from_pcm(
+  pcm_data: Buffer,
+  sample_rate: u32,
+  channels: u8,
+  options: Option<AudioDescriptorOptions>,
+) -> AudioDescriptorsResult {
+  let options = options.unwrap_or_default();
+  analyze_pcm_buffer(
+    &pcm_data,
+    sample_rate,
+    channels,
+    |samples| match DescriptorAnalyzer::new(sample_rate, channels as u16) {
+      Ok(mut analyzer) => {
+        analyzer.push(samples);
+        to_result(None, analyzer.finish(&options))
+      }
+      Err(error) => AudioDescriptorsResult::error_result(None, error),
+    },
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RATE: u32 = 44_100;
+
+  fn analyze(pcm: &[f32], options: &AudioDescriptorOptions) -> DescriptorReport {
+    let mut analyzer = DescriptorAnalyzer::new(RATE, 1).unwrap();
+    for chunk in pcm.chunks(10_000) {
+      analyzer.push(chunk);
+    }
+    analyzer.finish(options)
+  }
+
+  /// 128 BPM 四拍底鼓 + 反拍噪声镲片
+  fn kick_loop(seconds: f64) -> Vec<f32> {
+    let beat_frames = (RATE as f64 * 60.0 / 128.0) as usize;
+    let mut seed = 0x1234_5678_u32;
+    (0..(seconds * RATE as f64) as usize)
+      .map(|frame| {
+        let in_beat = frame % beat_frames;
+        let t = in_beat as f32 / RATE as f32;
+        let kick = (-t * 25.0).exp() * (std::f32::consts::TAU * 55.0 * t).sin() * 0.9;
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let noise = (seed as f32 / u32::MAX as f32) * 2.0 - 1.0;
+        let hat_t = ((in_beat + beat_frames / 2) % beat_frames) as f32 / RATE as f32;
+        kick + noise * 0.3 * (-hat_t * 60.0).exp()
+      })
+      .collect()
+  }
+
+  fn pad(seconds: f64, gain: f32) -> Vec<f32> {
+    (0..(seconds * RATE as f64) as usize)
+      .map(|frame| {
+        let t = frame as f32 / RATE as f32;
+        gain
+          * [220.0f32, 277.2, 329.6]
+            .iter()
+            .map(|hz| (std::f32::consts::TAU * hz * t).sin())
+            .sum::<f32>()
+          / 3.0
+      })
+      .collect()
+  }
+
+  #[test]
+  fn kick_loop_scores_higher_than_pad() {
+    let options = AudioDescriptorOptions::default();
+    let kick = analyze(&kick_loop(12.0), &options);
+    let pad = analyze(&pad(12.0, 0.1), &options);
+
+    assert!(kick.danceability > 0.5, "{}", kick.danceability);
+    assert!(pad.danceability < 0.2, "{}", pad.danceability);
+    assert!(kick.percussiveness > pad.percussiveness + 0.3);
+    assert!(kick.onset_rate > 3.0, "{}", kick.onset_rate);
+    assert!(kick.energy_level > pad.energy_level);
+    assert!((1..=10).contains(&kick.energy_level));
+    assert!((1..=10).contains(&pad.energy_level));
+    assert!(kick.energy_curve.is_none());
+  }
+
+  #[test]
+  fn chroma_brightness_and_curve_are_deterministic() {
+    let a440: Vec<f32> = (0..RATE as usize * 6)
+      .map(|frame| 0.5 * (std::f32::consts::TAU * 440.0 * frame as f32 / RATE as f32).sin())
+      .collect();
+    let options = AudioDescriptorOptions {
+      energy_curve: Some(true),
+      bpm: Some(120.0),
+      first_beat_ms: Some(500.0),
+      beats_per_bar: None,
+    };
+    let first = analyze(&a440, &options);
+    let second = analyze(&a440, &options);
+    assert_eq!(first, second);
+
+    let argmax = first
+      .chroma
+      .iter()
+      .enumerate()
+      .max_by(|a, b| a.1.total_cmp(b.1))
+      .map(|(index, _)| index);
+    assert_eq!(argmax, Some(9));
+    assert!((first.spectral_centroid_hz - 440.0).abs() < 30.0);
+
+    // 首拍 0.5 秒、小节 2 秒：[0, 0.5) 补头段，之后按小节切到曲尾
+    let curve = first.energy_curve.unwrap();
+    let starts: Vec<f64> = curve.iter().map(|point| point.0).collect();
+    assert_eq!(starts, vec![0.0, 0.5, 2.5, 4.5]);
+    assert!(curve.iter().all(|point| (1.0..=10.0).contains(&point.2)));
+  }
+}
@@ -0,0 +1,542 @@
+//! 相似曲目用的音频特征向量：MFCC 统计、频谱对比度、速度、QM 色度与调性拼成定长向量；
+//! 各特征组先各自单位化再乘组权重，向量间直接用欧氏距离比较（配合 similarity_index）
+
+use std::cmp::min;
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::analysis_utils::{
+  analyze_file_in_chunks, analyze_pcm_buffer, hann_window, power_spectrum, to_stereo,
+  AnalysisErrorResult, K_ANALYSIS_FRAMES_PER_CHUNK,
+};
+use crate::key_id_to_id3_text;
+use crate::qm_key::{ChromaAccumulator, KeyDetector};
+
+/// 算法或向量布局变化时递增；版本不同的向量不可混用
+pub const AUDIO_EMBEDDING_VERSION: u32 = 1;
+
+const FFT_SIZE: usize = 2048;
+const HOP_SIZE: usize = 1024;
+const MEL_BANDS: usize = 40;
+const MEL_MIN_HZ: f64 = 30.0;
+const MEL_MAX_HZ: f64 = 16_000.0;
+/// 去掉 c0（整体响度），保留 c1..c12
+const MFCC_COEFFS: usize = 12;
+/// 频谱对比度子带边界（Hz），最后一段到 Nyquist
+const CONTRAST_EDGES_HZ: [f64; 7] = [0.0, 200.0, 400.0, 800.0, 1_600.0, 3_200.0, 6_400.0];
+const CONTRAST_BANDS: usize = CONTRAST_EDGES_HZ.len();
+/// 子带内取最强 / 最弱各 20% 的 bin 求峰谷
+const CONTRAST_QUANTILE: f64 = 0.2;
+const SILENT_FRAME_DB: f64 = -70.0;
+const TEMPO_MIN_BPM: f64 = 60.0;
+const TEMPO_MAX_BPM: f64 = 200.0;
+/// 速度先验：以 120 BPM 为中心、1 个八度宽的对数高斯
+const TEMPO_PRIOR_BPM: f64 = 120.0;
+const TEMPO_FEATURES: usize = 3;
+const KEY_FEATURES: usize = 3;
+const READ_CHUNK_FRAMES: usize = 65_536;
+
+/// 组权重：MFCC 均值、MFCC 标准差、频谱对比度、色度、速度、调性
+const WEIGHT_MFCC_MEAN: f64 = 1.0;
+const WEIGHT_MFCC_STD: f64 = 0.6;
+const WEIGHT_CONTRAST: f64 = 0.7;
+const WEIGHT_CHROMA: f64 = 0.7;
+const WEIGHT_TEMPO: f64 = 0.6;
+const WEIGHT_KEY: f64 = 0.5;
+
+/// 向量维度
+pub const AUDIO_EMBEDDING_DIMENSION: usize =
+  MFCC_COEFFS * 2 + CONTRAST_BANDS + 12 + TEMPO_FEATURES + KEY_FEATURES;
+
+/// 特征提取选项
+#[napi(object)]
+#[derive(Default)]
+pub struct AudioEmbeddingOptions {
+  /// 已知 BPM；不填时由起音包络自相关估计
+  pub bpm: Option<f64>,
+}
+
+/// 特征向量结果
+#[napi(object)]
+pub struct AudioEmbeddingResult {
+  pub file_path: Option<String>,
+  /// 算法版本，同 audioEmbeddingVersion()
+  pub version: u32,
+  /// 定长特征向量（长度 audioEmbeddingDimension()）；失败时为空
+  pub vector: Vec<f64>,
+  /// 参与编码的 BPM（传入值或估计值，无法估计时为 0）
+  pub bpm: f64,
+  /// QM 调性（ID3 文本，未知为 "o"）
+  pub key_text: String,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct EmbeddingReport {
+  vector: Vec<f64>,
+  bpm: f64,
+  key_id: i32,
+}
+
+/// 三角 mel 滤波器：每个滤波器为 (起始 bin, 权重)
+struct MelFilter {
+  start: usize,
+  weights: Vec<f64>,
+}
+
+fn hz_to_mel(hz: f64) -> f64 {
+  2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f64) -> f64 {
+  700.0 * (10f64.powf(mel / 2595.0) - 1.0)
+}
+
+fn mel_filter_bank(sample_rate: u32) -> Vec<MelFilter> {
+  let bin_hz = sample_rate as f64 / FFT_SIZE as f64;
+  let max_hz = MEL_MAX_HZ.min(sample_rate as f64 / 2.0);
+  let (mel_lo, mel_hi) = (hz_to_mel(MEL_MIN_HZ), hz_to_mel(max_hz));
+  let edges: Vec<f64> = (0..MEL_BANDS + 2)
+    .map(|index| mel_to_hz(mel_lo + (mel_hi - mel_lo) * index as f64 / (MEL_BANDS + 1) as f64))
+    .collect();
+  edges
+    .windows(3)
+    .map(|edge| {
+      let start = (edge[0] / bin_hz).floor() as usize;
+      let end = ((edge[2] / bin_hz).ceil() as usize).min(FFT_SIZE / 2);
+      let weights = (start..=end)
+        .map(|bin| {
+          let hz = bin as f64 * bin_hz;
+          if hz <= edge[1] {
+            (hz - edge[0]) / (edge[1] - edge[0])
+          } else {
+            (edge[2] - hz) / (edge[2] - edge[1])
+          }
+          .max(0.0)
+        })
+        .collect();
+      MelFilter { start, weights }
+    })
+    .collect()
+}
+
+/// DCT-II 系数表（c1..c12）
+fn dct_table() -> Vec<[f64; MEL_BANDS]> {
+  (1..=MFCC_COEFFS)
+    .map(|coeff| {
+      std::array::from_fn(|band| {
+        (std::f64::consts::PI * coeff as f64 * (band as f64 + 0.5) / MEL_BANDS as f64).cos()
+      })
+    })
+    .collect()
+}
+
+/// 把一组特征缩放到单位长度再乘权重；全零组保持为零
+fn push_group(out: &mut Vec<f64>, values: &[f64], weight: f64) {
+  let norm = values.iter().map(|value| value * value).sum::<f64>().sqrt();
+  out.extend(values.iter().map(|value| {
+    if norm > 0.0 {
+      value / norm * weight
+    } else {
+      0.0
+    }
+  }));
+}
+
+/// 速度编码：八度折叠后的相位（cos/sin，70 与 140 BPM 视为同一位置）加线性位置
+fn tempo_features(bpm: f64) -> [f64; TEMPO_FEATURES] {
+  if bpm <= 0.0 || !bpm.is_finite() {
+    return [0.0; TEMPO_FEATURES];
+  }
+  let phase = (bpm / TEMPO_MIN_BPM).log2().fract() * std::f64::consts::TAU;
+  let linear = ((bpm - TEMPO_MIN_BPM) / (TEMPO_MAX_BPM - TEMPO_MIN_BPM)).clamp(0.0, 1.0);
+  [phase.cos(), phase.sin(), linear]
+}
+
+/// 调性编码：关系大调在五度圈上的位置（cos/sin）加调式（大调 0、小调 1）
+fn key_features(key_id: i32) -> [f64; KEY_FEATURES] {
+  if !(1..=24).contains(&key_id) {
+    return [0.0; KEY_FEATURES];
+  }
+  let minor = key_id > 12;
+  let tonic = (key_id - 1) % 12;
+  let relative_major = if minor { (tonic + 3) % 12 } else { tonic };
+  let fifths = (relative_major * 7) % 12;
+  let angle = fifths as f64 / 12.0 * std::f64::consts::TAU;
+  [angle.cos(), angle.sin(), if minor { 1.0 } else { 0.0 }]
+}
+
+/// 增量提取器：逐块喂入交错 f32 PCM
+pub(crate) struct EmbeddingExtractor {
+  sample_rate: u32,
+  channels: usize,
+  window: Vec<f64>,
+  mel: Vec<MelFilter>,
+  dct: Vec<[f64; MEL_BANDS]>,
+  contrast_bins: Vec<(usize, usize)>,
+  pending: Vec<f64>,
+  frames: usize,
+  mfcc_sum: [f64; MFCC_COEFFS],
+  mfcc_sq_sum: [f64; MFCC_COEFFS],
+  contrast_sum: [f64; CONTRAST_BANDS],
+  previous_mel_db: Option<[f64; MEL_BANDS]>,
+  onset: Vec<f64>,
+  chroma: Option<ChromaAccumulator>,
+  key: Option<KeyDetector>,
+}
+
+impl EmbeddingExtractor {
+  pub(crate) fn new(sample_rate: u32, channels: u16) -> Self {
+    let bin_hz = sample_rate as f64 / FFT_SIZE as f64;
+    let contrast_bins = (0..CONTRAST_BANDS)
+      .map(|band| {
+        let lo = (CONTRAST_EDGES_HZ[band] / bin_hz).ceil() as usize;
+        let hi = CONTRAST_EDGES_HZ
+          .get(band + 1)
+          .map(|hz| (hz / bin_hz).floor() as usize)
+          .unwrap_or(FFT_SIZE / 2)
+          .min(FFT_SIZE / 2);
+        (lo.max(1), hi)
+      })
+      .collect();
+    Self {
+      sample_rate,
+      channels: channels.max(1) as usize,
+      window: hann_window(FFT_SIZE),
+      mel: mel_filter_bank(sample_rate),
+      dct: dct_table(),
+      contrast_bins,
+      pending: Vec::with_capacity(FFT_SIZE),
+      frames: 0,
+      mfcc_sum: [0.0; MFCC_COEFFS],
+      mfcc_sq_sum: [0.0; MFCC_COEFFS],
+      contrast_sum: [0.0; CONTRAST_BANDS],
+      previous_mel_db: None,
+      onset: Vec::new(),
+      // 采样率过低时 QM 前端无法构建，对应特征组留零
+      chroma: ChromaAccumulator::new(sample_rate).ok(),
+      key: KeyDetector::new(sample_rate).ok(),
+    }
+  }
+
+  pub(crate) fn push(&mut self, interleaved: &[f32]) {
+    let channels = self.channels;
+    let frames = interleaved.len() / channels;
+    let stereo = to_stereo(&interleaved[..frames * channels], channels, frames);
+    let mut offset = 0usize;
+    while offset < frames {
+      let chunk_frames = min(K_ANALYSIS_FRAMES_PER_CHUNK, frames - offset);
+      let chunk = &stereo[offset * 2..(offset + chunk_frames) * 2];
+      if let Some(chroma) = self.chroma.as_mut() {
+        if chroma.process(chunk, chunk_frames).is_err() {
+          self.chroma = None;
+        }
+      }
+      if let Some(key) = self.key.as_mut() {
+        if key.process(chunk, chunk_frames, 2).is_err() {
+          self.key = None;
+        }
+      }
+      offset += chunk_frames;
+    }
+
+    for frame in interleaved.chunks_exact(channels) {
+      let mono = frame.iter().map(|sample| *sample as f64).sum::<f64>() / channels as f64;
+      self.pending.push(mono);
+      if self.pending.len() == FFT_SIZE {
+        self.analyze_frame();
+        self.pending.drain(..HOP_SIZE);
+      }
+    }
+  }
+
+  fn analyze_frame(&mut self) {
+    let power = power_spectrum(&self.pending, &self.window);
+    let mel_db: [f64; MEL_BANDS] = std::array::from_fn(|band| {
+      let filter = &self.mel[band];
+      let energy = filter
+        .weights
+        .iter()
+        .zip(&power[filter.start..])
+        .map(|(weight, value)| weight * value)
+        .sum::<f64>();
+      10.0 * (energy + 1e-10).log10()
+    });
+    // 起音包络：mel 频带正向变化之和（静音帧也要占位，保持时间轴均匀）
+    let flux = self
+      .previous_mel_db
+      .map(|previous| {
+        mel_db
+          .iter()
+          .zip(previous.iter())
+          .map(|(current, last)| (current - last).max(0.0))
+          .sum::<f64>()
+          / MEL_BANDS as f64
+      })
+      .unwrap_or(0.0);
+    self.onset.push(flux);
+    self.previous_mel_db = Some(mel_db);
+
+    let total = power.iter().sum::<f64>() / (FFT_SIZE * FFT_SIZE) as f64;
+    if total <= 0.0 || 10.0 * total.log10() < SILENT_FRAME_DB {
+      return;
+    }
+    self.frames += 1;
+    for (coeff, basis) in self.dct.iter().enumerate() {
+      let value = basis
+        .iter()
+        .zip(mel_db.iter())
+        .map(|(weight, db)| weight * db)
+        .sum::<f64>()
+        / MEL_BANDS as f64;
+      self.mfcc_sum[coeff] += value;
+      self.mfcc_sq_sum[coeff] += value * value;
+    }
+    for (band, (lo, hi)) in self.contrast_bins.iter().enumerate() {
+      if hi <= lo {
+        continue;
+      }
+      let mut values: Vec<f64> = power[*lo..*hi].to_vec();
+      values.sort_by(f64::total_cmp);
+      let take = ((values.len() as f64 * CONTRAST_QUANTILE).round() as usize).max(1);
+      let valley = values[..take].iter().sum::<f64>() / take as f64;
+      let peak = values[values.len() - take..].iter().sum::<f64>() / take as f64;
+      self.contrast_sum[band] += 10.0 * ((peak + 1e-10) / (valley + 1e-10)).log10();
+    }
+  }
+
+  /// 起音包络自相关（乘以速度先验）的最大周期
+  fn estimate_bpm(&self) -> f64 {
+    let fps = self.sample_rate as f64 / HOP_SIZE as f64;
+    let min_lag = (60.0 * fps / TEMPO_MAX_BPM).floor().max(1.0) as usize;
+    let max_lag = (60.0 * fps / TEMPO_MIN_BPM).ceil() as usize;
+    if self.onset.len() < max_lag * 4 {
+      return 0.0;
+    }
+    let mean = self.onset.iter().sum::<f64>() / self.onset.len() as f64;
+    let centered: Vec<f64> = self.onset.iter().map(|value| value - mean).collect();
+    let autocorrelation = |lag: usize| -> f64 {
+      centered
+        .iter()
+        .zip(centered[lag..].iter())
+        .map(|(a, b)| a * b)
+        .sum::<f64>()
+        / (centered.len() - lag) as f64
+    };
+    let scores: Vec<(usize, f64)> = (min_lag.saturating_sub(1)..=max_lag + 1)
+      .map(|lag| (lag, autocorrelation(lag)))
+      .collect();
+    let best = scores
+      .windows(3)
+      .filter(|window| {
+        window[1].1 > 0.0 && window[1].1 >= window[0].1 && window[1].1 >= window[2].1
+      })
+      .map(|window| {
+        let bpm = 60.0 * fps / window[1].0 as f64;
+        let prior = (-0.5 * (bpm / TEMPO_PRIOR_BPM).log2().powi(2)).exp();
+        (window, window[1].1 * prior)
+      })
+      .max_by(|a, b| a.1.total_cmp(&b.1));
+    let Some((window, _)) = best else {
+      return 0.0;
+    };
+    // 抛物线插值细化周期
+    let (y0, y1, y2) = (window[0].1, window[1].1, window[2].1);
+    let denominator = y0 - 2.0 * y1 + y2;
+    let shift = if denominator.abs() > f64::EPSILON {
+      (0.5 * (y0 - y2) / denominator).clamp(-0.5, 0.5)
+    } else {
+      0.0
+    };
+    60.0 * fps / (window[1].0 as f64 + shift)
+  }
+
+  pub(crate) fn finish(mut self, options: &AudioEmbeddingOptions) -> EmbeddingReport {
+    let frames = self.frames.max(1) as f64;
+    let mfcc_mean: Vec<f64> = self.mfcc_sum.iter().map(|sum| sum / frames).collect();
+    let mfcc_std: Vec<f64> = self
+      .mfcc_sq_sum
+      .iter()
+      .zip(mfcc_mean.iter())
+      .map(|(sq_sum, mean)| (sq_sum / frames - mean * mean).max(0.0).sqrt())
+      .collect();
+    let contrast: Vec<f64> = self.contrast_sum.iter().map(|sum| sum / frames).collect();
+    let chroma = self
+      .chroma
+      .as_mut()
+      .and_then(ChromaAccumulator::finalize)
+      .unwrap_or([0.0; 12]);
+    let key_id = self
+      .key
+      .as_mut()
+      .and_then(|key| key.finalize().ok())
+      .unwrap_or(0);
+    let bpm = options
+      .bpm
+      .filter(|bpm| bpm.is_finite() && *bpm > 0.0)
+      .unwrap_or_else(|| self.estimate_bpm());
+
+    let mut vector = Vec::with_capacity(AUDIO_EMBEDDING_DIMENSION);
+    if self.frames > 0 {
+      push_group(&mut vector, &mfcc_mean, WEIGHT_MFCC_MEAN);
+      push_group(&mut vector, &mfcc_std, WEIGHT_MFCC_STD);
+      push_group(&mut vector, &contrast, WEIGHT_CONTRAST);
+    } else {
+      vector.resize(MFCC_COEFFS * 2 + CONTRAST_BANDS, 0.0);
+    }
+    push_group(&mut vector, &chroma, WEIGHT_CHROMA);
+    vector.extend(tempo_features(bpm).iter().map(|value| value * WEIGHT_TEMPO));
+    vector.extend(key_features(key_id).iter().map(|value| value * WEIGHT_KEY));
+    EmbeddingReport {
+      vector,
+      bpm,
+      key_id,
+    }
+  }
+}
+
+fn to_result(file_path: Option<String>, report: EmbeddingReport) -> AudioEmbeddingResult {
+  AudioEmbeddingResult {
+    file_path,
+    version: AUDIO_EMBEDDING_VERSION,
+    vector: report.vector,
+    bpm: report.bpm,
+    key_text: key_id_to_id3_text(report.key_id),
+    error: None,
+  }
+}
+
+impl AnalysisErrorResult for AudioEmbeddingResult {
+  fn error_result(file_path: Option<String>, error: String) -> Self {
+    AudioEmbeddingResult {
+      file_path,
+      version: AUDIO_EMBEDDING_VERSION,
+      vector: Vec::new(),
+      bpm: 0.0,
+      key_text: key_id_to_id3_text(0),
+      error: Some(error),
+    }
+  }
+}
+
+/// 当前特征向量算法版本
+#[napi]
+pub fn audio_embedding_version() -> u32 {
+  AUDIO_EMBEDDING_VERSION
+}
+
+/// 特征向量维度
+#[napi]
+pub fn audio_embedding_dimension() -> u32 {
+  AUDIO_EMBEDDING_DIMENSION as u32
+}
+
+/// 解码整轨提取相似度特征向量
+#[napi]
+pub fn analyze_audio_embedding(
+  file_path: String,
+  options: Option<AudioEmbeddingOptions>,
+) -> AudioEmbeddingResult {
+  let options = options.unwrap_or_default();
+  analyze_file_in_chunks(
+    file_path,
+    READ_CHUNK_FRAMES,
+    |sample_rate, channels| Ok(EmbeddingExtractor::new(sample_rate, channels)),
+    EmbeddingExtractor::push,
+    |extractor, file_path, _, _| to_result(Some(file_path), extractor.finish(&options)),
+  )
+}
+
+/// 对已解码的交错 f32 PCM 提取相似度特征向量
+#[napi]
+pub fn analyze_audio_embedding_from_pcm(
+  pcm_data: Buffer,
+  sample_rate: u32,
+  channels: u8,
+  options: Option<AudioEmbeddingOptions>,
+) -> AudioEmbeddingResult {
+  let options = options.unwrap_or_default();
+  analyze_pcm_buffer(&pcm_data, sample_rate, channels, |samples| {
+    let mut extractor = EmbeddingExtractor::new(sample_rate, channels as u16);
+    extractor.push(samples);
+    to_result(None, extractor.finish(&options))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RATE: u32 = 44_100;
+
+  fn extract(pcm: &[f32], options: &AudioEmbeddingOptions) -> EmbeddingReport {
+    let mut extractor = EmbeddingExtractor::new(RATE, 1);
+    for chunk in pcm.chunks(30_000) {
+      extractor.push(chunk);
+    }
+    extractor.finish(options)
+  }
+
+  fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+      .zip(b)
+      .map(|(x, y)| (x - y) * (x - y))
+      .sum::<f64>()
+      .sqrt()
+  }
+
+  /// 固定 BPM 的衰减正弦“鼓点”，音高决定音色
+  fn pulse_track(bpm: f64, pitch_hz: f32, seconds: f64) -> Vec<f32> {
+    let beat_frames = (RATE as f64 * 60.0 / bpm) as usize;
+    (0..(seconds * RATE as f64) as usize)
+      .map(|frame| {
+        let t = (frame % beat_frames) as f32 / RATE as f32;
+        0.8 * (-t * 12.0).exp() * (std::f32::consts::TAU * pitch_hz * t).sin()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn estimates_tempo_and_keeps_fixed_dimension() {
+    let report = extract(
+      &pulse_track(124.0, 220.0, 20.0),
+      &AudioEmbeddingOptions::default(),
+    );
+    assert_eq!(report.vector.len(), AUDIO_EMBEDDING_DIMENSION);
+    assert!((report.bpm - 124.0).abs() < 2.0, "{}", report.bpm);
+
+    let given = extract(
+      &pulse_track(124.0, 220.0, 20.0),
+      &AudioEmbeddingOptions { bpm: Some(128.0) },
+    );
+    assert_eq!(given.bpm, 128.0);
+
+    let silent = extract(
+      &vec![0.0; RATE as usize * 5],
+      &AudioEmbeddingOptions::default(),
+    );
+    assert_eq!(silent.vector.len(), AUDIO_EMBEDDING_DIMENSION);
+  }
+
+  #[test]
+  fn similar_material_is_closer_than_different_material() {
+    let options = AudioEmbeddingOptions::default();
+    let base = extract(&pulse_track(124.0, 220.0, 20.0), &options);
+    let near = extract(&pulse_track(126.0, 233.0, 20.0), &options);
+    let far = extract(&pulse_track(90.0, 2_500.0, 20.0), &options);
+    assert!(distance(&base.vector, &near.vector) < distance(&base.vector, &far.vector));
+    assert_eq!(base, extract(&pulse_track(124.0, 220.0, 20.0), &options));
+  }
+
+  #[test]
+  fn key_features_map_relative_keys_together() {
+    // C 大调（1）与 A 小调（22）共享五度圈位置，只差调式
+    let c_major = key_features(1);
+    let a_minor = key_features(22);
+    assert!((c_major[0] - a_minor[0]).abs() < 1e-9);
+    assert!((c_major[1] - a_minor[1]).abs() < 1e-9);
+    assert_eq!((c_major[2], a_minor[2]), (0.0, 1.0));
+    assert_eq!(key_features(0), [0.0; KEY_FEATURES]);
+  }
+}
@@ -0,0 +1,433 @@
+//! 首尾静音与首个发声点检测：给出首/末可闻样本位置、首尾静音长度、淡入淡出区段，
+//! 并按节拍网格吸附出建议的默认 cue（同 rekordbox 的 auto cue）
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::analysis_utils::{analyze_file_in_chunks, analyze_pcm_buffer, AnalysisErrorResult};
+
+/// 默认可闻阈值（dBFS，按样本峰值判断）
+const DEFAULT_THRESHOLD_DB: f64 = -60.0;
+/// 淡入淡出判定：短时 RMS 低于主体电平该值以内视为进入主体
+const DEFAULT_FADE_RANGE_DB: f64 = 6.0;
+/// 短于该时长的渐变不报告为淡入淡出
+const DEFAULT_MIN_FADE_MS: f64 = 500.0;
+/// 短时 RMS 窗长
+const ENVELOPE_WINDOW_MS: f64 = 50.0;
+/// 主体电平取短时 RMS 的该分位
+const BODY_LEVEL_PERCENTILE: f64 = 0.9;
+const READ_CHUNK_FRAMES: usize = 65_536;
+
+/// 静音检测选项
+#[napi(object)]
+#[derive(Default)]
+pub struct AudioSilenceOptions {
+  /// 可闻阈值（dBFS），默认 -60
+  pub threshold_db: Option<f64>,
+  /// 淡入淡出判定范围（dB，相对主体电平），默认 6
+  pub fade_range_db: Option<f64>,
+  /// 最短淡入淡出时长（毫秒），默认 500
+  pub min_fade_ms: Option<f64>,
+  /// 节拍网格，用于吸附建议 cue；缺省时 cue 即首个发声点
+  pub bpm: Option<f64>,
+  pub first_beat_ms: Option<f64>,
+}
+
+/// 淡入/淡出区段
+#[napi(object)]
+pub struct AudioFadeRegion {
+  pub start_sec: f64,
+  pub end_sec: f64,
+}
+
+/// 静音检测结果
+#[napi(object)]
+pub struct AudioSilenceResult {
+  pub file_path: Option<String>,
+  pub sample_rate: u32,
+  pub duration_sec: f64,
+  /// 首个可闻样本（帧序号），全程静音时为空
+  pub first_audible_frame: Option<f64>,
+  /// 最后一个可闻样本（帧序号）
+  pub last_audible_frame: Option<f64>,
+  pub first_sound_sec: Option<f64>,
+  pub last_sound_sec: Option<f64>,
+  pub leading_silence_sec: f64,
+  pub trailing_silence_sec: f64,
+  pub fade_in: Option<AudioFadeRegion>,
+  pub fade_out: Option<AudioFadeRegion>,
+  /// 建议的默认 cue（秒）；有节拍网格时为首个发声点处或之后的第一拍
+  pub suggested_cue_sec: Option<f64>,
+  /// 建议 cue 是否已吸附到节拍网格
+  pub cue_snapped_to_grid: bool,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SilenceSettings {
+  threshold: f32,
+  fade_range_db: f64,
+  min_fade_sec: f64,
+  grid: Option<(f64, f64)>,
+}
+
+impl SilenceSettings {
+  fn from_options(options: &AudioSilenceOptions) -> Self {
+    let threshold_db = options
+      .threshold_db
+      .filter(|value| value.is_finite() && *value < 0.0)
+      .unwrap_or(DEFAULT_THRESHOLD_DB);
+    let grid = options.bpm.filter(|bpm| bpm.is_finite() && *bpm > 0.0).zip(
+      options
+        .first_beat_ms
+        .filter(|value| value.is_finite())
+        .map(|ms| ms / 1000.0),
+    );
+    Self {
+      threshold: 10f64.powf(threshold_db / 20.0) as f32,
+      fade_range_db: options
+        .fade_range_db
+        .filter(|value| value.is_finite() && *value > 0.0)
+        .unwrap_or(DEFAULT_FADE_RANGE_DB),
+      min_fade_sec: options
+        .min_fade_ms
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .unwrap_or(DEFAULT_MIN_FADE_MS)
+        / 1000.0,
+      grid,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SilenceReport {
+  frames: u64,
+  first_audible_frame: Option<u64>,
+  last_audible_frame: Option<u64>,
+  fade_in: Option<(f64, f64)>,
+  fade_out: Option<(f64, f64)>,
+  suggested_cue_sec: Option<f64>,
+  cue_snapped_to_grid: bool,
+}
+
+/// 增量检测器：逐块喂入交错 f32 PCM
+pub(crate) struct SilenceDetector {
+  sample_rate: u32,
+  channels: usize,
+  settings: SilenceSettings,
+  frames: u64,
+  first_audible_frame: Option<u64>,
+  last_audible_frame: Option<u64>,
+  window_frames: u64,
+  window_energy: f64,
+  window_filled: u64,
+  /// 每个短时窗的 RMS（dBFS）
+  envelope_db: Vec<f64>,
+}
+
+fn energy_to_db(mean_square: f64) -> f64 {
+  if mean_square > 0.0 {
+    10.0 * mean_square.log10()
+  } else {
+    f64::NEG_INFINITY
+  }
+}
+
+/// 判断起音是否正好落在节拍上的容差（拍数）
+const GRID_SNAP_EPSILON_BEATS: f64 = 1e-6;
+
+/// 吸附到起音处或之后最近的节拍，避免 cue 落在起音之前的静音里
+fn snap_to_grid(sec: f64, bpm: f64, first_beat_sec: f64) -> f64 {
+  let beat_sec = 60.0 / bpm;
+  let beats = ((sec.max(0.0) - first_beat_sec) / beat_sec - GRID_SNAP_EPSILON_BEATS).ceil();
+  first_beat_sec + beats * beat_sec
+}
+
+impl SilenceDetector {
+  pub(crate) fn new(sample_rate: u32, channels: u16, options: &AudioSilenceOptions) -> Self {
+    Self {
+      sample_rate,
+      channels: channels.max(1) as usize,
+      settings: SilenceSettings::from_options(options),
+      frames: 0,
+      first_audible_frame: None,
+      last_audible_frame: None,
+      window_frames: ((sample_rate as f64 * ENVELOPE_WINDOW_MS / 1000.0) as u64).max(1),
+      window_energy: 0.0,
+      window_filled: 0,
+      envelope_db: Vec::new(),
+    }
+  }
+
+  pub(crate) fn push(&mut self, interleaved: &[f32]) {
+    for frame in interleaved.chunks_exact(self.channels) {
+      let peak = frame
+        .iter()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+      if peak >= self.settings.threshold {
+        self.first_audible_frame.get_or_insert(self.frames);
+        self.last_audible_frame = Some(self.frames);
+      }
+      self.window_energy += frame
+        .iter()
+        .map(|sample| (*sample as f64) * (*sample as f64))
+        .sum::<f64>()
+        / self.channels as f64;
+      self.window_filled += 1;
+      if self.window_filled >= self.window_frames {
+        self.flush_window();
+      }
+      self.frames += 1;
+    }
+  }
+
+  fn flush_window(&mut self) {
+    if self.window_filled == 0 {
+      return;
+    }
+    self
+      .envelope_db
+      .push(energy_to_db(self.window_energy / self.window_filled as f64));
+    self.window_energy = 0.0;
+    self.window_filled = 0;
+  }
+
+  fn window_sec(&self) -> f64 {
+    self.window_frames as f64 / self.sample_rate as f64
+  }
+
+  /// 主体电平：可闻区间内短时 RMS 的高分位
+  fn body_level_db(&self, first_window: usize, last_window: usize) -> Option<f64> {
+    let mut levels: Vec<f64> = self.envelope_db[first_window..=last_window]
+      .iter()
+      .copied()
+      .filter(|value| value.is_finite())
+      .collect();
+    if levels.is_empty() {
+      return None;
+    }
+    levels.sort_by(|a, b| a.total_cmp(b));
+    let index = ((levels.len() - 1) as f64 * BODY_LEVEL_PERCENTILE).round() as usize;
+    Some(levels[index])
+  }
+
+  pub(crate) fn finish(mut self) -> SilenceReport {
+    self.flush_window();
+    let sample_rate = self.sample_rate.max(1) as f64;
+    let mut report = SilenceReport {
+      frames: self.frames,
+      first_audible_frame: self.first_audible_frame,
+      last_audible_frame: self.last_audible_frame,
+      fade_in: None,
+      fade_out: None,
+      suggested_cue_sec: None,
+      cue_snapped_to_grid: false,
+    };
+    let (Some(first_frame), Some(last_frame)) = (self.first_audible_frame, self.last_audible_frame)
+    else {
+      return report;
+    };
+    let first_sec = first_frame as f64 / sample_rate;
+    let last_sec = (last_frame + 1) as f64 / sample_rate;
+
+    let first_window = (first_frame / self.window_frames) as usize;
+    let last_window = ((last_frame / self.window_frames) as usize).min(self.envelope_db.len() - 1);
+    if let Some(body_db) = self.body_level_db(first_window, last_window) {
+      let body_threshold = body_db - self.settings.fade_range_db;
+      let window_sec = self.window_sec();
+      let reached = self.envelope_db[first_window..=last_window]
+        .iter()
+        .position(|db| *db >= body_threshold);
+      let left = self.envelope_db[first_window..=last_window]
+        .iter()
+        .rposition(|db| *db >= body_threshold);
+      if let (Some(reached), Some(left)) = (reached, left) {
+        let fade_in_end = (first_window + reached) as f64 * window_sec;
+        if fade_in_end - first_sec >= self.settings.min_fade_sec {
+          report.fade_in = Some((first_sec, fade_in_end));
+        }
+        let fade_out_start = ((first_window + left + 1) as f64 * window_sec).min(last_sec);
+        if last_sec - fade_out_start >= self.settings.min_fade_sec {
+          report.fade_out = Some((fade_out_start, last_sec));
+        }
+      }
+    }
+
+    report.suggested_cue_sec = Some(match self.settings.grid {
+      Some((bpm, first_beat_sec)) => {
+        report.cue_snapped_to_grid = true;
+        snap_to_grid(first_sec, bpm, first_beat_sec)
+      }
+      None => first_sec,
+    });
+    report
+  }
+}
+
+fn to_result(
+  file_path: Option<String>,
+  sample_rate: u32,
+  report: SilenceReport,
+) -> AudioSilenceResult {
+  let rate = sample_rate.max(1) as f64;
+  let duration_sec = report.frames as f64 / rate;
+  let first_sound_sec = report.first_audible_frame.map(|frame| frame as f64 / rate);
+  let last_sound_sec = report
+    .last_audible_frame
+    .map(|frame| (frame + 1) as f64 / rate);
+  AudioSilenceResult {
+    file_path,
+    sample_rate,
+    duration_sec,
+    first_audible_frame: report.first_audible_frame.map(|frame| frame as f64),
+    last_audible_frame: report.last_audible_frame.map(|frame| frame as f64),
+    first_sound_sec,
+    last_sound_sec,
+    leading_silence_sec: first_sound_sec.unwrap_or(duration_sec),
+    trailing_silence_sec: last_sound_sec
+      .map(|sec| (duration_sec - sec).max(0.0))
+      .unwrap_or(duration_sec),
+    fade_in: report
+      .fade_in
+      .map(|(start_sec, end_sec)| AudioFadeRegion { start_sec, end_sec }),
+    fade_out: report
+      .fade_out
+      .map(|(start_sec, end_sec)| AudioFadeRegion { start_sec, end_sec }),
+    suggested_cue_sec: report.suggested_cue_sec,
+    cue_snapped_to_grid: report.cue_snapped_to_grid,
+    error: None,
+  }
+}
+
+impl AnalysisErrorResult for AudioSilenceResult {
+  fn error_result(file_path: Option<String>, error: String) -> Self {
+    AudioSilenceResult {
+      file_path,
+      sample_rate: 0,
+      duration_sec: 0.0,
+      first_audible_frame: None,
+      last_audible_frame: None,
+      first_sound_sec: None,
+      last_sound_sec: None,
+      leading_silence_sec: 0.0,
+      trailing_silence_sec: 0.0,
+      fade_in: None,
+      fade_out: None,
+      suggested_cue_sec: None,
+      cue_snapped_to_grid: false,
+      error: Some(error),
+    }
+  }
+}
+
+/// 解码整轨检测首尾静音、淡入淡出与建议 cue
+#[napi]
+pub fn analyze_audio_silence(
+  file_path: String,
+  options: Option<AudioSilenceOptions>,
+) -> AudioSilenceResult {
+  let options = options.unwrap_or_default();
+  analyze_file_in_chunks(
+    file_path,
+    READ_CHUNK_FRAMES,
+    |sample_rate, channels| Ok(SilenceDetector::new(sample_rate, channels, &options)),
+    SilenceDetector::push,
+    |detector, file_path, sample_rate, _| {
+      to_result(Some(file_path), sample_rate, detector.finish())
+    },
+  )
+}
+
+/// 对已解码的交错 f32 PCM 检测首尾静音
+#[napi]
+pub fn analyze_audio_silence_from_pcm(
+  pcm_data: Buffer,
+  sample_rate: u32,
+  channels: u8,
+  options: Option<AudioSilenceOptions>,
+) -> AudioSilenceResult {
+  let options = options.unwrap_or_default();
+  analyze_pcm_buffer(&pcm_data, sample_rate, channels, |samples| {
+    let mut detector = SilenceDetector::new(sample_rate, channels as u16, &options);
+    detector.push(samples);
+    to_result(None, sample_rate, detector.finish())
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RATE: u32 = 1_000;
+
+  /// 1 kHz 采样率下的包络信号：每帧幅度由 gain(秒) 给出，正负交替
+  fn enveloped(seconds: f64, gain: impl Fn(f64) -> f32) -> Vec<f32> {
+    (0..(seconds * RATE as f64) as usize)
+      .map(|frame| {
+        let sign = if frame % 2 == 0 { 1.0 } else { -1.0 };
+        sign * gain(frame as f64 / RATE as f64)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn finds_audible_bounds_and_fades() {
+    // 2 秒静音，2 秒线性淡入，4 秒主体，2 秒淡出，2 秒静音
+    let pcm = enveloped(12.0, |sec| match sec {
+      s if s < 2.0 => 0.0,
+      s if s < 4.0 => 0.5 * ((s - 2.0) / 2.0) as f32,
+      s if s < 8.0 => 0.5,
+      s if s < 10.0 => 0.5 * ((10.0 - s) / 2.0) as f32,
+      _ => 0.0,
+    });
+    let mut detector = SilenceDetector::new(RATE, 1, &AudioSilenceOptions::default());
+    detector.push(&pcm);
+    let result = to_result(None, RATE, detector.finish());
+
+    // -60 dBFS 对应淡入/淡出开始后约 4 ms
+    let first_frame = result.first_audible_frame.expect("first frame");
+    assert!((2003.0..=2005.0).contains(&first_frame), "{first_frame}");
+    let last_frame = result.last_audible_frame.expect("last frame");
+    assert!((9995.0..=9997.0).contains(&last_frame), "{last_frame}");
+    assert!((result.leading_silence_sec - 2.0).abs() < 0.01);
+    assert!((result.trailing_silence_sec - 2.0).abs() < 0.01);
+    let fade_in = result.fade_in.expect("fade in");
+    assert!((fade_in.end_sec - 3.0).abs() < 0.1, "{}", fade_in.end_sec);
+    let fade_out = result.fade_out.expect("fade out");
+    assert!(
+      (fade_out.start_sec - 9.0).abs() < 0.1,
+      "{}",
+      fade_out.start_sec
+    );
+    assert_eq!(result.suggested_cue_sec, result.first_sound_sec);
+    assert!(!result.cue_snapped_to_grid);
+  }
+
+  #[test]
+  fn snaps_cue_to_grid_and_handles_silence() {
+    let pcm = enveloped(3.0, |sec| if sec >= 1.03 { 0.3 } else { 0.0 });
+    let options = AudioSilenceOptions {
+      bpm: Some(120.0),
+      first_beat_ms: Some(20.0),
+      ..Default::default()
+    };
+    let mut detector = SilenceDetector::new(RATE, 1, &options);
+    detector.push(&pcm);
+    let report = detector.finish();
+    assert!(report.fade_in.is_none());
+    assert!(report.cue_snapped_to_grid);
+    assert!((report.suggested_cue_sec.unwrap() - 1.52).abs() < 1e-9);
+
+    assert!((snap_to_grid(0.01, 120.0, 0.3) - 0.3).abs() < 1e-9);
+    assert!((snap_to_grid(1.52, 120.0, 0.02) - 1.52).abs() < 1e-9);
+    assert!((snap_to_grid(1.021, 120.0, 0.02) - 1.52).abs() < 1e-9);
+    assert!((snap_to_grid(0.0, 120.0, 1.3) - 0.3).abs() < 1e-9);
+
+    let mut detector = SilenceDetector::new(RATE, 2, &AudioSilenceOptions::default());
+    detector.push(&vec![0.0; 4_000]);
+    let result = to_result(None, RATE, detector.finish());
+    assert!(result.first_sound_sec.is_none());
+    assert!(result.suggested_cue_sec.is_none());
+    assert!((result.leading_silence_sec - 2.0).abs() < 1e-9);
+  }
+}
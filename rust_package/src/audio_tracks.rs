@@ -0,0 +1,473 @@
+//! 多音轨容器与内嵌 cue sheet：枚举音轨/章节/分轨，并按分轨或时间范围解码虚拟子轨
+
+use std::path::Path;
+use std::result::Result as StdResult;
+
+use bytemuck::cast_slice;
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::decode_error::{check_source_file, DecodeBackend, DecodeError, DecodeErrorKind};
+use crate::ffmpeg_decode::{ffmpeg_decode_stream_i16, ffmpeg_probe_layout, FfmpegMediaLayout};
+use crate::gapless_info;
+use crate::DecodeAudioResult;
+
+/// cue sheet 时间码每秒帧数
+const CUE_FRAMES_PER_SEC: f64 = 75.0;
+
+/// 容器内的音频流
+#[napi(object)]
+pub struct AudioTrackInfo {
+  /// 音轨序号（只计音频流，从 0 开始），用于 decodeAudioSubTrack 的 trackIndex
+  pub index: u32,
+  pub codec: String,
+  pub sample_rate: u32,
+  pub channels: u32,
+  pub duration_sec: Option<f64>,
+  pub language: Option<String>,
+  pub title: Option<String>,
+  /// 默认解码的音轨
+  pub is_default: bool,
+}
+
+/// 分轨（cue sheet 曲目或容器章节）
+#[napi(object)]
+pub struct AudioSubTrackInfo {
+  /// 分轨序号，用于 decodeAudioSubTrack 的 subTrackIndex
+  pub index: u32,
+  /// cue sheet 中的 TRACK 编号
+  pub number: Option<u32>,
+  pub title: Option<String>,
+  pub performer: Option<String>,
+  pub start_sec: f64,
+  /// 结束位置（秒），最后一轨且时长未知时为空
+  pub end_sec: Option<f64>,
+  /// cuesheet / chapter
+  pub source: String,
+}
+
+/// 音轨与分轨布局
+#[napi(object)]
+pub struct AudioTrackLayout {
+  pub file_path: String,
+  pub tracks: Vec<AudioTrackInfo>,
+  pub sub_tracks: Vec<AudioSubTrackInfo>,
+  /// cue sheet 中的专辑标题/艺人
+  pub album_title: Option<String>,
+  pub album_performer: Option<String>,
+  pub error_code: Option<String>,
+  pub error: Option<String>,
+}
+
+/// 子轨解码选项：subTrackIndex 与 startSec/endSec 二选一
+#[napi(object)]
+pub struct AudioSubTrackDecodeOptions {
+  /// 音轨序号，缺省为默认音轨
+  pub track_index: Option<u32>,
+  pub sub_track_index: Option<u32>,
+  pub start_sec: Option<f64>,
+  pub end_sec: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct CueSheetTrack {
+  number: u32,
+  title: Option<String>,
+  performer: Option<String>,
+  start_sec: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct CueSheet {
+  title: Option<String>,
+  performer: Option<String>,
+  tracks: Vec<CueSheetTrack>,
+}
+
+/// 取命令参数：带引号时取引号内全部内容，否则取第一个词
+fn cue_argument(rest: &str) -> Option<String> {
+  let rest = rest.trim();
+  let value = if let Some(quoted) = rest.strip_prefix('"') {
+    quoted.split('"').next().unwrap_or_default()
+  } else {
+    rest.split_whitespace().next().unwrap_or_default()
+  };
+  let value = value.trim();
+  (!value.is_empty()).then(|| value.to_string())
+}
+
+/// mm:ss:ff（ff 为 1/75 秒）
+fn parse_cue_time(text: &str) -> Option<f64> {
+  let mut parts = text.trim().split(':');
+  let minutes: u32 = parts.next()?.parse().ok()?;
+  let seconds: u32 = parts.next()?.parse().ok()?;
+  let frames: u32 = parts.next()?.parse().ok()?;
+  if parts.next().is_some() || seconds >= 60 || frames >= CUE_FRAMES_PER_SEC as u32 {
+    return None;
+  }
+  Some(minutes as f64 * 60.0 + seconds as f64 + frames as f64 / CUE_FRAMES_PER_SEC)
+}
+
+fn parse_cue_sheet(text: &str) -> CueSheet {
+  let mut sheet = CueSheet::default();
+  let mut pregap_start: Option<f64> = None;
+  for line in text.trim_start_matches('\u{feff}').lines() {
+    let line = line.trim();
+    let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    match command.to_ascii_uppercase().as_str() {
+      "TRACK" => {
+        if let Some(track) = sheet.tracks.last_mut() {
+          track.start_sec = track.start_sec.or(pregap_start);
+        }
+        pregap_start = None;
+        let Some(number) = rest
+          .split_whitespace()
+          .next()
+          .and_then(|value| value.parse().ok())
+        else {
+          continue;
+        };
+        sheet.tracks.push(CueSheetTrack {
+          number,
+          ..Default::default()
+        });
+      }
+      "TITLE" | "PERFORMER" => {
+        let value = cue_argument(rest);
+        let is_title = command.eq_ignore_ascii_case("TITLE");
+        match (sheet.tracks.last_mut(), is_title) {
+          (Some(track), true) => track.title = value,
+          (Some(track), false) => track.performer = value,
+          (None, true) => sheet.title = value,
+          (None, false) => sheet.performer = value,
+        }
+      }
+      "INDEX" => {
+        let mut args = rest.split_whitespace();
+        let index: Option<u32> = args.next().and_then(|value| value.parse().ok());
+        let time = args.next().and_then(parse_cue_time);
+        let Some(track) = sheet.tracks.last_mut() else {
+          continue;
+        };
+        match index {
+          Some(1) => track.start_sec = time,
+          Some(0) => pregap_start = time,
+          _ => {}
+        }
+      }
+      _ => {}
+    }
+  }
+  if let Some(track) = sheet.tracks.last_mut() {
+    track.start_sec = track.start_sec.or(pregap_start);
+  }
+  sheet
+}
+
+/// 内嵌 cue sheet 优先（带曲目标题/艺人），否则退回容器章节；结束位置取下一轨起点或总时长
+fn build_sub_tracks(
+  layout: &FfmpegMediaLayout,
+  total_duration_sec: Option<f64>,
+) -> (Vec<AudioSubTrackInfo>, Option<CueSheet>) {
+  let in_range = |start: f64| total_duration_sec.is_none_or(|total| start < total);
+  let sheet = layout.cuesheet.as_deref().map(parse_cue_sheet);
+  if let Some(sheet) = sheet.as_ref() {
+    let mut entries: Vec<(&CueSheetTrack, f64)> = sheet
+      .tracks
+      .iter()
+      .filter_map(|track| track.start_sec.map(|start| (track, start)))
+      .filter(|(_, start)| in_range(*start))
+      .collect();
+    entries.sort_by(|a, b| a.1.total_cmp(&b.1));
+    if !entries.is_empty() {
+      let sub_tracks = entries
+        .iter()
+        .enumerate()
+        .map(|(index, (track, start))| AudioSubTrackInfo {
+          index: index as u32,
+          number: Some(track.number),
+          title: track.title.clone(),
+          performer: track.performer.clone().or_else(|| sheet.performer.clone()),
+          start_sec: *start,
+          end_sec: entries
+            .get(index + 1)
+            .map(|(_, next)| *next)
+            .or(total_duration_sec),
+          source: "cuesheet".to_string(),
+        })
+        .collect();
+      return (sub_tracks, Some(sheet.clone()));
+    }
+  }
+
+  let mut chapters: Vec<_> = layout
+    .chapters
+    .iter()
+    .filter(|chapter| in_range(chapter.start_sec))
+    .collect();
+  chapters.sort_by(|a, b| a.start_sec.total_cmp(&b.start_sec));
+  let sub_tracks = chapters
+    .iter()
+    .enumerate()
+    .map(|(index, chapter)| AudioSubTrackInfo {
+      index: index as u32,
+      number: None,
+      title: chapter.title.clone(),
+      performer: None,
+      start_sec: chapter.start_sec,
+      end_sec: chapter
+        .end_sec
+        .or_else(|| chapters.get(index + 1).map(|next| next.start_sec))
+        .or(total_duration_sec),
+      source: "chapter".to_string(),
+    })
+    .collect();
+  (sub_tracks, sheet)
+}
+
+/// 默认解码的音轨序号：带 default 标记的第一条，否则第一条
+fn default_stream_index(layout: &FfmpegMediaLayout) -> usize {
+  layout
+    .streams
+    .iter()
+    .position(|stream| stream.is_default)
+    .unwrap_or(0)
+}
+
+fn default_duration_sec(layout: &FfmpegMediaLayout) -> Option<f64> {
+  layout
+    .streams
+    .get(default_stream_index(layout))
+    .and_then(|stream| stream.duration_sec)
+}
+
+fn probe_sub_tracks(path: &Path) -> StdResult<Vec<AudioSubTrackInfo>, DecodeError> {
+  let layout = ffmpeg_probe_layout(path)?;
+  Ok(build_sub_tracks(&layout, default_duration_sec(&layout)).0)
+}
+
+/// 枚举容器内的音频流与分轨（内嵌 cue sheet / 章节）
+#[napi]
+pub fn list_audio_tracks(file_path: String) -> AudioTrackLayout {
+  let mut result = AudioTrackLayout {
+    file_path: file_path.clone(),
+    tracks: Vec::new(),
+    sub_tracks: Vec::new(),
+    album_title: None,
+    album_performer: None,
+    error_code: None,
+    error: None,
+  };
+  let path = Path::new(&file_path);
+  let layout = match check_source_file(path).and_then(|_| ffmpeg_probe_layout(path)) {
+    Ok(layout) => layout,
+    Err(error) => {
+      result.error_code = Some(error.kind.as_str().to_string());
+      result.error = Some(error.to_string());
+      return result;
+    }
+  };
+  let (sub_tracks, sheet) = build_sub_tracks(&layout, default_duration_sec(&layout));
+  result.tracks = layout
+    .streams
+    .iter()
+    .enumerate()
+    .map(|(index, stream)| AudioTrackInfo {
+      index: index as u32,
+      codec: stream.codec_name.clone(),
+      sample_rate: stream.sample_rate,
+      channels: stream.channels as u32,
+      duration_sec: stream.duration_sec,
+      language: stream.language.clone(),
+      title: stream.title.clone(),
+      is_default: stream.is_default,
+    })
+    .collect();
+  result.sub_tracks = sub_tracks;
+  if let Some(sheet) = sheet {
+    result.album_title = sheet.title;
+    result.album_performer = sheet.performer;
+  }
+  result
+}
+
+fn invalid_argument(detail: &str) -> DecodeError {
+  DecodeError::new(
+    DecodeErrorKind::InvalidArgument,
+    DecodeBackend::Ffmpeg,
+    detail,
+  )
+}
+
+fn decode_sub_track(
+  path: &Path,
+  options: &AudioSubTrackDecodeOptions,
+) -> StdResult<DecodeAudioResult, DecodeError> {
+  check_source_file(path)?;
+  let (start_sec, end_sec) = match options.sub_track_index {
+    Some(sub_track_index) => {
+      let sub_tracks = probe_sub_tracks(path)?;
+      let sub_track = sub_tracks
+        .get(sub_track_index as usize)
+        .ok_or_else(|| invalid_argument("分轨序号超出范围"))?;
+      (sub_track.start_sec, sub_track.end_sec)
+    }
+    None => (
+      options
+        .start_sec
+        .filter(|value| value.is_finite())
+        .unwrap_or(0.0)
+        .max(0.0),
+      options.end_sec.filter(|value| value.is_finite()),
+    ),
+  };
+  if end_sec.is_some_and(|end| end <= start_sec) {
+    return Err(invalid_argument("结束位置必须晚于开始位置"));
+  }
+
+  let decoded = ffmpeg_decode_stream_i16(
+    path,
+    options.track_index,
+    Some(start_sec),
+    end_sec.map(|end| end - start_sec),
+  )?;
+  let channels = decoded.channels.max(1) as usize;
+  let mut pcm_f32: Vec<f32> = decoded
+    .samples_i16
+    .iter()
+    .map(|sample| *sample as f32 / 32768.0)
+    .collect();
+  // gapless 标签描述的是默认音轨
+  let is_default_track = match options.track_index {
+    None => true,
+    Some(track_index) => ffmpeg_probe_layout(path)
+      .is_ok_and(|layout| default_stream_index(&layout) == track_index as usize),
+  };
+  let gapless = if is_default_track {
+    gapless_info::read_gapless_info(path)
+  } else {
+    None
+  };
+  if let Some(info) = gapless.as_ref() {
+    gapless_info::apply_ffmpeg_gapless(
+      &mut pcm_f32,
+      channels,
+      decoded.sample_rate,
+      start_sec,
+      info,
+    );
+  }
+
+  Ok(DecodeAudioResult {
+    total_frames: (pcm_f32.len() / channels) as f64,
+    pcm_data: Buffer::from(cast_slice(&pcm_f32).to_vec()),
+    sample_rate: decoded.sample_rate,
+    channels: decoded.channels.min(u8::MAX as u16) as u8,
+    decoder_backend: Some("ffmpeg-native".to_string()),
+    priming_offset_ms: gapless.and_then(|info| info.priming_offset_ms(decoded.sample_rate)),
+    error: None,
+    error_code: None,
+    decode_errors: None,
+  })
+}
+
+/// 解码虚拟子轨：按分轨序号或时间范围截取，可选择多音轨容器中的音轨
+#[napi]
+pub fn decode_audio_sub_track(
+  file_path: String,
+  options: AudioSubTrackDecodeOptions,
+) -> DecodeAudioResult {
+  match decode_sub_track(Path::new(&file_path), &options) {
+    Ok(result) => result,
+    Err(error) => DecodeAudioResult::failure(Some("ffmpeg-native"), &[error]),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ffmpeg_decode::{FfmpegAudioStreamInfo, FfmpegChapterInfo};
+
+  const ALBUM_CUE: &str = "\u{feff}REM GENRE Electronic\r\n\
+PERFORMER \"Various\"\r\n\
+TITLE \"Live Set\"\r\n\
+FILE \"set.flac\" WAVE\r\n\
+  TRACK 01 AUDIO\r\n\
+    TITLE \"Opening\"\r\n\
+    PERFORMER \"Artist A\"\r\n\
+    INDEX 01 00:00:00\r\n\
+  TRACK 02 AUDIO\r\n\
+    TITLE \"Second\"\r\n\
+    INDEX 00 04:58:50\r\n\
+    INDEX 01 05:00:37\r\n\
+  TRACK 03 AUDIO\r\n\
+    TITLE \"Pregap Only\"\r\n\
+    INDEX 00 09:30:00\r\n";
+
+  #[test]
+  fn parse_cue_sheet_reads_album_and_track_fields() {
+    let sheet = parse_cue_sheet(ALBUM_CUE);
+    assert_eq!(sheet.title.as_deref(), Some("Live Set"));
+    assert_eq!(sheet.performer.as_deref(), Some("Various"));
+    assert_eq!(sheet.tracks.len(), 3);
+    assert_eq!(sheet.tracks[0].performer.as_deref(), Some("Artist A"));
+    assert_eq!(sheet.tracks[1].number, 2);
+    // INDEX 01 优先于 INDEX 00；37 帧 = 37/75 秒
+    assert!((sheet.tracks[1].start_sec.unwrap() - (300.0 + 37.0 / 75.0)).abs() < 1e-9);
+    // 只有 INDEX 00 时退回 pregap 起点
+    assert_eq!(sheet.tracks[2].start_sec, Some(570.0));
+    assert_eq!(parse_cue_time("01:60:00"), None);
+  }
+
+  #[test]
+  fn build_sub_tracks_prefers_cuesheet_and_falls_back_to_chapters() {
+    let chapter = |start_sec: f64, title: &str| FfmpegChapterInfo {
+      start_sec,
+      end_sec: None,
+      title: Some(title.to_string()),
+    };
+    let mut layout = FfmpegMediaLayout {
+      streams: Vec::new(),
+      chapters: vec![chapter(120.0, "B"), chapter(0.0, "A")],
+      cuesheet: Some(ALBUM_CUE.to_string()),
+    };
+
+    let (sub_tracks, sheet) = build_sub_tracks(&layout, Some(600.0));
+    assert!(sheet.is_some());
+    assert_eq!(sub_tracks.len(), 3);
+    assert_eq!(sub_tracks[0].source, "cuesheet");
+    assert_eq!(sub_tracks[1].performer.as_deref(), Some("Various"));
+    assert_eq!(sub_tracks[0].end_sec, Some(sub_tracks[1].start_sec));
+    assert_eq!(sub_tracks[2].end_sec, Some(600.0));
+
+    layout.cuesheet = None;
+    let (sub_tracks, _) = build_sub_tracks(&layout, Some(600.0));
+    assert_eq!(sub_tracks.len(), 2);
+    assert_eq!(sub_tracks[0].title.as_deref(), Some("A"));
+    assert_eq!(sub_tracks[0].end_sec, Some(120.0));
+    assert_eq!(sub_tracks[1].end_sec, Some(600.0));
+    assert_eq!(sub_tracks[1].source, "chapter");
+  }
+
+  #[test]
+  fn default_stream_index_follows_disposition_flag() {
+    let stream = |is_default: bool, duration_sec: f64| FfmpegAudioStreamInfo {
+      sample_rate: 44_100,
+      channels: 2,
+      duration_sec: Some(duration_sec),
+      is_default,
+      codec_name: "aac".to_string(),
+      language: None,
+      title: None,
+    };
+    let mut layout = FfmpegMediaLayout {
+      streams: vec![stream(false, 60.0), stream(true, 61.0)],
+      chapters: Vec::new(),
+      cuesheet: None,
+    };
+    assert_eq!(default_stream_index(&layout), 1);
+    assert_eq!(default_duration_sec(&layout), Some(61.0));
+    layout.streams[1].is_default = false;
+    assert_eq!(default_stream_index(&layout), 0);
+  }
+}
@@ -0,0 +1,350 @@
+//! 原生转码：基于已链接的 libav 把任意可解码音频转为 WAV/AIFF/FLAC/MP3/AAC，
+//! 替代导出流程对 ffmpeg 可执行文件的依赖
+
+use std::cell::Cell;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_double, c_int, c_void};
+use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+
+use crate::cancel_registry::CancelRegistry;
+use crate::decode_error::{
+  check_source_file, DecodeBackend, DecodeError, DecodeErrorKind, FRKB_ERR_CANCELLED,
+};
+
+/// 与 frkb_ffmpeg_decode_wrapper.h 中的 FRKB_TRANSCODE_* 保持一致
+const TRANSCODE_WAV: c_int = 0;
+const TRANSCODE_AIFF: c_int = 1;
+const TRANSCODE_FLAC: c_int = 2;
+const TRANSCODE_MP3: c_int = 3;
+const TRANSCODE_AAC: c_int = 4;
+/// 进度回调的最小百分比步长
+const PROGRESS_STEP_PERCENT: f64 = 1.0;
+const PART_FILE_SUFFIX: &str = ".frkb-part";
+
+#[repr(C)]
+struct FrkbTranscodeOptions {
+  format: c_int,
+  sample_rate: c_int,
+  channels: c_int,
+  bit_depth: c_int,
+  bitrate_kbps: c_int,
+  preserve_metadata: c_int,
+  preserve_cover_art: c_int,
+}
+
+extern "C" {
+  fn frkb_ffmpeg_transcode(
+    input_path: *const c_char,
+    output_path: *const c_char,
+    options: *const FrkbTranscodeOptions,
+    opaque: *mut c_void,
+    should_cancel: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+    on_progress: Option<unsafe extern "C" fn(*mut c_void, c_double, c_double)>,
+    av_error_out: *mut c_int,
+  ) -> c_int;
+}
+
+/// 转码参数
+#[napi(object)]
+pub struct AudioTranscodeOptions {
+  /// wav / aiff / flac / mp3 / aac（输出 M4A 容器）
+  pub format: String,
+  /// 输出采样率，缺省保留源采样率
+  pub sample_rate: Option<u32>,
+  /// 输出声道数，缺省保留源声道数
+  pub channels: Option<u32>,
+  /// 16 / 24 / 32，仅 WAV/AIFF/FLAC 有效，缺省 16
+  pub bit_depth: Option<u32>,
+  /// 仅 MP3/AAC 有效，缺省 320 / 256
+  pub bitrate_kbps: Option<u32>,
+  /// 复制标签，缺省 true
+  pub preserve_metadata: Option<bool>,
+  /// 复制内嵌封面（WAV 不支持），缺省 true
+  pub preserve_cover_art: Option<bool>,
+}
+
+/// 转码进度
+#[napi(object)]
+pub struct AudioTranscodeProgress {
+  pub task_id: String,
+  pub processed_sec: f64,
+  pub total_sec: Option<f64>,
+  /// 0..100，总时长未知时为 0
+  pub percent: f64,
+}
+
+/// 转码结果
+#[napi(object)]
+pub struct AudioTranscodeResult {
+  pub task_id: String,
+  pub input_path: String,
+  pub output_path: String,
+  pub success: bool,
+  pub cancelled: bool,
+  pub elapsed_ms: f64,
+  pub error_code: Option<String>,
+  pub error: Option<String>,
+}
+
+static TRANSCODE_TASKS: CancelRegistry = CancelRegistry::new();
+
+struct TranscodeCallbackState<'a> {
+  task_id: &'a str,
+  cancel_flag: &'a AtomicBool,
+  callback: Option<&'a ThreadsafeFunction<AudioTranscodeProgress>>,
+  last_percent: Cell<f64>,
+}
+
+unsafe extern "C" fn transcode_should_cancel(opaque: *mut c_void) -> c_int {
+  if opaque.is_null() {
+    return 0;
+  }
+  let state = &*(opaque as *const TranscodeCallbackState);
+  state.cancel_flag.load(Ordering::Relaxed) as c_int
+}
+
+unsafe extern "C" fn transcode_on_progress(
+  opaque: *mut c_void,
+  processed_sec: c_double,
+  total_sec: c_double,
+) {
+  if opaque.is_null() {
+    return;
+  }
+  let state = &*(opaque as *const TranscodeCallbackState);
+  let Some(callback) = state.callback else {
+    return;
+  };
+  let total_sec = Some(total_sec).filter(|value| value.is_finite() && *value > 0.0);
+  let percent = total_sec
+    .map(|total| (processed_sec / total * 100.0).clamp(0.0, 100.0))
+    .unwrap_or(0.0);
+  let is_final = percent >= 100.0;
+  if !is_final && percent - state.last_percent.get() < PROGRESS_STEP_PERCENT {
+    return;
+  }
+  state.last_percent.set(percent);
+  callback.call(
+    Ok(AudioTranscodeProgress {
+      task_id: state.task_id.to_string(),
+      processed_sec: processed_sec.max(0.0),
+      total_sec,
+      percent,
+    }),
+    ThreadsafeFunctionCallMode::Blocking,
+  );
+}
+
+fn invalid_argument(detail: impl Into<String>) -> DecodeError {
+  DecodeError::new(
+    DecodeErrorKind::InvalidArgument,
+    DecodeBackend::Ffmpeg,
+    detail,
+  )
+}
+
+fn parse_transcode_format(format: &str) -> Option<c_int> {
+  match format
+    .trim()
+    .trim_start_matches('.')
+    .to_ascii_lowercase()
+    .as_str()
+  {
+    "wav" | "wave" => Some(TRANSCODE_WAV),
+    "aif" | "aiff" => Some(TRANSCODE_AIFF),
+    "flac" => Some(TRANSCODE_FLAC),
+    "mp3" => Some(TRANSCODE_MP3),
+    "aac" | "m4a" => Some(TRANSCODE_AAC),
+    _ => None,
+  }
+}
+
+fn build_native_options(
+  options: &AudioTranscodeOptions,
+) -> StdResult<FrkbTranscodeOptions, DecodeError> {
+  let format = parse_transcode_format(&options.format)
+    .ok_or_else(|| invalid_argument(format!("不支持的转码格式: {}", options.format)))?;
+  if let Some(bit_depth) = options.bit_depth {
+    if ![16, 24, 32].contains(&bit_depth) {
+      return Err(invalid_argument(format!("不支持的位深: {}", bit_depth)));
+    }
+  }
+  let clamp = |value: Option<u32>| value.unwrap_or(0).min(c_int::MAX as u32) as c_int;
+  Ok(FrkbTranscodeOptions {
+    format,
+    sample_rate: clamp(options.sample_rate),
+    channels: clamp(options.channels),
+    bit_depth: clamp(options.bit_depth),
+    bitrate_kbps: clamp(options.bitrate_kbps),
+    preserve_metadata: options.preserve_metadata.unwrap_or(true) as c_int,
+    preserve_cover_art: options.preserve_cover_art.unwrap_or(true) as c_int,
+  })
+}
+
+/// 先写到同目录的临时文件，成功后再替换目标，避免留下半截文件
+fn part_path_for(output_path: &Path) -> PathBuf {
+  let mut part = output_path.as_os_str().to_owned();
+  part.push(PART_FILE_SUFFIX);
+  PathBuf::from(part)
+}
+
+fn path_to_cstring(path: &Path) -> StdResult<CString, DecodeError> {
+  CString::new(path.to_string_lossy().into_owned())
+    .map_err(|_| invalid_argument("路径包含无效的 NUL 字符"))
+}
+
+/// 返回 Ok(false) 表示被取消
+fn run_transcode(
+  input_path: &Path,
+  output_path: &Path,
+  options: &AudioTranscodeOptions,
+  state: &TranscodeCallbackState,
+) -> StdResult<bool, DecodeError> {
+  check_source_file(input_path)?;
+  let native_options = build_native_options(options)?;
+  if let Some(parent) = output_path
+    .parent()
+    .filter(|parent| !parent.as_os_str().is_empty())
+  {
+    std::fs::create_dir_all(parent)
+      .map_err(|error| DecodeError::from_io(DecodeBackend::Io, &error))?;
+  }
+  let part_path = part_path_for(output_path);
+  let c_input = path_to_cstring(input_path)?;
+  let c_part = path_to_cstring(&part_path)?;
+
+  let mut av_error: c_int = 0;
+  let rc = unsafe {
+    frkb_ffmpeg_transcode(
+      c_input.as_ptr(),
+      c_part.as_ptr(),
+      &native_options,
+      state as *const TranscodeCallbackState as *mut c_void,
+      Some(transcode_should_cancel),
+      Some(transcode_on_progress),
+      &mut av_error,
+    )
+  };
+  if rc != 0 {
+    let _ = std::fs::remove_file(&part_path);
+    if rc == FRKB_ERR_CANCELLED {
+      return Ok(false);
+    }
+    return Err(DecodeError::from_ffmpeg(rc, av_error, None));
+  }
+  std::fs::rename(&part_path, output_path).map_err(|error| {
+    let _ = std::fs::remove_file(&part_path);
+    DecodeError::from_io(DecodeBackend::Io, &error)
+  })?;
+  Ok(true)
+}
+
+/// 转码单个文件；同一 taskId 可通过 cancelAudioTranscode 取消
+#[napi]
+pub async fn transcode_audio_file(
+  task_id: String,
+  input_path: String,
+  output_path: String,
+  options: AudioTranscodeOptions,
+  callback: Option<ThreadsafeFunction<AudioTranscodeProgress>>,
+) -> napi::Result<AudioTranscodeResult> {
+  let started_at = Instant::now();
+  let guard = TRANSCODE_TASKS.register(&task_id);
+  let state = TranscodeCallbackState {
+    task_id: &task_id,
+    cancel_flag: guard.cancel_flag(),
+    callback: callback.as_ref(),
+    last_percent: Cell::new(f64::NEG_INFINITY),
+  };
+  let outcome = run_transcode(
+    Path::new(&input_path),
+    Path::new(&output_path),
+    &options,
+    &state,
+  );
+  drop(guard);
+
+  let mut result = AudioTranscodeResult {
+    task_id: task_id.clone(),
+    input_path,
+    output_path,
+    success: false,
+    cancelled: false,
+    elapsed_ms: started_at.elapsed().as_secs_f64() * 1000.0,
+    error_code: None,
+    error: None,
+  };
+  match outcome {
+    Ok(true) => result.success = true,
+    Ok(false) => result.cancelled = true,
+    Err(error) => {
+      result.error_code = Some(error.kind.as_str().to_string());
+      result.error = Some(format!("转码失败: {}", error.detail));
+    }
+  }
+  Ok(result)
+}
+
+/// 请求取消转码任务；任务不存在（已结束）时返回 false
+#[napi]
+pub fn cancel_audio_transcode(task_id: String) -> bool {
+  TRANSCODE_TASKS.cancel(&task_id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn options(format: &str, bit_depth: Option<u32>) -> AudioTranscodeOptions {
+    AudioTranscodeOptions {
+      format: format.to_string(),
+      sample_rate: Some(44_100),
+      channels: None,
+      bit_depth,
+      bitrate_kbps: None,
+      preserve_metadata: None,
+      preserve_cover_art: Some(false),
+    }
+  }
+
+  #[test]
+  fn build_native_options_maps_formats_and_rejects_bad_depth() {
+    let native = build_native_options(&options(".AIFF", Some(24))).unwrap();
+    assert_eq!(native.format, TRANSCODE_AIFF);
+    assert_eq!(native.bit_depth, 24);
+    assert_eq!(native.sample_rate, 44_100);
+    assert_eq!(native.channels, 0);
+    assert_eq!(native.preserve_metadata, 1);
+    assert_eq!(native.preserve_cover_art, 0);
+    assert_eq!(
+      build_native_options(&options("m4a", None)).unwrap().format,
+      TRANSCODE_AAC
+    );
+
+    let error = build_native_options(&options("flac", Some(20)))
+      .err()
+      .unwrap();
+    assert_eq!(error.kind, DecodeErrorKind::InvalidArgument);
+    assert!(build_native_options(&options("ogg", None)).is_err());
+  }
+
+  #[test]
+  fn cancel_only_reaches_registered_tasks_and_guard_cleans_up() {
+    assert!(!cancel_audio_transcode("missing".to_string()));
+    let guard = TRANSCODE_TASKS.register("job-1");
+    assert!(cancel_audio_transcode("job-1".to_string()));
+    assert!(guard.is_cancelled());
+    drop(guard);
+    assert!(!cancel_audio_transcode("job-1".to_string()));
+    assert_eq!(
+      part_path_for(Path::new("/out/a.mp3")),
+      PathBuf::from("/out/a.mp3.frkb-part")
+    );
+  }
+}
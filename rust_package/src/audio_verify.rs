@@ -0,0 +1,303 @@
+//! 音频完整性校验：整轨解码统计坏包、比对声明时长，并可 remux 出丢弃坏包的修复副本
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+use rayon::prelude::*;
+use ring::digest::{digest, SHA256};
+
+use crate::decode_error::{check_source_file, DecodeBackend, DecodeError, FRKB_ERR_DECODE};
+use crate::ffmpeg_decode::{ffmpeg_verify_audio, FfmpegVerifyReport};
+use crate::ProcessProgress;
+
+/// 解码时长短于声明时长超过该值（取绝对值与比例中较大者）才判为截断；
+/// 留出编码器 priming/padding 的余量
+const TRUNCATION_TOLERANCE_SEC: f64 = 0.5;
+const TRUNCATION_TOLERANCE_RATIO: f64 = 0.01;
+
+/// 校验选项
+#[napi(object)]
+pub struct AudioVerifyOptions {
+  /// 修复副本输出目录；为空时只校验不修复
+  pub salvage_dir: Option<String>,
+}
+
+/// 坏包明细
+#[napi(object)]
+pub struct AudioVerifyBadPacket {
+  /// 包时间戳（秒）
+  pub position_sec: Option<f64>,
+  /// 包在文件中的字节偏移
+  pub byte_offset: Option<f64>,
+  /// 错误类型，同 DecodeErrorInfo.code
+  pub code: String,
+  pub message: String,
+}
+
+/// 单个文件的校验结果
+#[napi(object)]
+pub struct AudioVerifyResult {
+  pub file_path: String,
+  /// ok / damaged / truncated / unreadable
+  pub status: String,
+  pub packet_count: f64,
+  pub bad_packet_count: f64,
+  /// 坏包明细（最多记录前 256 个）
+  pub bad_packets: Vec<AudioVerifyBadPacket>,
+  /// 容器声明的时长（秒）
+  pub declared_duration_sec: Option<f64>,
+  /// 声明时长由码率估算（此时不做截断判断）
+  pub declared_duration_estimated: bool,
+  /// 实际解码出的时长（秒）
+  pub decoded_duration_sec: Option<f64>,
+  /// 解码时长减去声明时长（秒），负值表示缺失
+  pub duration_delta_sec: Option<f64>,
+  /// 修复副本路径（仅在有问题且请求修复时生成）
+  pub salvaged_path: Option<String>,
+  /// 写入修复副本的包数
+  pub salvaged_packet_count: Option<f64>,
+  /// 错误类型（unreadable 或修复失败时）
+  pub error_code: Option<String>,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VerifyStatus {
+  Ok,
+  Damaged,
+  Truncated,
+}
+
+impl VerifyStatus {
+  fn as_str(self) -> &'static str {
+    match self {
+      VerifyStatus::Ok => "ok",
+      VerifyStatus::Damaged => "damaged",
+      VerifyStatus::Truncated => "truncated",
+    }
+  }
+}
+
+fn decoded_duration_sec(report: &FfmpegVerifyReport) -> Option<f64> {
+  (report.sample_rate > 0).then(|| report.decoded_frames as f64 / report.sample_rate as f64)
+}
+
+fn classify_report(report: &FfmpegVerifyReport) -> VerifyStatus {
+  if !report.declared_duration_estimated {
+    if let (Some(declared), Some(decoded)) =
+      (report.declared_duration_sec, decoded_duration_sec(report))
+    {
+      let tolerance = TRUNCATION_TOLERANCE_SEC.max(declared * TRUNCATION_TOLERANCE_RATIO);
+      if declared - decoded > tolerance {
+        return VerifyStatus::Truncated;
+      }
+    }
+  }
+  if report.bad_packet_count > 0 || report.read_error.is_some() {
+    VerifyStatus::Damaged
+  } else {
+    VerifyStatus::Ok
+  }
+}
+
+/// 修复副本命名：原文件名 + 路径哈希，避免同名文件互相覆盖，重复修复时覆盖旧副本
+fn salvage_path_for(source: &Path, salvage_dir: &Path) -> PathBuf {
+  let stem = source
+    .file_stem()
+    .map(|value| value.to_string_lossy().into_owned())
+    .unwrap_or_else(|| "audio".to_string());
+  let path_hash = digest(&SHA256, source.to_string_lossy().as_bytes());
+  let mut file_name = format!(
+    "{}.salvaged-{}",
+    stem,
+    hex::encode(&path_hash.as_ref()[..4])
+  );
+  if let Some(ext) = source.extension() {
+    file_name.push('.');
+    file_name.push_str(&ext.to_string_lossy());
+  }
+  salvage_dir.join(file_name)
+}
+
+fn unreadable_result(file_path: &str, error: &DecodeError) -> AudioVerifyResult {
+  AudioVerifyResult {
+    file_path: file_path.to_string(),
+    status: "unreadable".to_string(),
+    packet_count: 0.0,
+    bad_packet_count: 0.0,
+    bad_packets: Vec::new(),
+    declared_duration_sec: None,
+    declared_duration_estimated: false,
+    decoded_duration_sec: None,
+    duration_delta_sec: None,
+    salvaged_path: None,
+    salvaged_packet_count: None,
+    error_code: Some(error.kind.as_str().to_string()),
+    error: Some(error.to_string()),
+  }
+}
+
+fn build_result(file_path: &str, report: &FfmpegVerifyReport) -> AudioVerifyResult {
+  let decoded = decoded_duration_sec(report);
+  AudioVerifyResult {
+    file_path: file_path.to_string(),
+    status: classify_report(report).as_str().to_string(),
+    packet_count: report.packet_count as f64,
+    bad_packet_count: report.bad_packet_count as f64,
+    bad_packets: report
+      .bad_packets
+      .iter()
+      .map(|packet| {
+        let error = DecodeError::from_ffmpeg(FRKB_ERR_DECODE, packet.av_error, packet.position_sec);
+        AudioVerifyBadPacket {
+          position_sec: packet.position_sec,
+          byte_offset: packet.byte_offset.map(|value| value as f64),
+          code: error.kind.as_str().to_string(),
+          message: error.to_string(),
+        }
+      })
+      .collect(),
+    declared_duration_sec: report.declared_duration_sec,
+    declared_duration_estimated: report.declared_duration_estimated,
+    decoded_duration_sec: decoded,
+    duration_delta_sec: report
+      .declared_duration_sec
+      .zip(decoded)
+      .map(|(declared, decoded)| decoded - declared),
+    salvaged_path: None,
+    salvaged_packet_count: None,
+    error_code: None,
+    error: None,
+  }
+}
+
+fn verify_audio_file(file_path: &str, salvage_dir: Option<&Path>) -> AudioVerifyResult {
+  let path = Path::new(file_path);
+  if let Err(error) = check_source_file(path) {
+    return unreadable_result(file_path, &error);
+  }
+  let report = match ffmpeg_verify_audio(path, None) {
+    Ok(report) => report,
+    Err(error) => return unreadable_result(file_path, &error),
+  };
+  let mut result = build_result(file_path, &report);
+  let Some(salvage_dir) = salvage_dir else {
+    return result;
+  };
+  if classify_report(&report) == VerifyStatus::Ok || report.packet_count <= report.bad_packet_count
+  {
+    return result;
+  }
+
+  // 健康文件占绝大多数，只对有问题的文件再跑一遍带 remux 的解码
+  let salvage_path = salvage_path_for(path, salvage_dir);
+  let salvaged = std::fs::create_dir_all(salvage_dir)
+    .map_err(|error| DecodeError::from_io(DecodeBackend::Io, &error))
+    .and_then(|_| ffmpeg_verify_audio(path, Some(&salvage_path)));
+  match salvaged {
+    Ok(salvage_report) => {
+      result.salvaged_path = Some(salvage_path.to_string_lossy().into_owned());
+      result.salvaged_packet_count = Some(salvage_report.salvaged_packet_count as f64);
+    }
+    Err(error) => {
+      let _ = std::fs::remove_file(&salvage_path);
+      result.error_code = Some(error.kind.as_str().to_string());
+      result.error = Some(format!("修复副本生成失败: {}", error));
+    }
+  }
+  result
+}
+
+/// 批量完整性校验：整轨解码每个文件，报告坏包位置/数量与声明时长差异；
+/// 指定 `salvageDir` 时为有问题的文件生成丢弃坏包的修复副本（不重新编码）
+#[napi]
+pub async fn verify_audio_files(
+  file_paths: Vec<String>,
+  options: Option<AudioVerifyOptions>,
+  callback: Option<ThreadsafeFunction<ProcessProgress>>,
+) -> napi::Result<Vec<AudioVerifyResult>> {
+  let salvage_dir = options
+    .and_then(|options| options.salvage_dir)
+    .filter(|value| !value.trim().is_empty())
+    .map(PathBuf::from);
+  let total = file_paths.len() as i32;
+  let processed = AtomicI32::new(0);
+  let results = file_paths
+    .par_iter()
+    .map(|path| {
+      let result = verify_audio_file(path, salvage_dir.as_deref());
+      if let Some(callback) = callback.as_ref() {
+        let current = processed.fetch_add(1, Ordering::SeqCst) + 1;
+        callback.call(
+          Ok(ProcessProgress {
+            processed: current,
+            total,
+          }),
+          ThreadsafeFunctionCallMode::Blocking,
+        );
+      }
+      result
+    })
+    .collect();
+  Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn report(declared: Option<f64>, decoded_frames: u64, bad_packets: u64) -> FfmpegVerifyReport {
+    FfmpegVerifyReport {
+      packet_count: 1000,
+      bad_packet_count: bad_packets,
+      decoded_frames,
+      sample_rate: 44_100,
+      declared_duration_sec: declared,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn classify_report_flags_truncation_beyond_tolerance() {
+    // 声明 200 秒，解码 199.7 秒：在 priming/padding 容差内
+    assert_eq!(
+      classify_report(&report(Some(200.0), 44_100 * 1997 / 10, 0)),
+      VerifyStatus::Ok
+    );
+    assert_eq!(
+      classify_report(&report(Some(200.0), 44_100 * 150, 3)),
+      VerifyStatus::Truncated
+    );
+    assert_eq!(
+      classify_report(&report(Some(200.0), 44_100 * 200, 3)),
+      VerifyStatus::Damaged
+    );
+
+    let mut estimated = report(Some(200.0), 44_100 * 150, 0);
+    estimated.declared_duration_estimated = true;
+    assert_eq!(classify_report(&estimated), VerifyStatus::Ok);
+
+    let mut read_failed = report(None, 44_100 * 150, 0);
+    read_failed.read_error = Some(-5);
+    assert_eq!(classify_report(&read_failed), VerifyStatus::Damaged);
+  }
+
+  #[test]
+  fn salvage_path_keeps_extension_and_disambiguates_same_names() {
+    let dir = Path::new("/salvage");
+    let first = salvage_path_for(Path::new("/music/a/01 Intro.mp3"), dir);
+    let second = salvage_path_for(Path::new("/music/b/01 Intro.mp3"), dir);
+    assert_ne!(first, second);
+    assert_eq!(first.extension().and_then(|ext| ext.to_str()), Some("mp3"));
+    assert!(first
+      .file_name()
+      .and_then(|name| name.to_str())
+      .is_some_and(|name| name.starts_with("01 Intro.salvaged-")));
+    assert_eq!(
+      first,
+      salvage_path_for(Path::new("/music/a/01 Intro.mp3"), dir)
+    );
+  }
+}
@@ -0,0 +1,92 @@
+//! 按 taskId 登记的取消标志表：长任务开始时登记，对应的 cancel 接口置位，任务结束时自动移除
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use parking_lot::Mutex;
+
+pub(crate) struct CancelRegistry {
+  tasks: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl CancelRegistry {
+  pub(crate) const fn new() -> Self {
+    Self {
+      tasks: OnceLock::new(),
+    }
+  }
+
+  fn tasks(&self) -> &Mutex<HashMap<String, Arc<AtomicBool>>> {
+    self.tasks.get_or_init(|| Mutex::new(HashMap::new()))
+  }
+
+  /// 登记任务；同一 taskId 重复登记时新任务覆盖旧任务
+  pub(crate) fn register(&'static self, task_id: &str) -> CancelTaskGuard {
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    self
+      .tasks()
+      .lock()
+      .insert(task_id.to_string(), Arc::clone(&cancel_flag));
+    CancelTaskGuard {
+      registry: self,
+      task_id: task_id.to_string(),
+      cancel_flag,
+    }
+  }
+
+  /// 请求取消；任务不存在（已结束）时返回 false
+  pub(crate) fn cancel(&self, task_id: &str) -> bool {
+    match self.tasks().lock().get(task_id) {
+      Some(flag) => {
+        flag.store(true, Ordering::Relaxed);
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+/// 任务结束时从取消表中移除
+pub(crate) struct CancelTaskGuard {
+  registry: &'static CancelRegistry,
+  task_id: String,
+  cancel_flag: Arc<AtomicBool>,
+}
+
+impl CancelTaskGuard {
+  pub(crate) fn cancel_flag(&self) -> &AtomicBool {
+    &self.cancel_flag
+  }
+
+  pub(crate) fn is_cancelled(&self) -> bool {
+    self.cancel_flag.load(Ordering::Relaxed)
+  }
+}
+
+impl Drop for CancelTaskGuard {
+  fn drop(&mut self) {
+    let mut tasks = self.registry.tasks().lock();
+    if tasks
+      .get(&self.task_id)
+      .is_some_and(|flag| Arc::ptr_eq(flag, &self.cancel_flag))
+    {
+      tasks.remove(&self.task_id);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn stale_guard_keeps_newer_task_with_same_id() {
+    static REGISTRY: CancelRegistry = CancelRegistry::new();
+    let first = REGISTRY.register("a");
+    let second = REGISTRY.register("a");
+    drop(first);
+    assert!(REGISTRY.cancel("a"));
+    assert!(second.is_cancelled());
+  }
+}
@@ -0,0 +1,335 @@
+//! 解码错误模型：统一 Symphonia / FFmpeg / 文件系统的失败原因，供 UI 分组展示
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use napi_derive::napi;
+use symphonia::core::errors::Error as SymphoniaError;
+
+/// 与 native/ffmpeg/frkb_ffmpeg_decode_wrapper.c 中的错误码保持一致
+pub(crate) const FRKB_ERR_OPEN_INPUT: i32 = 1;
+pub(crate) const FRKB_ERR_STREAM_INFO: i32 = 2;
+pub(crate) const FRKB_ERR_NO_AUDIO: i32 = 3;
+pub(crate) const FRKB_ERR_OPEN_CODEC: i32 = 4;
+pub(crate) const FRKB_ERR_SWR_INIT: i32 = 5;
+pub(crate) const FRKB_ERR_SEEK: i32 = 8;
+pub(crate) const FRKB_ERR_DECODE: i32 = 9;
+pub(crate) const FRKB_ERR_INVALID_ARG: i32 = 10;
+pub(crate) const FRKB_ERR_CANCELLED: i32 = 11;
+pub(crate) const FRKB_ERR_DRM: i32 = 12;
+pub(crate) const FRKB_ERR_OUTPUT: i32 = 13;
+
+const fn fferrtag(a: u8, b: u8, c: u8, d: u8) -> i32 {
+  -((a as i32) | ((b as i32) << 8) | ((c as i32) << 16) | ((d as i32) << 24))
+}
+
+const AVERROR_EOF: i32 = fferrtag(b'E', b'O', b'F', b' ');
+const AVERROR_INVALIDDATA: i32 = fferrtag(b'I', b'N', b'D', b'A');
+const AVERROR_DECODER_NOT_FOUND: i32 = fferrtag(0xF8, b'D', b'E', b'C');
+const AVERROR_ENOENT: i32 = -2;
+const AVERROR_EPERM: i32 = -1;
+const AVERROR_EACCES: i32 = -13;
+
+/// 受 DRM 保护的扩展名（iTunes FairPlay）
+const DRM_EXTENSIONS: [&str; 1] = ["m4p"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DecodeErrorKind {
+  NotFound,
+  Permission,
+  UnsupportedCodec,
+  Corrupt,
+  Drm,
+  Truncated,
+  Cancelled,
+  InvalidArgument,
+  Other,
+}
+
+impl DecodeErrorKind {
+  pub(crate) fn as_str(self) -> &'static str {
+    match self {
+      DecodeErrorKind::NotFound => "not-found",
+      DecodeErrorKind::Permission => "permission",
+      DecodeErrorKind::UnsupportedCodec => "unsupported-codec",
+      DecodeErrorKind::Corrupt => "corrupt",
+      DecodeErrorKind::Drm => "drm",
+      DecodeErrorKind::Truncated => "truncated",
+      DecodeErrorKind::Cancelled => "cancelled",
+      DecodeErrorKind::InvalidArgument => "invalid-argument",
+      DecodeErrorKind::Other => "other",
+    }
+  }
+
+  /// 多个后端都失败时，越具体的原因越优先展示
+  fn priority(self) -> u8 {
+    match self {
+      DecodeErrorKind::NotFound => 0,
+      DecodeErrorKind::Permission => 1,
+      DecodeErrorKind::Drm => 2,
+      DecodeErrorKind::Truncated => 3,
+      DecodeErrorKind::Corrupt => 4,
+      DecodeErrorKind::UnsupportedCodec => 5,
+      DecodeErrorKind::InvalidArgument => 6,
+      DecodeErrorKind::Cancelled => 7,
+      DecodeErrorKind::Other => 8,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DecodeBackend {
+  Io,
+  Symphonia,
+  Ffmpeg,
+}
+
+impl DecodeBackend {
+  pub(crate) fn as_str(self) -> &'static str {
+    match self {
+      DecodeBackend::Io => "io",
+      DecodeBackend::Symphonia => "symphonia",
+      DecodeBackend::Ffmpeg => "ffmpeg",
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      DecodeBackend::Io => "IO",
+      DecodeBackend::Symphonia => "Symphonia",
+      DecodeBackend::Ffmpeg => "FFmpeg",
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DecodeError {
+  pub(crate) kind: DecodeErrorKind,
+  pub(crate) backend: DecodeBackend,
+  pub(crate) detail: String,
+  /// 出错位置（秒），未知时为空
+  pub(crate) position_sec: Option<f64>,
+}
+
+impl DecodeError {
+  pub(crate) fn new(
+    kind: DecodeErrorKind,
+    backend: DecodeBackend,
+    detail: impl Into<String>,
+  ) -> Self {
+    Self {
+      kind,
+      backend,
+      detail: detail.into(),
+      position_sec: None,
+    }
+  }
+
+  pub(crate) fn at(mut self, position_sec: Option<f64>) -> Self {
+    self.position_sec = position_sec.filter(|value| value.is_finite() && *value >= 0.0);
+    self
+  }
+
+  /// 在详情前补充所处阶段，如“探测音频格式失败”
+  pub(crate) fn context(mut self, stage: &str) -> Self {
+    self.detail = format!("{}: {}", stage, self.detail);
+    self
+  }
+
+  pub(crate) fn from_io(backend: DecodeBackend, error: &io::Error) -> Self {
+    let kind = match error.kind() {
+      io::ErrorKind::NotFound => DecodeErrorKind::NotFound,
+      io::ErrorKind::PermissionDenied => DecodeErrorKind::Permission,
+      io::ErrorKind::UnexpectedEof => DecodeErrorKind::Truncated,
+      _ => DecodeErrorKind::Other,
+    };
+    Self::new(kind, backend, error.to_string())
+  }
+
+  pub(crate) fn from_symphonia(error: &SymphoniaError) -> Self {
+    match error {
+      SymphoniaError::IoError(io_error) => Self::from_io(DecodeBackend::Symphonia, io_error),
+      SymphoniaError::DecodeError(detail) => {
+        Self::new(DecodeErrorKind::Corrupt, DecodeBackend::Symphonia, *detail)
+      }
+      SymphoniaError::Unsupported(detail) => Self::new(
+        DecodeErrorKind::UnsupportedCodec,
+        DecodeBackend::Symphonia,
+        *detail,
+      ),
+      other => Self::new(
+        DecodeErrorKind::Other,
+        DecodeBackend::Symphonia,
+        other.to_string(),
+      ),
+    }
+  }
+
+  /// 将 C wrapper 的错误码与 libav 的 AVERROR 一起映射为错误类型
+  pub(crate) fn from_ffmpeg(code: i32, av_error: i32, position_sec: Option<f64>) -> Self {
+    let kind = match (code, av_error) {
+      (FRKB_ERR_CANCELLED, _) => DecodeErrorKind::Cancelled,
+      (FRKB_ERR_DRM, _) => DecodeErrorKind::Drm,
+      (FRKB_ERR_INVALID_ARG, _) => DecodeErrorKind::InvalidArgument,
+      (_, AVERROR_ENOENT) => DecodeErrorKind::NotFound,
+      (_, AVERROR_EACCES) | (_, AVERROR_EPERM) => DecodeErrorKind::Permission,
+      (_, AVERROR_EOF) => DecodeErrorKind::Truncated,
+      (_, AVERROR_DECODER_NOT_FOUND) => DecodeErrorKind::UnsupportedCodec,
+      (FRKB_ERR_NO_AUDIO, _) | (FRKB_ERR_OPEN_CODEC, _) => DecodeErrorKind::UnsupportedCodec,
+      (FRKB_ERR_OPEN_INPUT, AVERROR_INVALIDDATA) => DecodeErrorKind::UnsupportedCodec,
+      (FRKB_ERR_STREAM_INFO, _) | (FRKB_ERR_DECODE, _) | (FRKB_ERR_SEEK, _) => {
+        DecodeErrorKind::Corrupt
+      }
+      _ => DecodeErrorKind::Other,
+    };
+    let stage = match code {
+      FRKB_ERR_OPEN_INPUT => "打开输入失败",
+      FRKB_ERR_STREAM_INFO => "读取流信息失败",
+      FRKB_ERR_NO_AUDIO => "未找到可解码音轨",
+      FRKB_ERR_OPEN_CODEC => "打开解码器失败",
+      FRKB_ERR_SWR_INIT => "初始化重采样失败",
+      FRKB_ERR_SEEK => "定位失败",
+      FRKB_ERR_DECODE => "解码数据失败",
+      FRKB_ERR_INVALID_ARG => "参数无效",
+      FRKB_ERR_CANCELLED => "解码被取消",
+      FRKB_ERR_DRM => "文件受 DRM 保护",
+      FRKB_ERR_OUTPUT => "写入输出文件失败",
+      _ => "未知错误",
+    };
+    let detail = if av_error != 0 {
+      format!("{}（错误码 {}，AVERROR {}）", stage, code, av_error)
+    } else {
+      format!("{}（错误码 {}）", stage, code)
+    };
+    Self::new(kind, DecodeBackend::Ffmpeg, detail).at(position_sec)
+  }
+
+  pub(crate) fn cancelled(backend: DecodeBackend) -> Self {
+    Self::new(DecodeErrorKind::Cancelled, backend, "解码被取消")
+  }
+
+  pub(crate) fn to_info(&self) -> DecodeErrorInfo {
+    DecodeErrorInfo {
+      code: self.kind.as_str().to_string(),
+      backend: self.backend.as_str().to_string(),
+      message: self.detail.clone(),
+      position_sec: self.position_sec,
+    }
+  }
+}
+
+impl fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.backend {
+      DecodeBackend::Io => write!(f, "文件读取失败: {}", self.detail)?,
+      backend => write!(f, "{} 解码失败: {}", backend.label(), self.detail)?,
+    }
+    if let Some(position_sec) = self.position_sec {
+      write!(f, " @ {:.3}s", position_sec)?;
+    }
+    Ok(())
+  }
+}
+
+impl From<DecodeError> for String {
+  fn from(error: DecodeError) -> Self {
+    error.to_string()
+  }
+}
+
+/// 单个后端的解码错误
+#[napi(object)]
+#[derive(Debug)]
+pub struct DecodeErrorInfo {
+  /// not-found / permission / unsupported-codec / corrupt / drm / truncated / cancelled / invalid-argument / other
+  pub code: String,
+  /// io / symphonia / ffmpeg
+  pub backend: String,
+  pub message: String,
+  /// 出错位置（秒）
+  pub position_sec: Option<f64>,
+}
+
+/// 解码前先确认文件可读，避免两个后端各自给出含糊的打开失败；DRM 扩展名直接拒绝
+pub(crate) fn check_source_file(path: &Path) -> Result<(), DecodeError> {
+  std::fs::File::open(path).map_err(|error| DecodeError::from_io(DecodeBackend::Io, &error))?;
+  let is_drm_extension = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(|ext| DRM_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+    .unwrap_or(false);
+  if is_drm_extension {
+    return Err(DecodeError::new(
+      DecodeErrorKind::Drm,
+      DecodeBackend::Io,
+      "文件受 DRM 保护",
+    ));
+  }
+  Ok(())
+}
+
+pub(crate) fn primary_decode_error(errors: &[DecodeError]) -> Option<&DecodeError> {
+  errors.iter().min_by_key(|error| error.kind.priority())
+}
+
+/// 兼容旧的 error 字符串："Symphonia 解码失败: ...; FFmpeg 解码失败: ..."
+pub(crate) fn join_decode_errors(errors: &[DecodeError]) -> String {
+  errors
+    .iter()
+    .map(|error| error.to_string())
+    .collect::<Vec<_>>()
+    .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ffmpeg_codes_map_to_kinds() {
+    assert_eq!(
+      DecodeError::from_ffmpeg(FRKB_ERR_OPEN_INPUT, AVERROR_ENOENT, None).kind,
+      DecodeErrorKind::NotFound
+    );
+    assert_eq!(
+      DecodeError::from_ffmpeg(FRKB_ERR_OPEN_INPUT, AVERROR_EACCES, None).kind,
+      DecodeErrorKind::Permission
+    );
+    assert_eq!(
+      DecodeError::from_ffmpeg(FRKB_ERR_STREAM_INFO, AVERROR_EOF, None).kind,
+      DecodeErrorKind::Truncated
+    );
+    assert_eq!(
+      DecodeError::from_ffmpeg(FRKB_ERR_DRM, 0, None).kind,
+      DecodeErrorKind::Drm
+    );
+    let corrupt = DecodeError::from_ffmpeg(FRKB_ERR_DECODE, AVERROR_INVALIDDATA, Some(12.5));
+    assert_eq!(corrupt.kind, DecodeErrorKind::Corrupt);
+    assert_eq!(corrupt.to_info().position_sec, Some(12.5));
+    assert!(corrupt.to_string().starts_with("FFmpeg 解码失败"));
+  }
+
+  #[test]
+  fn primary_error_prefers_specific_kinds_and_join_keeps_legacy_text() {
+    let errors = vec![
+      DecodeError::new(
+        DecodeErrorKind::UnsupportedCodec,
+        DecodeBackend::Symphonia,
+        "no reader",
+      ),
+      DecodeError::new(DecodeErrorKind::Drm, DecodeBackend::Ffmpeg, "drms"),
+    ];
+    assert_eq!(
+      primary_decode_error(&errors).map(|error| error.kind),
+      Some(DecodeErrorKind::Drm)
+    );
+    assert_eq!(
+      join_decode_errors(&errors),
+      "Symphonia 解码失败: no reader; FFmpeg 解码失败: drms"
+    );
+    let missing = check_source_file(Path::new("/nonexistent/frkb/track.mp3")).unwrap_err();
+    assert_eq!(missing.kind, DecodeErrorKind::NotFound);
+    assert_eq!(missing.backend, DecodeBackend::Io);
+  }
+}
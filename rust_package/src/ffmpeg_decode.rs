@@ -2,24 +2,133 @@ use std::ffi::CString;
 use std::os::raw::{c_char, c_double, c_int, c_void};
 use std::path::Path;
 use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Instant;
 
+use crate::decode_error::{DecodeBackend, DecodeError, DecodeErrorKind, FRKB_ERR_CANCELLED};
+use crate::gapless_info::{apply_ffmpeg_gapless, read_gapless_info};
+
+#[repr(C)]
+struct FrkbDecodeErrorInfo {
+  av_error: c_int,
+  position_sec: c_double,
+}
+
+/// C 侧不透明的流式解码句柄
+#[repr(C)]
+struct FrkbFfmpegStream {
+  _private: [u8; 0],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct FrkbBadPacket {
+  position_sec: c_double,
+  byte_offset: i64,
+  av_error: c_int,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct FrkbVerifyReport {
+  packet_count: i64,
+  bad_packet_count: i64,
+  decoded_frames: i64,
+  sample_rate: c_int,
+  declared_duration_sec: c_double,
+  declared_duration_estimated: c_int,
+  last_position_sec: c_double,
+  read_error: c_int,
+  salvaged_packet_count: i64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FrkbAudioStreamInfo {
+  sample_rate: c_int,
+  channels: c_int,
+  duration_sec: c_double,
+  is_default: c_int,
+  codec_name: [c_char; 32],
+  language: [c_char; 16],
+  title: [c_char; 128],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FrkbChapterInfo {
+  start_sec: c_double,
+  end_sec: c_double,
+  title: [c_char; 256],
+}
+
 extern "C" {
   fn frkb_ffmpeg_transport_decode(
     file_path: *const c_char,
+    audio_stream_ordinal: c_int,
     start_sec: c_double,
     max_duration_sec: c_double,
     target_sample_rate: c_int,
     target_channels: c_int,
+    trim_lead: c_int,
     samples_out: *mut *mut i16,
     sample_count_out: *mut usize,
     sample_rate_out: *mut c_int,
     channels_out: *mut c_int,
     cancel_opaque: *mut c_void,
     should_cancel: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+    error_out: *mut FrkbDecodeErrorInfo,
   ) -> c_int;
 
   fn frkb_ffmpeg_transport_free_samples(ptr: *mut i16);
+
+  fn frkb_ffmpeg_stream_open(
+    file_path: *const c_char,
+    audio_stream_ordinal: c_int,
+    stream_out: *mut *mut FrkbFfmpegStream,
+    sample_rate_out: *mut c_int,
+    channels_out: *mut c_int,
+    error_out: *mut FrkbDecodeErrorInfo,
+  ) -> c_int;
+
+  fn frkb_ffmpeg_stream_next(
+    stream: *mut FrkbFfmpegStream,
+    samples_out: *mut *const i16,
+    frames_out: *mut c_int,
+    error_out: *mut FrkbDecodeErrorInfo,
+  ) -> c_int;
+
+  fn frkb_ffmpeg_stream_seek(
+    stream: *mut FrkbFfmpegStream,
+    start_sec: c_double,
+    error_out: *mut FrkbDecodeErrorInfo,
+  ) -> c_int;
+
+  fn frkb_ffmpeg_stream_close(stream: *mut FrkbFfmpegStream);
+
+  fn frkb_ffmpeg_probe_layout(
+    file_path: *const c_char,
+    streams: *mut FrkbAudioStreamInfo,
+    stream_capacity: c_int,
+    stream_count_out: *mut c_int,
+    chapters: *mut FrkbChapterInfo,
+    chapter_capacity: c_int,
+    chapter_count_out: *mut c_int,
+    cuesheet_out: *mut *mut c_char,
+    av_error_out: *mut c_int,
+  ) -> c_int;
+
+  fn frkb_ffmpeg_free_string(ptr: *mut c_char);
+
+  fn frkb_ffmpeg_verify_audio(
+    file_path: *const c_char,
+    salvage_path: *const c_char,
+    report_out: *mut FrkbVerifyReport,
+    bad_packets: *mut FrkbBadPacket,
+    bad_packet_capacity: c_int,
+    bad_packet_written_out: *mut c_int,
+    av_error_out: *mut c_int,
+  ) -> c_int;
 }
 
 #[derive(Debug)]
@@ -51,9 +160,137 @@ pub(crate) struct FfmpegTransportDecodeMetrics {
   pub(crate) read_iterations: f64,
 }
 
+/// 布局探测时最多枚举的音频流与章节数
+const LAYOUT_MAX_STREAMS: usize = 32;
+const LAYOUT_MAX_CHAPTERS: usize = 256;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FfmpegAudioStreamInfo {
+  pub(crate) sample_rate: u32,
+  pub(crate) channels: u16,
+  pub(crate) duration_sec: Option<f64>,
+  pub(crate) is_default: bool,
+  pub(crate) codec_name: String,
+  pub(crate) language: Option<String>,
+  pub(crate) title: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FfmpegChapterInfo {
+  pub(crate) start_sec: f64,
+  pub(crate) end_sec: Option<f64>,
+  pub(crate) title: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct FfmpegMediaLayout {
+  pub(crate) streams: Vec<FfmpegAudioStreamInfo>,
+  pub(crate) chapters: Vec<FfmpegChapterInfo>,
+  /// 内嵌 cue sheet 原文
+  pub(crate) cuesheet: Option<String>,
+}
+
+/// 校验时最多记录的坏包明细条数（超出部分只计数）
+const VERIFY_MAX_BAD_PACKETS: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct FfmpegBadPacket {
+  pub(crate) position_sec: Option<f64>,
+  pub(crate) byte_offset: Option<u64>,
+  pub(crate) av_error: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct FfmpegVerifyReport {
+  pub(crate) packet_count: u64,
+  pub(crate) bad_packet_count: u64,
+  pub(crate) bad_packets: Vec<FfmpegBadPacket>,
+  pub(crate) decoded_frames: u64,
+  pub(crate) sample_rate: u32,
+  pub(crate) declared_duration_sec: Option<f64>,
+  /// 声明时长由码率估算，不可用于截断判断
+  pub(crate) declared_duration_estimated: bool,
+  pub(crate) last_position_sec: Option<f64>,
+  /// 读包以非 EOF 错误结束时的 AVERROR
+  pub(crate) read_error: Option<i32>,
+  pub(crate) salvaged_packet_count: u64,
+}
+
+/// 编码器前导（priming）的处理方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LeadTrim {
+  /// 保持 libavcodec 原始输出、不额外探测与 seek；内容哈希与指纹依赖其逐字节稳定
+  Raw,
+  /// 时间线从有效首帧起算，start_sec 按裁剪后的位置定位
+  Timeline,
+}
+
+impl LeadTrim {
+  fn c_flag(self) -> c_int {
+    match self {
+      LeadTrim::Raw => 0,
+      LeadTrim::Timeline => 1,
+    }
+  }
+}
+
+/// libav 解码输出格式；采样率 / 声道为 0 表示保留源格式（声道按 mono/stereo）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct NativeDecodeFormat {
+  pub(crate) sample_rate: u32,
+  pub(crate) channels: u16,
+  pub(crate) lead_trim: LeadTrim,
+}
+
+impl NativeDecodeFormat {
+  /// 内容哈希 / 批量分析：源格式、保留编码器前导，与引入前导裁剪前逐字节一致
+  pub(crate) const CONTENT: Self = Self {
+    sample_rate: 0,
+    channels: 0,
+    lead_trim: LeadTrim::Raw,
+  };
+
+  /// 按裁剪后的时间线解码
+  pub(crate) fn timeline(sample_rate: u32, channels: u16) -> Self {
+    Self {
+      sample_rate,
+      channels,
+      lead_trim: LeadTrim::Timeline,
+    }
+  }
+}
+
 pub(crate) const TRANSPORT_FFMPEG_SAMPLE_RATE: u32 = 44_100;
 pub(crate) const TRANSPORT_FFMPEG_CHANNELS: u16 = 2;
-const FRKB_ERR_CANCELLED: c_int = 11;
+
+/// 输出流实际使用的采样率；为 0（输出流尚未启动）时 transport 固定解码为 44.1k
+static TRANSPORT_OUTPUT_SAMPLE_RATE: AtomicU32 = AtomicU32::new(0);
+
+pub(crate) fn set_transport_output_sample_rate(sample_rate: u32) {
+  TRANSPORT_OUTPUT_SAMPLE_RATE.store(sample_rate, Ordering::Relaxed);
+}
+
+/// transport 解码结果的目标采样率
+pub(crate) fn transport_decode_sample_rate() -> u32 {
+  match TRANSPORT_OUTPUT_SAMPLE_RATE.load(Ordering::Relaxed) {
+    0 => TRANSPORT_FFMPEG_SAMPLE_RATE,
+    sample_rate => sample_rate,
+  }
+}
+
+fn invalid_output(detail: &str) -> DecodeError {
+  DecodeError::new(DecodeErrorKind::Other, DecodeBackend::Ffmpeg, detail)
+}
+
+fn path_to_cstring(path: &Path) -> StdResult<CString, DecodeError> {
+  CString::new(path.to_string_lossy().into_owned()).map_err(|_| {
+    DecodeError::new(
+      DecodeErrorKind::InvalidArgument,
+      DecodeBackend::Ffmpeg,
+      "音频路径包含无效的 NUL 字符",
+    )
+  })
+}
 
 fn elapsed_ms(started_at: Instant) -> f64 {
   started_at.elapsed().as_secs_f64() * 1000.0
@@ -76,17 +313,16 @@ where
 
 pub(crate) fn ffmpeg_decode_native_i16_cancellable<F>(
   path: &Path,
+  audio_stream: Option<u32>,
   start_sec: Option<f64>,
   max_duration_sec: Option<f64>,
-  target_sample_rate: u32,
-  target_channels: u16,
+  format: NativeDecodeFormat,
   should_cancel: F,
-) -> StdResult<Option<(FfmpegPcmData, f64)>, String>
+) -> StdResult<Option<(FfmpegPcmData, f64)>, DecodeError>
 where
   F: Fn() -> bool,
 {
-  let c_path = CString::new(path.to_string_lossy().into_owned())
-    .map_err(|_| "音频路径包含无效的 NUL 字符".to_string())?;
+  let c_path = path_to_cstring(path)?;
   let start_sec = start_sec
     .filter(|value| value.is_finite() && *value > 0.0)
     .unwrap_or(0.0);
@@ -98,21 +334,28 @@ where
   let mut sample_count: usize = 0;
   let mut sample_rate: c_int = 0;
   let mut channels: c_int = 0;
+  let mut error_info = FrkbDecodeErrorInfo {
+    av_error: 0,
+    position_sec: -1.0,
+  };
 
   let native_started_at = Instant::now();
   let rc = unsafe {
     frkb_ffmpeg_transport_decode(
       c_path.as_ptr(),
+      audio_stream.map_or(-1, |value| value.min(c_int::MAX as u32) as c_int),
       start_sec,
       max_duration_sec,
-      target_sample_rate as c_int,
-      target_channels as c_int,
+      format.sample_rate as c_int,
+      format.channels as c_int,
+      format.lead_trim.c_flag(),
       &mut samples_ptr,
       &mut sample_count,
       &mut sample_rate,
       &mut channels,
       &should_cancel as *const F as *mut c_void,
       Some(cancel_trampoline::<F>),
+      &mut error_info,
     )
   };
   let native_ms = elapsed_ms(native_started_at);
@@ -122,18 +365,22 @@ where
     return Ok(None);
   }
   if rc != 0 {
-    return Err(format!("FFmpeg native transport 解码失败，错误码: {}", rc));
+    return Err(DecodeError::from_ffmpeg(
+      rc,
+      error_info.av_error,
+      Some(error_info.position_sec),
+    ));
   }
   if sample_rate <= 0 {
     unsafe { frkb_ffmpeg_transport_free_samples(samples_ptr) };
-    return Err("FFmpeg native transport 输出采样率无效".to_string());
+    return Err(invalid_output("FFmpeg native transport 输出采样率无效"));
   }
   if channels <= 0 {
     unsafe { frkb_ffmpeg_transport_free_samples(samples_ptr) };
-    return Err("FFmpeg native transport 输出声道数无效".to_string());
+    return Err(invalid_output("FFmpeg native transport 输出声道数无效"));
   }
   if sample_count > 0 && samples_ptr.is_null() {
-    return Err("FFmpeg native transport 输出样本指针为空".to_string());
+    return Err(invalid_output("FFmpeg native transport 输出样本指针为空"));
   }
 
   let samples_i16 = if sample_count == 0 {
@@ -166,16 +413,37 @@ pub(crate) fn ffmpeg_decode_native_i16(
   max_duration_sec: Option<f64>,
   target_sample_rate: u32,
   target_channels: u16,
-) -> StdResult<(FfmpegPcmData, f64), String> {
+) -> StdResult<(FfmpegPcmData, f64), DecodeError> {
   ffmpeg_decode_native_i16_cancellable(
     path,
+    None,
     start_sec,
     max_duration_sec,
-    target_sample_rate,
-    target_channels,
+    NativeDecodeFormat::timeline(target_sample_rate, target_channels),
     || false,
   )?
-  .ok_or_else(|| "FFmpeg native transport 解码被取消".to_string())
+  .ok_or_else(|| DecodeError::cancelled(DecodeBackend::Ffmpeg))
+}
+
+/// 内容哈希 / 批量分析用的整轨解码；输出与引入前导裁剪前逐字节一致，已存的内容哈希保持有效
+pub(crate) fn ffmpeg_decode_content_i16_cancellable<F>(
+  path: &Path,
+  should_cancel: F,
+) -> StdResult<Option<FfmpegPcmData>, DecodeError>
+where
+  F: Fn() -> bool,
+{
+  Ok(
+    ffmpeg_decode_native_i16_cancellable(
+      path,
+      None,
+      None,
+      None,
+      NativeDecodeFormat::CONTENT,
+      should_cancel,
+    )?
+    .map(|(decoded, _native_ms)| decoded),
+  )
 }
 
 pub(crate) fn ffmpeg_decode_native_f32(
@@ -184,7 +452,7 @@ pub(crate) fn ffmpeg_decode_native_f32(
   max_duration_sec: Option<f64>,
   target_sample_rate: u32,
   target_channels: u16,
-) -> StdResult<(Vec<f32>, u32, u16, f64, f64), String> {
+) -> StdResult<(Vec<f32>, u32, u16, f64, f64), DecodeError> {
   ffmpeg_decode_native_f32_cancellable(
     path,
     start_sec,
@@ -193,7 +461,7 @@ pub(crate) fn ffmpeg_decode_native_f32(
     target_channels,
     || false,
   )?
-  .ok_or_else(|| "FFmpeg native transport 解码被取消".to_string())
+  .ok_or_else(|| DecodeError::cancelled(DecodeBackend::Ffmpeg))
 }
 
 pub(crate) fn ffmpeg_decode_native_f32_cancellable<F>(
@@ -203,16 +471,16 @@ pub(crate) fn ffmpeg_decode_native_f32_cancellable<F>(
   target_sample_rate: u32,
   target_channels: u16,
   should_cancel: F,
-) -> StdResult<Option<(Vec<f32>, u32, u16, f64, f64)>, String>
+) -> StdResult<Option<(Vec<f32>, u32, u16, f64, f64)>, DecodeError>
 where
   F: Fn() -> bool,
 {
   let Some((decoded, native_ms)) = ffmpeg_decode_native_i16_cancellable(
     path,
+    None,
     start_sec,
     max_duration_sec,
-    target_sample_rate,
-    target_channels,
+    NativeDecodeFormat::timeline(target_sample_rate, target_channels),
     should_cancel,
   )?
   else {
@@ -238,9 +506,9 @@ pub(crate) fn ffmpeg_decode_transport_native(
   path: &Path,
   start_sec: Option<f64>,
   max_duration_sec: Option<f64>,
-) -> StdResult<FfmpegTransportPcmData, String> {
+) -> StdResult<FfmpegTransportPcmData, DecodeError> {
   ffmpeg_decode_transport_native_cancellable(path, start_sec, max_duration_sec, || false)?
-    .ok_or_else(|| "FFmpeg native transport 解码被取消".to_string())
+    .ok_or_else(|| DecodeError::cancelled(DecodeBackend::Ffmpeg))
 }
 
 pub(crate) fn ffmpeg_decode_transport_native_cancellable<F>(
@@ -248,22 +516,53 @@ pub(crate) fn ffmpeg_decode_transport_native_cancellable<F>(
   start_sec: Option<f64>,
   max_duration_sec: Option<f64>,
   should_cancel: F,
-) -> StdResult<Option<FfmpegTransportPcmData>, String>
+) -> StdResult<Option<FfmpegTransportPcmData>, DecodeError>
+where
+  F: Fn() -> bool,
+{
+  ffmpeg_decode_transport_at_rate_cancellable(
+    path,
+    start_sec,
+    max_duration_sec,
+    transport_decode_sample_rate(),
+    should_cancel,
+  )
+}
+
+/// 按指定采样率做 transport 解码（分轨须与所属 deck 的 PCM 同采样率）
+pub(crate) fn ffmpeg_decode_transport_at_rate_cancellable<F>(
+  path: &Path,
+  start_sec: Option<f64>,
+  max_duration_sec: Option<f64>,
+  sample_rate: u32,
+  should_cancel: F,
+) -> StdResult<Option<FfmpegTransportPcmData>, DecodeError>
 where
   F: Fn() -> bool,
 {
-  let Some((samples_f32, sample_rate, channels, native_ms, convert_ms)) =
+  // 直接由 swr 在解码过程中转到目标采样率（一次连续转换，源采样率一致时不做转换）；
+  // sinc 只留给 resamplePcm 与导出
+  let Some((mut samples_f32, sample_rate, channels, native_ms, convert_ms)) =
     ffmpeg_decode_native_f32_cancellable(
       path,
       start_sec,
       max_duration_sec,
-      TRANSPORT_FFMPEG_SAMPLE_RATE,
+      sample_rate,
       TRANSPORT_FFMPEG_CHANNELS,
       should_cancel,
     )?
   else {
     return Ok(None);
   };
+  if let Some(info) = read_gapless_info(path) {
+    apply_ffmpeg_gapless(
+      &mut samples_f32,
+      channels.max(1) as usize,
+      sample_rate,
+      start_sec.unwrap_or(0.0),
+      &info,
+    );
+  }
   Ok(Some(FfmpegTransportPcmData {
     samples_f32,
     sample_rate,
@@ -282,6 +581,266 @@ where
   }))
 }
 
+/// 按音频流序号（只计音频流）解码指定时间范围，保留源采样率与 mono/stereo
+pub(crate) fn ffmpeg_decode_stream_i16(
+  path: &Path,
+  audio_stream: Option<u32>,
+  start_sec: Option<f64>,
+  max_duration_sec: Option<f64>,
+) -> StdResult<FfmpegPcmData, DecodeError> {
+  ffmpeg_decode_native_i16_cancellable(
+    path,
+    audio_stream,
+    start_sec,
+    max_duration_sec,
+    NativeDecodeFormat::timeline(0, 0),
+    || false,
+  )?
+  .map(|(decoded, _native_ms)| decoded)
+  .ok_or_else(|| DecodeError::cancelled(DecodeBackend::Ffmpeg))
+}
+
+/// 整轨只打开一次的 libav 解码上下文，按帧增量取出交错 i16（源采样率、mono/stereo）
+pub(crate) struct FfmpegDecodeStream {
+  raw: *mut FrkbFfmpegStream,
+  sample_rate: u32,
+  channels: u16,
+}
+
+// SAFETY: 句柄只经 &mut self 访问，libav 上下文不依赖创建它的线程
+unsafe impl Send for FfmpegDecodeStream {}
+
+impl FfmpegDecodeStream {
+  pub(crate) fn open(path: &Path, audio_stream: Option<u32>) -> StdResult<Self, DecodeError> {
+    let c_path = path_to_cstring(path)?;
+    let mut raw: *mut FrkbFfmpegStream = std::ptr::null_mut();
+    let mut sample_rate: c_int = 0;
+    let mut channels: c_int = 0;
+    let mut error_info = FrkbDecodeErrorInfo {
+      av_error: 0,
+      position_sec: -1.0,
+    };
+    let rc = unsafe {
+      frkb_ffmpeg_stream_open(
+        c_path.as_ptr(),
+        audio_stream.map_or(-1, |value| value.min(c_int::MAX as u32) as c_int),
+        &mut raw,
+        &mut sample_rate,
+        &mut channels,
+        &mut error_info,
+      )
+    };
+    if rc != 0 {
+      return Err(DecodeError::from_ffmpeg(rc, error_info.av_error, None));
+    }
+    let stream = Self {
+      raw,
+      sample_rate: sample_rate.max(0) as u32,
+      channels: channels.max(0) as u16,
+    };
+    if stream.raw.is_null() || stream.sample_rate == 0 || stream.channels == 0 {
+      return Err(invalid_output("FFmpeg 流式解码返回的音频参数无效"));
+    }
+    Ok(stream)
+  }
+
+  pub(crate) fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  pub(crate) fn channels(&self) -> u16 {
+    self.channels
+  }
+
+  /// 解码下一批帧追加到 out（f32）；返回 false 表示已到结尾
+  pub(crate) fn next_into(&mut self, out: &mut Vec<f32>) -> StdResult<bool, DecodeError> {
+    let mut samples: *const i16 = std::ptr::null();
+    let mut frames: c_int = 0;
+    let mut error_info = FrkbDecodeErrorInfo {
+      av_error: 0,
+      position_sec: -1.0,
+    };
+    let rc =
+      unsafe { frkb_ffmpeg_stream_next(self.raw, &mut samples, &mut frames, &mut error_info) };
+    if rc != 0 {
+      return Err(DecodeError::from_ffmpeg(
+        rc,
+        error_info.av_error,
+        Some(error_info.position_sec),
+      ));
+    }
+    if frames <= 0 || samples.is_null() {
+      return Ok(false);
+    }
+    let sample_count = frames as usize * self.channels as usize;
+    let samples = unsafe { std::slice::from_raw_parts(samples, sample_count) };
+    out.extend(samples.iter().map(|sample| *sample as f32 / 32768.0));
+    Ok(true)
+  }
+
+  pub(crate) fn seek(&mut self, start_sec: f64) -> StdResult<(), DecodeError> {
+    let mut error_info = FrkbDecodeErrorInfo {
+      av_error: 0,
+      position_sec: -1.0,
+    };
+    let rc = unsafe { frkb_ffmpeg_stream_seek(self.raw, start_sec, &mut error_info) };
+    if rc != 0 {
+      return Err(DecodeError::from_ffmpeg(
+        rc,
+        error_info.av_error,
+        Some(error_info.position_sec),
+      ));
+    }
+    Ok(())
+  }
+}
+
+impl Drop for FfmpegDecodeStream {
+  fn drop(&mut self) {
+    unsafe { frkb_ffmpeg_stream_close(self.raw) };
+  }
+}
+
+fn c_chars_to_string(chars: &[c_char]) -> Option<String> {
+  let bytes: Vec<u8> = chars
+    .iter()
+    .take_while(|value| **value != 0)
+    .map(|value| *value as u8)
+    .collect();
+  let text = String::from_utf8_lossy(&bytes).trim().to_string();
+  (!text.is_empty()).then_some(text)
+}
+
+/// 枚举音频流、章节与内嵌 cue sheet
+pub(crate) fn ffmpeg_probe_layout(path: &Path) -> StdResult<FfmpegMediaLayout, DecodeError> {
+  let c_path = path_to_cstring(path)?;
+  let empty_stream = FrkbAudioStreamInfo {
+    sample_rate: 0,
+    channels: 0,
+    duration_sec: -1.0,
+    is_default: 0,
+    codec_name: [0; 32],
+    language: [0; 16],
+    title: [0; 128],
+  };
+  let empty_chapter = FrkbChapterInfo {
+    start_sec: 0.0,
+    end_sec: -1.0,
+    title: [0; 256],
+  };
+  let mut streams = vec![empty_stream; LAYOUT_MAX_STREAMS];
+  let mut chapters = vec![empty_chapter; LAYOUT_MAX_CHAPTERS];
+  let mut stream_count: c_int = 0;
+  let mut chapter_count: c_int = 0;
+  let mut cuesheet_ptr: *mut c_char = std::ptr::null_mut();
+  let mut av_error: c_int = 0;
+
+  let rc = unsafe {
+    frkb_ffmpeg_probe_layout(
+      c_path.as_ptr(),
+      streams.as_mut_ptr(),
+      LAYOUT_MAX_STREAMS as c_int,
+      &mut stream_count,
+      chapters.as_mut_ptr(),
+      LAYOUT_MAX_CHAPTERS as c_int,
+      &mut chapter_count,
+      &mut cuesheet_ptr,
+      &mut av_error,
+    )
+  };
+  if rc != 0 {
+    return Err(DecodeError::from_ffmpeg(rc, av_error, None));
+  }
+
+  let cuesheet = if cuesheet_ptr.is_null() {
+    None
+  } else {
+    let text = unsafe { std::ffi::CStr::from_ptr(cuesheet_ptr) }
+      .to_string_lossy()
+      .into_owned();
+    unsafe { frkb_ffmpeg_free_string(cuesheet_ptr) };
+    Some(text).filter(|value| !value.trim().is_empty())
+  };
+  streams.truncate((stream_count.max(0) as usize).min(LAYOUT_MAX_STREAMS));
+  chapters.truncate((chapter_count.max(0) as usize).min(LAYOUT_MAX_CHAPTERS));
+  let non_negative = |value: f64| Some(value).filter(|value| value.is_finite() && *value >= 0.0);
+  Ok(FfmpegMediaLayout {
+    streams: streams
+      .iter()
+      .map(|stream| FfmpegAudioStreamInfo {
+        sample_rate: stream.sample_rate.max(0) as u32,
+        channels: stream.channels.clamp(0, u16::MAX as c_int) as u16,
+        duration_sec: non_negative(stream.duration_sec),
+        is_default: stream.is_default != 0,
+        codec_name: c_chars_to_string(&stream.codec_name).unwrap_or_default(),
+        language: c_chars_to_string(&stream.language),
+        title: c_chars_to_string(&stream.title),
+      })
+      .collect(),
+    chapters: chapters
+      .iter()
+      .map(|chapter| FfmpegChapterInfo {
+        start_sec: non_negative(chapter.start_sec).unwrap_or(0.0),
+        end_sec: non_negative(chapter.end_sec),
+        title: c_chars_to_string(&chapter.title),
+      })
+      .collect(),
+    cuesheet,
+  })
+}
+
+/// 完整解码校验；`salvage_path` 非空时把可解码的包 remux 到该路径
+pub(crate) fn ffmpeg_verify_audio(
+  path: &Path,
+  salvage_path: Option<&Path>,
+) -> StdResult<FfmpegVerifyReport, DecodeError> {
+  let c_path = path_to_cstring(path)?;
+  let c_salvage_path = salvage_path.map(path_to_cstring).transpose()?;
+  let mut report = FrkbVerifyReport::default();
+  let mut bad_packets = vec![FrkbBadPacket::default(); VERIFY_MAX_BAD_PACKETS];
+  let mut bad_packet_written: c_int = 0;
+  let mut av_error: c_int = 0;
+
+  let rc = unsafe {
+    frkb_ffmpeg_verify_audio(
+      c_path.as_ptr(),
+      c_salvage_path
+        .as_ref()
+        .map_or(std::ptr::null(), |value| value.as_ptr()),
+      &mut report,
+      bad_packets.as_mut_ptr(),
+      VERIFY_MAX_BAD_PACKETS as c_int,
+      &mut bad_packet_written,
+      &mut av_error,
+    )
+  };
+  if rc != 0 {
+    return Err(DecodeError::from_ffmpeg(rc, av_error, None));
+  }
+
+  bad_packets.truncate((bad_packet_written.max(0) as usize).min(VERIFY_MAX_BAD_PACKETS));
+  let non_negative = |value: f64| Some(value).filter(|value| value.is_finite() && *value >= 0.0);
+  Ok(FfmpegVerifyReport {
+    packet_count: report.packet_count.max(0) as u64,
+    bad_packet_count: report.bad_packet_count.max(0) as u64,
+    bad_packets: bad_packets
+      .into_iter()
+      .map(|packet| FfmpegBadPacket {
+        position_sec: non_negative(packet.position_sec),
+        byte_offset: u64::try_from(packet.byte_offset).ok(),
+        av_error: packet.av_error,
+      })
+      .collect(),
+    decoded_frames: report.decoded_frames.max(0) as u64,
+    sample_rate: report.sample_rate.max(0) as u32,
+    declared_duration_sec: non_negative(report.declared_duration_sec),
+    declared_duration_estimated: report.declared_duration_estimated != 0,
+    last_position_sec: non_negative(report.last_position_sec),
+    read_error: Some(report.read_error).filter(|value| *value != 0),
+    salvaged_packet_count: report.salvaged_packet_count.max(0) as u64,
+  })
+}
+
 #[cfg(test)]
 fn append_raw_s16le_to_f32(
   bytes: &[u8],
@@ -342,6 +901,16 @@ fn parse_raw_s16le_to_f32(
 mod tests {
   use super::*;
 
+  #[test]
+  fn content_decode_keeps_raw_native_output_for_stable_hashes() {
+    // 内容哈希依赖此路径逐字节稳定：不重采样、不改声道、不裁前导
+    let content = NativeDecodeFormat::CONTENT;
+    assert_eq!((content.sample_rate, content.channels), (0, 0));
+    assert_eq!(content.lead_trim, LeadTrim::Raw);
+    assert_eq!(LeadTrim::Raw.c_flag(), 0);
+    assert_eq!(LeadTrim::Timeline.c_flag(), 1);
+  }
+
   #[test]
   fn parse_raw_s16le_to_f32_builds_pcm_metadata_from_fixed_transport_format() {
     let parsed = parse_raw_s16le_to_f32(
@@ -0,0 +1,367 @@
+//! 无缝（gapless）信息：解析 MP3 LAME/Xing 标签与 MP4 iTunSMPB，
+//! 统一裁掉编码器延迟与尾部补零，使 Symphonia / FFmpeg 输出的时间线逐样本一致
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// MP3 解码器固有延迟（528 + 1），与 Symphonia 的 LAME 解析保持一致
+const MP3_DECODER_DELAY: u64 = 529;
+const MP3_INFO_SCAN_BYTES: usize = 16 * 1024;
+/// moov 超过该大小时不再读取（通常只有几百 KB）
+const MP4_MOOV_MAX_BYTES: u64 = 32 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GaplessSource {
+  Lame,
+  ITunSmpb,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct GaplessInfo {
+  pub(crate) source: GaplessSource,
+  /// 需裁掉的前导帧（编码器延迟 + 解码器延迟），源采样率
+  pub(crate) delay_frames: u64,
+  /// 尾部补零帧数
+  pub(crate) padding_frames: u64,
+  /// 裁剪后的有效帧数（标签未提供时为空）
+  pub(crate) valid_frames: Option<u64>,
+  /// 源采样率（未能解析时为空）
+  pub(crate) sample_rate: Option<u32>,
+  /// Info 标签由 LAME 写入（而非 Lavf/Lavc）
+  pub(crate) lame_encoder: bool,
+}
+
+impl GaplessInfo {
+  pub(crate) fn priming_offset_ms(&self, fallback_sample_rate: u32) -> Option<f64> {
+    let sample_rate = self.sample_rate.unwrap_or(fallback_sample_rate);
+    if sample_rate == 0 {
+      return None;
+    }
+    Some(self.delay_frames as f64 * 1000.0 / sample_rate as f64)
+  }
+
+  /// 输出采样率下的有效帧数
+  pub(crate) fn valid_frames_at(&self, output_sample_rate: u32) -> Option<u64> {
+    self
+      .valid_frames
+      .map(|frames| self.scale_frames(frames, output_sample_rate))
+  }
+
+  /// 源采样率帧数换算到输出采样率
+  fn scale_frames(&self, frames: u64, output_sample_rate: u32) -> u64 {
+    match self.sample_rate {
+      Some(source_rate) if source_rate > 0 && source_rate != output_sample_rate => {
+        (frames as f64 * output_sample_rate as f64 / source_rate as f64).round() as u64
+      }
+      _ => frames,
+    }
+  }
+}
+
+/// 读取文件的无缝信息；非 MP3/MP4 或标签缺失时返回 None
+pub(crate) fn read_gapless_info(path: &Path) -> Option<GaplessInfo> {
+  let mut file = File::open(path).ok()?;
+  let mut head = [0u8; 12];
+  file.read_exact(&mut head).ok()?;
+  if &head[4..8] == b"ftyp" {
+    return read_mp4_gapless_info(&mut file);
+  }
+  let frame_offset = if &head[..3] == b"ID3" {
+    let size = head[6..10]
+      .iter()
+      .fold(0u64, |acc, byte| (acc << 7) | u64::from(byte & 0x7f));
+    let footer = if head[5] & 0x10 != 0 { 10 } else { 0 };
+    10 + size + footer
+  } else {
+    0
+  };
+  file.seek(SeekFrom::Start(frame_offset)).ok()?;
+  let mut buf = Vec::with_capacity(MP3_INFO_SCAN_BYTES);
+  file
+    .take(MP3_INFO_SCAN_BYTES as u64)
+    .read_to_end(&mut buf)
+    .ok()?;
+  parse_mp3_gapless_info(&buf)
+}
+
+/// 在首个 MPEG Layer III 帧中解析 Xing/Info + LAME 扩展
+pub(crate) fn parse_mp3_gapless_info(buf: &[u8]) -> Option<GaplessInfo> {
+  let start = (0..buf.len().saturating_sub(4))
+    .find(|&index| buf[index] == 0xff && buf[index + 1] & 0xe0 == 0xe0)?;
+  let header = &buf[start..];
+  let version_bits = (header[1] >> 3) & 0x03;
+  let layer_bits = (header[1] >> 1) & 0x03;
+  if version_bits == 1 || layer_bits != 1 {
+    return None;
+  }
+  let is_mpeg1 = version_bits == 3;
+  let sample_rate = {
+    let base = match (header[2] >> 2) & 0x03 {
+      0 => 44_100,
+      1 => 48_000,
+      2 => 32_000,
+      _ => return None,
+    };
+    match version_bits {
+      3 => base,
+      2 => base / 2,
+      _ => base / 4,
+    }
+  };
+  let is_mono = header[3] >> 6 == 3;
+  let side_info_len = match (is_mpeg1, is_mono) {
+    (true, true) => 17,
+    (true, false) => 32,
+    (false, true) => 9,
+    (false, false) => 17,
+  };
+  let samples_per_frame: u64 = if is_mpeg1 { 1152 } else { 576 };
+
+  let mut pos = 4 + side_info_len;
+  let tag_id = header.get(pos..pos + 4)?;
+  if tag_id != b"Xing" && tag_id != b"Info" {
+    return None;
+  }
+  let flags = read_be_u32(header, pos + 4)?;
+  pos += 8;
+  let num_frames = if flags & 0x1 != 0 {
+    pos += 4;
+    Some(u64::from(read_be_u32(header, pos - 4)?))
+  } else {
+    None
+  };
+  if flags & 0x2 != 0 {
+    pos += 4;
+  }
+  if flags & 0x4 != 0 {
+    pos += 100;
+  }
+  if flags & 0x8 != 0 {
+    pos += 4;
+  }
+  let lame = header.get(pos..pos + 24)?;
+  let encoder = &lame[..4];
+  if encoder != b"LAME" && encoder != b"Lavf" && encoder != b"Lavc" {
+    return None;
+  }
+  let trim = (u64::from(lame[21]) << 16) | (u64::from(lame[22]) << 8) | u64::from(lame[23]);
+  let delay_frames = MP3_DECODER_DELAY + (trim >> 12);
+  let padding_frames = (trim & 0xfff).saturating_sub(MP3_DECODER_DELAY);
+  let valid_frames = num_frames
+    .and_then(|frames| (frames * samples_per_frame).checked_sub(delay_frames + padding_frames));
+  Some(GaplessInfo {
+    source: GaplessSource::Lame,
+    delay_frames,
+    padding_frames,
+    valid_frames,
+    sample_rate: Some(sample_rate),
+    lame_encoder: encoder == b"LAME",
+  })
+}
+
+/// iTunSMPB 文本：" 00000000 00000840 000001CA 00000000003F1AB6 ..."
+pub(crate) fn parse_itunsmpb(text: &str) -> Option<GaplessInfo> {
+  let fields: Vec<u64> = text
+    .split_whitespace()
+    .take(4)
+    .map(|field| u64::from_str_radix(field, 16))
+    .collect::<Result<_, _>>()
+    .ok()?;
+  if fields.len() < 4 {
+    return None;
+  }
+  Some(GaplessInfo {
+    source: GaplessSource::ITunSmpb,
+    delay_frames: fields[1],
+    padding_frames: fields[2],
+    valid_frames: (fields[3] > 0).then_some(fields[3]),
+    sample_rate: None,
+    lame_encoder: false,
+  })
+}
+
+fn read_mp4_gapless_info(file: &mut File) -> Option<GaplessInfo> {
+  let moov = read_mp4_moov(file)?;
+  let key_pos = find_bytes(&moov, b"iTunSMPB", 0)?;
+  let data_pos = find_bytes(&moov, b"data", key_pos)?;
+  let data_size = read_be_u32(&moov, data_pos.checked_sub(4)?)? as usize;
+  let text = moov.get(data_pos + 12..data_pos - 4 + data_size)?;
+  let mut info = parse_itunsmpb(&String::from_utf8_lossy(text))?;
+  // mp4a 样本描述中的 16.16 定点采样率
+  info.sample_rate = find_bytes(&moov, b"mp4a", 0)
+    .and_then(|pos| moov.get(pos + 28..pos + 30))
+    .map(|rate| u32::from(u16::from_be_bytes([rate[0], rate[1]])))
+    .filter(|rate| *rate > 0);
+  Some(info)
+}
+
+fn read_mp4_moov(file: &mut File) -> Option<Vec<u8>> {
+  let file_len = file.metadata().ok()?.len();
+  let mut offset = 0u64;
+  while offset + 8 <= file_len {
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut header = [0u8; 16];
+    file.read_exact(&mut header[..8]).ok()?;
+    let mut size = u64::from(u32::from_be_bytes([
+      header[0], header[1], header[2], header[3],
+    ]));
+    let mut header_len = 8;
+    if size == 1 {
+      file.read_exact(&mut header[8..16]).ok()?;
+      size = u64::from_be_bytes(header[8..16].try_into().ok()?);
+      header_len = 16;
+    } else if size == 0 {
+      size = file_len - offset;
+    }
+    if size < header_len {
+      return None;
+    }
+    if &header[4..8] == b"moov" {
+      let body_len = size - header_len;
+      if body_len > MP4_MOOV_MAX_BYTES {
+        return None;
+      }
+      let mut body = vec![0u8; body_len as usize];
+      file.read_exact(&mut body).ok()?;
+      return Some(body);
+    }
+    offset += size;
+  }
+  None
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+  haystack
+    .get(from..)?
+    .windows(needle.len())
+    .position(|window| window == needle)
+    .map(|pos| pos + from)
+}
+
+fn read_be_u32(buf: &[u8], pos: usize) -> Option<u32> {
+  let bytes = buf.get(pos..pos + 4)?;
+  Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn trim_interleaved(
+  samples: &mut Vec<f32>,
+  channels: usize,
+  lead_frames: u64,
+  max_frames: Option<u64>,
+) {
+  let channels = channels.max(1);
+  let lead_samples = (lead_frames as usize)
+    .saturating_mul(channels)
+    .min(samples.len());
+  samples.drain(..lead_samples);
+  if let Some(max_frames) = max_frames {
+    samples.truncate((max_frames as usize).saturating_mul(channels));
+  }
+}
+
+/// Symphonia 输出：MP3 已由 enable_gapless 在包级裁剪，MP4 需手动裁剪
+pub(crate) fn apply_symphonia_gapless(samples: &mut Vec<f32>, channels: usize, info: &GaplessInfo) {
+  if info.source == GaplessSource::Lame {
+    return;
+  }
+  trim_interleaved(samples, channels, info.delay_frames, info.valid_frames);
+}
+
+/// FFmpeg 输出：前导帧已由封装按 AV_PKT_DATA_SKIP_SAMPLES / initial_padding 裁掉，
+/// 时间线从有效首帧起算；这里只截掉尾部补零
+pub(crate) fn apply_ffmpeg_gapless(
+  samples: &mut Vec<f32>,
+  channels: usize,
+  output_sample_rate: u32,
+  start_sec: f64,
+  info: &GaplessInfo,
+) {
+  let Some(valid_frames) = info.valid_frames_at(output_sample_rate) else {
+    return;
+  };
+  let start_frame = (start_sec.max(0.0) * output_sample_rate as f64).round() as u64;
+  trim_interleaved(
+    samples,
+    channels,
+    0,
+    Some(valid_frames.saturating_sub(start_frame)),
+  );
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lame_frame(num_frames: u32, enc_delay: u32, enc_padding: u32) -> Vec<u8> {
+    // MPEG1 Layer III，44.1 kHz，立体声
+    let mut frame = vec![0xff, 0xfb, 0x90, 0x00];
+    frame.extend_from_slice(&[0u8; 32]);
+    frame.extend_from_slice(b"Info");
+    frame.extend_from_slice(&0x1u32.to_be_bytes());
+    frame.extend_from_slice(&num_frames.to_be_bytes());
+    let mut lame = [0u8; 36];
+    lame[..9].copy_from_slice(b"LAME3.100");
+    let trim = (enc_delay << 12) | enc_padding;
+    lame[21] = (trim >> 16) as u8;
+    lame[22] = (trim >> 8) as u8;
+    lame[23] = trim as u8;
+    frame.extend_from_slice(&lame);
+    frame
+  }
+
+  #[test]
+  fn parses_lame_delay_and_padding_like_symphonia() {
+    let mut buf = vec![0u8; 7];
+    buf.extend_from_slice(&lame_frame(100, 576, 1_200));
+    let info = parse_mp3_gapless_info(&buf).expect("lame info");
+    assert_eq!(info.source, GaplessSource::Lame);
+    assert_eq!(info.delay_frames, 576 + 529);
+    assert_eq!(info.padding_frames, 1_200 - 529);
+    assert_eq!(info.valid_frames, Some(100 * 1152 - 1_105 - 671));
+    assert_eq!(info.sample_rate, Some(44_100));
+    assert!(info.lame_encoder);
+    let priming = info.priming_offset_ms(0).expect("priming");
+    assert!((priming - 1_105.0 * 1000.0 / 44_100.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn parses_itunsmpb_fields() {
+    let info = parse_itunsmpb(" 00000000 00000840 000001CA 00000000003F1AB6 00000000 00000000")
+      .expect("itunsmpb");
+    assert_eq!(info.source, GaplessSource::ITunSmpb);
+    assert_eq!(info.delay_frames, 2112);
+    assert_eq!(info.padding_frames, 458);
+    assert_eq!(info.valid_frames, Some(0x3f1ab6));
+    assert!(parse_itunsmpb("garbage").is_none());
+  }
+
+  #[test]
+  fn ffmpeg_trim_clamps_padding_relative_to_start() {
+    let info = GaplessInfo {
+      source: GaplessSource::ITunSmpb,
+      delay_frames: 4,
+      padding_frames: 3,
+      valid_frames: Some(10),
+      sample_rate: Some(8_000),
+      lame_encoder: false,
+    };
+    let ramp: Vec<f32> = (0..17).map(|frame| frame as f32).collect();
+
+    // 前导已由 FFmpeg 裁掉：与 Symphonia 手动裁剪结果一致
+    let mut ffmpeg = ramp[4..].to_vec();
+    apply_ffmpeg_gapless(&mut ffmpeg, 1, 8_000, 0.0, &info);
+    let mut symphonia = ramp.clone();
+    apply_symphonia_gapless(&mut symphonia, 1, &info);
+    assert_eq!(
+      ffmpeg,
+      (4..14).map(|frame| frame as f32).collect::<Vec<_>>()
+    );
+    assert_eq!(ffmpeg, symphonia);
+
+    // 从第 6 帧起解码时只剩 4 个有效帧
+    let mut from_offset = ramp[10..].to_vec();
+    apply_ffmpeg_gapless(&mut from_offset, 1, 8_000, 6.0 / 8_000.0, &info);
+    assert_eq!(from_offset, symphonia[6..].to_vec());
+  }
+}
@@ -9,6 +9,8 @@ use napi::bindgen_prelude::*;
 mod horizontal_browse_transport_audio;
 #[path = "horizontal_browse_transport_auto_gain.rs"]
 mod horizontal_browse_transport_auto_gain;
+#[path = "horizontal_browse_transport_crossfader.rs"]
+mod horizontal_browse_transport_crossfader;
 #[path = "horizontal_browse_transport_decode.rs"]
 mod horizontal_browse_transport_decode;
 #[path = "horizontal_browse_transport_dynamic_grid.rs"]
@@ -21,29 +23,49 @@ mod horizontal_browse_transport_grid_sync;
 mod horizontal_browse_transport_limiter;
 #[path = "horizontal_browse_transport_loop.rs"]
 mod horizontal_browse_transport_loop;
+#[path = "horizontal_browse_transport_meter.rs"]
+mod horizontal_browse_transport_meter;
+#[path = "horizontal_browse_transport_midi.rs"]
+mod horizontal_browse_transport_midi;
+#[path = "horizontal_browse_transport_midi_io.rs"]
+mod horizontal_browse_transport_midi_io;
 #[path = "horizontal_browse_transport_mix.rs"]
 mod horizontal_browse_transport_mix;
+#[path = "horizontal_browse_transport_motion.rs"]
+mod horizontal_browse_transport_motion;
 #[path = "horizontal_browse_transport_napi.rs"]
 mod horizontal_browse_transport_napi;
+#[path = "horizontal_browse_transport_pcm_cache.rs"]
+mod horizontal_browse_transport_pcm_cache;
 #[path = "horizontal_browse_transport_recording.rs"]
 mod horizontal_browse_transport_recording;
 #[path = "horizontal_browse_transport_runtime.rs"]
 mod horizontal_browse_transport_runtime;
 #[path = "horizontal_browse_transport_snapshot.rs"]
 mod horizontal_browse_transport_snapshot;
+#[path = "horizontal_browse_transport_stems.rs"]
+mod horizontal_browse_transport_stems;
 #[path = "horizontal_browse_transport_types.rs"]
 mod horizontal_browse_transport_types;
 #[path = "horizontal_browse_transport_visualizer.rs"]
 mod horizontal_browse_transport_visualizer;
 use crate::FfmpegTransportDecodeMetrics;
 use horizontal_browse_transport_auto_gain::{DeckAutoGainState, LoudnessAnalysis};
+use horizontal_browse_transport_crossfader::CrossfaderSettings;
 use horizontal_browse_transport_decode::prepare_decoded_audio;
+use horizontal_browse_transport_motion::DeckMotionState;
 pub use horizontal_browse_transport_napi::*;
+pub use horizontal_browse_transport_pcm_cache::{
+  HorizontalBrowseTransportPcmCacheEntry, HorizontalBrowseTransportPcmCacheStatus,
+};
 pub use horizontal_browse_transport_recording::HorizontalBrowseTransportRecordingStatus;
 use horizontal_browse_transport_runtime::{
   drain_decode_diagnostics, engine, execute_decode_request_sync, native_now_ms,
   next_snapshot_sequence, schedule_decode_request,
 };
+use horizontal_browse_transport_stems::{
+  parse_stem_kind, schedule_stem_decode_request, DeckStemSet,
+};
 use horizontal_browse_transport_types::{
   parse_deck_id, BeatGridSnapshot, DeckDerivedState, DeckId, DecodeRequest,
   DynamicBeatGridClipSnapshot,
@@ -52,8 +74,11 @@ pub use horizontal_browse_transport_types::{
   HorizontalBrowseTransportBandState, HorizontalBrowseTransportBeatGridClipInput,
   HorizontalBrowseTransportBeatGridInput, HorizontalBrowseTransportDeckInput,
   HorizontalBrowseTransportDeckSnapshot, HorizontalBrowseTransportDecodeDiagnostic,
-  HorizontalBrowseTransportOutputSnapshot, HorizontalBrowseTransportSnapshot,
-  HorizontalBrowseTransportRekordboxBeatGridEntryInput, HorizontalBrowseTransportStateInput,
+  HorizontalBrowseTransportLevelMeterSnapshot, HorizontalBrowseTransportMeterSnapshot,
+  HorizontalBrowseTransportMidiEvent, HorizontalBrowseTransportMidiStatus,
+  HorizontalBrowseTransportOutputSnapshot, HorizontalBrowseTransportRekordboxBeatGridEntryInput,
+  HorizontalBrowseTransportSnapshot, HorizontalBrowseTransportStateInput,
+  HorizontalBrowseTransportStemSnapshot, HorizontalBrowseTransportStemsInput,
   HorizontalBrowseTransportVisualizerSnapshot,
 };
 
@@ -109,6 +134,11 @@ struct DeckState {
   loop_end_sec: f64,
   master_tempo_state: horizontal_browse_transport_audio::DeckMasterTempoState,
   scrub_preview: ScrubPreviewState,
+  stems: DeckStemSet,
+  motion: DeckMotionState,
+  resample_quality: crate::resampler::ResampleQuality,
+  /// sinc 回放时由控制线程装入，音频线程只查表
+  sinc_kernels: Option<Arc<crate::resampler::PlaybackSincKernels>>,
 }
 
 struct MetronomeState {
@@ -286,6 +316,10 @@ impl Default for DeckState {
       loop_end_sec: 0.0,
       master_tempo_state: horizontal_browse_transport_audio::DeckMasterTempoState::default(),
       scrub_preview: ScrubPreviewState::default(),
+      stems: DeckStemSet::default(),
+      motion: DeckMotionState::default(),
+      resample_quality: crate::resampler::ResampleQuality::default(),
+      sinc_kernels: None,
     }
   }
 }
@@ -306,8 +340,10 @@ struct HorizontalBrowseTransportEngine {
   quantize_enabled: [bool; 2],
   bpm_multiplier: [f64; 2],
   trim_gain: [f32; 2],
+  channel_fader: [f32; 2],
   master_gain: f32,
   crossfader_value: f32,
+  crossfader: CrossfaderSettings,
   master_limiter: horizontal_browse_transport_limiter::MasterLimiterState,
   visualizer_ring: Vec<f32>,
   visualizer_write_index: usize,
@@ -335,8 +371,10 @@ impl Default for HorizontalBrowseTransportEngine {
       quantize_enabled: [true, true],
       bpm_multiplier: [1.0, 1.0],
       trim_gain: [1.0, 1.0],
+      channel_fader: [1.0, 1.0],
       master_gain: 1.0,
       crossfader_value: 0.0,
+      crossfader: CrossfaderSettings::default(),
       master_limiter: horizontal_browse_transport_limiter::MasterLimiterState::default(),
       visualizer_ring: vec![0.0; HORIZONTAL_BROWSE_VISUALIZER_SAMPLE_COUNT],
       visualizer_write_index: 0,
@@ -353,6 +391,7 @@ impl HorizontalBrowseTransportEngine {
     *self = Self::default();
     self.output_sample_rate = output_sample_rate;
     self.output_channels = output_channels;
+    horizontal_browse_transport_meter::meter_ring().clear();
   }
 
   fn mark_state_changed(&mut self) {
@@ -514,6 +553,8 @@ impl HorizontalBrowseTransportEngine {
         eprintln!("[horizontal-browse-transport] default output config failed");
         return;
       };
+      // 以实际打开的输出配置为准，源采样率与之相同时 transport 解码保留原生采样率
+      crate::ffmpeg_decode::set_transport_output_sample_rate(supported.sample_rate().0);
       {
         let mut engine = engine().lock();
         engine.output_sample_rate = supported.sample_rate().0;
@@ -601,8 +642,10 @@ impl HorizontalBrowseTransportEngine {
     let mut playback_right = 0.0_f32;
     let mut record_left = 0.0_f32;
     let mut record_right = 0.0_f32;
+    let mut deck_programs = [(0.0_f32, 0.0_f32); 2];
     for deck in [DeckId::Top, DeckId::Bottom] {
       let deck_output = self.sample_deck_mix(deck);
+      deck_programs[Self::deck_index(deck)] = deck_output.program;
       playback_left += deck_output.program.0 + deck_output.monitor.0 + deck_output.metronome;
       playback_right += deck_output.program.1 + deck_output.monitor.1 + deck_output.metronome;
       record_left += deck_output.program.0;
@@ -620,6 +663,17 @@ impl HorizontalBrowseTransportEngine {
     let protected_playback_right =
       horizontal_browse_transport_limiter::soft_limit_sample(playback_right);
     self.push_visualizer_sample((protected_playback_left + protected_playback_right) * 0.5);
+    horizontal_browse_transport_meter::meter_ring().push_frame(
+      [
+        deck_programs[0].0,
+        deck_programs[0].1,
+        deck_programs[1].0,
+        deck_programs[1].1,
+        protected_playback_left,
+        protected_playback_right,
+      ],
+      self.output_sample_rate.max(1),
+    );
     self.recording.capture_frame(
       self.output_sample_rate.max(1),
       limited_record_left,
@@ -874,23 +928,23 @@ impl HorizontalBrowseTransportEngine {
     horizontal_browse_transport_audio::prime_master_tempo_state(target, output_sample_rate);
   }
 
-  fn resolve_crossfader_volumes(value: f32) -> (f32, f32) {
-    let safe_value = value.clamp(-1.0, 1.0);
-    if safe_value >= 0.0 {
-      (1.0, 1.0 - safe_value)
-    } else {
-      (1.0 + safe_value, 1.0)
-    }
-  }
-
+  /// 增益级联：trim → 自动增益 → 通道推子 → 横推曲线 → 总音量；监听取推子前信号
   fn refresh_output_gains(&mut self) {
     let (top_crossfader_gain, bottom_crossfader_gain) =
-      Self::resolve_crossfader_volumes(self.crossfader_value);
+      horizontal_browse_transport_crossfader::resolve_crossfader_volumes(
+        self.crossfader_value,
+        self.crossfader,
+      );
     let top_trim = self.trim_gain[Self::deck_index(DeckId::Top)];
     let bottom_trim = self.trim_gain[Self::deck_index(DeckId::Bottom)];
-    self.top.gain =
-      (top_trim * self.top.auto_gain.current_linear * self.master_gain * top_crossfader_gain)
-        .max(0.0);
+    let top_fader = self.channel_fader[Self::deck_index(DeckId::Top)];
+    let bottom_fader = self.channel_fader[Self::deck_index(DeckId::Bottom)];
+    self.top.gain = (top_trim
+      * self.top.auto_gain.current_linear
+      * top_fader
+      * self.master_gain
+      * top_crossfader_gain)
+      .max(0.0);
     self.top.cue_monitor_gain = if self.top.cue_monitor_enabled {
       (top_trim * self.top.auto_gain.current_linear * self.master_gain).max(0.0)
     } else {
@@ -898,6 +952,7 @@ impl HorizontalBrowseTransportEngine {
     };
     self.bottom.gain = (bottom_trim
       * self.bottom.auto_gain.current_linear
+      * bottom_fader
       * self.master_gain
       * bottom_crossfader_gain)
       .max(0.0);
@@ -950,5 +1005,14 @@ mod horizontal_browse_transport_decode_tests;
 #[path = "horizontal_browse_transport_grid_sync_tests.rs"]
 mod horizontal_browse_transport_grid_sync_tests;
 #[cfg(test)]
+#[path = "horizontal_browse_transport_midi_tests.rs"]
+mod horizontal_browse_transport_midi_tests;
+#[cfg(test)]
+#[path = "horizontal_browse_transport_motion_tests.rs"]
+mod horizontal_browse_transport_motion_tests;
+#[cfg(test)]
+#[path = "horizontal_browse_transport_stems_tests.rs"]
+mod horizontal_browse_transport_stems_tests;
+#[cfg(test)]
 #[path = "horizontal_browse_transport_tests.rs"]
 mod horizontal_browse_transport_tests;
@@ -1,3 +1,5 @@
+use super::horizontal_browse_transport_motion;
+use super::horizontal_browse_transport_stems;
 use super::DeckState;
 use std::ffi::c_void;
 
@@ -354,7 +356,7 @@ fn ensure_processor_configured(target: &mut DeckState, output_sample_rate: f64)
   configure_processor(target, output_sample_rate);
 }
 
-fn read_source_sample(
+pub(super) fn read_source_sample(
   source_data: &[f32],
   frame_count: usize,
   source_channels: usize,
@@ -382,6 +384,68 @@ fn read_source_sample(
   left + (right - left) * frac
 }
 
+/// 分轨就绪时以分轨混音替代主 PCM，保持同一源帧位置；
+/// source_step 为每个输出帧推进的源帧数，决定 sinc 核的截止
+fn read_deck_source_sample(
+  target: &DeckState,
+  frame_count: usize,
+  source_frame: f64,
+  channel: usize,
+  source_step: f64,
+) -> f32 {
+  if horizontal_browse_transport_stems::stems_render_active(target) {
+    return horizontal_browse_transport_stems::read_stem_mix_sample(
+      target,
+      frame_count,
+      source_frame,
+      channel,
+    );
+  }
+  match target.sinc_kernels.as_deref() {
+    Some(kernels) => kernels.select(source_step).interpolate(
+      target.pcm_data.as_ref().as_slice(),
+      frame_count,
+      target.channels.max(1) as usize,
+      source_frame,
+      channel,
+    ),
+    None => read_source_sample(
+      target.pcm_data.as_ref().as_slice(),
+      frame_count,
+      target.channels.max(1) as usize,
+      source_frame,
+      channel,
+    ),
+  }
+}
+
+fn source_step(target: &DeckState, rate: f64, output_sample_rate: f64) -> f64 {
+  rate.abs() * target.sample_rate as f64 / output_sample_rate.max(1.0)
+}
+
+pub(super) fn read_deck_timeline_frame(
+  target: &DeckState,
+  timeline_sec: f64,
+  rate: f64,
+  output_sample_rate: f64,
+) -> (f32, f32) {
+  if target.sample_rate == 0 || target.channels == 0 || !timeline_sec.is_finite() {
+    return (0.0, 0.0);
+  }
+  let frame_count = target.pcm_data.len() / target.channels as usize;
+  let audio_sec =
+    super::HorizontalBrowseTransportEngine::timeline_sec_to_audio_sec(target, timeline_sec);
+  let source_frame = (audio_sec - target.pcm_start_sec) * target.sample_rate as f64;
+  if source_frame < 0.0 || source_frame >= frame_count as f64 {
+    return (0.0, 0.0);
+  }
+  let source_step = source_step(target, rate, output_sample_rate);
+  (
+    read_deck_source_sample(target, frame_count, source_frame, 0, source_step),
+    read_deck_source_sample(target, frame_count, source_frame, 1, source_step),
+  )
+}
+
 fn sample_silent_lead_in(target: &mut DeckState, output_sample_rate: f64) -> bool {
   if !target.current_sec.is_finite() {
     target.current_sec = 0.0;
@@ -428,18 +492,10 @@ fn append_source_chunk(target: &mut DeckState, output_sample_rate: f64) -> bool
     .staging_input
     .resize(frames_to_feed * channels, 0.0);
 
-  let source_channels = target.channels.max(1) as usize;
-  let pcm_data = target.pcm_data.as_ref().as_slice();
   for frame_offset in 0..frames_to_feed {
     let source_frame = start_frame + frame_offset as f64 * source_step;
     for channel in 0..channels {
-      let sample = read_source_sample(
-        pcm_data,
-        frame_count,
-        source_channels,
-        source_frame,
-        channel,
-      );
+      let sample = read_deck_source_sample(target, frame_count, source_frame, channel, source_step);
       target.master_tempo_state.staging_input[frame_offset * channels + channel] = sample;
     }
   }
@@ -531,24 +587,11 @@ fn sample_deck_rate(target: &mut DeckState, output_sample_rate: f64) -> (f32, f3
     finish_deck_at_decoded_pcm_end(target);
     return (0.0, 0.0);
   }
-  let frac = (source_frame - base_index as f64) as f32;
-  let next_index = (base_index + 1).min(frame_count - 1);
-  let channels = target.channels as usize;
-  let read_sample = |frame_index: usize, channel: usize, data: &[f32]| -> f32 {
-    data
-      .get(frame_index * channels + channel.min(channels - 1))
-      .copied()
-      .unwrap_or(0.0)
-  };
-  let pcm_data = target.pcm_data.as_ref().as_slice();
-  let l0 = read_sample(base_index, 0, pcm_data);
-  let l1 = read_sample(next_index, 0, pcm_data);
-  let r0 = read_sample(base_index, if channels > 1 { 1 } else { 0 }, pcm_data);
-  let r1 = read_sample(next_index, if channels > 1 { 1 } else { 0 }, pcm_data);
-  let left = l0 + (l1 - l0) * frac;
-  let right = r0 + (r1 - r0) * frac;
-
   let rate = clamp_rate(target.playback_rate);
+  let source_step = source_step(target, rate, output_sample_rate);
+  let left = read_deck_source_sample(target, frame_count, source_frame, 0, source_step);
+  let right = read_deck_source_sample(target, frame_count, source_frame, 1, source_step);
+
   target.current_sec += rate / output_sample_rate;
   target.last_observed_at_ms = -1.0;
   if target.loop_active
@@ -618,24 +661,11 @@ fn sample_deck_scrub_preview(target: &mut DeckState, output_sample_rate: f64) ->
     return (0.0, 0.0);
   }
 
-  let frac = (source_frame - base_index as f64) as f32;
-  let next_index = (base_index + 1).min(frame_count - 1);
-  let channels = target.channels as usize;
-  let pcm_data = target.pcm_data.as_ref().as_slice();
-  let read_sample = |frame_index: usize, channel: usize| -> f32 {
-    pcm_data
-      .get(frame_index * channels + channel.min(channels - 1))
-      .copied()
-      .unwrap_or(0.0)
-  };
-  let l0 = read_sample(base_index, 0);
-  let l1 = read_sample(next_index, 0);
-  let r0 = read_sample(base_index, if channels > 1 { 1 } else { 0 });
-  let r1 = read_sample(next_index, if channels > 1 { 1 } else { 0 });
-  let left = l0 + (l1 - l0) * frac;
-  let right = r0 + (r1 - r0) * frac;
-
   let rate = clamp_scrub_rate(target.scrub_preview.rate);
+  let source_step = source_step(target, rate, output_sample_rate);
+  let left = read_deck_source_sample(target, frame_count, source_frame, 0, source_step);
+  let right = read_deck_source_sample(target, frame_count, source_frame, 1, source_step);
+
   target.scrub_preview.current_sec += rate / output_sample_rate;
   target.scrub_preview.current_sec = if target.duration_sec.is_finite() && target.duration_sec > 0.0
   {
@@ -715,6 +745,7 @@ fn sample_deck_master_tempo(target: &mut DeckState, output_sample_rate: f64) ->
 }
 
 pub(super) fn sample_deck(target: &mut DeckState, output_sample_rate: f64) -> (f32, f32) {
+  horizontal_browse_transport_stems::advance_stem_gains(target, output_sample_rate);
   if is_scrub_preview_rendering(target) {
     return sample_deck_scrub_preview(target, output_sample_rate);
   }
@@ -725,6 +756,9 @@ pub(super) fn sample_deck(target: &mut DeckState, output_sample_rate: f64) -> (f
   {
     return (0.0, 0.0);
   }
+  if target.motion.is_active() {
+    return horizontal_browse_transport_motion::sample_deck_motion(target, output_sample_rate);
+  }
   if sample_silent_lead_in(target, output_sample_rate) {
     return (0.0, 0.0);
   }
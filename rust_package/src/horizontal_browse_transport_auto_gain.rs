@@ -9,7 +9,6 @@ pub(super) const AUTO_GAIN_STATUS_UNAVAILABLE: &str = "unavailable";
 const AUTO_GAIN_MAX_BOOST_DB: f64 = 16.0;
 const AUTO_GAIN_MIN_GAIN_DB: f64 = -60.0;
 const AUTO_GAIN_PEAK_LIMIT_DBFS: f64 = -1.0;
-const AUTO_GAIN_LOUDNESS_FLOOR_DB: f64 = -70.0;
 const AUTO_GAIN_EPSILON: f64 = 1e-9;
 const AUTO_GAIN_SMOOTHING_SEC: f64 = 0.12;
 
@@ -39,32 +38,9 @@ impl Default for DeckAutoGainState {
 }
 
 pub(super) fn analyze_loudness(samples: &[f32], channels: u16) -> Option<LoudnessAnalysis> {
-  let channel_count = channels.max(1) as usize;
-  let frame_count = samples.len() / channel_count;
-  if frame_count == 0 {
-    return None;
-  }
-
-  let mut total_energy = 0.0_f64;
-  let mut peak = 0.0_f64;
-  for frame in 0..frame_count {
-    let base = frame * channel_count;
-    let mut frame_energy = 0.0_f64;
-    for channel in 0..channel_count {
-      let sample = samples.get(base + channel).copied().unwrap_or(0.0) as f64;
-      frame_energy += sample * sample;
-      peak = peak.max(sample.abs());
-    }
-    total_energy += frame_energy / channel_count as f64;
-  }
-
-  let mean_square = total_energy / frame_count as f64;
-  let integrated_db =
-    (10.0 * mean_square.max(AUTO_GAIN_EPSILON).log10()).max(AUTO_GAIN_LOUDNESS_FLOOR_DB);
-  let peak_db = 20.0 * peak.max(AUTO_GAIN_EPSILON).log10();
-  if !integrated_db.is_finite() || !peak_db.is_finite() {
-    return None;
-  }
+  let mut accumulator = crate::analysis_utils::LoudnessAccumulator::default();
+  accumulator.push(samples, channels);
+  let (integrated_db, peak_db) = accumulator.finish()?;
   Some(LoudnessAnalysis {
     integrated_db,
     peak_db,
@@ -0,0 +1,156 @@
+use std::f32::consts::FRAC_PI_2;
+
+/// 硬切曲线在推子末端的淡出宽度（推子全程为 2.0）
+const CROSSFADER_SHARP_CUT_WIDTH: f32 = 0.04;
+const CROSSFADER_CUSTOM_MIN_WIDTH: f32 = 0.02;
+const CROSSFADER_CUSTOM_MAX_WIDTH: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum CrossfaderCurve {
+  /// 中点两侧均为满电平，越过中点后线性衰减（原有默认曲线）
+  Linear,
+  /// 等功率，中点两侧约 -3 dB
+  ConstantPower,
+  /// 搓碟用硬切，只在推子末端极短行程内切换
+  SharpCut,
+  /// 由 slope 在等功率（0）与硬切（1）之间连续调节
+  Custom,
+}
+
+impl CrossfaderCurve {
+  pub(super) fn as_str(self) -> &'static str {
+    match self {
+      CrossfaderCurve::Linear => "linear",
+      CrossfaderCurve::ConstantPower => "constantPower",
+      CrossfaderCurve::SharpCut => "sharpCut",
+      CrossfaderCurve::Custom => "custom",
+    }
+  }
+}
+
+pub(super) fn parse_crossfader_curve(raw: &str) -> Option<CrossfaderCurve> {
+  match raw.trim() {
+    "linear" => Some(CrossfaderCurve::Linear),
+    "constantPower" => Some(CrossfaderCurve::ConstantPower),
+    "sharpCut" => Some(CrossfaderCurve::SharpCut),
+    "custom" => Some(CrossfaderCurve::Custom),
+    _ => None,
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct CrossfaderSettings {
+  pub(super) curve: CrossfaderCurve,
+  pub(super) slope: f32,
+  /// hamster：推子方向反转
+  pub(super) reverse: bool,
+}
+
+impl Default for CrossfaderSettings {
+  fn default() -> Self {
+    Self {
+      curve: CrossfaderCurve::Linear,
+      slope: 0.5,
+      reverse: false,
+    }
+  }
+}
+
+/// distance 为推子到对侧末端的距离（0..=2），width 内线性或正弦淡出
+fn fader_side_gain(distance: f32, width: f32, shaped: bool) -> f32 {
+  let linear = (distance / width.max(0.0001)).clamp(0.0, 1.0);
+  if shaped {
+    (linear * FRAC_PI_2).sin()
+  } else {
+    linear
+  }
+}
+
+/// 返回 (top, bottom) 两路的横推增益；value 为 -1（只出 bottom）..1（只出 top）
+pub(super) fn resolve_crossfader_volumes(value: f32, settings: CrossfaderSettings) -> (f32, f32) {
+  let safe_value = if value.is_finite() {
+    value.clamp(-1.0, 1.0)
+  } else {
+    0.0
+  };
+  let position = if settings.reverse {
+    -safe_value
+  } else {
+    safe_value
+  };
+  let top_distance = 1.0 + position;
+  let bottom_distance = 1.0 - position;
+  let (width, shaped) = match settings.curve {
+    CrossfaderCurve::Linear => (1.0, false),
+    CrossfaderCurve::ConstantPower => (CROSSFADER_CUSTOM_MAX_WIDTH, true),
+    CrossfaderCurve::SharpCut => (CROSSFADER_SHARP_CUT_WIDTH, false),
+    CrossfaderCurve::Custom => {
+      let slope = settings.slope.clamp(0.0, 1.0);
+      (
+        CROSSFADER_CUSTOM_MAX_WIDTH
+          + (CROSSFADER_CUSTOM_MIN_WIDTH - CROSSFADER_CUSTOM_MAX_WIDTH) * slope,
+        true,
+      )
+    }
+  };
+  (
+    fader_side_gain(top_distance, width, shaped),
+    fader_side_gain(bottom_distance, width, shaped),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn settings(curve: CrossfaderCurve, slope: f32, reverse: bool) -> CrossfaderSettings {
+    CrossfaderSettings {
+      curve,
+      slope,
+      reverse,
+    }
+  }
+
+  #[test]
+  fn linear_curve_keeps_full_level_until_center() {
+    let linear = settings(CrossfaderCurve::Linear, 0.5, false);
+    assert_eq!(resolve_crossfader_volumes(0.0, linear), (1.0, 1.0));
+    assert_eq!(resolve_crossfader_volumes(0.5, linear), (1.0, 0.5));
+    assert_eq!(resolve_crossfader_volumes(-1.0, linear), (0.0, 1.0));
+  }
+
+  #[test]
+  fn constant_power_curve_sums_to_unit_power() {
+    let curve = settings(CrossfaderCurve::ConstantPower, 0.5, false);
+    for step in 0..=20 {
+      let value = step as f32 / 10.0 - 1.0;
+      let (top, bottom) = resolve_crossfader_volumes(value, curve);
+      assert!((top * top + bottom * bottom - 1.0).abs() < 1e-5);
+    }
+    let (top, bottom) = resolve_crossfader_volumes(0.0, curve);
+    assert!((top - bottom).abs() < 1e-6);
+    assert!((top - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-5);
+  }
+
+  #[test]
+  fn sharp_cut_only_fades_at_the_far_edge() {
+    let curve = settings(CrossfaderCurve::SharpCut, 0.5, false);
+    assert_eq!(resolve_crossfader_volumes(0.95, curve), (1.0, 1.0));
+    assert_eq!(resolve_crossfader_volumes(1.0, curve), (1.0, 0.0));
+    assert_eq!(resolve_crossfader_volumes(-1.0, curve), (0.0, 1.0));
+  }
+
+  #[test]
+  fn custom_slope_spans_constant_power_to_cut_and_reverse_swaps_sides() {
+    let smooth = resolve_crossfader_volumes(0.5, settings(CrossfaderCurve::Custom, 0.0, false));
+    let power =
+      resolve_crossfader_volumes(0.5, settings(CrossfaderCurve::ConstantPower, 0.0, false));
+    assert!((smooth.0 - power.0).abs() < 1e-6 && (smooth.1 - power.1).abs() < 1e-6);
+    let steep = resolve_crossfader_volumes(0.9, settings(CrossfaderCurve::Custom, 1.0, false));
+    assert_eq!(steep, (1.0, 1.0));
+
+    let normal = resolve_crossfader_volumes(-0.6, settings(CrossfaderCurve::Linear, 0.5, false));
+    let hamster = resolve_crossfader_volumes(0.6, settings(CrossfaderCurve::Linear, 0.5, true));
+    assert_eq!(normal, hamster);
+  }
+}
@@ -32,6 +32,7 @@ impl HorizontalBrowseTransportEngine {
       target.pcm_start_sec = 0.0;
       target.sample_rate = 0;
       target.channels = 0;
+      target.stems.retain_owner("");
       horizontal_browse_transport_audio::clear_master_tempo_state(target);
       horizontal_browse_transport_audio::reset_band_filter_state(target);
       self.mark_state_changed();
@@ -60,6 +61,7 @@ impl HorizontalBrowseTransportEngine {
     target.pending_decode_file_path = Some(file_path.clone());
     target.pending_decode_start_sec = Some(startup_start_sec);
     target.pending_decode_max_duration_sec = Some(HORIZONTAL_BROWSE_STARTUP_DECODE_SEC);
+    target.stems.retain_owner(&file_path);
     if should_reset_loaded_audio {
       target.loaded_file_path = None;
       target.fully_decoded_file_path = None;
@@ -68,6 +70,7 @@ impl HorizontalBrowseTransportEngine {
       target.pcm_start_sec = 0.0;
       target.sample_rate = 0;
       target.channels = 0;
+      horizontal_browse_transport_motion::clear_deck_motion(target);
       horizontal_browse_transport_audio::clear_master_tempo_state(target);
       horizontal_browse_transport_audio::reset_band_filter_state(target);
     }
@@ -165,6 +168,34 @@ impl HorizontalBrowseTransportEngine {
     if !self.request_matches(deck, file_path, request_id, fully_decoded) {
       return false;
     }
+    self.install_prepared_decoded_audio(deck, file_path, prepared, fully_decoded);
+    true
+  }
+
+  /// 起播请求命中 PCM 缓存时直接装入完整音频，并让已排队的整轨解码失效
+  pub(super) fn apply_cached_full_decoded_audio(
+    &mut self,
+    deck: DeckId,
+    file_path: &str,
+    request_id: u64,
+    prepared: PreparedDecodedAudio,
+  ) -> bool {
+    if !self.request_matches(deck, file_path, request_id, false) {
+      return false;
+    }
+    let target = self.deck_mut(deck);
+    target.full_decode_request_id = target.full_decode_request_id.wrapping_add(1);
+    self.install_prepared_decoded_audio(deck, file_path, prepared, true);
+    true
+  }
+
+  fn install_prepared_decoded_audio(
+    &mut self,
+    deck: DeckId,
+    file_path: &str,
+    prepared: PreparedDecodedAudio,
+    fully_decoded: bool,
+  ) {
     let output_sample_rate = self.output_sample_rate.max(1) as f64;
     let target = self.deck_mut(deck);
     let should_reset_master_tempo = !prepared.preserve_master_tempo_state
@@ -195,7 +226,6 @@ impl HorizontalBrowseTransportEngine {
       horizontal_browse_transport_audio::reset_band_filter_state(target);
     }
     self.mark_state_changed();
-    true
   }
 
   pub(super) fn mark_decode_finished(
@@ -367,3 +367,33 @@ fn seek_outside_startup_block_does_not_request_stream_decode() {
   assert_eq!(engine.deck(DeckId::Top).decode_request_id, 11);
   assert_eq!(engine.deck(DeckId::Top).pending_decode_file_path, None);
 }
+
+#[test]
+fn cached_startup_decode_marks_deck_fully_decoded_and_stales_full_request() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  {
+    let top = engine.deck_mut(DeckId::Top);
+    top.file_path = Some("cached.mp3".to_string());
+    top.duration_sec = 10.0;
+    top.current_sec = 4.0;
+  }
+
+  let bootstrap = engine.prepare_decode_request(DeckId::Top).unwrap();
+  let full_request_id = engine.deck(DeckId::Top).full_decode_request_id;
+  let prepared = prepare_decoded_audio(None, vec![0.5; 40], 4, 1, bootstrap.start_sec, true);
+  assert!(engine.apply_cached_full_decoded_audio(
+    DeckId::Top,
+    "cached.mp3",
+    bootstrap.request_id,
+    prepared,
+  ));
+
+  let snapshot = engine.snapshot(1000.0);
+  assert!(snapshot.top.fully_decoded);
+  assert!(!snapshot.top.decoding);
+  assert_eq!(engine.deck(DeckId::Top).pcm_start_sec, 0.0);
+  assert!(engine
+    .capture_decode_apply_baseline(DeckId::Top, "cached.mp3", full_request_id, true)
+    .is_none());
+  assert!(engine.prepare_full_decode_request(DeckId::Top).is_none());
+}
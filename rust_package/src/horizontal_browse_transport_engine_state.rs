@@ -526,6 +526,7 @@ impl HorizontalBrowseTransportEngine {
         if allow_phase_alignment
           && self.sync_lock[deck_index] == "full"
           && self.quantize_enabled[deck_index]
+          && !self.deck(deck).motion.is_active()
           && self.is_playing_audible_at(deck, now_ms)
         {
           let target_duration_sec = self.deck(deck).duration_sec.max(0.0);
@@ -720,6 +721,42 @@ impl HorizontalBrowseTransportEngine {
     self.refresh_output_gains();
   }
 
+  pub(super) fn set_channel_fader(&mut self, deck: DeckId, value: f64) {
+    self.mark_state_changed();
+    self.channel_fader[Self::deck_index(deck)] = Self::clamp_unit_gain(value);
+    self.refresh_output_gains();
+  }
+
+  pub(super) fn set_crossfader_curve(
+    &mut self,
+    curve: horizontal_browse_transport_crossfader::CrossfaderCurve,
+    slope: Option<f64>,
+    reverse: Option<bool>,
+  ) {
+    self.mark_state_changed();
+    self.crossfader.curve = curve;
+    if let Some(slope) = slope.filter(|value| value.is_finite()) {
+      self.crossfader.slope = slope.clamp(0.0, 1.0) as f32;
+    }
+    if let Some(reverse) = reverse {
+      self.crossfader.reverse = reverse;
+    }
+    self.refresh_output_gains();
+  }
+
+  /// sinc_kernels 须在取引擎锁之前构建好，sinc 以外的质量传 None
+  pub(super) fn set_resample_quality(
+    &mut self,
+    quality: crate::resampler::ResampleQuality,
+    sinc_kernels: Option<Arc<crate::resampler::PlaybackSincKernels>>,
+  ) {
+    self.mark_state_changed();
+    for target in [&mut self.top, &mut self.bottom] {
+      target.resample_quality = quality;
+      target.sinc_kernels = sinc_kernels.clone();
+    }
+  }
+
   pub(super) fn set_band_state(&mut self, deck: DeckId, bands: HorizontalBrowseTransportBandState) {
     self.mark_state_changed();
     let target = self.deck_mut(deck);
@@ -765,6 +802,7 @@ impl HorizontalBrowseTransportEngine {
     {
       let target = self.deck_mut(deck);
       target.playing = playing;
+      horizontal_browse_transport_motion::clear_deck_motion(target);
       if playing {
         target.scrub_preview.active = false;
         target.scrub_preview.rate = 0.0;
@@ -790,6 +828,7 @@ impl HorizontalBrowseTransportEngine {
       target.metronome_state.next_beat_index = None;
       target.scrub_preview.active = false;
       target.scrub_preview.rate = 0.0;
+      horizontal_browse_transport_motion::clear_deck_motion(target);
       horizontal_browse_transport_audio::reset_band_filter_state(target);
     }
     self.reset_and_prime_master_tempo_state(deck);
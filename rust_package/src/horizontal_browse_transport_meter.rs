@@ -0,0 +1,403 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use super::{HorizontalBrowseTransportLevelMeterSnapshot, HorizontalBrowseTransportMeterSnapshot};
+use crate::analysis_utils::{hann_window, power_spectrum};
+
+const METER_PEAK_WINDOW_SEC: f64 = 0.3;
+const METER_RMS_WINDOW_SEC: f64 = 0.3;
+const METER_SHORT_TERM_WINDOW_SEC: f64 = 3.0;
+/// 环容量按输出设备可能的最高采样率覆盖完整的 short-term 窗口
+const METER_MAX_SAMPLE_RATE: usize = 192_000;
+const METER_RING_READ_MARGIN_FRAMES: usize = 4096;
+const METER_RING_FRAMES: usize =
+  METER_SHORT_TERM_WINDOW_SEC as usize * METER_MAX_SAMPLE_RATE + 2 * METER_RING_READ_MARGIN_FRAMES;
+const METER_RING_LANES: usize = 6;
+const METER_FLOOR_DB: f64 = -100.0;
+const METER_EPSILON: f64 = 1e-10;
+const LUFS_OFFSET_DB: f64 = -0.691;
+const SPECTRUM_FFT_SIZE: usize = 4096;
+const SPECTRUM_DEFAULT_BAND_COUNT: u32 = 32;
+const SPECTRUM_MIN_BAND_COUNT: u32 = 4;
+const SPECTRUM_MAX_BAND_COUNT: u32 = 256;
+const SPECTRUM_MIN_HZ: f64 = 20.0;
+const SPECTRUM_MAX_HZ: f64 = 20_000.0;
+
+const LANE_TOP_LEFT: usize = 0;
+const LANE_BOTTOM_LEFT: usize = 2;
+const LANE_MASTER_LEFT: usize = 4;
+
+/// 音频线程只写、快照线程只读的无锁帧环：top L/R、bottom L/R、master L/R
+/// （master 为最终输出，含监听与节拍器）
+pub(super) struct MeterRing {
+  samples: Box<[AtomicU32]>,
+  written_frames: AtomicU64,
+  /// clear 时的写入位置；读取不早于此帧，避免重置后仍显示旧峰值
+  cleared_frames: AtomicU64,
+  sample_rate: AtomicU32,
+}
+
+impl MeterRing {
+  fn new() -> Self {
+    Self {
+      samples: (0..METER_RING_FRAMES * METER_RING_LANES)
+        .map(|_| AtomicU32::new(0))
+        .collect(),
+      written_frames: AtomicU64::new(0),
+      cleared_frames: AtomicU64::new(0),
+      sample_rate: AtomicU32::new(0),
+    }
+  }
+
+  pub(super) fn push_frame(&self, lanes: [f32; METER_RING_LANES], sample_rate: u32) {
+    if self.sample_rate.load(Ordering::Relaxed) != sample_rate {
+      self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+    let frame = self.written_frames.load(Ordering::Relaxed);
+    let base = (frame as usize % METER_RING_FRAMES) * METER_RING_LANES;
+    for (lane, value) in lanes.iter().enumerate() {
+      let safe_value = if value.is_finite() { *value } else { 0.0 };
+      self.samples[base + lane].store(safe_value.to_bits(), Ordering::Relaxed);
+    }
+    self.written_frames.store(frame + 1, Ordering::Release);
+  }
+
+  /// 丢弃已写入的帧；只移动读取下界，不与音频线程的写入竞争
+  pub(super) fn clear(&self) {
+    let written = self.written_frames.load(Ordering::Acquire);
+    self.cleared_frames.store(written, Ordering::Release);
+  }
+
+  /// 读取最近 frame_count 帧（按时间顺序），返回每个通道一条序列
+  fn recent_lanes(&self, frame_count: usize) -> Vec<Vec<f32>> {
+    let written = self.written_frames.load(Ordering::Acquire);
+    let cleared = self.cleared_frames.load(Ordering::Acquire).min(written);
+    let available = ((written - cleared) as usize)
+      .min(METER_RING_FRAMES - METER_RING_READ_MARGIN_FRAMES)
+      .min(frame_count);
+    let mut lanes: Vec<Vec<f32>> = (0..METER_RING_LANES)
+      .map(|_| Vec::with_capacity(available))
+      .collect();
+    let start = written - available as u64;
+    for frame in start..written {
+      let base = (frame as usize % METER_RING_FRAMES) * METER_RING_LANES;
+      for (lane, values) in lanes.iter_mut().enumerate() {
+        values.push(f32::from_bits(
+          self.samples[base + lane].load(Ordering::Relaxed),
+        ));
+      }
+    }
+    lanes
+  }
+
+  fn sample_rate(&self) -> u32 {
+    self.sample_rate.load(Ordering::Relaxed)
+  }
+}
+
+pub(super) fn meter_ring() -> &'static MeterRing {
+  static METER_RING: OnceLock<MeterRing> = OnceLock::new();
+  METER_RING.get_or_init(MeterRing::new)
+}
+
+#[derive(Clone, Copy)]
+struct Biquad {
+  b0: f64,
+  b1: f64,
+  b2: f64,
+  a1: f64,
+  a2: f64,
+  z1: f64,
+  z2: f64,
+}
+
+impl Biquad {
+  fn process(&mut self, input: f64) -> f64 {
+    let output = self.b0 * input + self.z1;
+    self.z1 = self.b1 * input - self.a1 * output + self.z2;
+    self.z2 = self.b2 * input - self.a2 * output;
+    output
+  }
+}
+
+/// ITU-R BS.1770 K 加权（高架 + 高通），系数按采样率推导
+fn k_weighting_filters(sample_rate: f64) -> [Biquad; 2] {
+  let shelf_f0 = 1_681.974_450_955_533;
+  let shelf_gain_db = 3.999_843_853_973_347;
+  let shelf_q = 0.707_175_236_955_419_6;
+  let k = (std::f64::consts::PI * shelf_f0 / sample_rate).tan();
+  let vh = 10.0_f64.powf(shelf_gain_db / 20.0);
+  let vb = vh.powf(0.499_666_774_154_541_6);
+  let a0 = 1.0 + k / shelf_q + k * k;
+  let shelf = Biquad {
+    b0: (vh + vb * k / shelf_q + k * k) / a0,
+    b1: 2.0 * (k * k - vh) / a0,
+    b2: (vh - vb * k / shelf_q + k * k) / a0,
+    a1: 2.0 * (k * k - 1.0) / a0,
+    a2: (1.0 - k / shelf_q + k * k) / a0,
+    z1: 0.0,
+    z2: 0.0,
+  };
+
+  let high_pass_f0 = 38.135_470_876_024_44;
+  let high_pass_q = 0.500_327_037_323_877_3;
+  let k = (std::f64::consts::PI * high_pass_f0 / sample_rate).tan();
+  let a0 = 1.0 + k / high_pass_q + k * k;
+  let high_pass = Biquad {
+    b0: 1.0,
+    b1: -2.0,
+    b2: 1.0,
+    a1: 2.0 * (k * k - 1.0) / a0,
+    a2: (1.0 - k / high_pass_q + k * k) / a0,
+    z1: 0.0,
+    z2: 0.0,
+  };
+  [shelf, high_pass]
+}
+
+fn power_to_db(power: f64) -> f64 {
+  (10.0 * power.max(METER_EPSILON).log10()).max(METER_FLOOR_DB)
+}
+
+fn amplitude_to_db(amplitude: f64) -> f64 {
+  (20.0 * amplitude.max(METER_EPSILON).log10()).max(METER_FLOOR_DB)
+}
+
+fn tail(values: &[f32], frame_count: usize) -> &[f32] {
+  &values[values.len().saturating_sub(frame_count)..]
+}
+
+fn peak_of(values: &[f32]) -> f64 {
+  values
+    .iter()
+    .fold(0.0_f64, |peak, value| peak.max(value.abs() as f64))
+}
+
+fn mean_square_of(values: &[f32]) -> f64 {
+  if values.is_empty() {
+    return 0.0;
+  }
+  values
+    .iter()
+    .map(|value| (*value as f64) * (*value as f64))
+    .sum::<f64>()
+    / values.len() as f64
+}
+
+fn k_weighted_mean_square(values: &[f32], sample_rate: f64) -> f64 {
+  if values.is_empty() {
+    return 0.0;
+  }
+  let [mut shelf, mut high_pass] = k_weighting_filters(sample_rate);
+  let mut sum = 0.0_f64;
+  for value in values {
+    let weighted = high_pass.process(shelf.process(*value as f64));
+    sum += weighted * weighted;
+  }
+  sum / values.len() as f64
+}
+
+fn level_meter(
+  left: &[f32],
+  right: &[f32],
+  sample_rate: f64,
+) -> HorizontalBrowseTransportLevelMeterSnapshot {
+  let peak_frames = (METER_PEAK_WINDOW_SEC * sample_rate).round() as usize;
+  let rms_frames = (METER_RMS_WINDOW_SEC * sample_rate).round() as usize;
+  let short_term_frames = (METER_SHORT_TERM_WINDOW_SEC * sample_rate).round() as usize;
+  let short_term_power = k_weighted_mean_square(tail(left, short_term_frames), sample_rate)
+    + k_weighted_mean_square(tail(right, short_term_frames), sample_rate);
+  HorizontalBrowseTransportLevelMeterSnapshot {
+    peak_left_db: amplitude_to_db(peak_of(tail(left, peak_frames))),
+    peak_right_db: amplitude_to_db(peak_of(tail(right, peak_frames))),
+    rms_left_db: power_to_db(mean_square_of(tail(left, rms_frames))),
+    rms_right_db: power_to_db(mean_square_of(tail(right, rms_frames))),
+    short_term_lufs: if short_term_power > METER_EPSILON {
+      (LUFS_OFFSET_DB + 10.0 * short_term_power.log10()).max(METER_FLOOR_DB)
+    } else {
+      METER_FLOOR_DB
+    },
+  }
+}
+
+fn silent_level_meter() -> HorizontalBrowseTransportLevelMeterSnapshot {
+  HorizontalBrowseTransportLevelMeterSnapshot {
+    peak_left_db: METER_FLOOR_DB,
+    peak_right_db: METER_FLOOR_DB,
+    rms_left_db: METER_FLOOR_DB,
+    rms_right_db: METER_FLOOR_DB,
+    short_term_lufs: METER_FLOOR_DB,
+  }
+}
+
+fn normalize_band_count(band_count: Option<u32>) -> usize {
+  band_count
+    .unwrap_or(SPECTRUM_DEFAULT_BAND_COUNT)
+    .clamp(SPECTRUM_MIN_BAND_COUNT, SPECTRUM_MAX_BAND_COUNT) as usize
+}
+
+/// 对数频率分带频谱：每带取最大功率 bin，0 dB 对应满幅正弦
+fn log_band_spectrum(
+  left: &[f32],
+  right: &[f32],
+  sample_rate: f64,
+  band_count: usize,
+) -> (Vec<f64>, Vec<f64>) {
+  let nyquist = sample_rate * 0.5;
+  let max_hz = SPECTRUM_MAX_HZ.min(nyquist * 0.999);
+  if max_hz <= SPECTRUM_MIN_HZ || band_count == 0 {
+    return (Vec::new(), Vec::new());
+  }
+  let ratio = (max_hz / SPECTRUM_MIN_HZ).powf(1.0 / band_count as f64);
+  let band_hz: Vec<f64> = (0..band_count)
+    .map(|band| SPECTRUM_MIN_HZ * ratio.powf(band as f64 + 0.5))
+    .collect();
+
+  let left = tail(left, SPECTRUM_FFT_SIZE);
+  let right = tail(right, SPECTRUM_FFT_SIZE);
+  let mut frame = vec![0.0_f64; SPECTRUM_FFT_SIZE];
+  let offset = SPECTRUM_FFT_SIZE - left.len().min(right.len());
+  for (index, (l, r)) in left.iter().zip(right.iter()).enumerate() {
+    frame[offset + index] = (*l as f64 + *r as f64) * 0.5;
+  }
+  let window = hann_window(SPECTRUM_FFT_SIZE);
+  let power = power_spectrum(&frame, &window);
+  let bin_hz = sample_rate / SPECTRUM_FFT_SIZE as f64;
+  let full_scale = (SPECTRUM_FFT_SIZE as f64 / 4.0).powi(2);
+
+  let spectrum_db = (0..band_count)
+    .map(|band| {
+      let low_hz = SPECTRUM_MIN_HZ * ratio.powf(band as f64);
+      let high_hz = low_hz * ratio;
+      let first_bin = (low_hz / bin_hz).ceil() as usize;
+      let last_bin = ((high_hz / bin_hz).floor() as usize).min(power.len().saturating_sub(1));
+      let band_power = if first_bin <= last_bin {
+        power[first_bin..=last_bin]
+          .iter()
+          .copied()
+          .fold(0.0_f64, f64::max)
+      } else {
+        let nearest = ((band_hz[band] / bin_hz).round() as usize).min(power.len() - 1);
+        power[nearest]
+      };
+      power_to_db(band_power / full_scale)
+    })
+    .collect();
+  (spectrum_db, band_hz)
+}
+
+pub(super) fn compute_meter_snapshot(
+  ring: &MeterRing,
+  spectrum_band_count: Option<u32>,
+) -> HorizontalBrowseTransportMeterSnapshot {
+  let sample_rate = ring.sample_rate();
+  let band_count = normalize_band_count(spectrum_band_count);
+  if sample_rate == 0 {
+    return HorizontalBrowseTransportMeterSnapshot {
+      sample_rate: 0.0,
+      top: silent_level_meter(),
+      bottom: silent_level_meter(),
+      master: silent_level_meter(),
+      spectrum_db: vec![METER_FLOOR_DB; band_count],
+      spectrum_band_hz: Vec::new(),
+    };
+  }
+  let sample_rate_f = sample_rate as f64;
+  let window_frames =
+    ((METER_SHORT_TERM_WINDOW_SEC * sample_rate_f).ceil() as usize).max(SPECTRUM_FFT_SIZE);
+  let lanes = ring.recent_lanes(window_frames);
+  let lane_meter =
+    |left_lane: usize| level_meter(&lanes[left_lane], &lanes[left_lane + 1], sample_rate_f);
+  let (spectrum_db, spectrum_band_hz) = log_band_spectrum(
+    &lanes[LANE_MASTER_LEFT],
+    &lanes[LANE_MASTER_LEFT + 1],
+    sample_rate_f,
+    band_count,
+  );
+  HorizontalBrowseTransportMeterSnapshot {
+    sample_rate: sample_rate_f,
+    top: lane_meter(LANE_TOP_LEFT),
+    bottom: lane_meter(LANE_BOTTOM_LEFT),
+    master: lane_meter(LANE_MASTER_LEFT),
+    spectrum_db,
+    spectrum_band_hz,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::analysis_utils::sine_wave as sine;
+
+  #[test]
+  fn sine_levels_match_expected_peak_rms_and_short_term_loudness() {
+    let sample_rate = 48_000.0;
+    let wave = sine(1000.0, 1.0, sample_rate, 48_000 * 3);
+    let meter = level_meter(&wave, &wave, sample_rate);
+    assert!(meter.peak_left_db.abs() < 0.01);
+    assert!((meter.rms_left_db - -3.0103).abs() < 0.05);
+    // BS.1770：满幅 1 kHz 正弦单声道为 -3.01 LUFS，两声道叠加约 0 LUFS
+    assert!(meter.short_term_lufs.abs() < 0.1);
+  }
+
+  #[test]
+  fn silence_reports_meter_floor() {
+    let silence = vec![0.0_f32; 4800];
+    let meter = level_meter(&silence, &silence, 48_000.0);
+    assert_eq!(meter.peak_left_db, METER_FLOOR_DB);
+    assert_eq!(meter.short_term_lufs, METER_FLOOR_DB);
+  }
+
+  #[test]
+  fn spectrum_peaks_in_band_containing_tone() {
+    let sample_rate = 48_000.0;
+    let wave = sine(1000.0, 0.5, sample_rate, SPECTRUM_FFT_SIZE);
+    let (spectrum_db, band_hz) = log_band_spectrum(&wave, &wave, sample_rate, 24);
+    assert_eq!(spectrum_db.len(), 24);
+    let loudest = spectrum_db
+      .iter()
+      .enumerate()
+      .max_by(|left, right| left.1.partial_cmp(right.1).unwrap())
+      .map(|(index, _)| index)
+      .unwrap();
+    let ratio = band_hz[1] / band_hz[0];
+    assert!(band_hz[loudest] / ratio.sqrt() <= 1000.0);
+    assert!(band_hz[loudest] * ratio.sqrt() >= 1000.0);
+    assert!((spectrum_db[loudest] - -6.02).abs() < 1.5);
+  }
+
+  #[test]
+  fn ring_returns_most_recent_frames_in_order() {
+    let ring = MeterRing::new();
+    for index in 0..10 {
+      let value = index as f32;
+      ring.push_frame([value, 0.0, 0.0, 0.0, 0.0, -value], 44_100);
+    }
+    let lanes = ring.recent_lanes(4);
+    assert_eq!(lanes[0], vec![6.0, 7.0, 8.0, 9.0]);
+    assert_eq!(lanes[5], vec![-6.0, -7.0, -8.0, -9.0]);
+    assert_eq!(ring.sample_rate(), 44_100);
+  }
+
+  #[test]
+  fn ring_holds_short_term_window_at_max_sample_rate() {
+    let ring = MeterRing::new();
+    let window_frames = (METER_SHORT_TERM_WINDOW_SEC * METER_MAX_SAMPLE_RATE as f64) as usize;
+    for _ in 0..window_frames + METER_RING_READ_MARGIN_FRAMES * 3 {
+      ring.push_frame([0.25; METER_RING_LANES], METER_MAX_SAMPLE_RATE as u32);
+    }
+    assert_eq!(ring.recent_lanes(window_frames)[4].len(), window_frames);
+  }
+
+  #[test]
+  fn cleared_ring_only_returns_frames_written_after_clear() {
+    let ring = MeterRing::new();
+    for _ in 0..8 {
+      ring.push_frame([1.0; METER_RING_LANES], 44_100);
+    }
+    ring.clear();
+    assert!(ring.recent_lanes(4)[0].is_empty());
+    ring.push_frame([0.5; METER_RING_LANES], 44_100);
+    assert_eq!(ring.recent_lanes(4)[0], vec![0.5]);
+  }
+}
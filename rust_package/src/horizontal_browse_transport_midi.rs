@@ -0,0 +1,761 @@
+use std::collections::VecDeque;
+use std::result::Result as StdResult;
+
+use serde_json::Value;
+
+use super::*;
+
+const MIDI_STATUS_NOTE_OFF: u8 = 0x80;
+const MIDI_STATUS_NOTE_ON: u8 = 0x90;
+const MIDI_STATUS_CONTROL_CHANGE: u8 = 0xB0;
+const MIDI_BUTTON_PRESS_THRESHOLD: u8 = 64;
+const MIDI_7BIT_MAX: f64 = 127.0;
+const MIDI_14BIT_MAX: f64 = 16383.0;
+/// 软接管窗口：硬件值与引擎值相差不超过约 3 个 7bit 刻度即视为已接管
+const MIDI_SOFT_TAKEOVER_THRESHOLD: f64 = 3.0 / 128.0;
+const MIDI_SOFT_TAKEOVER_EPSILON: f64 = 0.0001;
+const MIDI_DEFAULT_TEMPO_RANGE: f64 = 0.08;
+/// 停止时每个刻度的搓碟位移；约 33⅓ 转黑胶一圈 128 刻度
+const MIDI_JOG_SCRUB_SEC_PER_TICK: f64 = 1.8 / 128.0;
+/// 播放中每个刻度的临时变速比例，与界面推挤按钮一致最多 ±4%
+const MIDI_JOG_NUDGE_RATIO_PER_TICK: f64 = 0.01;
+const MIDI_JOG_NUDGE_MAX_RATIO: f64 = 0.04;
+/// 两条刻度消息间隔的估算下限，避免搓碟速率失控
+const MIDI_JOG_MIN_TICK_INTERVAL_MS: f64 = 10.0;
+/// 超过该时长没有刻度即视为松手，恢复速率或落定搓碟位置
+const MIDI_JOG_RELEASE_MS: f64 = 120.0;
+const MAX_MIDI_EVENTS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum MidiJogEncoding {
+  /// 1..63 正转，65..127 为补码负转
+  TwosComplement,
+  /// 64 为静止，偏移量即刻度数
+  Offset64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum MidiBindingAction {
+  Play,
+  Sync,
+  LoopToggle,
+  LoopHalve,
+  LoopDouble,
+  HotCue(u32),
+  Jog(MidiJogEncoding),
+  Gain,
+  ChannelFader,
+  Crossfader,
+  MasterGain,
+  Tempo(f64),
+  Brake,
+  Spinback,
+  Reverse,
+  Censor,
+  CueMonitor,
+  /// 引擎内未实现的动作原样交给渲染进程处理
+  Event(String),
+}
+
+impl MidiBindingAction {
+  fn as_str(&self) -> &str {
+    match self {
+      MidiBindingAction::Play => "play",
+      MidiBindingAction::Sync => "sync",
+      MidiBindingAction::LoopToggle => "loop",
+      MidiBindingAction::LoopHalve => "loopHalve",
+      MidiBindingAction::LoopDouble => "loopDouble",
+      MidiBindingAction::HotCue(_) => "hotCue",
+      MidiBindingAction::Jog(_) => "jog",
+      MidiBindingAction::Gain => "gain",
+      MidiBindingAction::ChannelFader => "channelFader",
+      MidiBindingAction::Crossfader => "crossfader",
+      MidiBindingAction::MasterGain => "masterGain",
+      MidiBindingAction::Tempo(_) => "tempo",
+      MidiBindingAction::Brake => "brake",
+      MidiBindingAction::Spinback => "spinback",
+      MidiBindingAction::Reverse => "reverse",
+      MidiBindingAction::Censor => "censor",
+      MidiBindingAction::CueMonitor => "cueMonitor",
+      MidiBindingAction::Event(name) => name.as_str(),
+    }
+  }
+
+  fn is_absolute(&self) -> bool {
+    matches!(
+      self,
+      MidiBindingAction::Gain
+        | MidiBindingAction::ChannelFader
+        | MidiBindingAction::Crossfader
+        | MidiBindingAction::MasterGain
+        | MidiBindingAction::Tempo(_)
+    )
+  }
+
+  fn requires_deck(&self) -> bool {
+    !matches!(
+      self,
+      MidiBindingAction::Crossfader | MidiBindingAction::MasterGain | MidiBindingAction::Event(_)
+    )
+  }
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct MidiInputBinding {
+  pub(super) status: u8,
+  pub(super) number: u8,
+  pub(super) lsb_number: Option<u8>,
+  pub(super) deck: Option<DeckId>,
+  pub(super) action: MidiBindingAction,
+  pub(super) soft_takeover: bool,
+  pub(super) invert: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum MidiFeedbackSource {
+  Loaded,
+  Playing,
+  Sync,
+  Leader,
+  LoopActive,
+  CueMonitor,
+  Motion,
+  Reverse,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct MidiOutputBinding {
+  pub(super) status: u8,
+  pub(super) number: u8,
+  pub(super) deck: DeckId,
+  pub(super) source: MidiFeedbackSource,
+  pub(super) on_value: u8,
+  pub(super) off_value: u8,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(super) struct MidiMapping {
+  pub(super) name: String,
+  pub(super) inputs: Vec<MidiInputBinding>,
+  pub(super) outputs: Vec<MidiOutputBinding>,
+}
+
+/// 反馈输出端口；真实设备与测试用虚拟端口共用
+pub(super) trait MidiFeedbackSink {
+  fn send(&mut self, message: &[u8]);
+}
+
+/// 不依赖硬件的虚拟端口，记录所有发出的反馈消息
+#[cfg(test)]
+#[derive(Default)]
+pub(super) struct VirtualMidiPort {
+  pub(super) sent: Vec<Vec<u8>>,
+}
+
+#[cfg(test)]
+impl MidiFeedbackSink for VirtualMidiPort {
+  fn send(&mut self, message: &[u8]) {
+    self.sent.push(message.to_vec());
+  }
+}
+
+fn parse_midi_byte(value: Option<&Value>, field: &str, max: u8) -> StdResult<u8, String> {
+  let parsed = match value {
+    Some(Value::Number(number)) => number.as_u64(),
+    Some(Value::String(text)) => {
+      let text = text.trim();
+      match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse::<u64>().ok(),
+      }
+    }
+    _ => None,
+  };
+  match parsed {
+    Some(byte) if byte <= max as u64 => Ok(byte as u8),
+    _ => Err(format!("invalid midi {}: {:?}", field, value)),
+  }
+}
+
+fn parse_mapping_deck(value: Option<&Value>) -> StdResult<Option<DeckId>, String> {
+  match value
+    .and_then(Value::as_str)
+    .map(|raw| raw.trim().to_lowercase())
+  {
+    None => Ok(None),
+    Some(raw) if raw == "top" => Ok(Some(DeckId::Top)),
+    Some(raw) if raw == "bottom" => Ok(Some(DeckId::Bottom)),
+    Some(raw) => Err(format!("unknown deck id: {}", raw)),
+  }
+}
+
+fn parse_binding_action(entry: &Value) -> StdResult<MidiBindingAction, String> {
+  let name = entry
+    .get("action")
+    .and_then(Value::as_str)
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+    .ok_or_else(|| "midi input binding is missing action".to_string())?;
+  Ok(match name {
+    "play" => MidiBindingAction::Play,
+    "sync" => MidiBindingAction::Sync,
+    "loop" => MidiBindingAction::LoopToggle,
+    "loopHalve" => MidiBindingAction::LoopHalve,
+    "loopDouble" => MidiBindingAction::LoopDouble,
+    "hotCue" => MidiBindingAction::HotCue(
+      entry
+        .get("index")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| "hotCue binding is missing index".to_string())? as u32,
+    ),
+    "jog" => MidiBindingAction::Jog(match entry.get("encoding").and_then(Value::as_str) {
+      None | Some("twosComplement") => MidiJogEncoding::TwosComplement,
+      Some("offset64") => MidiJogEncoding::Offset64,
+      Some(other) => return Err(format!("unknown jog encoding: {}", other)),
+    }),
+    "gain" => MidiBindingAction::Gain,
+    "channelFader" => MidiBindingAction::ChannelFader,
+    "crossfader" => MidiBindingAction::Crossfader,
+    "masterGain" => MidiBindingAction::MasterGain,
+    "tempo" => MidiBindingAction::Tempo(
+      entry
+        .get("range")
+        .and_then(Value::as_f64)
+        .filter(|value| value.is_finite() && *value > 0.0)
+        .unwrap_or(MIDI_DEFAULT_TEMPO_RANGE)
+        .min(1.0),
+    ),
+    "brake" => MidiBindingAction::Brake,
+    "spinback" => MidiBindingAction::Spinback,
+    "reverse" => MidiBindingAction::Reverse,
+    "censor" => MidiBindingAction::Censor,
+    "cueMonitor" => MidiBindingAction::CueMonitor,
+    other => MidiBindingAction::Event(other.to_string()),
+  })
+}
+
+fn parse_input_binding(entry: &Value) -> StdResult<MidiInputBinding, String> {
+  let status = parse_midi_byte(entry.get("status"), "status", 0xEF)?;
+  let kind = status & 0xF0;
+  if kind != MIDI_STATUS_NOTE_ON && kind != MIDI_STATUS_CONTROL_CHANGE {
+    return Err(format!("unsupported midi status: 0x{:02X}", status));
+  }
+  let number = parse_midi_byte(
+    entry.get("control").or_else(|| entry.get("note")),
+    "control",
+    0x7F,
+  )?;
+  let lsb_number = match entry.get("lsbControl") {
+    Some(value) => Some(parse_midi_byte(Some(value), "lsbControl", 0x7F)?),
+    None => None,
+  };
+  let action = parse_binding_action(entry)?;
+  let deck = parse_mapping_deck(entry.get("deck"))?;
+  if deck.is_none() && action.requires_deck() {
+    return Err(format!("midi action {} requires deck", action.as_str()));
+  }
+  Ok(MidiInputBinding {
+    status,
+    number,
+    lsb_number,
+    deck,
+    soft_takeover: action.is_absolute()
+      && entry
+        .get("softTakeover")
+        .and_then(Value::as_bool)
+        .unwrap_or(true),
+    invert: entry
+      .get("invert")
+      .and_then(Value::as_bool)
+      .unwrap_or(false),
+    action,
+  })
+}
+
+fn parse_output_binding(entry: &Value) -> StdResult<MidiOutputBinding, String> {
+  let source = match entry.get("source").and_then(Value::as_str) {
+    Some("loaded") => MidiFeedbackSource::Loaded,
+    Some("playing") => MidiFeedbackSource::Playing,
+    Some("sync") => MidiFeedbackSource::Sync,
+    Some("leader") => MidiFeedbackSource::Leader,
+    Some("loop") => MidiFeedbackSource::LoopActive,
+    Some("cueMonitor") => MidiFeedbackSource::CueMonitor,
+    Some("motion") => MidiFeedbackSource::Motion,
+    Some("reverse") => MidiFeedbackSource::Reverse,
+    other => return Err(format!("unknown midi feedback source: {:?}", other)),
+  };
+  Ok(MidiOutputBinding {
+    status: parse_midi_byte(entry.get("status"), "status", 0xEF)?,
+    number: parse_midi_byte(
+      entry.get("control").or_else(|| entry.get("note")),
+      "control",
+      0x7F,
+    )?,
+    deck: parse_mapping_deck(entry.get("deck"))?
+      .ok_or_else(|| "midi output binding requires deck".to_string())?,
+    source,
+    on_value: match entry.get("on") {
+      Some(value) => parse_midi_byte(Some(value), "on", 0x7F)?,
+      None => 0x7F,
+    },
+    off_value: match entry.get("off") {
+      Some(value) => parse_midi_byte(Some(value), "off", 0x7F)?,
+      None => 0x00,
+    },
+  })
+}
+
+/// 解析声明式映射文件（JSON）：inputs 绑定控制器消息到传输命令，outputs 描述 LED 反馈
+pub(super) fn parse_midi_mapping(raw: &str) -> StdResult<MidiMapping, String> {
+  let root: Value =
+    serde_json::from_str(raw).map_err(|error| format!("invalid midi mapping: {}", error))?;
+  let list = |key: &str| -> Vec<Value> {
+    root
+      .get(key)
+      .and_then(Value::as_array)
+      .cloned()
+      .unwrap_or_default()
+  };
+  let inputs = list("inputs")
+    .iter()
+    .map(parse_input_binding)
+    .collect::<StdResult<Vec<_>, _>>()?;
+  let outputs = list("outputs")
+    .iter()
+    .map(parse_output_binding)
+    .collect::<StdResult<Vec<_>, _>>()?;
+  Ok(MidiMapping {
+    name: root
+      .get("name")
+      .and_then(Value::as_str)
+      .unwrap_or_default()
+      .to_string(),
+    inputs,
+    outputs,
+  })
+}
+
+#[derive(Clone, Copy, Default)]
+struct MidiBindingRuntime {
+  pending_msb: Option<u8>,
+  last_hardware_value: Option<f64>,
+  applied_value: Option<f64>,
+}
+
+fn decode_jog_ticks(encoding: MidiJogEncoding, value: u8) -> f64 {
+  match encoding {
+    MidiJogEncoding::TwosComplement => {
+      if value >= 64 {
+        value as f64 - 128.0
+      } else {
+        value as f64
+      }
+    }
+    MidiJogEncoding::Offset64 => value as f64 - 64.0,
+  }
+}
+
+#[derive(Clone, Copy)]
+enum MidiJogMode {
+  /// 播放中推挤：记录推挤前的速率，松手后恢复
+  Nudge { base_rate: f64 },
+  /// 停止时搓碟：走试听通道，松手后定位到 target_sec
+  Scrub { target_sec: f64 },
+}
+
+#[derive(Clone, Copy)]
+struct MidiJogState {
+  mode: MidiJogMode,
+  last_tick_ms: f64,
+}
+
+#[derive(Default)]
+pub(super) struct MidiControllerState {
+  mapping: MidiMapping,
+  runtime: Vec<MidiBindingRuntime>,
+  jog: [Option<MidiJogState>; 2],
+  feedback_cache: Vec<Option<u8>>,
+  events: VecDeque<HorizontalBrowseTransportMidiEvent>,
+}
+
+impl MidiControllerState {
+  pub(super) fn mapping(&self) -> &MidiMapping {
+    &self.mapping
+  }
+
+  pub(super) fn load_mapping(&mut self, mapping: MidiMapping) {
+    self.runtime = vec![MidiBindingRuntime::default(); mapping.inputs.len()];
+    self.feedback_cache = vec![None; mapping.outputs.len()];
+    self.mapping = mapping;
+    self.events.clear();
+  }
+
+  /// 下次反馈时重发全部 LED 状态（重连设备或控制器掉电后使用）
+  pub(super) fn invalidate_feedback(&mut self) {
+    self
+      .feedback_cache
+      .iter_mut()
+      .for_each(|value| *value = None);
+  }
+
+  pub(super) fn drain_events(&mut self) -> Vec<HorizontalBrowseTransportMidiEvent> {
+    self.events.drain(..).collect()
+  }
+
+  fn push_event(&mut self, event: HorizontalBrowseTransportMidiEvent) {
+    while self.events.len() >= MAX_MIDI_EVENTS {
+      self.events.pop_front();
+    }
+    self.events.push_back(event);
+  }
+
+  /// 处理一条原始 MIDI 消息；返回是否命中映射
+  pub(super) fn handle_message(
+    &mut self,
+    engine: &mut HorizontalBrowseTransportEngine,
+    message: &[u8],
+    now_ms: f64,
+  ) -> bool {
+    if message.len() < 3 {
+      return false;
+    }
+    let status = message[0];
+    let number = message[1] & 0x7F;
+    let value = message[2] & 0x7F;
+    let kind = status & 0xF0;
+    let channel = status & 0x0F;
+    let (match_status, pressed) = match kind {
+      MIDI_STATUS_NOTE_OFF => (MIDI_STATUS_NOTE_ON | channel, false),
+      MIDI_STATUS_NOTE_ON => (status, value > 0),
+      MIDI_STATUS_CONTROL_CHANGE => (status, value >= MIDI_BUTTON_PRESS_THRESHOLD),
+      _ => return false,
+    };
+    let mut handled = false;
+    for index in 0..self.mapping.inputs.len() {
+      let binding = &self.mapping.inputs[index];
+      if binding.status != match_status {
+        continue;
+      }
+      if binding.number == number {
+        let binding = binding.clone();
+        handled = true;
+        if binding.lsb_number.is_some() {
+          // 14bit 控件：MSB 到达即按低 7 位为 0 生效，LSB 到达后再细化
+          self.runtime[index].pending_msb = Some(value);
+          let coarse = ((value as u16) << 7) as f64 / MIDI_14BIT_MAX;
+          self.apply_absolute(engine, index, &binding, coarse, now_ms);
+          continue;
+        }
+        if binding.action.is_absolute() {
+          self.apply_absolute(
+            engine,
+            index,
+            &binding,
+            value as f64 / MIDI_7BIT_MAX,
+            now_ms,
+          );
+        } else if let MidiBindingAction::Jog(encoding) = binding.action {
+          self.apply_jog(engine, &binding, decode_jog_ticks(encoding, value), now_ms);
+        } else {
+          self.apply_button(engine, &binding, pressed, value, now_ms);
+        }
+      } else if binding.lsb_number == Some(number) {
+        let binding = binding.clone();
+        handled = true;
+        let msb = self.runtime[index].pending_msb.unwrap_or(0);
+        let combined = ((msb as u16) << 7 | value as u16) as f64 / MIDI_14BIT_MAX;
+        self.apply_absolute(engine, index, &binding, combined, now_ms);
+      }
+    }
+    handled
+  }
+
+  fn apply_absolute(
+    &mut self,
+    engine: &mut HorizontalBrowseTransportEngine,
+    index: usize,
+    binding: &MidiInputBinding,
+    raw_value: f64,
+    now_ms: f64,
+  ) {
+    let value = if binding.invert {
+      1.0 - raw_value
+    } else {
+      raw_value
+    };
+    let current = absolute_engine_value(engine, binding);
+    let runtime = &mut self.runtime[index];
+    let previous_hardware_value = runtime.last_hardware_value.replace(value);
+    if binding.soft_takeover {
+      let in_control = runtime
+        .applied_value
+        .map(|applied| (applied - current).abs() <= MIDI_SOFT_TAKEOVER_EPSILON)
+        .unwrap_or(false);
+      let within_window = (value - current).abs() <= MIDI_SOFT_TAKEOVER_THRESHOLD;
+      let crossed = previous_hardware_value
+        .map(|previous| (previous - current) * (value - current) <= 0.0)
+        .unwrap_or(false);
+      if !in_control && !within_window && !crossed {
+        return;
+      }
+    }
+    apply_absolute_engine_value(engine, binding, value, now_ms);
+    runtime.applied_value = Some(absolute_engine_value(engine, binding));
+  }
+
+  fn apply_jog(
+    &mut self,
+    engine: &mut HorizontalBrowseTransportEngine,
+    binding: &MidiInputBinding,
+    ticks: f64,
+    now_ms: f64,
+  ) {
+    let Some(deck) = binding.deck else {
+      return;
+    };
+    if ticks == 0.0 || !engine.is_loaded(deck) {
+      return;
+    }
+    let deck_index = HorizontalBrowseTransportEngine::deck_index(deck);
+    let playing = engine.deck(deck).playing;
+    let previous = match self.jog[deck_index] {
+      Some(state) if matches!(state.mode, MidiJogMode::Nudge { .. }) != playing => {
+        self.release_jog(engine, deck, now_ms);
+        None
+      }
+      state => state,
+    };
+    let mode = if playing {
+      let base_rate = match previous.map(|state| state.mode) {
+        Some(MidiJogMode::Nudge { base_rate }) => base_rate,
+        _ => engine.deck(deck).playback_rate,
+      };
+      let ratio = (ticks * MIDI_JOG_NUDGE_RATIO_PER_TICK)
+        .clamp(-MIDI_JOG_NUDGE_MAX_RATIO, MIDI_JOG_NUDGE_MAX_RATIO);
+      engine.set_tempo_nudge_playback_rate(deck, now_ms, base_rate * (1.0 + ratio));
+      MidiJogMode::Nudge { base_rate }
+    } else {
+      let (anchor_sec, interval_ms) = match previous {
+        Some(MidiJogState {
+          mode: MidiJogMode::Scrub { target_sec },
+          last_tick_ms,
+        }) => (target_sec, now_ms - last_tick_ms),
+        _ => (
+          HorizontalBrowseTransportEngine::estimate_current_sec(engine.deck(deck), now_ms),
+          MIDI_JOG_RELEASE_MS,
+        ),
+      };
+      let target_sec = (anchor_sec + ticks * MIDI_JOG_SCRUB_SEC_PER_TICK).max(0.0);
+      // 试听从上一个落点出发，在一个刻度间隔内走到新落点
+      let interval_sec =
+        interval_ms.clamp(MIDI_JOG_MIN_TICK_INTERVAL_MS, MIDI_JOG_RELEASE_MS) / 1000.0;
+      engine.set_scrub_preview(
+        deck,
+        now_ms,
+        true,
+        anchor_sec,
+        (target_sec - anchor_sec) / interval_sec,
+      );
+      MidiJogMode::Scrub { target_sec }
+    };
+    self.jog[deck_index] = Some(MidiJogState {
+      mode,
+      last_tick_ms: now_ms,
+    });
+  }
+
+  fn release_jog(
+    &mut self,
+    engine: &mut HorizontalBrowseTransportEngine,
+    deck: DeckId,
+    now_ms: f64,
+  ) {
+    let deck_index = HorizontalBrowseTransportEngine::deck_index(deck);
+    let Some(state) = self.jog[deck_index].take() else {
+      return;
+    };
+    match state.mode {
+      MidiJogMode::Nudge { base_rate } => {
+        engine.set_tempo_nudge_playback_rate(deck, now_ms, base_rate)
+      }
+      MidiJogMode::Scrub { target_sec } => {
+        engine.set_scrub_preview(deck, now_ms, false, target_sec, 0.0);
+        engine.seek(deck, now_ms, target_sec);
+      }
+    }
+  }
+
+  /// 周期调用：转盘停止超过松手时长后结束推挤或搓碟
+  pub(super) fn release_idle_jogs(
+    &mut self,
+    engine: &mut HorizontalBrowseTransportEngine,
+    now_ms: f64,
+  ) {
+    for deck in [DeckId::Top, DeckId::Bottom] {
+      let deck_index = HorizontalBrowseTransportEngine::deck_index(deck);
+      if let Some(state) = self.jog[deck_index] {
+        if now_ms - state.last_tick_ms >= MIDI_JOG_RELEASE_MS {
+          self.release_jog(engine, deck, now_ms);
+        }
+      }
+    }
+  }
+
+  fn apply_button(
+    &mut self,
+    engine: &mut HorizontalBrowseTransportEngine,
+    binding: &MidiInputBinding,
+    pressed: bool,
+    value: u8,
+    now_ms: f64,
+  ) {
+    let deck = binding.deck;
+    match (&binding.action, deck) {
+      (MidiBindingAction::Reverse, Some(deck)) => engine.set_reverse(deck, now_ms, pressed, false),
+      (MidiBindingAction::Censor, Some(deck)) => engine.set_reverse(deck, now_ms, pressed, true),
+      (_, _) if !pressed => {
+        if let MidiBindingAction::HotCue(_) | MidiBindingAction::Event(_) = binding.action {
+          self.push_binding_event(binding, false, value);
+        }
+      }
+      (MidiBindingAction::Play, Some(deck)) => {
+        let playing = engine.deck(deck).playing;
+        engine.set_playing(deck, now_ms, !playing);
+        engine.refresh_auto_gain();
+      }
+      (MidiBindingAction::Sync, Some(deck)) => engine.beatsync(deck),
+      (MidiBindingAction::LoopToggle, Some(deck)) => engine.toggle_loop(deck, now_ms),
+      (MidiBindingAction::LoopHalve, Some(deck)) => {
+        engine.step_loop_beats_command(deck, -1, now_ms)
+      }
+      (MidiBindingAction::LoopDouble, Some(deck)) => {
+        engine.step_loop_beats_command(deck, 1, now_ms)
+      }
+      (MidiBindingAction::Brake, Some(deck)) => engine.start_brake(deck, now_ms, None),
+      (MidiBindingAction::Spinback, Some(deck)) => engine.start_spinback(deck, now_ms, None),
+      (MidiBindingAction::CueMonitor, Some(deck)) => {
+        let enabled = engine.deck(deck).cue_monitor_enabled;
+        engine.set_cue_monitor_enabled(deck, !enabled);
+      }
+      (MidiBindingAction::HotCue(_), _) | (MidiBindingAction::Event(_), _) => {
+        self.push_binding_event(binding, true, value)
+      }
+      _ => {}
+    }
+  }
+
+  fn push_binding_event(&mut self, binding: &MidiInputBinding, pressed: bool, value: u8) {
+    self.push_event(HorizontalBrowseTransportMidiEvent {
+      action: binding.action.as_str().to_string(),
+      deck: binding.deck.map(|deck| deck.as_str().to_string()),
+      index: match binding.action {
+        MidiBindingAction::HotCue(index) => Some(index),
+        _ => None,
+      },
+      value: value as f64 / MIDI_7BIT_MAX,
+      pressed,
+    });
+  }
+
+  /// 按快照状态计算 LED 反馈，只发送与上次不同的值
+  pub(super) fn flush_feedback(
+    &mut self,
+    engine: &HorizontalBrowseTransportEngine,
+    now_ms: f64,
+    sink: &mut dyn MidiFeedbackSink,
+  ) -> usize {
+    if self.mapping.outputs.is_empty() {
+      return 0;
+    }
+    let top = engine.deck_snapshot(DeckId::Top, now_ms);
+    let bottom = engine.deck_snapshot(DeckId::Bottom, now_ms);
+    let mut sent = 0;
+    for (index, binding) in self.mapping.outputs.iter().enumerate() {
+      let snapshot = match binding.deck {
+        DeckId::Top => &top,
+        DeckId::Bottom => &bottom,
+      };
+      let value = if feedback_source_active(snapshot, binding.source) {
+        binding.on_value
+      } else {
+        binding.off_value
+      };
+      if self.feedback_cache[index] == Some(value) {
+        continue;
+      }
+      self.feedback_cache[index] = Some(value);
+      sink.send(&[binding.status, binding.number, value]);
+      sent += 1;
+    }
+    sent
+  }
+}
+
+fn feedback_source_active(
+  snapshot: &HorizontalBrowseTransportDeckSnapshot,
+  source: MidiFeedbackSource,
+) -> bool {
+  match source {
+    MidiFeedbackSource::Loaded => snapshot.loaded,
+    MidiFeedbackSource::Playing => snapshot.playing,
+    MidiFeedbackSource::Sync => snapshot.sync_enabled,
+    MidiFeedbackSource::Leader => snapshot.leader,
+    MidiFeedbackSource::LoopActive => snapshot.loop_active,
+    MidiFeedbackSource::CueMonitor => snapshot.cue_monitor_enabled,
+    MidiFeedbackSource::Motion => snapshot.motion_mode != "none",
+    MidiFeedbackSource::Reverse => {
+      snapshot.motion_mode == "reverse" || snapshot.motion_mode == "censor"
+    }
+  }
+}
+
+/// 引擎当前值归一化到 0..1，与推子位置同一坐标系，供软接管比较
+fn absolute_engine_value(
+  engine: &HorizontalBrowseTransportEngine,
+  binding: &MidiInputBinding,
+) -> f64 {
+  match (&binding.action, binding.deck) {
+    (MidiBindingAction::Gain, Some(deck)) => {
+      engine.trim_gain[HorizontalBrowseTransportEngine::deck_index(deck)] as f64
+    }
+    (MidiBindingAction::ChannelFader, Some(deck)) => {
+      engine.channel_fader[HorizontalBrowseTransportEngine::deck_index(deck)] as f64
+    }
+    (MidiBindingAction::Crossfader, _) => (engine.crossfader_value as f64 + 1.0) * 0.5,
+    (MidiBindingAction::MasterGain, _) => engine.master_gain as f64,
+    (MidiBindingAction::Tempo(range), Some(deck)) => {
+      let rate = engine.deck(deck).playback_rate;
+      (((rate - 1.0) / range + 1.0) * 0.5).clamp(0.0, 1.0)
+    }
+    _ => 0.0,
+  }
+}
+
+fn apply_absolute_engine_value(
+  engine: &mut HorizontalBrowseTransportEngine,
+  binding: &MidiInputBinding,
+  value: f64,
+  now_ms: f64,
+) {
+  match (&binding.action, binding.deck) {
+    (MidiBindingAction::Gain, Some(deck)) => {
+      engine.mark_state_changed();
+      engine.trim_gain[HorizontalBrowseTransportEngine::deck_index(deck)] =
+        HorizontalBrowseTransportEngine::clamp_unit_gain(value);
+      engine.refresh_output_gains();
+    }
+    (MidiBindingAction::ChannelFader, Some(deck)) => engine.set_channel_fader(deck, value),
+    (MidiBindingAction::Crossfader, _) => {
+      let master_gain = engine.master_gain as f64;
+      engine.set_output_state(value * 2.0 - 1.0, master_gain);
+    }
+    (MidiBindingAction::MasterGain, _) => {
+      let crossfader_value = engine.crossfader_value as f64;
+      engine.set_output_state(crossfader_value, value);
+    }
+    (MidiBindingAction::Tempo(range), Some(deck)) => {
+      engine.set_playback_rate(deck, now_ms, 1.0 + (value * 2.0 - 1.0) * range);
+    }
+    _ => {}
+  }
+}
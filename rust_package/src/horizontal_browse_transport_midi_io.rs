@@ -0,0 +1,182 @@
+use super::*;
+use horizontal_browse_transport_midi::{MidiControllerState, MidiFeedbackSink};
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const MIDI_CLIENT_NAME: &str = "FRKB";
+const MIDI_FEEDBACK_INTERVAL_MS: u64 = 40;
+
+#[derive(Default)]
+struct MidiConnections {
+  input: Option<(String, MidiInputConnection<()>)>,
+  feedback_stop: Option<Arc<AtomicBool>>,
+}
+
+struct MidiOutputPortSink<'a>(&'a mut MidiOutputConnection);
+
+impl MidiFeedbackSink for MidiOutputPortSink<'_> {
+  fn send(&mut self, message: &[u8]) {
+    let _ = self.0.send(message);
+  }
+}
+
+pub(super) fn midi_controller() -> &'static Mutex<MidiControllerState> {
+  static MIDI_CONTROLLER: OnceLock<Mutex<MidiControllerState>> = OnceLock::new();
+  MIDI_CONTROLLER.get_or_init(|| Mutex::new(MidiControllerState::default()))
+}
+
+fn midi_connections() -> &'static Mutex<MidiConnections> {
+  static MIDI_CONNECTIONS: OnceLock<Mutex<MidiConnections>> = OnceLock::new();
+  MIDI_CONNECTIONS.get_or_init(|| Mutex::new(MidiConnections::default()))
+}
+
+fn midi_output() -> &'static Mutex<Option<(String, MidiOutputConnection)>> {
+  static MIDI_OUTPUT: OnceLock<Mutex<Option<(String, MidiOutputConnection)>>> = OnceLock::new();
+  MIDI_OUTPUT.get_or_init(|| Mutex::new(None))
+}
+
+pub(super) fn list_midi_input_ports() -> napi::Result<Vec<String>> {
+  let midi_in = MidiInput::new(MIDI_CLIENT_NAME)
+    .map_err(|error| Error::from_reason(format!("midi input init failed: {}", error)))?;
+  Ok(
+    midi_in
+      .ports()
+      .iter()
+      .filter_map(|port| midi_in.port_name(port).ok())
+      .collect(),
+  )
+}
+
+pub(super) fn list_midi_output_ports() -> napi::Result<Vec<String>> {
+  let midi_out = MidiOutput::new(MIDI_CLIENT_NAME)
+    .map_err(|error| Error::from_reason(format!("midi output init failed: {}", error)))?;
+  Ok(
+    midi_out
+      .ports()
+      .iter()
+      .filter_map(|port| midi_out.port_name(port).ok())
+      .collect(),
+  )
+}
+
+/// 锁顺序固定为 控制器 → 引擎 → 输出端口，输入回调与反馈线程共用
+fn flush_midi_feedback(
+  controller: &mut MidiControllerState,
+  engine: &HorizontalBrowseTransportEngine,
+) {
+  let mut output = midi_output().lock();
+  if let Some((_, connection)) = output.as_mut() {
+    let now_ms = engine.current_external_now_ms();
+    controller.flush_feedback(engine, now_ms, &mut MidiOutputPortSink(connection));
+  }
+}
+
+pub(super) fn dispatch_midi_message(message: &[u8]) -> bool {
+  let mut controller = midi_controller().lock();
+  let mut engine_guard = engine().lock();
+  let now_ms = engine_guard.current_external_now_ms();
+  if !controller.handle_message(&mut engine_guard, message, now_ms) {
+    return false;
+  }
+  let _ = engine_guard.ensure_output_stream();
+  flush_midi_feedback(&mut controller, &engine_guard);
+  true
+}
+
+fn spawn_feedback_thread(stop: Arc<AtomicBool>) {
+  thread::spawn(move || {
+    while !stop.load(Ordering::Relaxed) {
+      thread::sleep(Duration::from_millis(MIDI_FEEDBACK_INTERVAL_MS));
+      let mut controller = midi_controller().lock();
+      let mut engine_guard = engine().lock();
+      let now_ms = engine_guard.current_external_now_ms();
+      controller.release_idle_jogs(&mut engine_guard, now_ms);
+      flush_midi_feedback(&mut controller, &engine_guard);
+    }
+  });
+}
+
+pub(super) fn disconnect_midi_ports() {
+  let mut connections = midi_connections().lock();
+  if let Some(stop) = connections.feedback_stop.take() {
+    stop.store(true, Ordering::Relaxed);
+  }
+  if let Some((_, connection)) = connections.input.take() {
+    connection.close();
+  }
+  if let Some((_, connection)) = midi_output().lock().take() {
+    connection.close();
+  }
+}
+
+pub(super) fn connect_midi_ports(
+  input_port_name: &str,
+  output_port_name: Option<&str>,
+) -> napi::Result<()> {
+  disconnect_midi_ports();
+  let midi_in = MidiInput::new(MIDI_CLIENT_NAME)
+    .map_err(|error| Error::from_reason(format!("midi input init failed: {}", error)))?;
+  let input_port = midi_in
+    .ports()
+    .into_iter()
+    .find(|port| midi_in.port_name(port).ok().as_deref() == Some(input_port_name))
+    .ok_or_else(|| Error::from_reason(format!("midi input port not found: {}", input_port_name)))?;
+  let input_connection = midi_in
+    .connect(
+      &input_port,
+      "frkb-transport-in",
+      |_timestamp, message, _| {
+        dispatch_midi_message(message);
+      },
+      (),
+    )
+    .map_err(|error| Error::from_reason(format!("midi input connect failed: {}", error)))?;
+
+  if let Some(output_port_name) = output_port_name {
+    let midi_out = MidiOutput::new(MIDI_CLIENT_NAME)
+      .map_err(|error| Error::from_reason(format!("midi output init failed: {}", error)))?;
+    let output_port = midi_out
+      .ports()
+      .into_iter()
+      .find(|port| midi_out.port_name(port).ok().as_deref() == Some(output_port_name))
+      .ok_or_else(|| {
+        Error::from_reason(format!("midi output port not found: {}", output_port_name))
+      })?;
+    let output_connection = midi_out
+      .connect(&output_port, "frkb-transport-out")
+      .map_err(|error| Error::from_reason(format!("midi output connect failed: {}", error)))?;
+    *midi_output().lock() = Some((output_port_name.to_string(), output_connection));
+    midi_controller().lock().invalidate_feedback();
+  }
+
+  let stop = Arc::new(AtomicBool::new(false));
+  spawn_feedback_thread(stop.clone());
+  let mut connections = midi_connections().lock();
+  connections.input = Some((input_port_name.to_string(), input_connection));
+  connections.feedback_stop = Some(stop);
+  Ok(())
+}
+
+pub(super) fn midi_status() -> HorizontalBrowseTransportMidiStatus {
+  let (mapping_name, input_binding_count, output_binding_count) = {
+    let controller = midi_controller().lock();
+    let mapping = controller.mapping();
+    (
+      mapping.name.clone(),
+      mapping.inputs.len() as u32,
+      mapping.outputs.len() as u32,
+    )
+  };
+  HorizontalBrowseTransportMidiStatus {
+    mapping_name,
+    input_binding_count,
+    output_binding_count,
+    input_port_name: midi_connections()
+      .lock()
+      .input
+      .as_ref()
+      .map(|(name, _)| name.clone()),
+    output_port_name: midi_output().lock().as_ref().map(|(name, _)| name.clone()),
+  }
+}
@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use super::horizontal_browse_transport_midi::{
+  parse_midi_mapping, MidiControllerState, VirtualMidiPort,
+};
+use super::*;
+
+const TEST_MAPPING: &str = r#"{
+  "name": "test-controller",
+  "inputs": [
+    { "status": "0x90", "note": 11, "action": "play", "deck": "top" },
+    { "status": "0x97", "note": 0, "action": "hotCue", "deck": "top", "index": 2 },
+    { "status": "0xB0", "control": 19, "action": "gain", "deck": "top" },
+    { "status": 182, "control": 31, "lsbControl": 63, "action": "crossfader" },
+    { "status": "0xB0", "control": 33, "action": "jog", "deck": "top" }
+  ],
+  "outputs": [
+    { "status": "0x90", "note": 11, "deck": "top", "source": "playing" }
+  ]
+}"#;
+
+fn install_loaded_deck(engine: &mut HorizontalBrowseTransportEngine, deck: DeckId) {
+  let file_name = format!("{}-midi.wav", deck.as_str());
+  let target = engine.deck_mut(deck);
+  target.file_path = Some(file_name.clone());
+  target.loaded_file_path = Some(file_name.clone());
+  target.fully_decoded_file_path = Some(file_name);
+  target.sample_rate = 100;
+  target.channels = 1;
+  target.pcm_data = Arc::new(vec![0.0; 100 * 20]);
+  target.duration_sec = 20.0;
+  target.current_sec = 8.0;
+  target.last_observed_at_ms = 1000.0;
+  engine.last_now_ms = 1000.0;
+}
+
+fn loaded_controller() -> MidiControllerState {
+  let mut controller = MidiControllerState::default();
+  controller.load_mapping(parse_midi_mapping(TEST_MAPPING).expect("mapping parses"));
+  controller
+}
+
+#[test]
+fn mapping_parser_accepts_hex_status_and_rejects_missing_deck() {
+  let mapping = parse_midi_mapping(TEST_MAPPING).expect("mapping parses");
+  assert_eq!(mapping.name, "test-controller");
+  assert_eq!(mapping.inputs.len(), 5);
+  assert_eq!(mapping.inputs[3].status, 0xB6);
+  assert_eq!(mapping.inputs[3].lsb_number, Some(63));
+  assert!(mapping.inputs[2].soft_takeover);
+
+  let error =
+    parse_midi_mapping(r#"{ "inputs": [{ "status": "0x90", "note": 1, "action": "play" }] }"#)
+      .unwrap_err();
+  assert!(error.contains("requires deck"));
+}
+
+#[test]
+fn play_pad_toggles_deck_and_led_feedback_follows_snapshot() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_loaded_deck(&mut engine, DeckId::Top);
+  let mut controller = loaded_controller();
+  let mut port = VirtualMidiPort::default();
+
+  assert_eq!(controller.flush_feedback(&engine, 1000.0, &mut port), 1);
+  assert_eq!(port.sent, vec![vec![0x90, 11, 0]]);
+
+  assert!(controller.handle_message(&mut engine, &[0x90, 11, 127], 1000.0));
+  assert!(controller.handle_message(&mut engine, &[0x80, 11, 0], 1000.0));
+  assert!(engine.deck(DeckId::Top).playing);
+  assert_eq!(controller.flush_feedback(&engine, 1000.0, &mut port), 1);
+  assert_eq!(port.sent.last(), Some(&vec![0x90, 11, 127]));
+  // 状态未变时不重复发送
+  assert_eq!(controller.flush_feedback(&engine, 1010.0, &mut port), 0);
+}
+
+#[test]
+fn gain_fader_waits_for_soft_takeover_before_applying() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  let mut controller = loaded_controller();
+  let top_index = HorizontalBrowseTransportEngine::deck_index(DeckId::Top);
+
+  controller.handle_message(&mut engine, &[0xB0, 19, 20], 1000.0);
+  assert_eq!(engine.trim_gain[top_index], 1.0);
+  controller.handle_message(&mut engine, &[0xB0, 19, 126], 1000.0);
+  assert!((engine.trim_gain[top_index] - 126.0 / 127.0).abs() < 1e-6);
+  controller.handle_message(&mut engine, &[0xB0, 19, 64], 1000.0);
+  assert!((engine.trim_gain[top_index] - 64.0 / 127.0).abs() < 1e-6);
+
+  // 渲染进程改动增益后需重新接管
+  engine.trim_gain[top_index] = 0.1;
+  controller.handle_message(&mut engine, &[0xB0, 19, 100], 1000.0);
+  assert_eq!(engine.trim_gain[top_index], 0.1);
+}
+
+#[test]
+fn crossfader_combines_14bit_msb_and_lsb() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  let mut controller = loaded_controller();
+
+  // MSB 单独到达即生效，LSB 再细化
+  controller.handle_message(&mut engine, &[0xB6, 31, 66], 1000.0);
+  let coarse = ((66u16 << 7) as f64 / 16383.0) * 2.0 - 1.0;
+  assert!((engine.crossfader_value as f64 - coarse).abs() < 1e-6);
+  controller.handle_message(&mut engine, &[0xB6, 31, 64], 1000.0);
+  controller.handle_message(&mut engine, &[0xB6, 63, 1], 1000.0);
+  let expected = ((64u16 << 7 | 1) as f64 / 16383.0) * 2.0 - 1.0;
+  assert!((engine.crossfader_value as f64 - expected).abs() < 1e-6);
+}
+
+#[test]
+fn jog_scrubs_stopped_deck_and_hot_cue_pad_emits_event() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_loaded_deck(&mut engine, DeckId::Top);
+  let mut controller = loaded_controller();
+
+  let step_sec = 1.8 / 128.0;
+  controller.handle_message(&mut engine, &[0xB0, 33, 10], 1000.0);
+  let deck = engine.deck(DeckId::Top);
+  // 搓碟只驱动试听通道，不逐刻度定位
+  assert!(deck.scrub_preview.active);
+  assert!(deck.scrub_preview.rate > 0.0);
+  assert!((deck.current_sec - 8.0).abs() < 1e-9);
+  controller.handle_message(&mut engine, &[0xB0, 33, 123], 1050.0);
+  let deck = engine.deck(DeckId::Top);
+  assert!((deck.scrub_preview.current_sec - (8.0 + 10.0 * step_sec)).abs() < 1e-9);
+  assert!((deck.scrub_preview.rate - (-5.0 * step_sec / 0.05)).abs() < 1e-9);
+
+  controller.release_idle_jogs(&mut engine, 1100.0);
+  assert!(engine.deck(DeckId::Top).scrub_preview.active);
+  controller.release_idle_jogs(&mut engine, 1200.0);
+  let deck = engine.deck(DeckId::Top);
+  assert!(!deck.scrub_preview.active);
+  assert!((deck.current_sec - (8.0 + 5.0 * step_sec)).abs() < 1e-9);
+
+  controller.handle_message(&mut engine, &[0x97, 0, 127], 1000.0);
+  controller.handle_message(&mut engine, &[0x97, 0, 0], 1000.0);
+  let events = controller.drain_events();
+  assert_eq!(events.len(), 2);
+  assert_eq!(events[0].action, "hotCue");
+  assert_eq!(events[0].index, Some(2));
+  assert_eq!(events[0].deck.as_deref(), Some("top"));
+  assert!(events[0].pressed);
+  assert!(!events[1].pressed);
+}
+
+#[test]
+fn jog_nudges_playing_deck_rate_and_restores_on_release() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_loaded_deck(&mut engine, DeckId::Top);
+  engine.deck_mut(DeckId::Top).playing = true;
+  let mut controller = loaded_controller();
+
+  controller.handle_message(&mut engine, &[0xB0, 33, 2], 1000.0);
+  assert!((engine.deck(DeckId::Top).playback_rate - 1.02).abs() < 1e-9);
+  controller.handle_message(&mut engine, &[0xB0, 33, 120], 1040.0);
+  assert!((engine.deck(DeckId::Top).playback_rate - 0.96).abs() < 1e-9);
+  assert!(!engine.deck(DeckId::Top).scrub_preview.active);
+
+  controller.release_idle_jogs(&mut engine, 1200.0);
+  assert!((engine.deck(DeckId::Top).playback_rate - 1.0).abs() < 1e-9);
+}
@@ -21,7 +21,8 @@ impl HorizontalBrowseTransportEngine {
     let before_sec = self.deck(deck).current_sec;
     let was_playing = self.deck(deck).playing;
     let scrub_rendering =
-      horizontal_browse_transport_audio::is_scrub_preview_rendering(self.deck(deck));
+      horizontal_browse_transport_audio::is_scrub_preview_rendering(self.deck(deck))
+        || horizontal_browse_transport_motion::is_motion_rendering(self.deck(deck));
     let (deck_left, deck_right) = {
       let target = self.deck_mut(deck);
       let (raw_left, raw_right) =
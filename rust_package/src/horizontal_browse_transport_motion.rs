@@ -0,0 +1,259 @@
+use super::*;
+
+const MOTION_DEFAULT_BRAKE_BEATS: f64 = 1.0;
+const MOTION_DEFAULT_SPINBACK_BEATS: f64 = 2.0;
+const MOTION_MAX_BEATS: f64 = 64.0;
+const MOTION_FALLBACK_BEAT_SEC: f64 = 0.5;
+const MOTION_SPINBACK_START_RATE: f64 = 3.0;
+const MOTION_LOOP_EPSILON_SEC: f64 = 0.0005;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum DeckMotionMode {
+  None,
+  Brake,
+  Spinback,
+  Reverse,
+  Censor,
+}
+
+impl DeckMotionMode {
+  pub(super) fn as_str(self) -> &'static str {
+    match self {
+      DeckMotionMode::None => "none",
+      DeckMotionMode::Brake => "brake",
+      DeckMotionMode::Spinback => "spinback",
+      DeckMotionMode::Reverse => "reverse",
+      DeckMotionMode::Censor => "censor",
+    }
+  }
+}
+
+/// 刹车/倒旋/倒放/消音的运动状态；slip_sec 为按正常速率推进的影子播放头，退出时据此回到原相位
+#[derive(Clone, Copy)]
+pub(super) struct DeckMotionState {
+  pub(super) mode: DeckMotionMode,
+  pub(super) rate: f64,
+  start_rate: f64,
+  end_rate: f64,
+  elapsed_sec: f64,
+  ramp_sec: f64,
+  pub(super) slip_sec: Option<f64>,
+}
+
+impl Default for DeckMotionState {
+  fn default() -> Self {
+    Self {
+      mode: DeckMotionMode::None,
+      rate: 0.0,
+      start_rate: 0.0,
+      end_rate: 0.0,
+      elapsed_sec: 0.0,
+      ramp_sec: 0.0,
+      slip_sec: None,
+    }
+  }
+}
+
+impl DeckMotionState {
+  fn ramp(mode: DeckMotionMode, start_rate: f64, ramp_sec: f64, slip_sec: Option<f64>) -> Self {
+    Self {
+      mode,
+      rate: start_rate,
+      start_rate,
+      end_rate: 0.0,
+      elapsed_sec: 0.0,
+      ramp_sec: ramp_sec.max(0.001),
+      slip_sec,
+    }
+  }
+
+  fn hold(mode: DeckMotionMode, rate: f64, slip_sec: Option<f64>) -> Self {
+    Self {
+      mode,
+      rate,
+      start_rate: rate,
+      end_rate: rate,
+      elapsed_sec: 0.0,
+      ramp_sec: 0.0,
+      slip_sec,
+    }
+  }
+
+  pub(super) fn is_active(&self) -> bool {
+    self.mode != DeckMotionMode::None
+  }
+}
+
+pub(super) fn is_motion_rendering(target: &DeckState) -> bool {
+  target.playing && target.motion.is_active()
+}
+
+fn clamp_motion_beats(beats: Option<f64>, fallback: f64) -> f64 {
+  beats
+    .filter(|value| value.is_finite() && *value > 0.0)
+    .unwrap_or(fallback)
+    .min(MOTION_MAX_BEATS)
+}
+
+fn wrap_forward_in_loop(target: &DeckState, sec: f64) -> f64 {
+  if target.loop_active
+    && target.loop_end_sec - target.loop_start_sec > 0.0001
+    && sec >= target.loop_end_sec - MOTION_LOOP_EPSILON_SEC
+  {
+    return target.loop_start_sec + (sec - target.loop_end_sec).max(0.0);
+  }
+  sec
+}
+
+fn wrap_in_loop(target: &DeckState, sec: f64) -> f64 {
+  if target.loop_active
+    && target.loop_end_sec - target.loop_start_sec > 0.0001
+    && sec < target.loop_start_sec
+  {
+    return target.loop_end_sec - MOTION_LOOP_EPSILON_SEC;
+  }
+  wrap_forward_in_loop(target, sec)
+}
+
+/// 运动期间绕开 master tempo，直接按带符号速率插值读取，音高随速率变化
+pub(super) fn sample_deck_motion(target: &mut DeckState, output_sample_rate: f64) -> (f32, f32) {
+  let step_sec = 1.0 / output_sample_rate.max(1.0);
+  let base_rate = target.playback_rate.clamp(0.25, 4.0);
+  let state = &mut target.motion;
+  if state.ramp_sec > 0.0 {
+    state.elapsed_sec += step_sec;
+    let progress = (state.elapsed_sec / state.ramp_sec).clamp(0.0, 1.0);
+    state.rate = state.start_rate + (state.end_rate - state.start_rate) * progress;
+  } else if state.mode == DeckMotionMode::Reverse || state.mode == DeckMotionMode::Censor {
+    state.rate = -base_rate;
+  }
+  let rate = state.rate;
+
+  let (left, right) = horizontal_browse_transport_audio::read_deck_timeline_frame(
+    target,
+    target.current_sec,
+    rate,
+    output_sample_rate,
+  );
+  let next_sec = (target.current_sec + rate * step_sec).max(0.0);
+  target.current_sec = if rate < 0.0 {
+    wrap_in_loop(target, next_sec)
+  } else {
+    wrap_forward_in_loop(target, next_sec)
+  };
+  if let Some(slip_sec) = target.motion.slip_sec {
+    let next_slip_sec = wrap_forward_in_loop(target, slip_sec + base_rate * step_sec);
+    target.motion.slip_sec = Some(
+      if target.duration_sec.is_finite() && target.duration_sec > 0.0 {
+        next_slip_sec.min(target.duration_sec)
+      } else {
+        next_slip_sec
+      },
+    );
+  }
+  target.last_observed_at_ms = -1.0;
+
+  let state = target.motion;
+  if state.ramp_sec > 0.0 && state.elapsed_sec >= state.ramp_sec {
+    finish_deck_motion(target);
+  }
+  (left, right)
+}
+
+/// 刹车结束后停机；倒旋/消音等带 slip 的动作回到影子播放头继续播放
+fn finish_deck_motion(target: &mut DeckState) {
+  let state = target.motion;
+  target.motion = DeckMotionState::default();
+  if state.mode == DeckMotionMode::Brake {
+    target.playing = false;
+  }
+  if let Some(slip_sec) = state.slip_sec {
+    target.current_sec = slip_sec;
+  }
+  target.last_observed_at_ms = -1.0;
+  horizontal_browse_transport_audio::reset_master_tempo_state(target);
+}
+
+pub(super) fn clear_deck_motion(target: &mut DeckState) {
+  target.motion = DeckMotionState::default();
+}
+
+impl HorizontalBrowseTransportEngine {
+  fn motion_beat_sec(&self, deck: DeckId, current_sec: f64) -> f64 {
+    let rate = Self::normalize_playback_rate(self.deck(deck).playback_rate);
+    self
+      .beat_grid_at_sec(deck, current_sec)
+      .map(|grid| grid.beat_sec)
+      .filter(|value| value.is_finite() && *value > 0.0)
+      .unwrap_or(MOTION_FALLBACK_BEAT_SEC)
+      / rate
+  }
+
+  fn prepare_motion_command(&mut self, deck: DeckId, now_ms: f64) -> bool {
+    self.mark_state_changed();
+    self.last_now_ms = now_ms;
+    self.sync_deck_to_now(deck, now_ms);
+    self.deck(deck).playing && self.is_loaded(deck)
+  }
+
+  pub(super) fn start_brake(&mut self, deck: DeckId, now_ms: f64, beats: Option<f64>) {
+    if !self.prepare_motion_command(deck, now_ms) {
+      return;
+    }
+    let current_sec = self.deck(deck).current_sec;
+    let ramp_sec = clamp_motion_beats(beats, MOTION_DEFAULT_BRAKE_BEATS)
+      * self.motion_beat_sec(deck, current_sec);
+    let target = self.deck_mut(deck);
+    let start_rate = if target.motion.is_active() {
+      target.motion.rate
+    } else {
+      Self::normalize_playback_rate(target.playback_rate)
+    };
+    target.motion = DeckMotionState::ramp(DeckMotionMode::Brake, start_rate, ramp_sec, None);
+  }
+
+  pub(super) fn start_spinback(&mut self, deck: DeckId, now_ms: f64, beats: Option<f64>) {
+    if !self.prepare_motion_command(deck, now_ms) {
+      return;
+    }
+    let current_sec = self.deck(deck).current_sec;
+    let ramp_sec = clamp_motion_beats(beats, MOTION_DEFAULT_SPINBACK_BEATS)
+      * self.motion_beat_sec(deck, current_sec);
+    let target = self.deck_mut(deck);
+    let rate = Self::normalize_playback_rate(target.playback_rate);
+    let slip_sec = target.motion.slip_sec.unwrap_or(current_sec);
+    target.motion = DeckMotionState::ramp(
+      DeckMotionMode::Spinback,
+      -MOTION_SPINBACK_START_RATE * rate,
+      ramp_sec,
+      Some(slip_sec),
+    );
+  }
+
+  pub(super) fn set_reverse(&mut self, deck: DeckId, now_ms: f64, enabled: bool, slip: bool) {
+    let mode = if slip {
+      DeckMotionMode::Censor
+    } else {
+      DeckMotionMode::Reverse
+    };
+    if !enabled {
+      self.mark_state_changed();
+      self.last_now_ms = now_ms;
+      if self.deck(deck).motion.mode != mode {
+        return;
+      }
+      finish_deck_motion(self.deck_mut(deck));
+      self.deck_mut(deck).last_observed_at_ms = now_ms;
+      self.reset_and_prime_master_tempo_state(deck);
+      self.refresh_sync_state(true);
+      return;
+    }
+    if !self.prepare_motion_command(deck, now_ms) {
+      return;
+    }
+    let target = self.deck_mut(deck);
+    let rate = Self::normalize_playback_rate(target.playback_rate);
+    let slip_sec = slip.then(|| target.motion.slip_sec.unwrap_or(target.current_sec));
+    target.motion = DeckMotionState::hold(mode, -rate, slip_sec);
+  }
+}
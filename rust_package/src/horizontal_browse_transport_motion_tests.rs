@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use super::*;
+
+const TEST_SAMPLE_RATE: u32 = 100;
+
+fn install_playing_deck(engine: &mut HorizontalBrowseTransportEngine, deck: DeckId) {
+  let file_name = format!("{}-motion.wav", deck.as_str());
+  let target = engine.deck_mut(deck);
+  target.file_path = Some(file_name.clone());
+  target.loaded_file_path = Some(file_name.clone());
+  target.fully_decoded_file_path = Some(file_name);
+  target.bpm = Some(120.0);
+  target.first_beat_ms = Some(0.0);
+  target.sample_rate = TEST_SAMPLE_RATE;
+  target.channels = 1;
+  target.pcm_start_sec = 0.0;
+  target.pcm_data = Arc::new(
+    (0..TEST_SAMPLE_RATE * 20)
+      .map(|index| index as f32 / 10_000.0)
+      .collect(),
+  );
+  target.duration_sec = 20.0;
+  target.current_sec = 8.0;
+  target.last_observed_at_ms = 1000.0;
+  target.playing = true;
+  target.playback_rate = 1.0;
+  target.master_tempo_enabled = false;
+  engine.last_now_ms = 1000.0;
+}
+
+fn render_frames(engine: &mut HorizontalBrowseTransportEngine, deck: DeckId, frames: usize) {
+  for _ in 0..frames {
+    horizontal_browse_transport_audio::sample_deck(engine.deck_mut(deck), TEST_SAMPLE_RATE as f64);
+  }
+}
+
+#[test]
+fn brake_ramps_rate_to_zero_over_requested_beats_and_stops() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_playing_deck(&mut engine, DeckId::Top);
+  engine.start_brake(DeckId::Top, 1000.0, Some(2.0));
+  assert_eq!(engine.deck(DeckId::Top).motion.mode.as_str(), "brake");
+
+  render_frames(&mut engine, DeckId::Top, 99);
+  assert!(engine.deck(DeckId::Top).playing);
+  render_frames(&mut engine, DeckId::Top, 2);
+  let target = engine.deck(DeckId::Top);
+  assert!(!target.playing);
+  assert!(!target.motion.is_active());
+  // 两拍 = 1 秒，线性减速平均速率 0.5
+  assert!((target.current_sec - 8.5).abs() < 0.02);
+}
+
+#[test]
+fn censor_plays_backwards_and_returns_to_slip_position() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_playing_deck(&mut engine, DeckId::Top);
+  engine.set_reverse(DeckId::Top, 1000.0, true, true);
+
+  let before = horizontal_browse_transport_audio::sample_deck(engine.deck_mut(DeckId::Top), 100.0);
+  let after = horizontal_browse_transport_audio::sample_deck(engine.deck_mut(DeckId::Top), 100.0);
+  assert!(after.0 < before.0);
+  render_frames(&mut engine, DeckId::Top, 48);
+  assert!((engine.deck(DeckId::Top).current_sec - 7.5).abs() < 0.0001);
+
+  engine.set_reverse(DeckId::Top, 1500.0, false, true);
+  let target = engine.deck(DeckId::Top);
+  assert!(target.playing);
+  assert!(!target.motion.is_active());
+  assert!((target.current_sec - 8.5).abs() < 0.0001);
+}
+
+#[test]
+fn reverse_wraps_inside_active_loop() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_playing_deck(&mut engine, DeckId::Top);
+  {
+    let target = engine.deck_mut(DeckId::Top);
+    target.loop_active = true;
+    target.loop_start_sec = 7.9;
+    target.loop_end_sec = 8.4;
+  }
+  engine.set_reverse(DeckId::Top, 1000.0, true, false);
+  render_frames(&mut engine, DeckId::Top, 20);
+  let current_sec = engine.deck(DeckId::Top).current_sec;
+  assert!(current_sec > 7.9 && current_sec < 8.4);
+
+  engine.set_reverse(DeckId::Top, 1200.0, false, false);
+  assert_eq!(engine.deck(DeckId::Top).motion.mode.as_str(), "none");
+  assert!((engine.deck(DeckId::Top).current_sec - current_sec).abs() < 0.0001);
+}
+
+#[test]
+fn spinback_resumes_playback_at_slip_position() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_playing_deck(&mut engine, DeckId::Top);
+  engine.start_spinback(DeckId::Top, 1000.0, Some(1.0));
+  render_frames(&mut engine, DeckId::Top, 20);
+  assert!(engine.deck(DeckId::Top).current_sec < 8.0);
+
+  render_frames(&mut engine, DeckId::Top, 31);
+  let target = engine.deck(DeckId::Top);
+  assert!(target.playing);
+  assert!(!target.motion.is_active());
+  assert!((target.current_sec - 8.5).abs() < 0.02);
+}
@@ -292,6 +292,36 @@ pub fn horizontal_browse_transport_set_band_state(
   Ok(engine.snapshot(engine.last_now_ms))
 }
 
+#[napi]
+pub fn horizontal_browse_transport_set_deck_stems(
+  deck: String,
+  payload: HorizontalBrowseTransportStemsInput,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let deck_id = parse_deck_id(&deck)?;
+  let mut engine_guard = engine().lock();
+  let stem_request = engine_guard.set_deck_stems(deck_id, payload);
+  let snapshot = engine_guard.snapshot(engine_guard.last_now_ms);
+  drop(engine_guard);
+  if let Some(request) = stem_request {
+    schedule_stem_decode_request(request);
+  }
+  Ok(snapshot)
+}
+
+#[napi]
+pub fn horizontal_browse_transport_set_stem_state(
+  deck: String,
+  stem: String,
+  gain: Option<f64>,
+  muted: Option<bool>,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let deck_id = parse_deck_id(&deck)?;
+  let stem_kind = parse_stem_kind(&stem)?;
+  let mut engine = engine().lock();
+  engine.set_deck_stem_state(deck_id, stem_kind, gain, muted);
+  Ok(engine.snapshot(engine.last_now_ms))
+}
+
 #[napi]
 pub fn horizontal_browse_transport_set_cue_monitor_enabled(
   deck: String,
@@ -351,6 +381,58 @@ pub fn horizontal_browse_transport_seek(
   Ok(engine_guard.snapshot(engine_guard.last_now_ms))
 }
 
+#[napi]
+pub fn horizontal_browse_transport_brake(
+  deck: String,
+  now_ms: f64,
+  beats: Option<f64>,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let deck_id = parse_deck_id(&deck)?;
+  let mut engine_guard = engine().lock();
+  engine_guard.observe_external_now_ms(now_ms);
+  engine_guard.start_brake(deck_id, now_ms, beats);
+  Ok(engine_guard.snapshot(engine_guard.last_now_ms))
+}
+
+#[napi]
+pub fn horizontal_browse_transport_spinback(
+  deck: String,
+  now_ms: f64,
+  beats: Option<f64>,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let deck_id = parse_deck_id(&deck)?;
+  let mut engine_guard = engine().lock();
+  engine_guard.observe_external_now_ms(now_ms);
+  engine_guard.start_spinback(deck_id, now_ms, beats);
+  Ok(engine_guard.snapshot(engine_guard.last_now_ms))
+}
+
+#[napi]
+pub fn horizontal_browse_transport_set_reverse(
+  deck: String,
+  now_ms: f64,
+  enabled: bool,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let deck_id = parse_deck_id(&deck)?;
+  let mut engine_guard = engine().lock();
+  engine_guard.observe_external_now_ms(now_ms);
+  engine_guard.set_reverse(deck_id, now_ms, enabled, false);
+  Ok(engine_guard.snapshot(engine_guard.last_now_ms))
+}
+
+#[napi]
+pub fn horizontal_browse_transport_set_censor(
+  deck: String,
+  now_ms: f64,
+  enabled: bool,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let deck_id = parse_deck_id(&deck)?;
+  let mut engine_guard = engine().lock();
+  engine_guard.observe_external_now_ms(now_ms);
+  engine_guard.set_reverse(deck_id, now_ms, enabled, true);
+  Ok(engine_guard.snapshot(engine_guard.last_now_ms))
+}
+
 #[napi]
 pub fn horizontal_browse_transport_set_scrub_preview(
   deck: String,
@@ -461,6 +543,64 @@ pub fn horizontal_browse_transport_set_output_state(
   engine.snapshot(engine.last_now_ms)
 }
 
+#[napi]
+pub fn horizontal_browse_transport_set_channel_fader(
+  deck: String,
+  value: f64,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let deck_id = parse_deck_id(&deck)?;
+  let mut engine = engine().lock();
+  engine.set_channel_fader(deck_id, value);
+  Ok(engine.snapshot(engine.last_now_ms))
+}
+
+/// curve: linear | constantPower | sharpCut | custom；slope 仅对 custom 生效，reverse 为 hamster 反向
+#[napi]
+pub fn horizontal_browse_transport_set_crossfader_curve(
+  curve: String,
+  slope: Option<f64>,
+  reverse: Option<bool>,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let curve = horizontal_browse_transport_crossfader::parse_crossfader_curve(&curve)
+    .ok_or_else(|| Error::from_reason(format!("unknown crossfader curve: {}", curve)))?;
+  let mut engine = engine().lock();
+  engine.set_crossfader_curve(curve, slope, reverse);
+  Ok(engine.snapshot(engine.last_now_ms))
+}
+
+/// quality: linear（预览，默认）| sinc（带限插值）
+#[napi]
+pub fn horizontal_browse_transport_set_resample_quality(
+  quality: String,
+) -> napi::Result<HorizontalBrowseTransportSnapshot> {
+  let quality = crate::resampler::parse_resample_quality(&quality)
+    .ok_or_else(|| Error::from_reason(format!("unknown resample quality: {}", quality)))?;
+  let sinc_kernels = (quality == crate::resampler::ResampleQuality::Sinc)
+    .then(crate::resampler::PlaybackSincKernels::shared);
+  let mut engine = engine().lock();
+  engine.set_resample_quality(quality, sinc_kernels);
+  Ok(engine.snapshot(engine.last_now_ms))
+}
+
+#[napi]
+pub fn horizontal_browse_transport_get_pcm_cache_status() -> HorizontalBrowseTransportPcmCacheStatus
+{
+  horizontal_browse_transport_pcm_cache::pcm_cache_status()
+}
+
+#[napi]
+pub fn horizontal_browse_transport_configure_pcm_cache(
+  directory: Option<String>,
+  max_bytes: Option<f64>,
+) -> HorizontalBrowseTransportPcmCacheStatus {
+  horizontal_browse_transport_pcm_cache::configure_pcm_cache(directory, max_bytes)
+}
+
+#[napi]
+pub fn horizontal_browse_transport_clear_pcm_cache() -> HorizontalBrowseTransportPcmCacheStatus {
+  horizontal_browse_transport_pcm_cache::clear_pcm_cache()
+}
+
 #[napi]
 pub fn horizontal_browse_transport_start_recording(
   file_path: String,
@@ -501,8 +641,85 @@ pub fn horizontal_browse_transport_visualizer_snapshot(
   engine.visualizer_snapshot()
 }
 
+/// 电平表与频谱在调用线程上计算，只读取无锁环，不占用引擎锁
+#[napi]
+pub fn horizontal_browse_transport_meter_snapshot(
+  spectrum_band_count: Option<u32>,
+) -> HorizontalBrowseTransportMeterSnapshot {
+  horizontal_browse_transport_meter::compute_meter_snapshot(
+    horizontal_browse_transport_meter::meter_ring(),
+    spectrum_band_count,
+  )
+}
+
 #[napi]
 pub fn horizontal_browse_transport_drain_decode_diagnostics(
 ) -> Vec<HorizontalBrowseTransportDecodeDiagnostic> {
   drain_decode_diagnostics()
 }
+
+#[napi]
+pub fn horizontal_browse_transport_midi_list_ports() -> napi::Result<Vec<String>> {
+  horizontal_browse_transport_midi_io::list_midi_input_ports()
+}
+
+#[napi]
+pub fn horizontal_browse_transport_midi_list_output_ports() -> napi::Result<Vec<String>> {
+  horizontal_browse_transport_midi_io::list_midi_output_ports()
+}
+
+/// 载入声明式映射（JSON 字符串）；替换映射会清空软接管与 LED 缓存
+#[napi]
+pub fn horizontal_browse_transport_midi_load_mapping(
+  mapping_json: String,
+) -> napi::Result<HorizontalBrowseTransportMidiStatus> {
+  let mapping = horizontal_browse_transport_midi::parse_midi_mapping(&mapping_json)
+    .map_err(Error::from_reason)?;
+  horizontal_browse_transport_midi_io::midi_controller()
+    .lock()
+    .load_mapping(mapping);
+  Ok(horizontal_browse_transport_midi_io::midi_status())
+}
+
+#[napi]
+pub fn horizontal_browse_transport_midi_connect(
+  input_port_name: String,
+  output_port_name: Option<String>,
+) -> napi::Result<HorizontalBrowseTransportMidiStatus> {
+  horizontal_browse_transport_midi_io::connect_midi_ports(
+    &input_port_name,
+    output_port_name.as_deref(),
+  )?;
+  Ok(horizontal_browse_transport_midi_io::midi_status())
+}
+
+#[napi]
+pub fn horizontal_browse_transport_midi_disconnect() -> HorizontalBrowseTransportMidiStatus {
+  horizontal_browse_transport_midi_io::disconnect_midi_ports();
+  horizontal_browse_transport_midi_io::midi_status()
+}
+
+#[napi]
+pub fn horizontal_browse_transport_midi_status() -> HorizontalBrowseTransportMidiStatus {
+  horizontal_browse_transport_midi_io::midi_status()
+}
+
+/// 注入一条原始 MIDI 消息，走与硬件输入相同的映射路径（虚拟控制器/调试用）；
+/// 仅在启用 midi-debug feature 时导出
+#[cfg(feature = "midi-debug")]
+#[napi]
+pub fn horizontal_browse_transport_midi_inject(
+  message: Vec<u32>,
+) -> HorizontalBrowseTransportSnapshot {
+  let bytes: Vec<u8> = message.iter().map(|byte| (*byte).min(0xFF) as u8).collect();
+  horizontal_browse_transport_midi_io::dispatch_midi_message(&bytes);
+  let engine_guard = engine().lock();
+  engine_guard.snapshot(engine_guard.current_external_now_ms())
+}
+
+#[napi]
+pub fn horizontal_browse_transport_midi_drain_events() -> Vec<HorizontalBrowseTransportMidiEvent> {
+  horizontal_browse_transport_midi_io::midi_controller()
+    .lock()
+    .drain_events()
+}
@@ -0,0 +1,571 @@
+//! 传输层解码 PCM 的磁盘缓存：按文件路径 + 大小 + mtime + 解码参数建键，LRU 按总字节数淘汰
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::result::Result as StdResult;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytemuck::{cast_slice, cast_slice_mut};
+use napi_derive::napi;
+use parking_lot::Mutex;
+use ring::digest::{digest, SHA256};
+
+const PCM_CACHE_MAGIC: &[u8; 8] = b"FRKBPCM1";
+/// 缓存内容格式版本；解码链路（重采样、gapless 裁剪等）变化导致 PCM 不同时递增
+const PCM_CACHE_FORMAT_VERSION: u32 = 3;
+const PCM_CACHE_HEADER_BYTES: usize = 64;
+const PCM_CACHE_EXTENSION: &str = "pcm";
+const PCM_CACHE_DIR_NAME: &str = "frkb-transport-pcm-cache";
+const DEFAULT_PCM_CACHE_MAX_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// 缓存条目（对外展示）
+#[napi(object)]
+pub struct HorizontalBrowseTransportPcmCacheEntry {
+  pub file_path: String,
+  pub bytes: f64,
+  pub sample_rate: u32,
+  pub channels: u32,
+  pub total_frames: f64,
+  /// 最近一次命中或写入的时间（Unix 毫秒）
+  pub last_access_ms: f64,
+}
+
+/// 缓存状态
+#[napi(object)]
+pub struct HorizontalBrowseTransportPcmCacheStatus {
+  pub directory: String,
+  pub max_bytes: f64,
+  pub total_bytes: f64,
+  /// 按最近访问时间倒序
+  pub entries: Vec<HorizontalBrowseTransportPcmCacheEntry>,
+  pub error: Option<String>,
+}
+
+pub(super) struct CachedPcm {
+  pub(super) samples: Vec<f32>,
+  pub(super) sample_rate: u32,
+  pub(super) channels: u16,
+}
+
+/// 产生缓存 PCM 的解码参数，任一项不同都不能互相复用
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct PcmDecodeProfile {
+  pub(super) sample_rate: u32,
+  pub(super) backend: &'static str,
+  /// 源采样率与目标不同时使用的重采样器
+  pub(super) resampler: &'static str,
+  pub(super) gapless_trim: bool,
+}
+
+impl PcmDecodeProfile {
+  /// transport 整曲解码：native libav，由 swr 转到目标采样率，按 gapless 信息裁剪首尾
+  pub(super) fn transport(sample_rate: u32) -> Self {
+    Self {
+      sample_rate,
+      backend: "native-libav",
+      resampler: "swr",
+      gapless_trim: true,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct SourceStamp {
+  size: u64,
+  mtime_ms: i64,
+}
+
+#[derive(Clone, Debug)]
+struct PcmCacheHeader {
+  format_version: u32,
+  gapless_trim: bool,
+  sample_rate: u32,
+  channels: u16,
+  frame_count: u64,
+  source: SourceStamp,
+  file_path: String,
+  data_offset: usize,
+}
+
+#[derive(Clone, Debug)]
+struct PcmCacheEntry {
+  file_path: String,
+  bytes: u64,
+  sample_rate: u32,
+  channels: u16,
+  frame_count: u64,
+  last_access: SystemTime,
+}
+
+struct PcmCacheState {
+  directory: PathBuf,
+  max_bytes: u64,
+  entries: HashMap<String, PcmCacheEntry>,
+  scanned: bool,
+}
+
+impl PcmCacheState {
+  fn new() -> Self {
+    Self {
+      directory: std::env::temp_dir().join(PCM_CACHE_DIR_NAME),
+      max_bytes: DEFAULT_PCM_CACHE_MAX_BYTES,
+      entries: HashMap::new(),
+      scanned: false,
+    }
+  }
+
+  fn entry_path(&self, key: &str) -> PathBuf {
+    self
+      .directory
+      .join(format!("{}.{}", key, PCM_CACHE_EXTENSION))
+  }
+
+  fn total_bytes(&self) -> u64 {
+    self.entries.values().map(|entry| entry.bytes).sum()
+  }
+
+  /// 首次使用时从目录重建索引，mtime 即上次访问时间
+  fn ensure_scanned(&mut self) {
+    if self.scanned {
+      return;
+    }
+    self.scanned = true;
+    let Ok(read_dir) = fs::read_dir(&self.directory) else {
+      return;
+    };
+    for dir_entry in read_dir.flatten() {
+      let path = dir_entry.path();
+      if path.extension().and_then(|ext| ext.to_str()) != Some(PCM_CACHE_EXTENSION) {
+        continue;
+      }
+      let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        continue;
+      };
+      let Ok(metadata) = dir_entry.metadata() else {
+        continue;
+      };
+      match read_header_from_file(&path) {
+        Ok(header) if header.format_version == PCM_CACHE_FORMAT_VERSION => {
+          self.entries.insert(
+            key.to_string(),
+            PcmCacheEntry {
+              file_path: header.file_path,
+              bytes: metadata.len(),
+              sample_rate: header.sample_rate,
+              channels: header.channels,
+              frame_count: header.frame_count,
+              last_access: metadata.modified().unwrap_or(UNIX_EPOCH),
+            },
+          );
+        }
+        _ => {
+          let _ = fs::remove_file(&path);
+        }
+      }
+    }
+    self.evict_to(self.max_bytes);
+  }
+
+  fn remove(&mut self, key: &str) {
+    self.entries.remove(key);
+    let _ = fs::remove_file(self.entry_path(key));
+  }
+
+  fn evict_to(&mut self, limit_bytes: u64) {
+    let mut total = self.total_bytes();
+    while total > limit_bytes {
+      let Some(oldest_key) = self
+        .entries
+        .iter()
+        .min_by_key(|(_, entry)| entry.last_access)
+        .map(|(key, _)| key.clone())
+      else {
+        break;
+      };
+      total = total.saturating_sub(self.entries[&oldest_key].bytes);
+      self.remove(&oldest_key);
+    }
+  }
+
+  fn status(&self, error: Option<String>) -> HorizontalBrowseTransportPcmCacheStatus {
+    let mut entries: Vec<&PcmCacheEntry> = self.entries.values().collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_access));
+    HorizontalBrowseTransportPcmCacheStatus {
+      directory: self.directory.to_string_lossy().into_owned(),
+      max_bytes: self.max_bytes as f64,
+      total_bytes: self.total_bytes() as f64,
+      entries: entries
+        .into_iter()
+        .map(|entry| HorizontalBrowseTransportPcmCacheEntry {
+          file_path: entry.file_path.clone(),
+          bytes: entry.bytes as f64,
+          sample_rate: entry.sample_rate,
+          channels: entry.channels as u32,
+          total_frames: entry.frame_count as f64,
+          last_access_ms: system_time_ms(entry.last_access) as f64,
+        })
+        .collect(),
+      error,
+    }
+  }
+}
+
+fn pcm_cache() -> &'static Mutex<PcmCacheState> {
+  static PCM_CACHE: OnceLock<Mutex<PcmCacheState>> = OnceLock::new();
+  PCM_CACHE.get_or_init(|| Mutex::new(PcmCacheState::new()))
+}
+
+fn system_time_ms(time: SystemTime) -> i64 {
+  match time.duration_since(UNIX_EPOCH) {
+    Ok(elapsed) => elapsed.as_millis() as i64,
+    Err(error) => -(error.duration().as_millis() as i64),
+  }
+}
+
+fn source_stamp(path: &Path) -> Option<SourceStamp> {
+  let metadata = fs::metadata(path).ok()?;
+  Some(SourceStamp {
+    size: metadata.len(),
+    mtime_ms: system_time_ms(metadata.modified().ok()?),
+  })
+}
+
+fn cache_key(file_path: &str, stamp: &SourceStamp, profile: &PcmDecodeProfile) -> String {
+  let material = format!(
+    "{}\0{}\0{}\0v{}\0{}\0{}\0{}\0{}",
+    file_path,
+    stamp.size,
+    stamp.mtime_ms,
+    PCM_CACHE_FORMAT_VERSION,
+    profile.backend,
+    profile.sample_rate,
+    profile.resampler,
+    profile.gapless_trim
+  );
+  hex::encode(&digest(&SHA256, material.as_bytes()).as_ref()[..16])
+}
+
+fn encode_header(
+  file_path: &str,
+  stamp: &SourceStamp,
+  profile: &PcmDecodeProfile,
+  channels: u16,
+  frame_count: u64,
+) -> Vec<u8> {
+  let path_bytes = file_path.as_bytes();
+  let data_offset = (PCM_CACHE_HEADER_BYTES + path_bytes.len()).next_multiple_of(4);
+  let mut header = vec![0u8; data_offset];
+  header[0..8].copy_from_slice(PCM_CACHE_MAGIC);
+  header[8..12].copy_from_slice(&profile.sample_rate.to_le_bytes());
+  header[12..14].copy_from_slice(&channels.to_le_bytes());
+  header[16..24].copy_from_slice(&frame_count.to_le_bytes());
+  header[24..32].copy_from_slice(&stamp.size.to_le_bytes());
+  header[32..40].copy_from_slice(&stamp.mtime_ms.to_le_bytes());
+  header[40..44].copy_from_slice(&(path_bytes.len() as u32).to_le_bytes());
+  header[44..48].copy_from_slice(&(data_offset as u32).to_le_bytes());
+  header[48..52].copy_from_slice(&PCM_CACHE_FORMAT_VERSION.to_le_bytes());
+  header[52] = profile.gapless_trim as u8;
+  header[PCM_CACHE_HEADER_BYTES..PCM_CACHE_HEADER_BYTES + path_bytes.len()]
+    .copy_from_slice(path_bytes);
+  header
+}
+
+fn decode_header(bytes: &[u8]) -> StdResult<PcmCacheHeader, String> {
+  if bytes.len() < PCM_CACHE_HEADER_BYTES || &bytes[0..8] != PCM_CACHE_MAGIC {
+    return Err("PCM 缓存头无效".to_string());
+  }
+  let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+  let u64_at = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+  let path_len = u32_at(40) as usize;
+  let data_offset = u32_at(44) as usize;
+  let path_end = PCM_CACHE_HEADER_BYTES + path_len;
+  if path_end > data_offset || !data_offset.is_multiple_of(4) || path_end > bytes.len() {
+    return Err("PCM 缓存头无效".to_string());
+  }
+  Ok(PcmCacheHeader {
+    format_version: u32_at(48),
+    gapless_trim: bytes[52] != 0,
+    sample_rate: u32_at(8),
+    channels: u16::from_le_bytes([bytes[12], bytes[13]]),
+    frame_count: u64_at(16),
+    source: SourceStamp {
+      size: u64_at(24),
+      mtime_ms: u64_at(32) as i64,
+    },
+    file_path: String::from_utf8_lossy(&bytes[PCM_CACHE_HEADER_BYTES..path_end]).into_owned(),
+    data_offset,
+  })
+}
+
+fn read_header_from_file(path: &Path) -> StdResult<PcmCacheHeader, String> {
+  let mut file = File::open(path).map_err(|e| e.to_string())?;
+  read_header(&mut file)
+}
+
+fn read_header(file: &mut File) -> StdResult<PcmCacheHeader, String> {
+  let mut fixed = [0u8; PCM_CACHE_HEADER_BYTES];
+  file.read_exact(&mut fixed).map_err(|e| e.to_string())?;
+  let path_len = u32::from_le_bytes(fixed[40..44].try_into().unwrap()) as usize;
+  let mut bytes = fixed.to_vec();
+  bytes.resize(PCM_CACHE_HEADER_BYTES + path_len, 0);
+  file
+    .read_exact(&mut bytes[PCM_CACHE_HEADER_BYTES..])
+    .map_err(|e| e.to_string())?;
+  decode_header(&bytes)
+}
+
+/// 整块读入而不做 mmap：deck 持有的是 Arc<Vec<f32>>，映射后仍要拷贝一次；
+/// 且条目随时可能被 LRU 淘汰或覆盖，映射期间文件被截断会触发 SIGBUS
+fn read_cache_file(
+  path: &Path,
+  expected: &SourceStamp,
+  profile: &PcmDecodeProfile,
+) -> StdResult<CachedPcm, String> {
+  let mut file = File::open(path).map_err(|e| e.to_string())?;
+  let header = read_header(&mut file)?;
+  if &header.source != expected
+    || header.format_version != PCM_CACHE_FORMAT_VERSION
+    || header.sample_rate != profile.sample_rate
+    || header.gapless_trim != profile.gapless_trim
+  {
+    return Err("PCM 缓存已过期".to_string());
+  }
+  file
+    .seek(SeekFrom::Start(header.data_offset as u64))
+    .map_err(|e| e.to_string())?;
+  let sample_count = header.frame_count as usize * header.channels as usize;
+  let mut samples = vec![0f32; sample_count];
+  file
+    .read_exact(cast_slice_mut::<f32, u8>(&mut samples))
+    .map_err(|_| "PCM 缓存数据不完整".to_string())?;
+  Ok(CachedPcm {
+    samples,
+    sample_rate: header.sample_rate,
+    channels: header.channels,
+  })
+}
+
+/// 命中时返回完整 PCM 并刷新 LRU 时间；源文件大小、mtime 或解码参数变化即视为未命中
+pub(super) fn load_cached_pcm(file_path: &str, profile: &PcmDecodeProfile) -> Option<CachedPcm> {
+  let stamp = source_stamp(Path::new(file_path))?;
+  let key = cache_key(file_path, &stamp, profile);
+  let cache_path = {
+    let mut cache = pcm_cache().lock();
+    cache.ensure_scanned();
+    if !cache.entries.contains_key(&key) {
+      return None;
+    }
+    cache.entry_path(&key)
+  };
+  let loaded = read_cache_file(&cache_path, &stamp, profile);
+  let mut cache = pcm_cache().lock();
+  match loaded {
+    Ok(cached) => {
+      let now = SystemTime::now();
+      if let Some(entry) = cache.entries.get_mut(&key) {
+        entry.last_access = now;
+      }
+      if let Ok(file) = File::options().write(true).open(&cache_path) {
+        let _ = file.set_modified(now);
+      }
+      Some(cached)
+    }
+    Err(_) => {
+      cache.remove(&key);
+      None
+    }
+  }
+}
+
+/// 写入完整解码结果；超过上限的单个文件不缓存
+pub(super) fn store_cached_pcm(
+  file_path: &str,
+  samples: &[f32],
+  profile: &PcmDecodeProfile,
+  channels: u16,
+) -> StdResult<(), String> {
+  if channels == 0 || profile.sample_rate == 0 || samples.is_empty() {
+    return Ok(());
+  }
+  let stamp = source_stamp(Path::new(file_path)).ok_or_else(|| "源文件不可读".to_string())?;
+  let key = cache_key(file_path, &stamp, profile);
+  let frame_count = (samples.len() / channels as usize) as u64;
+  let header = encode_header(file_path, &stamp, profile, channels, frame_count);
+  let data = cast_slice::<f32, u8>(&samples[..frame_count as usize * channels as usize]);
+  let bytes = (header.len() + data.len()) as u64;
+  let (directory, cache_path) = {
+    let mut cache = pcm_cache().lock();
+    cache.ensure_scanned();
+    if bytes > cache.max_bytes || cache.entries.contains_key(&key) {
+      return Ok(());
+    }
+    (cache.directory.clone(), cache.entry_path(&key))
+  };
+  fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+  let tmp_path = cache_path.with_extension(format!("{}.{}", std::process::id(), "tmp"));
+  let written = File::create(&tmp_path).and_then(|mut file| {
+    file.write_all(&header)?;
+    file.write_all(data)?;
+    file.sync_data()
+  });
+  if let Err(error) = written.and_then(|_| fs::rename(&tmp_path, &cache_path)) {
+    let _ = fs::remove_file(&tmp_path);
+    return Err(error.to_string());
+  }
+  let mut cache = pcm_cache().lock();
+  if cache.directory != directory {
+    let _ = fs::remove_file(&cache_path);
+    return Ok(());
+  }
+  cache.entries.insert(
+    key,
+    PcmCacheEntry {
+      file_path: file_path.to_string(),
+      bytes,
+      sample_rate: profile.sample_rate,
+      channels,
+      frame_count,
+      last_access: SystemTime::now(),
+    },
+  );
+  let max_bytes = cache.max_bytes;
+  cache.evict_to(max_bytes);
+  Ok(())
+}
+
+pub(super) fn pcm_cache_status() -> HorizontalBrowseTransportPcmCacheStatus {
+  let mut cache = pcm_cache().lock();
+  cache.ensure_scanned();
+  cache.status(None)
+}
+
+pub(super) fn configure_pcm_cache(
+  directory: Option<String>,
+  max_bytes: Option<f64>,
+) -> HorizontalBrowseTransportPcmCacheStatus {
+  let mut cache = pcm_cache().lock();
+  if let Some(directory) = directory
+    .map(|value| value.trim().to_string())
+    .filter(|value| !value.is_empty())
+  {
+    let directory = PathBuf::from(directory);
+    if directory != cache.directory {
+      cache.directory = directory;
+      cache.entries.clear();
+      cache.scanned = false;
+    }
+  }
+  if let Some(max_bytes) = max_bytes.filter(|value| value.is_finite() && *value >= 0.0) {
+    cache.max_bytes = max_bytes as u64;
+  }
+  cache.ensure_scanned();
+  let max_bytes = cache.max_bytes;
+  cache.evict_to(max_bytes);
+  cache.status(None)
+}
+
+pub(super) fn clear_pcm_cache() -> HorizontalBrowseTransportPcmCacheStatus {
+  let mut cache = pcm_cache().lock();
+  cache.ensure_scanned();
+  cache.evict_to(0);
+  let error = fs::read_dir(&cache.directory).ok().and_then(|read_dir| {
+    read_dir
+      .flatten()
+      .map(|dir_entry| dir_entry.path())
+      .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(PCM_CACHE_EXTENSION))
+      .filter_map(|path| fs::remove_file(path).err())
+      .map(|error| error.to_string())
+      .next()
+  });
+  cache.status(error)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn header_round_trips_and_keys_follow_source_stamp() {
+    let stamp = SourceStamp {
+      size: 1_234,
+      mtime_ms: 1_700_000_000_123,
+    };
+    let profile = PcmDecodeProfile::transport(48_000);
+    let header = encode_header("/music/曲目.mp3", &stamp, &profile, 2, 96_000);
+    assert!(header.len().is_multiple_of(4));
+    let decoded = decode_header(&header).expect("header");
+    assert_eq!(decoded.file_path, "/music/曲目.mp3");
+    assert_eq!(decoded.sample_rate, 48_000);
+    assert_eq!(decoded.format_version, PCM_CACHE_FORMAT_VERSION);
+    assert!(decoded.gapless_trim);
+    assert_eq!(decoded.channels, 2);
+    assert_eq!(decoded.frame_count, 96_000);
+    assert_eq!(decoded.source, stamp);
+    assert_eq!(decoded.data_offset, header.len());
+
+    let touched = SourceStamp {
+      mtime_ms: stamp.mtime_ms + 1,
+      ..stamp.clone()
+    };
+    assert_ne!(
+      cache_key("/music/曲目.mp3", &stamp, &profile),
+      cache_key("/music/曲目.mp3", &touched, &profile)
+    );
+    assert_ne!(
+      cache_key("/music/曲目.mp3", &stamp, &profile),
+      cache_key(
+        "/music/曲目.mp3",
+        &stamp,
+        &PcmDecodeProfile::transport(44_100)
+      )
+    );
+    assert_ne!(
+      cache_key("/music/曲目.mp3", &stamp, &profile),
+      cache_key(
+        "/music/曲目.mp3",
+        &stamp,
+        &PcmDecodeProfile {
+          resampler: "sinc",
+          ..profile
+        }
+      )
+    );
+    assert!(decode_header(&header[..32]).is_err());
+  }
+
+  #[test]
+  fn eviction_drops_least_recently_used_entries_first() {
+    let directory = std::env::temp_dir().join(format!(
+      "frkb-pcm-cache-test-{}-{}",
+      std::process::id(),
+      system_time_ms(SystemTime::now())
+    ));
+    let mut state = PcmCacheState::new();
+    state.directory = directory.clone();
+    state.scanned = true;
+    for (index, key) in ["old", "mid", "new"].iter().enumerate() {
+      state.entries.insert(
+        key.to_string(),
+        PcmCacheEntry {
+          file_path: format!("{}.mp3", key),
+          bytes: 100,
+          sample_rate: 44_100,
+          channels: 2,
+          frame_count: 10,
+          last_access: UNIX_EPOCH + std::time::Duration::from_secs(index as u64 + 1),
+        },
+      );
+    }
+    state.evict_to(200);
+    assert!(!state.entries.contains_key("old"));
+    assert_eq!(state.total_bytes(), 200);
+    let status = state.status(None);
+    assert_eq!(status.entries[0].file_path, "new.mp3");
+    state.evict_to(0);
+    assert!(state.entries.is_empty());
+    let _ = fs::remove_dir_all(directory);
+  }
+}
@@ -1,4 +1,8 @@
+use super::horizontal_browse_transport_pcm_cache::{
+  load_cached_pcm, store_cached_pcm, PcmDecodeProfile,
+};
 use super::*;
+use crate::decode_error::{DecodeBackend, DecodeError, DecodeErrorKind};
 use parking_lot::Mutex;
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -21,9 +25,11 @@ enum DecodeRequestAudioResult {
     channels: u16,
     ffmpeg_metrics: FfmpegTransportDecodeMetrics,
     backend_trace: DecodeBackendTrace,
+    from_cache: bool,
   },
   Failed {
     backend_trace: DecodeBackendTrace,
+    error: DecodeError,
   },
   Cancelled {
     backend_trace: DecodeBackendTrace,
@@ -146,6 +152,9 @@ pub(super) fn drain_decode_diagnostics() -> Vec<HorizontalBrowseTransportDecodeD
 }
 
 fn should_record_decode_diagnostic(diagnostic: &HorizontalBrowseTransportDecodeDiagnostic) -> bool {
+  if diagnostic.error_code.is_some() {
+    return true;
+  }
   let threshold_ms = if diagnostic.full_decode {
     FULL_DECODE_DIAGNOSTIC_THRESHOLD_MS
   } else {
@@ -189,6 +198,9 @@ fn empty_decode_diagnostic(
     start_sec: request.start_sec,
     max_duration_sec: request.max_duration_sec,
     decoder_backend: None,
+    error_code: None,
+    error_message: None,
+    error_position_sec: None,
     queue_wait_ms,
     total_ms,
     ffmpeg_total_ms: None,
@@ -224,6 +236,7 @@ fn record_decode_request_status(
     queue_wait_ms,
     total_ms,
     DecodeBackendTrace::default(),
+    None,
   );
 }
 
@@ -234,9 +247,15 @@ fn record_decode_request_status_with_trace(
   queue_wait_ms: Option<f64>,
   total_ms: f64,
   backend_trace: DecodeBackendTrace,
+  error: Option<&DecodeError>,
 ) {
   let mut diagnostic = empty_decode_diagnostic(request, operation, status, queue_wait_ms, total_ms);
   diagnostic.decoder_backend = backend_trace.decoder_backend;
+  if let Some(error) = error {
+    diagnostic.error_code = Some(error.kind.as_str().to_string());
+    diagnostic.error_message = Some(error.to_string());
+    diagnostic.error_position_sec = error.position_sec;
+  }
   push_decode_diagnostic(diagnostic);
 }
 
@@ -254,7 +273,10 @@ fn finish_decode_request(
       channels,
       ffmpeg_metrics,
       backend_trace,
+      from_cache,
     } => {
+      // 缓存命中时即便是起播请求也拿到了整轨 PCM
+      let full_pcm = request.is_full_decode || from_cache;
       let sample_count = samples.len() as f64;
       let frame_count = if channels > 0 {
         samples.len() as f64 / channels as f64
@@ -262,12 +284,12 @@ fn finish_decode_request(
         0.0
       };
       let loudness_started_at_ms = native_now_ms();
-      let loudness_analysis = if request.is_full_decode {
+      let loudness_analysis = if full_pcm {
         super::horizontal_browse_transport_auto_gain::analyze_loudness(&samples, channels)
       } else {
         None
       };
-      let loudness_ms = if request.is_full_decode {
+      let loudness_ms = if full_pcm {
         Some((native_now_ms() - loudness_started_at_ms).max(0.0))
       } else {
         None
@@ -289,6 +311,7 @@ fn finish_decode_request(
           queue_wait_ms,
           (native_now_ms() - started_at_ms).max(0.0),
           backend_trace,
+          None,
         );
         return;
       };
@@ -299,20 +322,35 @@ fn finish_decode_request(
         sample_rate,
         channels,
         request.start_sec,
-        request.is_full_decode,
+        full_pcm,
       );
+      let cache_pcm = if request.is_full_decode && !from_cache {
+        Some(prepared.pcm_data.clone())
+      } else {
+        None
+      };
       let prepare_ms = (native_now_ms() - prepare_started_at_ms).max(0.0);
       let apply_started_at_ms = native_now_ms();
       let (applied, full_decode_request) = {
         let mut engine_guard = engine().lock();
-        if engine_guard.apply_prepared_decoded_audio(
-          request.deck,
-          &request.file_path,
-          request.request_id,
-          prepared,
-          request.is_full_decode,
-        ) {
-          if request.is_full_decode {
+        let applied = if full_pcm && !request.is_full_decode {
+          engine_guard.apply_cached_full_decoded_audio(
+            request.deck,
+            &request.file_path,
+            request.request_id,
+            prepared,
+          )
+        } else {
+          engine_guard.apply_prepared_decoded_audio(
+            request.deck,
+            &request.file_path,
+            request.request_id,
+            prepared,
+            request.is_full_decode,
+          )
+        };
+        if applied {
+          if full_pcm {
             engine_guard.set_deck_loudness_result(
               request.deck,
               &request.file_path,
@@ -321,7 +359,7 @@ fn finish_decode_request(
           }
           engine_guard.refresh();
           engine_guard.refresh_auto_gain();
-          if request.is_full_decode {
+          if full_pcm {
             (true, None)
           } else {
             (true, engine_guard.prepare_full_decode_request(request.deck))
@@ -341,6 +379,9 @@ fn finish_decode_request(
         start_sec: request.start_sec,
         max_duration_sec: request.max_duration_sec,
         decoder_backend: backend_trace.decoder_backend,
+        error_code: None,
+        error_message: None,
+        error_position_sec: None,
         queue_wait_ms,
         total_ms: (native_now_ms() - started_at_ms).max(0.0),
         ffmpeg_total_ms: Some(ffmpeg_metrics.total_ms),
@@ -360,11 +401,22 @@ fn finish_decode_request(
         sample_rate: sample_rate as f64,
         channels: channels as f64,
       });
+      if let Some(pcm_data) = cache_pcm {
+        let _ = store_cached_pcm(
+          &request.file_path,
+          &pcm_data,
+          &PcmDecodeProfile::transport(sample_rate),
+          channels,
+        );
+      }
       if let Some(request) = full_decode_request {
         schedule_decode_request(request);
       }
     }
-    DecodeRequestAudioResult::Failed { backend_trace } => {
+    DecodeRequestAudioResult::Failed {
+      backend_trace,
+      error,
+    } => {
       let mut engine_guard = engine().lock();
       engine_guard.mark_decode_finished(
         request.deck,
@@ -385,6 +437,7 @@ fn finish_decode_request(
         queue_wait_ms,
         (native_now_ms() - started_at_ms).max(0.0),
         backend_trace,
+        Some(&error),
       );
     }
     DecodeRequestAudioResult::Cancelled { backend_trace } => {
@@ -395,6 +448,7 @@ fn finish_decode_request(
         queue_wait_ms,
         (native_now_ms() - started_at_ms).max(0.0),
         backend_trace,
+        None,
       );
     }
   }
@@ -432,13 +486,27 @@ fn decode_transport_audio_file(
           channels: ffmpeg_pcm.channels,
           ffmpeg_metrics: ffmpeg_pcm.metrics,
           backend_trace,
+          from_cache: false,
         }
       } else {
-        DecodeRequestAudioResult::Failed { backend_trace }
+        DecodeRequestAudioResult::Failed {
+          backend_trace,
+          error: DecodeError::new(
+            DecodeErrorKind::Other,
+            DecodeBackend::Ffmpeg,
+            "FFmpeg 返回的声道数无效",
+          ),
+        }
       }
     }
     Ok(None) => DecodeRequestAudioResult::Cancelled { backend_trace },
-    Err(_) => DecodeRequestAudioResult::Failed { backend_trace },
+    Err(error) if error.kind == DecodeErrorKind::Cancelled => {
+      DecodeRequestAudioResult::Cancelled { backend_trace }
+    }
+    Err(error) => DecodeRequestAudioResult::Failed {
+      backend_trace,
+      error,
+    },
   }
 }
 
@@ -448,6 +516,19 @@ pub(super) fn schedule_decode_request(mut request: DecodeRequest) {
 }
 
 fn decode_request_audio(request: &DecodeRequest) -> DecodeRequestAudioResult {
+  let profile = PcmDecodeProfile::transport(crate::ffmpeg_decode::transport_decode_sample_rate());
+  if let Some(cached) = load_cached_pcm(&request.file_path, &profile) {
+    return DecodeRequestAudioResult::Decoded {
+      samples: cached.samples,
+      sample_rate: cached.sample_rate,
+      channels: cached.channels,
+      ffmpeg_metrics: FfmpegTransportDecodeMetrics::default(),
+      backend_trace: DecodeBackendTrace {
+        decoder_backend: Some("pcm-cache".to_string()),
+      },
+      from_cache: true,
+    };
+  }
   if request.is_full_decode {
     decode_transport_audio_file(request, None, None)
   } else {
@@ -20,6 +20,12 @@ impl HorizontalBrowseTransportEngine {
       master_gain: self.master_gain as f64,
       top_deck_gain: self.top.gain as f64,
       bottom_deck_gain: self.bottom.gain as f64,
+      top_channel_fader: self.channel_fader[Self::deck_index(DeckId::Top)] as f64,
+      bottom_channel_fader: self.channel_fader[Self::deck_index(DeckId::Bottom)] as f64,
+      crossfader_curve: self.crossfader.curve.as_str().to_string(),
+      crossfader_slope: self.crossfader.slope as f64,
+      crossfader_reverse: self.crossfader.reverse,
+      resample_quality: self.top.resample_quality.as_str().to_string(),
       limiter_overload: self.master_limiter.overload(),
       limiter_gain_reduction_db: self.master_limiter.gain_reduction_db() as f64,
       sample_rate: self.output_sample_rate as f64,
@@ -95,6 +101,15 @@ impl HorizontalBrowseTransportEngine {
       auto_gain_enabled: deck_state.auto_gain.enabled,
       auto_gain_status: deck_state.auto_gain.status.to_string(),
       auto_gain_value: deck_state.auto_gain.target_linear as f64,
+      stems_active: horizontal_browse_transport_stems::stems_render_active(deck_state),
+      stems: self.stem_snapshots(deck),
+      motion_mode: deck_state.motion.mode.as_str().to_string(),
+      motion_rate: if deck_state.motion.is_active() {
+        deck_state.motion.rate
+      } else {
+        deck_state.playback_rate
+      },
+      slip_current_sec: deck_state.motion.slip_sec,
     }
   }
 }
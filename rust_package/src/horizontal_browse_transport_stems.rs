@@ -0,0 +1,461 @@
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::*;
+
+pub(super) const DECK_STEM_COUNT: usize = 4;
+const STEM_GAIN_MAX: f32 = 4.0;
+const STEM_GAIN_RAMP_SEC: f64 = 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum DeckStemKind {
+  Drums,
+  Bass,
+  Vocals,
+  Other,
+}
+
+impl DeckStemKind {
+  pub(super) const ALL: [DeckStemKind; DECK_STEM_COUNT] = [
+    DeckStemKind::Drums,
+    DeckStemKind::Bass,
+    DeckStemKind::Vocals,
+    DeckStemKind::Other,
+  ];
+
+  pub(super) fn as_str(self) -> &'static str {
+    match self {
+      DeckStemKind::Drums => "drums",
+      DeckStemKind::Bass => "bass",
+      DeckStemKind::Vocals => "vocals",
+      DeckStemKind::Other => "other",
+    }
+  }
+
+  fn index(self) -> usize {
+    match self {
+      DeckStemKind::Drums => 0,
+      DeckStemKind::Bass => 1,
+      DeckStemKind::Vocals => 2,
+      DeckStemKind::Other => 3,
+    }
+  }
+}
+
+pub(super) fn parse_stem_kind(raw: &str) -> napi::Result<DeckStemKind> {
+  match raw.trim().to_lowercase().as_str() {
+    "drums" => Ok(DeckStemKind::Drums),
+    "bass" => Ok(DeckStemKind::Bass),
+    "vocals" => Ok(DeckStemKind::Vocals),
+    "other" => Ok(DeckStemKind::Other),
+    _ => Err(Error::from_reason(format!("unknown stem: {}", raw))),
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StemLoadStatus {
+  Empty,
+  Loading,
+  Ready,
+  Failed,
+}
+
+impl StemLoadStatus {
+  fn as_str(self) -> &'static str {
+    match self {
+      StemLoadStatus::Empty => "empty",
+      StemLoadStatus::Loading => "loading",
+      StemLoadStatus::Ready => "ready",
+      StemLoadStatus::Failed => "failed",
+    }
+  }
+}
+
+pub(super) struct DeckStemState {
+  file_path: Option<String>,
+  status: StemLoadStatus,
+  pcm_data: Arc<Vec<f32>>,
+  sample_rate: u32,
+  channels: u16,
+  /// 解码失败原因
+  error: Option<String>,
+  gain: f32,
+  muted: bool,
+  applied_gain: f32,
+}
+
+impl Default for DeckStemState {
+  fn default() -> Self {
+    Self {
+      file_path: None,
+      status: StemLoadStatus::Empty,
+      pcm_data: Arc::new(Vec::new()),
+      sample_rate: 0,
+      channels: 0,
+      error: None,
+      gain: 1.0,
+      muted: false,
+      applied_gain: 1.0,
+    }
+  }
+}
+
+impl DeckStemState {
+  fn target_gain(&self) -> f32 {
+    if self.muted {
+      0.0
+    } else {
+      self.gain
+    }
+  }
+
+  /// 主 PCM 重新装入后采样率可能与已就绪的分轨不再一致，此时整组分轨停用并在快照里报错
+  fn error(&self, deck_sample_rate: u32) -> Option<String> {
+    if self.status == StemLoadStatus::Ready
+      && deck_sample_rate != 0
+      && self.sample_rate != deck_sample_rate
+    {
+      return Some(format!(
+        "分轨采样率 {} Hz 与主音轨 {} Hz 不一致",
+        self.sample_rate, deck_sample_rate
+      ));
+    }
+    self.error.clone()
+  }
+}
+
+/// 解码线程的取消标记；请求被替换或分轨集合被丢弃时自动置位
+#[derive(Default)]
+struct StemDecodeCancel(Arc<AtomicBool>);
+
+impl Drop for StemDecodeCancel {
+  fn drop(&mut self) {
+    self.0.store(true, Ordering::Relaxed);
+  }
+}
+
+/// 与主音轨同一时间轴的分轨集合；所有分轨结束解码且至少一条成功后替代主 PCM 发声
+#[derive(Default)]
+pub(super) struct DeckStemSet {
+  owner_file_path: Option<String>,
+  request_id: u64,
+  cancel: StemDecodeCancel,
+  stems: [DeckStemState; DECK_STEM_COUNT],
+  ready: bool,
+}
+
+impl DeckStemSet {
+  /// 主音轨换曲后分轨失效，gain/mute 一并复位
+  pub(super) fn retain_owner(&mut self, file_path: &str) {
+    if self.owner_file_path.is_none() || self.owner_file_path.as_deref() == Some(file_path) {
+      return;
+    }
+    let request_id = self.request_id.wrapping_add(1);
+    *self = Self::default();
+    self.request_id = request_id;
+  }
+
+  /// 单条分轨失败不拖累整组：其余分轨就绪即可发声，失败的分轨由残差代替
+  fn refresh_ready(&mut self) {
+    let any_loading = self
+      .stems
+      .iter()
+      .any(|stem| stem.status == StemLoadStatus::Loading);
+    let any_ready = self
+      .stems
+      .iter()
+      .any(|stem| stem.status == StemLoadStatus::Ready);
+    self.ready = any_ready && !any_loading;
+  }
+}
+
+pub(super) struct StemDecodeRequest {
+  pub(super) deck: DeckId,
+  pub(super) owner_file_path: String,
+  pub(super) request_id: u64,
+  pub(super) cancelled: Arc<AtomicBool>,
+  /// 分轨解码的目标采样率，与主 PCM 一致
+  pub(super) sample_rate: u32,
+  pub(super) stems: Vec<(DeckStemKind, String)>,
+}
+
+pub(super) fn stems_render_active(target: &DeckState) -> bool {
+  target.stems.ready
+    && target.stems.stems.iter().all(|stem| {
+      stem.status != StemLoadStatus::Ready
+        || (stem.sample_rate == target.sample_rate && stem.channels > 0)
+    })
+}
+
+/// 分轨从 0 秒开始全量解码，按主 PCM 的源帧位置换算后逐轨插值叠加；
+/// 解码失败的分轨以“主 PCM − 已就绪分轨”的残差代替，仍受该分轨的 gain/mute 控制
+pub(super) fn read_stem_mix_sample(
+  target: &DeckState,
+  frame_count: usize,
+  source_frame: f64,
+  channel: usize,
+) -> f32 {
+  let stem_frame = source_frame + target.pcm_start_sec * target.sample_rate as f64;
+  let mut mix = 0.0;
+  let mut ready_sum = 0.0;
+  let mut failed_gain = 0.0;
+  let mut failed_count = 0;
+  for stem in target.stems.stems.iter() {
+    match stem.status {
+      StemLoadStatus::Ready => {
+        let stem_channels = stem.channels.max(1) as usize;
+        let sample = horizontal_browse_transport_audio::read_source_sample(
+          stem.pcm_data.as_ref().as_slice(),
+          stem.pcm_data.len() / stem_channels,
+          stem_channels,
+          stem_frame,
+          channel,
+        );
+        ready_sum += sample;
+        mix += sample * stem.applied_gain;
+      }
+      StemLoadStatus::Failed => {
+        failed_gain += stem.applied_gain;
+        failed_count += 1;
+      }
+      StemLoadStatus::Empty | StemLoadStatus::Loading => {}
+    }
+  }
+  if failed_count > 0 && failed_gain > 0.0 && source_frame < frame_count as f64 {
+    let main_sample = horizontal_browse_transport_audio::read_source_sample(
+      target.pcm_data.as_ref().as_slice(),
+      frame_count,
+      target.channels.max(1) as usize,
+      source_frame,
+      channel,
+    );
+    mix += (main_sample - ready_sum) * (failed_gain / failed_count as f32);
+  }
+  mix
+}
+
+/// 每个输出帧推进一次，避免 mute/gain 切换产生爆音
+pub(super) fn advance_stem_gains(target: &mut DeckState, output_sample_rate: f64) {
+  if !target.stems.ready {
+    return;
+  }
+  let step = (1.0 / (STEM_GAIN_RAMP_SEC * output_sample_rate.max(1.0))) as f32 * STEM_GAIN_MAX;
+  for stem in target.stems.stems.iter_mut() {
+    let gain = stem.target_gain();
+    if stem.applied_gain < gain {
+      stem.applied_gain = (stem.applied_gain + step).min(gain);
+    } else if stem.applied_gain > gain {
+      stem.applied_gain = (stem.applied_gain - step).max(gain);
+    }
+  }
+}
+
+fn deck_file_path(target: &DeckState) -> &str {
+  target.file_path.as_deref().map(str::trim).unwrap_or("")
+}
+
+fn normalize_stem_path(value: Option<String>) -> Option<String> {
+  value
+    .map(|path| path.trim().to_string())
+    .filter(|path| !path.is_empty())
+}
+
+impl HorizontalBrowseTransportEngine {
+  pub(super) fn set_deck_stems(
+    &mut self,
+    deck: DeckId,
+    payload: HorizontalBrowseTransportStemsInput,
+  ) -> Option<StemDecodeRequest> {
+    let owner_file_path = deck_file_path(self.deck(deck)).to_string();
+    let paths = [
+      normalize_stem_path(payload.drums_path),
+      normalize_stem_path(payload.bass_path),
+      normalize_stem_path(payload.vocals_path),
+      normalize_stem_path(payload.other_path),
+    ];
+    self.mark_state_changed();
+    let target = self.deck_mut(deck);
+    target.stems.retain_owner(&owner_file_path);
+    target.stems.request_id = target.stems.request_id.wrapping_add(1);
+    target.stems.cancel = StemDecodeCancel::default();
+    let request_id = target.stems.request_id;
+    if owner_file_path.is_empty() || paths.iter().all(Option::is_none) {
+      target.stems = DeckStemSet {
+        request_id,
+        ..DeckStemSet::default()
+      };
+      return None;
+    }
+    target.stems.owner_file_path = Some(owner_file_path.clone());
+    let mut stems = Vec::new();
+    for (kind, path) in DeckStemKind::ALL.into_iter().zip(paths) {
+      let stem = &mut target.stems.stems[kind.index()];
+      stem.pcm_data = Arc::new(Vec::new());
+      stem.sample_rate = 0;
+      stem.channels = 0;
+      stem.error = None;
+      stem.status = if path.is_some() {
+        StemLoadStatus::Loading
+      } else {
+        StemLoadStatus::Empty
+      };
+      stem.file_path = path.clone();
+      if let Some(path) = path {
+        stems.push((kind, path));
+      }
+    }
+    target.stems.refresh_ready();
+    // 主 PCM 尚未解码时按 transport 解码采样率，两者在输出流启动后一致
+    let sample_rate = match target.sample_rate {
+      0 => crate::ffmpeg_decode::transport_decode_sample_rate(),
+      sample_rate => sample_rate,
+    };
+    Some(StemDecodeRequest {
+      deck,
+      owner_file_path,
+      request_id,
+      cancelled: Arc::clone(&target.stems.cancel.0),
+      sample_rate,
+      stems,
+    })
+  }
+
+  pub(super) fn is_stem_request_current(
+    &self,
+    deck: DeckId,
+    owner_file_path: &str,
+    request_id: u64,
+  ) -> bool {
+    let target = self.deck(deck);
+    target.stems.request_id == request_id
+      && target.stems.owner_file_path.as_deref() == Some(owner_file_path)
+      && deck_file_path(target) == owner_file_path
+  }
+
+  pub(super) fn apply_decoded_stem(
+    &mut self,
+    deck: DeckId,
+    owner_file_path: &str,
+    request_id: u64,
+    kind: DeckStemKind,
+    decoded: StdResult<(Vec<f32>, u32, u16), String>,
+  ) -> bool {
+    if !self.is_stem_request_current(deck, owner_file_path, request_id) {
+      return false;
+    }
+    let target = self.deck_mut(deck);
+    let was_ready = target.stems.ready;
+    let deck_sample_rate = target.sample_rate;
+    let stem = &mut target.stems.stems[kind.index()];
+    let decoded = decoded.and_then(|(samples, sample_rate, channels)| {
+      if channels == 0 || sample_rate == 0 {
+        Err("分轨解码结果无效".to_string())
+      } else if deck_sample_rate != 0 && sample_rate != deck_sample_rate {
+        Err(format!(
+          "分轨采样率 {} Hz 与主音轨 {} Hz 不一致",
+          sample_rate, deck_sample_rate
+        ))
+      } else {
+        Ok((samples, sample_rate, channels))
+      }
+    });
+    match decoded {
+      Ok((samples, sample_rate, channels)) => {
+        stem.pcm_data = Arc::new(samples);
+        stem.sample_rate = sample_rate;
+        stem.channels = channels;
+        stem.error = None;
+        stem.status = StemLoadStatus::Ready;
+      }
+      Err(error) => {
+        stem.pcm_data = Arc::new(Vec::new());
+        stem.error = Some(error);
+        stem.status = StemLoadStatus::Failed;
+      }
+    }
+    target.stems.refresh_ready();
+    if target.stems.ready && !was_ready {
+      for stem in target.stems.stems.iter_mut() {
+        stem.applied_gain = stem.target_gain();
+      }
+      horizontal_browse_transport_audio::reset_master_tempo_state(target);
+    }
+    self.mark_state_changed();
+    true
+  }
+
+  pub(super) fn set_deck_stem_state(
+    &mut self,
+    deck: DeckId,
+    kind: DeckStemKind,
+    gain: Option<f64>,
+    muted: Option<bool>,
+  ) {
+    self.mark_state_changed();
+    let stem = &mut self.deck_mut(deck).stems.stems[kind.index()];
+    if let Some(gain) = gain.filter(|value| value.is_finite()) {
+      stem.gain = (gain as f32).clamp(0.0, STEM_GAIN_MAX);
+    }
+    if let Some(muted) = muted {
+      stem.muted = muted;
+    }
+  }
+
+  pub(super) fn stem_snapshots(&self, deck: DeckId) -> Vec<HorizontalBrowseTransportStemSnapshot> {
+    let target = self.deck(deck);
+    DeckStemKind::ALL
+      .into_iter()
+      .map(|kind| {
+        let stem = &target.stems.stems[kind.index()];
+        HorizontalBrowseTransportStemSnapshot {
+          stem: kind.as_str().to_string(),
+          file_path: stem.file_path.clone(),
+          status: stem.status.as_str().to_string(),
+          gain: stem.gain as f64,
+          muted: stem.muted,
+          error: stem.error(target.sample_rate),
+        }
+      })
+      .collect()
+  }
+}
+
+pub(super) fn schedule_stem_decode_request(request: StemDecodeRequest) {
+  thread::spawn(move || execute_stem_decode_request(request));
+}
+
+/// 四条分轨并行解码，各自完成即提交；取消检查只读原子标记，不在解码循环里争用引擎锁，
+/// 提交结果时再由引擎校验请求是否仍有效
+fn execute_stem_decode_request(request: StemDecodeRequest) {
+  let is_cancelled = || request.cancelled.load(Ordering::Relaxed);
+  let request = &request;
+  let is_cancelled = &is_cancelled;
+  thread::scope(|scope| {
+    for (kind, file_path) in request.stems.iter() {
+      scope.spawn(move || {
+        if is_cancelled() {
+          return;
+        }
+        let decoded = crate::ffmpeg_decode_transport_at_rate_cancellable(
+          std::path::Path::new(file_path),
+          None,
+          None,
+          request.sample_rate,
+          is_cancelled,
+        );
+        let decoded = match decoded {
+          Ok(Some(pcm)) => Ok((pcm.samples_f32, pcm.sample_rate, pcm.channels)),
+          Ok(None) => return,
+          Err(error) => Err(error.to_string()),
+        };
+        engine().lock().apply_decoded_stem(
+          request.deck,
+          &request.owner_file_path,
+          request.request_id,
+          *kind,
+          decoded,
+        );
+      });
+    }
+  });
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use super::*;
+use horizontal_browse_transport_stems::DeckStemKind;
+
+fn install_constant_deck(engine: &mut HorizontalBrowseTransportEngine, deck: DeckId, value: f32) {
+  let file_name = format!("{}-stems.wav", deck.as_str());
+  let target = engine.deck_mut(deck);
+  target.file_path = Some(file_name.clone());
+  target.loaded_file_path = Some(file_name.clone());
+  target.fully_decoded_file_path = Some(file_name);
+  target.sample_rate = 4;
+  target.channels = 1;
+  target.pcm_start_sec = 0.0;
+  target.pcm_data = Arc::new(vec![value; 16]);
+  target.duration_sec = 4.0;
+  target.current_sec = 0.0;
+  target.last_observed_at_ms = 1000.0;
+  target.playing = true;
+  target.playback_rate = 1.0;
+}
+
+fn stems_input(vocals: bool) -> HorizontalBrowseTransportStemsInput {
+  HorizontalBrowseTransportStemsInput {
+    drums_path: Some("drums.wav".to_string()),
+    bass_path: None,
+    vocals_path: vocals.then(|| "vocals.wav".to_string()),
+    other_path: None,
+  }
+}
+
+fn apply_constant_stem(
+  engine: &mut HorizontalBrowseTransportEngine,
+  deck: DeckId,
+  request_id: u64,
+  kind: DeckStemKind,
+  value: f32,
+) -> bool {
+  let owner_file_path = engine.deck(deck).file_path.clone().unwrap_or_default();
+  engine.apply_decoded_stem(
+    deck,
+    &owner_file_path,
+    request_id,
+    kind,
+    Ok((vec![value; 32], 4, 2)),
+  )
+}
+
+fn sample_top(engine: &mut HorizontalBrowseTransportEngine) -> (f32, f32) {
+  horizontal_browse_transport_audio::sample_deck(engine.deck_mut(DeckId::Top), 4.0)
+}
+
+#[test]
+fn stems_replace_main_pcm_only_after_every_requested_stem_is_ready() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_constant_deck(&mut engine, DeckId::Top, 0.5);
+  let request = engine
+    .set_deck_stems(DeckId::Top, stems_input(true))
+    .unwrap();
+  assert_eq!(request.stems.len(), 2);
+
+  assert!(apply_constant_stem(
+    &mut engine,
+    DeckId::Top,
+    request.request_id,
+    DeckStemKind::Drums,
+    0.1,
+  ));
+  assert!((sample_top(&mut engine).0 - 0.5).abs() < 0.0001);
+
+  assert!(apply_constant_stem(
+    &mut engine,
+    DeckId::Top,
+    request.request_id,
+    DeckStemKind::Vocals,
+    0.2,
+  ));
+  let snapshot = engine.deck_snapshot(DeckId::Top, 1000.0);
+  assert!(snapshot.stems_active);
+  let (left, right) = sample_top(&mut engine);
+  assert!((left - 0.3).abs() < 0.0001);
+  assert!((right - 0.3).abs() < 0.0001);
+}
+
+#[test]
+fn muted_stem_ramps_out_of_the_deck_mix() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_constant_deck(&mut engine, DeckId::Top, 0.5);
+  let request = engine
+    .set_deck_stems(DeckId::Top, stems_input(true))
+    .unwrap();
+  apply_constant_stem(
+    &mut engine,
+    DeckId::Top,
+    request.request_id,
+    DeckStemKind::Drums,
+    0.1,
+  );
+  apply_constant_stem(
+    &mut engine,
+    DeckId::Top,
+    request.request_id,
+    DeckStemKind::Vocals,
+    0.2,
+  );
+
+  engine.set_deck_stem_state(DeckId::Top, DeckStemKind::Vocals, None, Some(true));
+  let (left, _) = sample_top(&mut engine);
+  assert!((left - 0.1).abs() < 0.0001);
+  let snapshot = engine.deck_snapshot(DeckId::Top, 1000.0);
+  assert!(snapshot.stems[2].muted);
+  assert_eq!(snapshot.stems[2].status, "ready");
+}
+
+#[test]
+fn stale_stem_decode_is_ignored_after_track_change() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_constant_deck(&mut engine, DeckId::Top, 0.5);
+  let request = engine
+    .set_deck_stems(DeckId::Top, stems_input(false))
+    .unwrap();
+
+  engine.deck_mut(DeckId::Top).file_path = Some("next.wav".to_string());
+  let _ = engine.prepare_decode_request(DeckId::Top);
+  assert!(!engine.apply_decoded_stem(
+    DeckId::Top,
+    &request.owner_file_path,
+    request.request_id,
+    DeckStemKind::Drums,
+    Ok((vec![0.1; 32], 4, 2)),
+  ));
+  let snapshot = engine.deck_snapshot(DeckId::Top, 1000.0);
+  assert!(!snapshot.stems_active);
+  assert!(snapshot.stems.iter().all(|stem| stem.status == "empty"));
+}
+
+#[test]
+fn failed_stem_is_replaced_by_residual_of_main_mix() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_constant_deck(&mut engine, DeckId::Top, 0.5);
+  let request = engine
+    .set_deck_stems(DeckId::Top, stems_input(true))
+    .unwrap();
+  apply_constant_stem(
+    &mut engine,
+    DeckId::Top,
+    request.request_id,
+    DeckStemKind::Drums,
+    0.1,
+  );
+  let owner_file_path = request.owner_file_path.clone();
+  assert!(engine.apply_decoded_stem(
+    DeckId::Top,
+    &owner_file_path,
+    request.request_id,
+    DeckStemKind::Vocals,
+    Err("decode failed".to_string()),
+  ));
+  let snapshot = engine.deck_snapshot(DeckId::Top, 1000.0);
+  assert!(snapshot.stems_active);
+  assert_eq!(snapshot.stems[2].status, "failed");
+  assert_eq!(snapshot.stems[2].error.as_deref(), Some("decode failed"));
+  assert!((sample_top(&mut engine).0 - 0.5).abs() < 0.0001);
+
+  engine.set_deck_stem_state(DeckId::Top, DeckStemKind::Vocals, None, Some(true));
+  let (left, _) = sample_top(&mut engine);
+  assert!((left - 0.1).abs() < 0.0001);
+}
+
+#[test]
+fn replacing_stem_request_cancels_previous_decode() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_constant_deck(&mut engine, DeckId::Top, 0.5);
+  let first = engine
+    .set_deck_stems(DeckId::Top, stems_input(false))
+    .unwrap();
+  assert!(!first.cancelled.load(std::sync::atomic::Ordering::Relaxed));
+  let second = engine
+    .set_deck_stems(DeckId::Top, stems_input(true))
+    .unwrap();
+  assert!(first.cancelled.load(std::sync::atomic::Ordering::Relaxed));
+  assert!(!second.cancelled.load(std::sync::atomic::Ordering::Relaxed));
+
+  engine.deck_mut(DeckId::Top).file_path = Some("next.wav".to_string());
+  let _ = engine.prepare_decode_request(DeckId::Top);
+  assert!(second.cancelled.load(std::sync::atomic::Ordering::Relaxed));
+}
+
+#[test]
+fn stem_with_mismatched_sample_rate_fails_with_error() {
+  let mut engine = HorizontalBrowseTransportEngine::default();
+  install_constant_deck(&mut engine, DeckId::Top, 0.5);
+  let request = engine
+    .set_deck_stems(DeckId::Top, stems_input(true))
+    .unwrap();
+  assert_eq!(request.sample_rate, 4);
+  apply_constant_stem(
+    &mut engine,
+    DeckId::Top,
+    request.request_id,
+    DeckStemKind::Drums,
+    0.1,
+  );
+  let owner_file_path = request.owner_file_path.clone();
+  assert!(engine.apply_decoded_stem(
+    DeckId::Top,
+    &owner_file_path,
+    request.request_id,
+    DeckStemKind::Vocals,
+    Ok((vec![0.2; 32], 8, 2)),
+  ));
+  let snapshot = engine.deck_snapshot(DeckId::Top, 1000.0);
+  assert!(snapshot.stems_active);
+  assert_eq!(snapshot.stems[2].status, "failed");
+  assert!(snapshot.stems[2].error.is_some());
+  assert!(snapshot.stems[0].error.is_none());
+  assert!((sample_top(&mut engine).0 - 0.5).abs() < 0.0001);
+
+  // 主 PCM 换了采样率：已就绪分轨停用并报错
+  engine.deck_mut(DeckId::Top).sample_rate = 8;
+  let snapshot = engine.deck_snapshot(DeckId::Top, 1000.0);
+  assert!(!snapshot.stems_active);
+  assert!(snapshot.stems[0].error.is_some());
+}
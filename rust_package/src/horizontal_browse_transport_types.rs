@@ -55,7 +55,8 @@ pub struct HorizontalBrowseTransportDeckInput {
   pub first_beat_ms: Option<f64>,
   pub downbeat_beat_offset: Option<f64>,
   pub beat_grid_clips: Option<Vec<HorizontalBrowseTransportBeatGridClipInput>>,
-  pub rekordbox_beat_grid_entries: Option<Vec<HorizontalBrowseTransportRekordboxBeatGridEntryInput>>,
+  pub rekordbox_beat_grid_entries:
+    Option<Vec<HorizontalBrowseTransportRekordboxBeatGridEntryInput>>,
   pub time_basis_offset_ms: Option<f64>,
   pub duration_sec: f64,
   pub current_sec: f64,
@@ -72,7 +73,8 @@ pub struct HorizontalBrowseTransportBeatGridInput {
   pub first_beat_ms: Option<f64>,
   pub downbeat_beat_offset: Option<f64>,
   pub beat_grid_clips: Option<Vec<HorizontalBrowseTransportBeatGridClipInput>>,
-  pub rekordbox_beat_grid_entries: Option<Vec<HorizontalBrowseTransportRekordboxBeatGridEntryInput>>,
+  pub rekordbox_beat_grid_entries:
+    Option<Vec<HorizontalBrowseTransportRekordboxBeatGridEntryInput>>,
   pub time_basis_offset_ms: Option<f64>,
 }
 
@@ -92,6 +94,25 @@ pub struct HorizontalBrowseTransportBandState {
   pub low: bool,
 }
 
+#[napi(object)]
+pub struct HorizontalBrowseTransportStemsInput {
+  pub drums_path: Option<String>,
+  pub bass_path: Option<String>,
+  pub vocals_path: Option<String>,
+  pub other_path: Option<String>,
+}
+
+#[napi(object)]
+pub struct HorizontalBrowseTransportStemSnapshot {
+  pub stem: String,
+  pub file_path: Option<String>,
+  pub status: String,
+  pub gain: f64,
+  pub muted: bool,
+  /// 解码失败或采样率与主音轨不一致时的原因
+  pub error: Option<String>,
+}
+
 #[napi(object)]
 pub struct HorizontalBrowseTransportDeckSnapshot {
   pub deck: String,
@@ -128,6 +149,11 @@ pub struct HorizontalBrowseTransportDeckSnapshot {
   pub auto_gain_enabled: bool,
   pub auto_gain_status: String,
   pub auto_gain_value: f64,
+  pub stems_active: bool,
+  pub stems: Vec<HorizontalBrowseTransportStemSnapshot>,
+  pub motion_mode: String,
+  pub motion_rate: f64,
+  pub slip_current_sec: Option<f64>,
 }
 
 #[napi(object)]
@@ -136,6 +162,12 @@ pub struct HorizontalBrowseTransportOutputSnapshot {
   pub master_gain: f64,
   pub top_deck_gain: f64,
   pub bottom_deck_gain: f64,
+  pub top_channel_fader: f64,
+  pub bottom_channel_fader: f64,
+  pub crossfader_curve: String,
+  pub crossfader_slope: f64,
+  pub crossfader_reverse: bool,
+  pub resample_quality: String,
   pub limiter_overload: bool,
   pub limiter_gain_reduction_db: f64,
   pub sample_rate: f64,
@@ -161,6 +193,45 @@ pub struct HorizontalBrowseTransportVisualizerSnapshot {
   pub pre_limiter_peak_right_db: f64,
 }
 
+#[napi(object)]
+pub struct HorizontalBrowseTransportLevelMeterSnapshot {
+  pub peak_left_db: f64,
+  pub peak_right_db: f64,
+  pub rms_left_db: f64,
+  pub rms_right_db: f64,
+  pub short_term_lufs: f64,
+}
+
+#[napi(object)]
+pub struct HorizontalBrowseTransportMeterSnapshot {
+  pub sample_rate: f64,
+  pub top: HorizontalBrowseTransportLevelMeterSnapshot,
+  pub bottom: HorizontalBrowseTransportLevelMeterSnapshot,
+  /// 最终输出（含监听与节拍器）
+  pub master: HorizontalBrowseTransportLevelMeterSnapshot,
+  pub spectrum_db: Vec<f64>,
+  pub spectrum_band_hz: Vec<f64>,
+}
+
+#[napi(object)]
+#[derive(Clone)]
+pub struct HorizontalBrowseTransportMidiEvent {
+  pub action: String,
+  pub deck: Option<String>,
+  pub index: Option<u32>,
+  pub value: f64,
+  pub pressed: bool,
+}
+
+#[napi(object)]
+pub struct HorizontalBrowseTransportMidiStatus {
+  pub mapping_name: String,
+  pub input_binding_count: u32,
+  pub output_binding_count: u32,
+  pub input_port_name: Option<String>,
+  pub output_port_name: Option<String>,
+}
+
 #[napi(object)]
 #[derive(Clone)]
 pub struct HorizontalBrowseTransportDecodeDiagnostic {
@@ -173,6 +244,10 @@ pub struct HorizontalBrowseTransportDecodeDiagnostic {
   pub start_sec: f64,
   pub max_duration_sec: Option<f64>,
   pub decoder_backend: Option<String>,
+  /// 失败原因分类（not-found / corrupt / drm 等）
+  pub error_code: Option<String>,
+  pub error_message: Option<String>,
+  pub error_position_sec: Option<f64>,
   pub queue_wait_ms: Option<f64>,
   pub total_ms: f64,
   pub ffmpeg_total_ms: Option<f64>,
@@ -45,20 +45,61 @@ use ring::digest::{Context, SHA256};
 extern crate napi_derive;
 
 mod analysis_utils;
+mod audio_audit;
+mod audio_decode_stream;
+mod audio_descriptors;
+mod audio_embedding;
+mod audio_silence;
+mod audio_tracks;
+mod audio_transcode;
+mod audio_verify;
+mod cancel_registry;
 mod chromaprint_native;
+mod decode_error;
 mod ffmpeg_decode;
+mod gapless_info;
 mod horizontal_browse_transport;
 mod mixxx_waveform;
 mod pioneer_anlz_raw;
 mod pioneer_cues;
 mod pioneer_export;
+mod pioneer_waveform_gen;
 mod qm_key;
+mod resampler;
+mod similarity_index;
+mod song_structure;
 mod soundtouch_native;
-
+mod spectral_quality;
+mod time_basis_offset;
+mod track_analysis;
+mod waveform_pyramid;
+mod waveform_render;
+
+pub use crate::audio_audit::*;
+pub use crate::audio_decode_stream::*;
+pub use crate::audio_descriptors::*;
+pub use crate::audio_embedding::*;
+pub use crate::audio_silence::*;
+pub use crate::audio_tracks::*;
+pub use crate::audio_transcode::*;
+pub use crate::audio_verify::*;
+pub use crate::decode_error::DecodeErrorInfo;
+use crate::decode_error::{
+  join_decode_errors, primary_decode_error, DecodeBackend, DecodeError, DecodeErrorKind,
+};
 pub use crate::horizontal_browse_transport::*;
-use crate::mixxx_waveform::MixxxWaveformData;
+use crate::mixxx_waveform::{MixxxWaveformChunk, MixxxWaveformData, MixxxWaveformFileOptions};
 pub use crate::pioneer_cues::*;
 pub use crate::pioneer_export::*;
+pub use crate::resampler::*;
+pub use crate::similarity_index::*;
+pub use crate::song_structure::*;
+pub use crate::spectral_quality::*;
+use crate::spectral_quality::{is_lossless_container, SpectralAnalyzer, HASH_ANALYSIS_STRIDE};
+pub use crate::time_basis_offset::*;
+pub use crate::track_analysis::*;
+pub use crate::waveform_pyramid::*;
+pub use crate::waveform_render::*;
 
 // ===== 类型定义 =====
 
@@ -79,6 +120,13 @@ pub struct AudioFileResult {
   pub file_path: String,
   /// 错误描述（当分析失败时）
   pub error: Option<String>,
+  /// 失败原因分类（not-found / corrupt / drm 等），成功时为空
+  pub error_code: Option<String>,
+  /// 各解码后端的失败详情
+  pub decode_errors: Option<Vec<DecodeErrorInfo>>,
+  /// 频谱质量标签（lossless / fake-lossless / upsampled / 320k 等），同 SpectralQualityResult.qualityLabel；
+  /// 仅在开启 withQualityLabel 时给出
+  pub quality_label: Option<String>,
 }
 
 /// 音频解码结果
@@ -94,8 +142,14 @@ pub struct DecodeAudioResult {
   pub total_frames: f64,
   /// 解码后端（symphonia / ffmpeg / ffmpeg-fallback）
   pub decoder_backend: Option<String>,
+  /// 已裁掉的编码器前导（priming）时长（毫秒），无 gapless 信息时为空
+  pub priming_offset_ms: Option<f64>,
   /// 错误描述（当解码失败时）
   pub error: Option<String>,
+  /// 失败原因分类（not-found / corrupt / drm 等），成功时为空
+  pub error_code: Option<String>,
+  /// 各解码后端的失败详情
+  pub decode_errors: Option<Vec<DecodeErrorInfo>>,
 }
 
 /// SoundTouch 处理结果
@@ -123,11 +177,14 @@ pub struct KeyAnalysisResult {
 }
 
 impl AudioFileResult {
-  fn error(path: &Path, err: &str) -> Self {
+  fn decode_failure(path: &Path, errors: &[DecodeError]) -> Self {
     AudioFileResult {
       sha256_hash: "error".to_string(),
       file_path: path.to_string_lossy().to_string(),
-      error: Some(err.to_string()),
+      error: Some(join_decode_errors(errors)),
+      error_code: primary_decode_error(errors).map(|error| error.kind.as_str().to_string()),
+      decode_errors: Some(errors.iter().map(DecodeError::to_info).collect()),
+      quality_label: None,
     }
   }
 
@@ -136,6 +193,25 @@ impl AudioFileResult {
       sha256_hash: String::new(),
       file_path: path.to_string_lossy().to_string(),
       error: None,
+      error_code: None,
+      decode_errors: None,
+      quality_label: None,
+    }
+  }
+}
+
+impl DecodeAudioResult {
+  fn failure(decoder_backend: Option<&str>, errors: &[DecodeError]) -> Self {
+    DecodeAudioResult {
+      pcm_data: Buffer::from(vec![]),
+      sample_rate: 0,
+      channels: 0,
+      total_frames: 0.0,
+      decoder_backend: decoder_backend.map(str::to_string),
+      priming_offset_ms: None,
+      error: Some(join_decode_errors(errors)),
+      error_code: primary_decode_error(errors).map(|error| error.kind.as_str().to_string()),
+      decode_errors: Some(errors.iter().map(DecodeError::to_info).collect()),
     }
   }
 }
@@ -144,6 +220,7 @@ impl AudioFileResult {
 pub struct AudioProcessTask {
   file_paths: Vec<String>,
   callback: Option<ThreadsafeFunction<ProcessProgress>>,
+  with_quality_label: bool,
 }
 
 // ===== 公共 API =====
@@ -152,15 +229,20 @@ pub struct AudioProcessTask {
 ///
 /// # 参数
 /// * `file_paths` - 音频文件路径数组
+/// * `with_quality_label` - 是否同时做频谱质量分析（逐帧 FFT，默认关闭）
 ///
 /// # 返回值
 /// * 包含每个文件哈希值和路径的结果数组
 #[napi]
-pub fn calculate_audio_hashes(file_paths: Vec<String>) -> Vec<AudioFileResult> {
+pub fn calculate_audio_hashes(
+  file_paths: Vec<String>,
+  with_quality_label: Option<bool>,
+) -> Vec<AudioFileResult> {
+  let with_quality_label = with_quality_label.unwrap_or(false);
   // 并行处理所有文件
   file_paths
     .par_iter()
-    .map(|path| calculate_audio_hash_for_file(path))
+    .map(|path| calculate_audio_hash_for_file(path, with_quality_label))
     .collect::<Vec<AudioFileResult>>()
 }
 
@@ -169,10 +251,12 @@ pub fn calculate_audio_hashes(file_paths: Vec<String>) -> Vec<AudioFileResult> {
 pub async fn calculate_audio_hashes_with_progress(
   file_paths: Vec<String>,
   callback: Option<ThreadsafeFunction<ProcessProgress>>,
+  with_quality_label: Option<bool>,
 ) -> napi::Result<Vec<AudioFileResult>> {
   let mut task = AudioProcessTask {
     file_paths,
     callback,
+    with_quality_label: with_quality_label.unwrap_or(false),
   };
   task.compute()
 }
@@ -237,31 +321,36 @@ pub async fn calculate_file_hashes_with_progress(
   Ok(Arc::try_unwrap(results).unwrap().into_inner())
 }
 
-/// 解码音频文件为 PCM Float32Array
-///
-/// # 参数
-/// * `file_path` - 音频文件路径
-///
-/// # 返回值
-/// * 包含 PCM 数据和元数据的解码结果
-#[napi]
-pub fn decode_audio_file(file_path: String) -> DecodeAudioResult {
-  let path = Path::new(&file_path);
+/// 已知仅 FFmpeg 覆盖较好的格式：避免多此一举的探测/失败再回退
+/// 仍保留兜底回退用于异常情况（伪装扩展名/损坏文件等）
+fn is_ffmpeg_only_extension(path: &Path) -> bool {
   let ext = path
     .extension()
     .and_then(|s| s.to_str())
     .map(|s| s.to_ascii_lowercase())
     .unwrap_or_default();
-
-  // 已知仅 FFmpeg 覆盖较好的格式：避免多此一举的探测/失败再回退
-  // 仍保留兜底回退用于异常情况（伪装扩展名/损坏文件等）
   let ffmpeg_only_exts = [
     "wma", "ac3", "dts", "mka", "webm", "ape", "tak", "tta",
     "wv",
     // 其他非常见格式如 voc/au/amr/gsm/ra/spx/mp2/mp1/mpc/shn/thd/dtshd 如后续加入设置也会被 FFmpeg 覆盖
   ];
+  ffmpeg_only_exts.contains(&ext.as_str())
+}
 
-  if ffmpeg_only_exts.contains(&ext.as_str()) {
+/// 解码音频文件为 PCM Float32Array
+///
+/// # 参数
+/// * `file_path` - 音频文件路径
+///
+/// # 返回值
+/// * 包含 PCM 数据和元数据的解码结果
+#[napi]
+pub fn decode_audio_file(file_path: String) -> DecodeAudioResult {
+  let path = Path::new(&file_path);
+  if let Err(error) = decode_error::check_source_file(path) {
+    return DecodeAudioResult::failure(None, &[error]);
+  }
+  if is_ffmpeg_only_extension(path) {
     return match decode_with_ffmpeg(path) {
       Ok(mut result) => {
         if result.decoder_backend.is_none() {
@@ -269,14 +358,7 @@ pub fn decode_audio_file(file_path: String) -> DecodeAudioResult {
         }
         result
       }
-      Err(ffmpeg_err) => DecodeAudioResult {
-        pcm_data: Buffer::from(vec![]),
-        sample_rate: 0,
-        channels: 0,
-        total_frames: 0.0,
-        decoder_backend: Some("ffmpeg".to_string()),
-        error: Some(format!("FFmpeg 解码失败: {}", ffmpeg_err)),
-      },
+      Err(ffmpeg_err) => DecodeAudioResult::failure(Some("ffmpeg"), &[ffmpeg_err]),
     };
   }
 
@@ -293,21 +375,13 @@ pub fn decode_audio_file(file_path: String) -> DecodeAudioResult {
         }
         result
       }
-      Err(ffmpeg_err) => DecodeAudioResult {
-        pcm_data: Buffer::from(vec![]),
-        sample_rate: 0,
-        channels: 0,
-        total_frames: 0.0,
-        decoder_backend: None,
-        error: Some(format!(
-          "Symphonia 解码失败: {}; FFmpeg 解码失败: {}",
-          symphonia_err, ffmpeg_err
-        )),
-      },
+      Err(ffmpeg_err) => DecodeAudioResult::failure(None, &[symphonia_err, ffmpeg_err]),
     },
   }
 }
 
+/// resample_quality 为空时由 FFmpeg swr 直接输出目标采样率；
+/// 指定 linear / sinc 时按原生采样率解码后走内置带限重采样
 #[napi]
 pub fn decode_audio_file_native_pcm(
   file_path: String,
@@ -315,37 +389,78 @@ pub fn decode_audio_file_native_pcm(
   max_duration_sec: Option<f64>,
   sample_rate: u32,
   channels: u32,
+  resample_quality: Option<String>,
 ) -> DecodeAudioResult {
   let path = Path::new(&file_path);
+  let resample_quality = match resample_quality.as_deref() {
+    Some(raw) => match resampler::parse_resample_quality(raw) {
+      Some(quality) => Some(quality),
+      None => {
+        return DecodeAudioResult::failure(
+          Some("ffmpeg-native"),
+          &[DecodeError::new(
+            DecodeErrorKind::InvalidArgument,
+            DecodeBackend::Ffmpeg,
+            format!("未知的重采样质量: {}", raw),
+          )],
+        );
+      }
+    },
+    None => None,
+  };
   if sample_rate == 0 {
-    return DecodeAudioResult {
-      pcm_data: Buffer::from(vec![]),
-      sample_rate: 0,
-      channels: 0,
-      total_frames: 0.0,
-      decoder_backend: Some("ffmpeg-native".to_string()),
-      error: Some("native PCM 解码采样率无效".to_string()),
-    };
+    return DecodeAudioResult::failure(
+      Some("ffmpeg-native"),
+      &[DecodeError::new(
+        DecodeErrorKind::InvalidArgument,
+        DecodeBackend::Ffmpeg,
+        "native PCM 解码采样率无效",
+      )],
+    );
   }
   if channels == 0 || channels > u8::MAX as u32 {
-    return DecodeAudioResult {
-      pcm_data: Buffer::from(vec![]),
-      sample_rate: 0,
-      channels: 0,
-      total_frames: 0.0,
-      decoder_backend: Some("ffmpeg-native".to_string()),
-      error: Some("native PCM 解码声道数无效".to_string()),
-    };
+    return DecodeAudioResult::failure(
+      Some("ffmpeg-native"),
+      &[DecodeError::new(
+        DecodeErrorKind::InvalidArgument,
+        DecodeBackend::Ffmpeg,
+        "native PCM 解码声道数无效",
+      )],
+    );
+  }
+  if let Err(error) = decode_error::check_source_file(path) {
+    return DecodeAudioResult::failure(Some("ffmpeg-native"), &[error]);
   }
 
-  match ffmpeg_decode_native_f32(
-    path,
-    start_sec,
-    max_duration_sec,
-    sample_rate,
-    channels as u16,
-  ) {
-    Ok((pcm_f32, decoded_sample_rate, decoded_channels, _native_ms, _convert_ms)) => {
+  let decoded = match resample_quality {
+    Some(quality) => decode_native_pcm_with_resampler(
+      path,
+      start_sec,
+      max_duration_sec,
+      sample_rate,
+      channels as u16,
+      quality,
+    ),
+    None => ffmpeg_decode_native_f32(
+      path,
+      start_sec,
+      max_duration_sec,
+      sample_rate,
+      channels as u16,
+    ),
+  };
+  match decoded {
+    Ok((mut pcm_f32, decoded_sample_rate, decoded_channels, _native_ms, _convert_ms)) => {
+      let gapless = gapless_info::read_gapless_info(path);
+      if let Some(info) = gapless.as_ref() {
+        gapless_info::apply_ffmpeg_gapless(
+          &mut pcm_f32,
+          decoded_channels as usize,
+          decoded_sample_rate,
+          start_sec.unwrap_or(0.0),
+          info,
+        );
+      }
       let total_frames = if decoded_channels > 0 {
         pcm_f32.len() as f64 / decoded_channels as f64
       } else {
@@ -357,20 +472,44 @@ pub fn decode_audio_file_native_pcm(
         channels: decoded_channels.min(u8::MAX as u16) as u8,
         total_frames,
         decoder_backend: Some("ffmpeg-native".to_string()),
+        priming_offset_ms: gapless.and_then(|info| info.priming_offset_ms(decoded_sample_rate)),
         error: None,
+        error_code: None,
+        decode_errors: None,
       }
     }
-    Err(error) => DecodeAudioResult {
-      pcm_data: Buffer::from(vec![]),
-      sample_rate: 0,
-      channels: 0,
-      total_frames: 0.0,
-      decoder_backend: Some("ffmpeg-native".to_string()),
-      error: Some(error),
-    },
+    Err(error) => DecodeAudioResult::failure(Some("ffmpeg-native"), &[error]),
   }
 }
 
+/// 按原生采样率解码，再用内置带限重采样转换到目标采样率
+fn decode_native_pcm_with_resampler(
+  path: &Path,
+  start_sec: Option<f64>,
+  max_duration_sec: Option<f64>,
+  sample_rate: u32,
+  channels: u16,
+  quality: resampler::ResampleQuality,
+) -> StdResult<(Vec<f32>, u32, u16, f64, f64), DecodeError> {
+  let (pcm_f32, decoded_sample_rate, decoded_channels, native_ms, convert_ms) =
+    ffmpeg_decode_native_f32(path, start_sec, max_duration_sec, 0, channels)?;
+  let resampled = resampler::resample_interleaved(
+    &pcm_f32,
+    decoded_channels as usize,
+    decoded_sample_rate,
+    sample_rate,
+    quality,
+  )
+  .map_err(|error| DecodeError::new(DecodeErrorKind::Other, DecodeBackend::Ffmpeg, error))?;
+  Ok((
+    resampled,
+    sample_rate,
+    decoded_channels,
+    native_ms,
+    convert_ms,
+  ))
+}
+
 /// 使用 SoundTouch 对交错 PCM 做不变调变速
 #[napi]
 pub fn process_soundtouch_pcm(
@@ -436,67 +575,53 @@ pub fn generate_chromaprint_fingerprint(
   file_path: String,
   max_length_seconds: Option<u32>,
 ) -> ChromaprintFingerprintResult {
-  let path = Path::new(&file_path);
-  if !path.exists() {
-    return ChromaprintFingerprintResult {
-      fingerprint: String::new(),
-      duration: 0.0,
-      error: Some("文件不存在".to_string()),
-    };
-  }
-
-  let max_sec = max_length_seconds.unwrap_or(120);
-
-  // 优先尝试 FFmpeg libavcodec 直接解码 + Chromaprint 一体化（最快路径）
-  match chromaprint_native::generate_fingerprint_from_file(&file_path, max_sec) {
+  match fingerprint_audio_file(&file_path, max_length_seconds.unwrap_or(120)) {
     Ok(result) => ChromaprintFingerprintResult {
       fingerprint: result.fingerprint,
       duration: result.duration,
       error: None,
     },
-    Err(ffmpeg_err) => {
-      // 回退到 Symphonia 解码 + Chromaprint
-      match decode_with_symphonia(path) {
-        Ok(decode_result) => {
-          let pcm_f32: &[f32] = cast_slice(&decode_result.pcm_data);
-          let max_samples = if max_sec > 0 {
-            (((max_sec as f64) * decode_result.sample_rate as f64 * decode_result.channels as f64)
-              as usize)
-              .min(pcm_f32.len())
-          } else {
-            pcm_f32.len()
-          };
-          match chromaprint_native::generate_fingerprint_from_f32(
-            &pcm_f32[..max_samples],
-            decode_result.sample_rate,
-            decode_result.channels as u16,
-            0,
-          ) {
-            Ok(result) => ChromaprintFingerprintResult {
-              fingerprint: result.fingerprint,
-              duration: result.duration,
-              error: None,
-            },
-            Err(e) => ChromaprintFingerprintResult {
-              fingerprint: String::new(),
-              duration: 0.0,
-              error: Some(e),
-            },
-          }
-        }
-        Err(symphonia_err) => ChromaprintFingerprintResult {
-          fingerprint: String::new(),
-          duration: 0.0,
-          error: Some(format!(
-            "FFmpeg: {} | Symphonia: {}",
-            ffmpeg_err, symphonia_err
-          )),
-        },
-      }
-    }
+    Err(error) => ChromaprintFingerprintResult {
+      fingerprint: String::new(),
+      duration: 0.0,
+      error: Some(error),
+    },
   }
 }
 
+/// 优先 FFmpeg 解码 + Chromaprint 一体化（最快路径），失败回退 Symphonia 解码；
+/// 批量分析的指纹也走这里，保证与单独生成的指纹一致
+pub(crate) fn fingerprint_audio_file(
+  file_path: &str,
+  max_sec: u32,
+) -> StdResult<chromaprint_native::ChromaprintResult, String> {
+  let path = Path::new(file_path);
+  if !path.exists() {
+    return Err("文件不存在".to_string());
+  }
+
+  let ffmpeg_err = match chromaprint_native::generate_fingerprint_from_file(file_path, max_sec) {
+    Ok(result) => return Ok(result),
+    Err(error) => error,
+  };
+  let decode_result = decode_with_symphonia(path)
+    .map_err(|symphonia_err| format!("FFmpeg: {} | Symphonia: {}", ffmpeg_err, symphonia_err))?;
+  let pcm_f32: &[f32] = cast_slice(&decode_result.pcm_data);
+  let max_samples = if max_sec > 0 {
+    (((max_sec as f64) * decode_result.sample_rate as f64 * decode_result.channels as f64)
+      as usize)
+      .min(pcm_f32.len())
+  } else {
+    pcm_f32.len()
+  };
+  chromaprint_native::generate_fingerprint_from_f32(
+    &pcm_f32[..max_samples],
+    decode_result.sample_rate,
+    decode_result.channels as u16,
+    0,
+  )
+}
+
 /// 基于 PCM 计算 Mixxx RGB 波形
 #[napi]
 pub fn compute_mixxx_waveform(
@@ -523,6 +648,20 @@ pub fn compute_mixxx_waveform_with_rate(
   )
 }
 
+/// 边解码文件边计算 Mixxx RGB 波形，按块回调增量结果
+#[napi]
+pub async fn compute_mixxx_waveform_from_file(
+  file_path: String,
+  options: Option<MixxxWaveformFileOptions>,
+  callback: Option<ThreadsafeFunction<MixxxWaveformChunk>>,
+) -> napi::Result<MixxxWaveformData> {
+  mixxx_waveform::compute_mixxx_waveform_from_file(
+    &file_path,
+    options.unwrap_or_default(),
+    callback.as_ref(),
+  )
+}
+
 /// 基于 PCM 计算调性（Mixxx Queen Mary）
 #[napi]
 pub fn analyze_key_from_pcm(
@@ -576,21 +715,34 @@ fn key_id_to_id3_text(key_id: i32) -> String {
 }
 
 /// 解码音频为 PCM Float32Array
-fn decode_audio_to_pcm(mut format: Box<dyn FormatReader>) -> napi::Result<DecodeAudioResult> {
+fn decode_audio_to_pcm(
+  mut format: Box<dyn FormatReader>,
+  gapless: Option<&gapless_info::GaplessInfo>,
+) -> StdResult<DecodeAudioResult, DecodeError> {
   let (track_id, codec_params) = {
-    let track = find_decode_track(&mut format)?;
+    let track = find_decode_track(&mut format).map_err(symphonia_setup_error)?;
     (track.id, track.codec_params.clone())
   };
 
   let channels = codec_params
     .channels
-    .ok_or_else(|| napi::Error::from_reason("缺少声道信息"))?
+    .ok_or_else(|| {
+      DecodeError::new(
+        DecodeErrorKind::Corrupt,
+        DecodeBackend::Symphonia,
+        "缺少声道信息",
+      )
+    })?
     .count() as u8;
-  let sample_rate = codec_params
-    .sample_rate
-    .ok_or_else(|| napi::Error::from_reason("缺少采样率信息"))?;
+  let sample_rate = codec_params.sample_rate.ok_or_else(|| {
+    DecodeError::new(
+      DecodeErrorKind::Corrupt,
+      DecodeBackend::Symphonia,
+      "缺少采样率信息",
+    )
+  })?;
 
-  let mut decoder = build_decoder(&codec_params)?;
+  let mut decoder = build_decoder(&codec_params).map_err(symphonia_setup_error)?;
 
   // 收集所有 PCM 样本（交错格式）
   let mut all_samples: Vec<f32> = Vec::new();
@@ -646,36 +798,63 @@ fn decode_audio_to_pcm(mut format: Box<dyn FormatReader>) -> napi::Result<Decode
             }
           }
           _ => {
-            return Err(napi::Error::from_reason("不支持的音频格式"));
+            return Err(DecodeError::new(
+              DecodeErrorKind::UnsupportedCodec,
+              DecodeBackend::Symphonia,
+              "不支持的音频格式",
+            ));
           }
         }
       }
       Err(SymphoniaError::IoError(_)) | Err(SymphoniaError::DecodeError(_)) => continue,
       Err(e) => {
-        return Err(napi::Error::from_reason(format!("解码错误: {}", e)));
+        return Err(
+          DecodeError::from_symphonia(&e).at(Some(total_frames as f64 / sample_rate.max(1) as f64)),
+        );
       }
     }
   }
 
+  if let Some(info) = gapless {
+    gapless_info::apply_symphonia_gapless(&mut all_samples, channels as usize, info);
+    total_frames = (all_samples.len() / channels.max(1) as usize) as u64;
+  }
+
   Ok(DecodeAudioResult {
     pcm_data: Buffer::from(cast_slice(&all_samples).to_vec()),
     sample_rate,
     channels,
     total_frames: total_frames as f64,
     decoder_backend: None,
+    priming_offset_ms: gapless.and_then(|info| info.priming_offset_ms(sample_rate)),
     error: None,
+    error_code: None,
+    decode_errors: None,
   })
 }
 
-fn decode_with_symphonia(path: &Path) -> StdResult<DecodeAudioResult, String> {
-  let file = File::open(path).map_err(|_| "打开文件失败".to_string())?;
+/// 选轨/建解码器失败说明 Symphonia 不支持该编码
+fn symphonia_setup_error(error: napi::Error) -> DecodeError {
+  DecodeError::new(
+    DecodeErrorKind::UnsupportedCodec,
+    DecodeBackend::Symphonia,
+    error.reason,
+  )
+}
+
+fn decode_with_symphonia(path: &Path) -> StdResult<DecodeAudioResult, DecodeError> {
+  let file = File::open(path).map_err(|e| DecodeError::from_io(DecodeBackend::Symphonia, &e))?;
   let media_stream = MediaSourceStream::new(Box::new(file), Default::default());
   let mut hint = Hint::new();
   if let Some(ext) = path.extension().and_then(|os| os.to_str()) {
     hint.with_extension(ext);
   }
 
-  let format_opts = FormatOptions::default();
+  // MP3 的 LAME 延迟/补零由 Symphonia 在包级裁剪，其余格式在 decode_audio_to_pcm 中补齐
+  let format_opts = FormatOptions {
+    enable_gapless: true,
+    ..Default::default()
+  };
   let metadata_opts = MetadataOptions {
     limit_metadata_bytes: Limit::None,
     limit_visual_bytes: Limit::None,
@@ -684,45 +863,75 @@ fn decode_with_symphonia(path: &Path) -> StdResult<DecodeAudioResult, String> {
 
   let probed = get_probe()
     .format(&hint, media_stream, &format_opts, &metadata_opts)
-    .map_err(|e| format!("探测音频格式失败: {}", e))?;
+    .map_err(|e| DecodeError::from_symphonia(&e).context("探测音频格式失败"))?;
+  let gapless = gapless_info::read_gapless_info(path);
 
-  match catch_unwind(AssertUnwindSafe(|| decode_audio_to_pcm(probed.format))) {
-    Ok(Ok(result)) => Ok(result),
-    Ok(Err(err)) => Err(err.to_string()),
-    Err(_) => Err("内部音频解码错误（panic）".to_string()),
+  match catch_unwind(AssertUnwindSafe(|| {
+    decode_audio_to_pcm(probed.format, gapless.as_ref())
+  })) {
+    Ok(result) => result,
+    Err(_) => Err(DecodeError::new(
+      DecodeErrorKind::Other,
+      DecodeBackend::Symphonia,
+      "内部音频解码错误（panic）",
+    )),
   }
 }
 
-fn decode_with_ffmpeg(path: &Path) -> StdResult<DecodeAudioResult, String> {
+fn decode_with_ffmpeg(path: &Path) -> StdResult<DecodeAudioResult, DecodeError> {
   let (ffmpeg_pcm, _native_ms) = ffmpeg_decode_native_i16(path, None, None, 0, 0)?;
   if ffmpeg_pcm.channels == 0 {
-    return Err("FFmpeg 返回的声道数无效".to_string());
+    return Err(DecodeError::new(
+      DecodeErrorKind::Other,
+      DecodeBackend::Ffmpeg,
+      "FFmpeg 返回的声道数无效",
+    ));
   }
 
   let channels_u8 = if ffmpeg_pcm.channels > u8::MAX as u16 {
-    return Err("声道数超过支持范围".to_string());
+    return Err(DecodeError::new(
+      DecodeErrorKind::UnsupportedCodec,
+      DecodeBackend::Ffmpeg,
+      "声道数超过支持范围",
+    ));
   } else {
     ffmpeg_pcm.channels as u8
   };
 
-  let pcm_f32: Vec<f32> = ffmpeg_pcm
+  let mut pcm_f32: Vec<f32> = ffmpeg_pcm
     .samples_i16
     .iter()
     .map(|sample| (*sample as f32) / 32768.0)
     .collect();
+  let mut total_frames = ffmpeg_pcm.total_frames;
+  let gapless = gapless_info::read_gapless_info(path);
+  if let Some(info) = gapless.as_ref() {
+    gapless_info::apply_ffmpeg_gapless(
+      &mut pcm_f32,
+      channels_u8 as usize,
+      ffmpeg_pcm.sample_rate,
+      0.0,
+      info,
+    );
+    total_frames = (pcm_f32.len() / channels_u8 as usize) as u64;
+  }
 
   Ok(DecodeAudioResult {
     pcm_data: Buffer::from(cast_slice(&pcm_f32).to_vec()),
     sample_rate: ffmpeg_pcm.sample_rate,
     channels: channels_u8,
-    total_frames: ffmpeg_pcm.total_frames as f64,
+    total_frames: total_frames as f64,
     decoder_backend: Some("ffmpeg-native".to_string()),
+    priming_offset_ms: gapless.and_then(|info| info.priming_offset_ms(ffmpeg_pcm.sample_rate)),
     error: None,
+    error_code: None,
+    decode_errors: None,
   })
 }
 
 pub(crate) use ffmpeg_decode::{
-  ffmpeg_decode_native_f32, ffmpeg_decode_native_i16, ffmpeg_decode_transport_native,
+  ffmpeg_decode_content_i16_cancellable, ffmpeg_decode_native_f32, ffmpeg_decode_native_i16,
+  ffmpeg_decode_transport_at_rate_cancellable, ffmpeg_decode_transport_native,
   ffmpeg_decode_transport_native_cancellable, FfmpegTransportDecodeMetrics,
 };
 
@@ -734,6 +943,7 @@ impl Task for AudioProcessTask {
 
   fn compute(&mut self) -> napi::Result<Self::Output> {
     let total = self.file_paths.len() as i32;
+    let with_quality_label = self.with_quality_label;
     let results = Arc::new(Mutex::new(Vec::with_capacity(self.file_paths.len())));
     let processed = Arc::new(std::sync::atomic::AtomicI32::new(0));
 
@@ -755,7 +965,7 @@ impl Task for AudioProcessTask {
           s.spawn(move |_| {
             let mut local_results = Vec::with_capacity(chunk.len());
             for path in chunk {
-              let result = calculate_audio_hash_for_file(path);
+              let result = calculate_audio_hash_for_file(path, with_quality_label);
               let current = processed.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
 
               // 回调进度信息
@@ -774,7 +984,7 @@ impl Task for AudioProcessTask {
     } else {
       // 无回调时直接并行处理
       self.file_paths.par_iter().for_each(|path| {
-        let result = calculate_audio_hash_for_file(path);
+        let result = calculate_audio_hash_for_file(path, with_quality_label);
         results.lock().push(result);
       });
     }
@@ -790,33 +1000,36 @@ impl Task for AudioProcessTask {
 // ===== 内部辅助函数 =====
 
 /// 处理单个音频文件并计算 SHA256 哈希
-fn calculate_audio_hash_for_file(path: &str) -> AudioFileResult {
+fn calculate_audio_hash_for_file(path: &str, with_quality_label: bool) -> AudioFileResult {
   let path = Path::new(path);
-  match calculate_hash_with_symphonia(path) {
-    Ok(hash) => {
+  if let Err(error) = decode_error::check_source_file(path) {
+    return AudioFileResult::decode_failure(path, &[error]);
+  }
+  match calculate_hash_with_symphonia(path, with_quality_label) {
+    Ok((hash, quality_label)) => {
       let mut result = AudioFileResult::with_path(path);
       result.sha256_hash = hash;
+      result.quality_label = quality_label;
       result
     }
-    Err(symphonia_err) => match calculate_hash_with_ffmpeg(path) {
-      Ok(hash) => {
+    Err(symphonia_err) => match calculate_hash_with_ffmpeg(path, with_quality_label) {
+      Ok((hash, quality_label)) => {
         let mut result = AudioFileResult::with_path(path);
         result.sha256_hash = hash;
+        result.quality_label = quality_label;
         result
       }
-      Err(ffmpeg_err) => AudioFileResult::error(
-        path,
-        &format!(
-          "Symphonia 解码失败: {}; FFmpeg 解码失败: {}",
-          symphonia_err, ffmpeg_err
-        ),
-      ),
+      Err(ffmpeg_err) => AudioFileResult::decode_failure(path, &[symphonia_err, ffmpeg_err]),
     },
   }
 }
 
-fn calculate_hash_with_symphonia(path: &Path) -> StdResult<String, String> {
-  let file = File::open(path).map_err(|_| "打开文件失败".to_string())?;
+/// 返回 (PCM 内容哈希, 频谱质量标签)
+fn calculate_hash_with_symphonia(
+  path: &Path,
+  with_quality_label: bool,
+) -> StdResult<(String, Option<String>), DecodeError> {
+  let file = File::open(path).map_err(|e| DecodeError::from_io(DecodeBackend::Symphonia, &e))?;
   let media_stream = MediaSourceStream::new(Box::new(file), Default::default());
   let mut hint = Hint::new();
   if let Some(ext) = path.extension().and_then(|os| os.to_str()) {
@@ -832,29 +1045,45 @@ fn calculate_hash_with_symphonia(path: &Path) -> StdResult<String, String> {
 
   let probed = get_probe()
     .format(&hint, media_stream, &format_opts, &metadata_opts)
-    .map_err(|e| format!("探测音频格式失败: {}", e))?;
+    .map_err(|e| DecodeError::from_symphonia(&e).context("探测音频格式失败"))?;
 
   let mut temp_result = AudioFileResult::with_path(path);
   match catch_unwind(AssertUnwindSafe(|| {
-    extract_audio_features(probed.format, &mut temp_result)
+    extract_audio_features(probed.format, &mut temp_result, with_quality_label)
   })) {
     Ok(Ok(())) => {
       if temp_result.sha256_hash.is_empty() {
-        Err("Symphonia 解码未生成哈希".to_string())
+        Err(DecodeError::new(
+          DecodeErrorKind::Other,
+          DecodeBackend::Symphonia,
+          "Symphonia 解码未生成哈希",
+        ))
       } else {
-        Ok(temp_result.sha256_hash)
+        Ok((temp_result.sha256_hash, temp_result.quality_label))
       }
     }
-    Ok(Err(err)) => Err(err.to_string()),
-    Err(_) => Err("内部音频解码错误（panic）".to_string()),
+    Ok(Err(err)) => Err(symphonia_setup_error(err)),
+    Err(_) => Err(DecodeError::new(
+      DecodeErrorKind::Other,
+      DecodeBackend::Symphonia,
+      "内部音频解码错误（panic）",
+    )),
   }
 }
 
-fn calculate_hash_with_ffmpeg(path: &Path) -> StdResult<String, String> {
-  let (ffmpeg_pcm, _native_ms) = ffmpeg_decode_native_i16(path, None, None, 0, 0)?;
-  let mut ctx = Context::new(&SHA256);
-  ctx.update(cast_slice(&ffmpeg_pcm.samples_i16));
-  Ok(hex::encode(ctx.finish()))
+fn calculate_hash_with_ffmpeg(
+  path: &Path,
+  with_quality_label: bool,
+) -> StdResult<(String, Option<String>), DecodeError> {
+  let ffmpeg_pcm = ffmpeg_decode_content_i16_cancellable(path, || false)?
+    .ok_or_else(|| DecodeError::cancelled(DecodeBackend::Ffmpeg))?;
+  let mut hasher = PcmContentHasher::new(with_quality_label);
+  hasher.push_i16(
+    &ffmpeg_pcm.samples_i16,
+    ffmpeg_pcm.sample_rate,
+    ffmpeg_pcm.channels,
+  );
+  Ok(hasher.finish(path))
 }
 
 /// 单文件整文件 SHA256 计算
@@ -863,7 +1092,9 @@ fn calculate_file_hash_for_file(path: &str) -> AudioFileResult {
   let mut result = AudioFileResult::with_path(p);
   let mut file = match std::fs::File::open(p) {
     Ok(f) => f,
-    Err(_) => return AudioFileResult::error(p, "打开文件失败"),
+    Err(e) => {
+      return AudioFileResult::decode_failure(p, &[DecodeError::from_io(DecodeBackend::Io, &e)])
+    }
   };
   let mut ctx = Context::new(&SHA256);
   let mut buf = vec![0u8; 1024 * 1024 * 2]; // 2MB buffer
@@ -871,24 +1102,101 @@ fn calculate_file_hash_for_file(path: &str) -> AudioFileResult {
     match file.read(&mut buf) {
       Ok(0) => break,
       Ok(n) => ctx.update(&buf[..n]),
-      Err(_) => return AudioFileResult::error(p, "读取文件失败"),
+      Err(e) => {
+        return AudioFileResult::decode_failure(p, &[DecodeError::from_io(DecodeBackend::Io, &e)])
+      }
     }
   }
   result.sha256_hash = hex::encode(ctx.finish());
   result
 }
 
+/// PCM 内容哈希与频谱质量标签的增量计算；中途 spec 变化时放弃频谱分析（无法保证频点一致）
+pub(crate) struct PcmContentHasher {
+  hasher: Context,
+  spectral: Option<SpectralAnalyzer>,
+  spectral_abandoned: bool,
+  spec: Option<(u32, u16)>,
+}
+
+impl PcmContentHasher {
+  /// with_quality_label 为 false 时只算哈希，不做频谱分析
+  pub(crate) fn new(with_quality_label: bool) -> Self {
+    PcmContentHasher {
+      hasher: Context::new(&SHA256),
+      spectral: None,
+      spectral_abandoned: !with_quality_label,
+      spec: None,
+    }
+  }
+
+  /// 追加交错 i16 PCM
+  pub(crate) fn push_i16(&mut self, samples: &[i16], sample_rate: u32, channels: u16) {
+    if self
+      .spec
+      .is_some_and(|spec| spec != (sample_rate, channels))
+    {
+      self.spectral = None;
+      self.spectral_abandoned = true;
+    }
+    self.spec = Some((sample_rate, channels));
+    self.hasher.update(cast_slice(samples));
+    if !self.spectral_abandoned {
+      self
+        .spectral
+        .get_or_insert_with(|| {
+          SpectralAnalyzer::new(sample_rate, channels, HASH_ANALYSIS_STRIDE, false)
+        })
+        .push_i16(samples);
+    }
+  }
+
+  /// 返回 (PCM 内容哈希, 频谱质量标签)
+  pub(crate) fn finish(self, path: &Path) -> (String, Option<String>) {
+    let lossless_container = is_lossless_container(path);
+    (
+      hex::encode(self.hasher.finish()),
+      self.spectral.map(|analyzer| {
+        analyzer
+          .finish(lossless_container)
+          .quality_label
+          .to_string()
+      }),
+    )
+  }
+}
+
 fn extract_audio_features(
-  mut format: Box<dyn FormatReader>,
+  format: Box<dyn FormatReader>,
   result: &mut AudioFileResult,
+  with_quality_label: bool,
+) -> napi::Result<()> {
+  // 基于解码后的 PCM 样本计算内容哈希，按需顺带做频谱质量分析
+  let mut hasher = PcmContentHasher::new(with_quality_label);
+  decode_symphonia_i16(format, &mut |samples, spec, _| {
+    hasher.push_i16(samples, spec.rate, spec.channels.count() as u16);
+    true
+  })?;
+
+  // 基于 PCM 内容的哈希，实现“跳过元数据”的唯一值
+  let (hash, quality_label) = hasher.finish(Path::new(&result.file_path));
+  result.sha256_hash = hash;
+  result.quality_label = quality_label;
+
+  Ok(())
+}
+
+/// Symphonia 逐包解码为交错 i16（内容哈希使用的同一路径），同时把原始解码缓冲交给回调；
+/// 回调返回 false 时提前结束
+pub(crate) fn decode_symphonia_i16(
+  mut format: Box<dyn FormatReader>,
+  on_packet: &mut dyn FnMut(&[i16], SignalSpec, AudioBufferRef<'_>) -> bool,
 ) -> napi::Result<()> {
   let (track_id, codec_params) = {
     let track = find_decode_track(&mut format)?;
     (track.id, track.codec_params.clone())
   };
 
-  // 已精简：移除非必要的编码参数输出
-
   codec_params
     .channels
     .ok_or_else(|| napi::Error::from_reason("缺少声道信息"))?;
@@ -896,16 +1204,11 @@ fn extract_audio_features(
     .sample_rate
     .ok_or_else(|| napi::Error::from_reason("缺少采样率信息"))?;
 
-  // 已精简：不再导出 duration/quality_label
-
   let mut decoder = build_decoder(&codec_params)?;
   // 延迟创建 SampleBuffer，确保与首个解码帧的 spec 严格一致
   let mut sample_buffer: Option<SampleBuffer<i16>> = None;
   let mut current_spec: Option<SignalSpec> = None;
 
-  // 基于解码后的 PCM 样本计算内容哈希
-  let mut pcm_hasher = Context::new(&SHA256);
-
   while let Some(packet) = next_packet(&mut format, track_id) {
     match decoder.decode(&packet) {
       Ok(audio_buf) => {
@@ -920,9 +1223,7 @@ fn extract_audio_features(
         let spec_changed = current_spec
           .map(|s| s.channels != want_spec.channels || s.rate != want_spec.rate)
           .unwrap_or(true);
-        let need_recreate = cap_insufficient || spec_changed;
-
-        if need_recreate {
+        if cap_insufficient || spec_changed {
           // 使用下一幂次容量，避免频繁扩容
           let required = frame_count.next_power_of_two().max(frame_count);
           sample_buffer = Some(SampleBuffer::new(required as u64, want_spec));
@@ -930,22 +1231,16 @@ fn extract_audio_features(
         }
 
         let sbuf = sample_buffer.as_mut().unwrap();
-        sbuf.copy_interleaved_ref(audio_buf);
-
-        // 将解码后的 i16 PCM 样本（交错）追加到哈希器
-        let samples_i16: &[i16] = sbuf.samples();
-        pcm_hasher.update(cast_slice(samples_i16));
+        sbuf.copy_interleaved_ref(audio_buf.clone());
+        if !on_packet(sbuf.samples(), want_spec, audio_buf) {
+          break;
+        }
       }
       Err(SymphoniaError::IoError(_)) | Err(SymphoniaError::DecodeError(_)) => continue,
       Err(_) => break,
     }
   }
 
-  // 已精简：不再导出时长/位深
-
-  // 基于 PCM 内容的哈希，实现“跳过元数据”的唯一值
-  result.sha256_hash = hex::encode(pcm_hasher.finish());
-
   Ok(())
 }
 
@@ -1,12 +1,17 @@
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use std::borrow::Cow;
 use std::f64::consts::PI;
+use std::path::Path;
 
-const MIXXX_WAVEFORM_POINTS_PER_SECOND: f64 = 441.0;
+use crate::audio_decode_stream::AudioDecodeStream;
+
+pub(crate) const MIXXX_WAVEFORM_POINTS_PER_SECOND: f64 = 441.0;
 const MIXXX_SUMMARY_MAX_SAMPLES: f64 = 2.0 * 1920.0;
 const MIXXX_LOWPASS_MAX_HZ: f64 = 600.0;
 const MIXXX_HIGHPASS_MIN_HZ: f64 = 4000.0;
 const MIXXX_HIGH_SCALE_EXP: f64 = 0.632;
+const MIXXX_FILE_READ_FRAMES: usize = 65_536;
 const MAXPZ: usize = 64;
 const TWOPI: f64 = 2.0 * PI;
 const BESSEL_4: [f64; 4] = [
@@ -19,7 +24,7 @@ const BESSEL_4: [f64; 4] = [
 type Complex = [f64; 2];
 
 #[derive(Clone, Copy)]
-enum BandKey {
+pub(crate) enum BandKey {
   Low,
   Mid,
   High,
@@ -50,6 +55,33 @@ pub struct MixxxWaveformData {
   pub bands: MixxxWaveformBands,
 }
 
+/// 从文件流式计算时的选项
+#[napi(object)]
+#[derive(Default)]
+pub struct MixxxWaveformFileOptions {
+  /// 可视采样率（点/秒）；不填时按解码器报告的总帧数压到总览分辨率（同 computeMixxxWaveform），
+  /// 总帧数未知时为 441；需要与整段计算严格一致时应显式传入
+  pub target_visual_rate: Option<f64>,
+  /// 新增点数达到该值才回调一次，默认每次解码读取后回调
+  pub min_chunk_points: Option<u32>,
+}
+
+/// 流式计算过程中的增量波形
+#[napi(object)]
+pub struct MixxxWaveformChunk {
+  /// 本块第一个点在整条波形中的下标
+  pub start_index: u32,
+  pub sample_rate: u32,
+  pub step: f64,
+  /// 已解码的秒数
+  pub processed_sec: f64,
+  /// 文件声明的总时长（未知时为空）
+  pub total_sec: Option<f64>,
+  /// 最后一块（含末尾补齐的点）
+  pub is_final: bool,
+  pub bands: MixxxWaveformBands,
+}
+
 #[derive(Clone)]
 struct MixxxBesselCoefficients {
   coefficients: Vec<f64>,
@@ -702,7 +734,7 @@ fn process_mixxx_band_sample(
   }
 }
 
-fn scale_mixxx_value(value: f64, band: BandKey) -> u8 {
+pub(crate) fn scale_mixxx_value(value: f64, band: BandKey) -> u8 {
   if value <= 0.0 || value.is_nan() {
     return 0;
   }
@@ -721,130 +753,451 @@ fn scale_mixxx_value(value: f64, band: BandKey) -> u8 {
   }
 }
 
-fn downsample_mixxx_band(
-  samples: &[f32],
-  channels: usize,
-  band: BandKey,
-  main_stride: f64,
-  summary_stride: f64,
-  coeffs: &MixxxBesselCoefficients,
-) -> MixxxWaveformBand {
-  let total_frames = samples.len() / channels;
-  let expected_frames = (total_frames as f64 / summary_stride).floor() as usize + 1;
-
-  let mut left_values: Vec<u8> = Vec::with_capacity(expected_frames);
-  let mut right_values: Vec<u8> = Vec::with_capacity(expected_frames);
-  let mut left_peak_values: Vec<u8> = Vec::with_capacity(expected_frames);
-  let mut right_peak_values: Vec<u8> = Vec::with_capacity(expected_frames);
-
-  let mut position = 0.0;
-  let mut next_main_store = main_stride;
-  let mut next_summary_store = summary_stride;
-  let mut left_peak = 0.0;
-  let mut right_peak = 0.0;
-  let mut left_average = 0.0;
-  let mut right_average = 0.0;
-  let mut average_divisor = 0.0;
-  let mut left_peak_max = 0.0;
-  let mut right_peak_max = 0.0;
-  let mut left_state = vec![0.0; coeffs.order];
-  let mut right_state = vec![0.0; coeffs.order];
-
-  for frame in 0..total_frames {
-    let base = frame * channels;
-    let left_sample = samples[base] as f64;
-    let right_sample = if channels > 1 {
-      samples[base + 1] as f64
-    } else {
-      left_sample
-    };
+/// 单频段的量化序列（左右均值与峰值）
+#[derive(Clone, Default, PartialEq, Debug)]
+pub(crate) struct MixxxBandSeries {
+  pub(crate) left: Vec<u8>,
+  pub(crate) right: Vec<u8>,
+  pub(crate) peak_left: Vec<u8>,
+  pub(crate) peak_right: Vec<u8>,
+}
+
+impl MixxxBandSeries {
+  pub(crate) fn len(&self) -> usize {
+    self.left.len()
+  }
+
+  /// 取出 start 之后的点（用于增量输出）
+  pub(crate) fn slice_from(&self, start: usize) -> MixxxBandSeries {
+    MixxxBandSeries {
+      left: self.left[start..].to_vec(),
+      right: self.right[start..].to_vec(),
+      peak_left: self.peak_left[start..].to_vec(),
+      peak_right: self.peak_right[start..].to_vec(),
+    }
+  }
+
+  fn fit_len(&mut self, expected_frames: usize) {
+    self.left.resize(expected_frames, 0);
+    self.right.resize(expected_frames, 0);
+    self.peak_left.resize(expected_frames, 0);
+    self.peak_right.resize(expected_frames, 0);
+  }
+
+  pub(crate) fn into_band(self) -> MixxxWaveformBand {
+    MixxxWaveformBand {
+      left: Buffer::from(self.left),
+      right: Buffer::from(self.right),
+      peak_left: Buffer::from(self.peak_left),
+      peak_right: Buffer::from(self.peak_right),
+    }
+  }
+}
 
-    let l =
-      process_mixxx_band_sample(band, &coeffs.coefficients, &mut left_state, left_sample).abs();
-    let r =
-      process_mixxx_band_sample(band, &coeffs.coefficients, &mut right_state, right_sample).abs();
+/// 按（可为小数的）帧步长切列的计数器：每来一帧前进一格，越过列边界时报告一次
+pub(crate) struct ColumnClock {
+  step: f64,
+  position: f64,
+  next_store: f64,
+}
 
-    if l > left_peak {
-      left_peak = l;
+impl ColumnClock {
+  pub(crate) fn new(step: f64) -> Self {
+    Self {
+      step,
+      position: 0.0,
+      next_store: step,
     }
-    if r > right_peak {
-      right_peak = r;
+  }
+
+  /// 计入一帧；该帧写满当前列时返回 true
+  pub(crate) fn tick(&mut self) -> bool {
+    self.position += 1.0;
+    if self.position >= self.next_store {
+      self.next_store += self.step;
+      true
+    } else {
+      false
     }
+  }
+}
 
-    position += 1.0;
+/// 逐帧记录 N 路绝对值峰值并按 ColumnClock 切列，波形金字塔、Mixxx 与 Pioneer 波形共用
+pub(crate) struct ColumnPeakAccumulator<const N: usize> {
+  clock: ColumnClock,
+  peaks: [f64; N],
+  pending: bool,
+  total_frames: u64,
+}
 
-    if position >= next_main_store {
-      if left_peak > left_peak_max {
-        left_peak_max = left_peak;
-      }
-      if right_peak > right_peak_max {
-        right_peak_max = right_peak;
+impl<const N: usize> ColumnPeakAccumulator<N> {
+  pub(crate) fn new(step: f64) -> Self {
+    Self {
+      clock: ColumnClock::new(step),
+      peaks: [0.0; N],
+      pending: false,
+      total_frames: 0,
+    }
+  }
+
+  /// 计入一帧；该帧写满当前列时返回该列峰值并开始新列
+  pub(crate) fn push_frame(&mut self, values: [f64; N]) -> Option<[f64; N]> {
+    for (peak, value) in self.peaks.iter_mut().zip(values) {
+      let value = value.abs();
+      if value > *peak {
+        *peak = value;
       }
-      left_average += left_peak;
-      right_average += right_peak;
-      average_divisor += 1.0;
-      left_peak = 0.0;
-      right_peak = 0.0;
-      next_main_store += main_stride;
     }
+    self.pending = true;
+    self.total_frames += 1;
+    self.clock.tick().then(|| self.take())
+  }
 
-    if position >= next_summary_store {
-      let left_value = if average_divisor > 0.0 {
-        left_average / average_divisor
-      } else {
-        left_peak
-      };
-      let right_value = if average_divisor > 0.0 {
-        right_average / average_divisor
-      } else {
-        right_peak
-      };
-      let left_peak_value = if average_divisor > 0.0 {
-        left_peak_max
+  /// 取走未写满的末列
+  pub(crate) fn flush(&mut self) -> Option<[f64; N]> {
+    self.pending.then(|| self.take())
+  }
+
+  /// 当前（未写满）列的峰值
+  pub(crate) fn current(&self) -> &[f64; N] {
+    &self.peaks
+  }
+
+  pub(crate) fn total_frames(&self) -> u64 {
+    self.total_frames
+  }
+
+  fn take(&mut self) -> [f64; N] {
+    self.pending = false;
+    std::mem::replace(&mut self.peaks, [0.0; N])
+  }
+}
+
+/// 单频段的逐帧降采样状态，可分块喂入
+pub(crate) struct MixxxBandDownsampler {
+  band: BandKey,
+  coeffs: MixxxBesselCoefficients,
+  summary_stride: f64,
+  /// 主步长列内的左右峰值
+  main_columns: ColumnPeakAccumulator<2>,
+  summary_clock: ColumnClock,
+  left_average: f64,
+  right_average: f64,
+  average_divisor: f64,
+  left_peak_max: f64,
+  right_peak_max: f64,
+  left_state: Vec<f64>,
+  right_state: Vec<f64>,
+  series: MixxxBandSeries,
+}
+
+impl MixxxBandDownsampler {
+  fn new(
+    band: BandKey,
+    coeffs: MixxxBesselCoefficients,
+    main_stride: f64,
+    summary_stride: f64,
+  ) -> Self {
+    Self {
+      band,
+      summary_stride,
+      main_columns: ColumnPeakAccumulator::new(main_stride),
+      summary_clock: ColumnClock::new(summary_stride),
+      left_average: 0.0,
+      right_average: 0.0,
+      average_divisor: 0.0,
+      left_peak_max: 0.0,
+      right_peak_max: 0.0,
+      left_state: vec![0.0; coeffs.order],
+      right_state: vec![0.0; coeffs.order],
+      coeffs,
+      series: MixxxBandSeries::default(),
+    }
+  }
+
+  fn push(&mut self, samples: &[f32], channels: usize) {
+    let band = self.band;
+    for frame in samples.chunks_exact(channels) {
+      let left_sample = frame[0] as f64;
+      let right_sample = if channels > 1 {
+        frame[1] as f64
       } else {
-        left_peak
+        left_sample
       };
-      let right_peak_value = if average_divisor > 0.0 {
-        right_peak_max
+
+      let l = process_mixxx_band_sample(
+        band,
+        &self.coeffs.coefficients,
+        &mut self.left_state,
+        left_sample,
+      );
+      let r = process_mixxx_band_sample(
+        band,
+        &self.coeffs.coefficients,
+        &mut self.right_state,
+        right_sample,
+      );
+
+      if let Some([left_peak, right_peak]) = self.main_columns.push_frame([l, r]) {
+        if left_peak > self.left_peak_max {
+          self.left_peak_max = left_peak;
+        }
+        if right_peak > self.right_peak_max {
+          self.right_peak_max = right_peak;
+        }
+        self.left_average += left_peak;
+        self.right_average += right_peak;
+        self.average_divisor += 1.0;
+      }
+
+      if self.summary_clock.tick() {
+        self.store_summary();
+      }
+    }
+  }
+
+  fn store_summary(&mut self) {
+    let band = self.band;
+    let [left_peak, right_peak] = *self.main_columns.current();
+    let has_average = self.average_divisor > 0.0;
+    let left_value = if has_average {
+      self.left_average / self.average_divisor
+    } else {
+      left_peak
+    };
+    let right_value = if has_average {
+      self.right_average / self.average_divisor
+    } else {
+      right_peak
+    };
+    let left_peak_value = if has_average {
+      self.left_peak_max
+    } else {
+      left_peak
+    };
+    let right_peak_value = if has_average {
+      self.right_peak_max
+    } else {
+      right_peak
+    };
+
+    self.series.left.push(scale_mixxx_value(left_value, band));
+    self.series.right.push(scale_mixxx_value(right_value, band));
+    self
+      .series
+      .peak_left
+      .push(scale_mixxx_value(left_peak_value, band));
+    self
+      .series
+      .peak_right
+      .push(scale_mixxx_value(right_peak_value, band));
+
+    self.left_average = 0.0;
+    self.right_average = 0.0;
+    self.average_divisor = 0.0;
+    self.left_peak_max = 0.0;
+    self.right_peak_max = 0.0;
+  }
+
+  pub(crate) fn series(&self) -> &MixxxBandSeries {
+    &self.series
+  }
+
+  /// 补齐 / 截断到 floor(总帧数 / 步长) + 1 个点
+  fn finish(mut self) -> MixxxBandSeries {
+    let expected_frames =
+      (self.main_columns.total_frames() as f64 / self.summary_stride).floor() as usize + 1;
+    self.series.fit_len(expected_frames);
+    self.series
+  }
+}
+
+/// 四个频段同时降采样，供整段 PCM 与流式解码共用
+pub(crate) struct MixxxWaveformAccumulator {
+  sample_rate: u32,
+  channels: usize,
+  summary_stride: f64,
+  bands: [MixxxBandDownsampler; 4],
+}
+
+impl MixxxWaveformAccumulator {
+  /// summary_visual_sample_rate 不合法时退回 441 点/秒
+  pub(crate) fn new(
+    sample_rate: u32,
+    channels: u8,
+    summary_visual_sample_rate: f64,
+  ) -> Result<Self> {
+    if channels == 0 {
+      return Err(Error::from_reason("Missing channels for Mixxx waveform"));
+    }
+    if sample_rate == 0 {
+      return Err(Error::from_reason("Missing sample rate for Mixxx waveform"));
+    }
+    let sample_rate_f = sample_rate as f64;
+    let mut visual_rate =
+      if summary_visual_sample_rate.is_finite() && summary_visual_sample_rate > 0.0 {
+        summary_visual_sample_rate
       } else {
-        right_peak
+        MIXXX_WAVEFORM_POINTS_PER_SECOND
       };
+    if visual_rate > sample_rate_f {
+      visual_rate = sample_rate_f;
+    }
+    // For high-detail waveform requests (e.g. beat-grid adjustment preview),
+    // keep the analysis stride at least as dense as the requested visual rate.
+    // This avoids a fixed 441Hz internal ceiling that makes zoomed waveform look blocky.
+    let analysis_rate = if visual_rate > MIXXX_WAVEFORM_POINTS_PER_SECOND {
+      visual_rate
+    } else {
+      MIXXX_WAVEFORM_POINTS_PER_SECOND
+    };
+    let main_stride = sample_rate_f / analysis_rate;
+    let summary_stride = sample_rate_f / visual_rate;
+
+    let low_coeffs = design_mixxx_bessel_lowpass(sample_rate_f, MIXXX_LOWPASS_MAX_HZ)?;
+    let mid_coeffs =
+      design_mixxx_bessel_bandpass(sample_rate_f, MIXXX_LOWPASS_MAX_HZ, MIXXX_HIGHPASS_MIN_HZ)?;
+    let high_coeffs = design_mixxx_bessel_highpass(sample_rate_f, MIXXX_HIGHPASS_MIN_HZ)?;
+    let all_coeffs = MixxxBesselCoefficients {
+      coefficients: Vec::new(),
+      order: 0,
+    };
+    let downsampler =
+      |band, coeffs| MixxxBandDownsampler::new(band, coeffs, main_stride, summary_stride);
+    Ok(Self {
+      sample_rate,
+      channels: channels as usize,
+      summary_stride,
+      bands: [
+        downsampler(BandKey::Low, low_coeffs),
+        downsampler(BandKey::Mid, mid_coeffs),
+        downsampler(BandKey::High, high_coeffs),
+        downsampler(BandKey::All, all_coeffs),
+      ],
+    })
+  }
+
+  pub(crate) fn push(&mut self, samples: &[f32]) {
+    for band in self.bands.iter_mut() {
+      band.push(samples, self.channels);
+    }
+  }
 
-      left_values.push(scale_mixxx_value(left_value, band));
-      right_values.push(scale_mixxx_value(right_value, band));
-      left_peak_values.push(scale_mixxx_value(left_peak_value, band));
-      right_peak_values.push(scale_mixxx_value(right_peak_value, band));
+  pub(crate) fn step(&self) -> f64 {
+    self.summary_stride
+  }
+
+  /// 已产出的点数（四个频段一致）
+  pub(crate) fn len(&self) -> usize {
+    self.bands[0].series().len()
+  }
 
-      left_average = 0.0;
-      right_average = 0.0;
-      average_divisor = 0.0;
-      left_peak_max = 0.0;
-      right_peak_max = 0.0;
-      next_summary_store += summary_stride;
+  /// start 之后新产出的点，顺序为 low / mid / high / all
+  pub(crate) fn series_from(&self, start: usize) -> [MixxxBandSeries; 4] {
+    std::array::from_fn(|index| self.bands[index].series().slice_from(start))
+  }
+
+  pub(crate) fn total_frames(&self) -> usize {
+    self.bands[0].main_columns.total_frames() as usize
+  }
+
+  pub(crate) fn finish(self) -> MixxxWaveformSeries {
+    let total_frames = self.total_frames();
+    MixxxWaveformSeries {
+      sample_rate: self.sample_rate,
+      total_frames,
+      step: self.summary_stride,
+      bands: self.bands.map(MixxxBandDownsampler::finish),
     }
   }
 
-  if left_values.len() < expected_frames {
-    left_values.resize(expected_frames, 0);
-    right_values.resize(expected_frames, 0);
-    left_peak_values.resize(expected_frames, 0);
-    right_peak_values.resize(expected_frames, 0);
-  } else if left_values.len() > expected_frames {
-    left_values.truncate(expected_frames);
-    right_values.truncate(expected_frames);
-    left_peak_values.truncate(expected_frames);
-    right_peak_values.truncate(expected_frames);
+  pub(crate) fn into_data(self) -> MixxxWaveformData {
+    self.finish().into_data()
   }
+}
 
-  MixxxWaveformBand {
-    left: Buffer::from(left_values),
-    right: Buffer::from(right_values),
-    peak_left: Buffer::from(left_peak_values),
-    peak_right: Buffer::from(right_peak_values),
+/// 降采样完成后的四频段序列
+pub(crate) struct MixxxWaveformSeries {
+  pub(crate) sample_rate: u32,
+  pub(crate) total_frames: usize,
+  pub(crate) step: f64,
+  /// low / mid / high / all
+  pub(crate) bands: [MixxxBandSeries; 4],
+}
+
+impl MixxxWaveformSeries {
+  pub(crate) fn into_data(self) -> MixxxWaveformData {
+    MixxxWaveformData {
+      duration: self.total_frames as f64 / self.sample_rate as f64,
+      sample_rate: self.sample_rate,
+      step: self.step,
+      bands: bands_from_series(self.bands),
+    }
   }
 }
 
-fn pcm_buffer_to_f32(buffer: &Buffer) -> Cow<'_, [f32]> {
+pub(crate) fn bands_from_series(series: [MixxxBandSeries; 4]) -> MixxxWaveformBands {
+  let [low, mid, high, all] = series;
+  MixxxWaveformBands {
+    low: low.into_band(),
+    mid: mid.into_band(),
+    high: high.into_band(),
+    all: all.into_band(),
+  }
+}
+
+/// 与波形相同的 Bessel 三分频（低 / 中 / 高），逐样本输出各频段滤波值，供描述符分析复用
+pub(crate) struct MixxxBandSplitter {
+  low: MixxxBesselCoefficients,
+  mid: MixxxBesselCoefficients,
+  high: MixxxBesselCoefficients,
+  low_state: Vec<f64>,
+  mid_state: Vec<f64>,
+  high_state: Vec<f64>,
+}
+
+impl MixxxBandSplitter {
+  pub(crate) fn new(sample_rate: f64) -> Result<Self> {
+    let low = design_mixxx_bessel_lowpass(sample_rate, MIXXX_LOWPASS_MAX_HZ)?;
+    let mid =
+      design_mixxx_bessel_bandpass(sample_rate, MIXXX_LOWPASS_MAX_HZ, MIXXX_HIGHPASS_MIN_HZ)?;
+    let high = design_mixxx_bessel_highpass(sample_rate, MIXXX_HIGHPASS_MIN_HZ)?;
+    Ok(Self {
+      low_state: vec![0.0; low.order],
+      mid_state: vec![0.0; mid.order],
+      high_state: vec![0.0; high.order],
+      low,
+      mid,
+      high,
+    })
+  }
+
+  /// 返回 [低, 中, 高] 频段滤波后的样本
+  pub(crate) fn process(&mut self, value: f64) -> [f64; 3] {
+    [
+      process_mixxx_band_sample(
+        BandKey::Low,
+        &self.low.coefficients,
+        &mut self.low_state,
+        value,
+      ),
+      process_mixxx_band_sample(
+        BandKey::Mid,
+        &self.mid.coefficients,
+        &mut self.mid_state,
+        value,
+      ),
+      process_mixxx_band_sample(
+        BandKey::High,
+        &self.high.coefficients,
+        &mut self.high_state,
+        value,
+      ),
+    ]
+  }
+}
+
+pub(crate) fn pcm_buffer_to_f32(buffer: &Buffer) -> Cow<'_, [f32]> {
   let bytes = buffer.as_ref();
   let (prefix, aligned, suffix) = unsafe { bytes.align_to::<f32>() };
   if prefix.is_empty() && suffix.is_empty() {
@@ -882,82 +1235,22 @@ fn compute_mixxx_waveform_with_summary_rate(
     return Err(Error::from_reason("Empty PCM buffer"));
   }
 
+  let mut accumulator =
+    MixxxWaveformAccumulator::new(sample_rate, channels, summary_visual_sample_rate)?;
+  accumulator.push(&samples[..total_frames * channels_usize]);
+  Ok(accumulator.into_data())
+}
+
+/// 未指定可视采样率时的总览采样率：长曲目压到约 1920 点（与整段 PCM 版本一致）
+pub(crate) fn mixxx_summary_visual_rate(sample_rate: u32, total_frames: usize) -> f64 {
   let sample_rate_f = sample_rate as f64;
-  let mut visual_rate =
-    if summary_visual_sample_rate.is_finite() && summary_visual_sample_rate > 0.0 {
-      summary_visual_sample_rate
-    } else {
-      MIXXX_WAVEFORM_POINTS_PER_SECOND
-    };
-  if visual_rate > sample_rate_f {
-    visual_rate = sample_rate_f;
-  }
-  // For high-detail waveform requests (e.g. beat-grid adjustment preview),
-  // keep the analysis stride at least as dense as the requested visual rate.
-  // This avoids a fixed 441Hz internal ceiling that makes zoomed waveform look blocky.
-  let analysis_rate = if visual_rate > MIXXX_WAVEFORM_POINTS_PER_SECOND {
-    visual_rate
+  let analysis_channels = 2.0;
+  if sample_rate_f > 0.0 && (total_frames as f64) > (MIXXX_SUMMARY_MAX_SAMPLES / analysis_channels)
+  {
+    (sample_rate_f * MIXXX_SUMMARY_MAX_SAMPLES) / analysis_channels / total_frames as f64
   } else {
-    MIXXX_WAVEFORM_POINTS_PER_SECOND
-  };
-  let main_stride = sample_rate_f / analysis_rate;
-  let summary_stride = sample_rate_f / visual_rate;
-
-  let low_coeffs = design_mixxx_bessel_lowpass(sample_rate_f, MIXXX_LOWPASS_MAX_HZ)?;
-  let mid_coeffs =
-    design_mixxx_bessel_bandpass(sample_rate_f, MIXXX_LOWPASS_MAX_HZ, MIXXX_HIGHPASS_MIN_HZ)?;
-  let high_coeffs = design_mixxx_bessel_highpass(sample_rate_f, MIXXX_HIGHPASS_MIN_HZ)?;
-
-  let low_band = downsample_mixxx_band(
-    &samples,
-    channels_usize,
-    BandKey::Low,
-    main_stride,
-    summary_stride,
-    &low_coeffs,
-  );
-  let mid_band = downsample_mixxx_band(
-    &samples,
-    channels_usize,
-    BandKey::Mid,
-    main_stride,
-    summary_stride,
-    &mid_coeffs,
-  );
-  let high_band = downsample_mixxx_band(
-    &samples,
-    channels_usize,
-    BandKey::High,
-    main_stride,
-    summary_stride,
-    &high_coeffs,
-  );
-  let all_coeffs = MixxxBesselCoefficients {
-    coefficients: Vec::new(),
-    order: 0,
-  };
-  let all_band = downsample_mixxx_band(
-    &samples,
-    channels_usize,
-    BandKey::All,
-    main_stride,
-    summary_stride,
-    &all_coeffs,
-  );
-
-  let duration = total_frames as f64 / sample_rate_f;
-
-  Ok(MixxxWaveformData {
-    duration,
-    sample_rate,
-    step: summary_stride,
-    bands: MixxxWaveformBands {
-      low: low_band,
-      mid: mid_band,
-      high: high_band,
-      all: all_band,
-    },
-  })
+    sample_rate_f
+  }
 }
 
 pub fn compute_mixxx_waveform(
@@ -965,16 +1258,11 @@ pub fn compute_mixxx_waveform(
   sample_rate: u32,
   channels: u8,
 ) -> Result<MixxxWaveformData> {
-  let sample_rate_f = sample_rate as f64;
-  let mut summary_visual_sample_rate = sample_rate_f;
-  let analysis_channels = 2.0;
-  if sample_rate_f > 0.0 && channels > 0 {
+  let mut summary_visual_sample_rate = sample_rate as f64;
+  if channels > 0 {
     let total_samples = pcm_data.len() / 4;
     let total_frames = total_samples / channels as usize;
-    if (total_frames as f64) > (MIXXX_SUMMARY_MAX_SAMPLES / analysis_channels) {
-      summary_visual_sample_rate =
-        (sample_rate_f * MIXXX_SUMMARY_MAX_SAMPLES) / analysis_channels / total_frames as f64;
-    }
+    summary_visual_sample_rate = mixxx_summary_visual_rate(sample_rate, total_frames);
   }
   compute_mixxx_waveform_with_summary_rate(
     pcm_data,
@@ -992,3 +1280,174 @@ pub fn compute_mixxx_waveform_with_rate(
 ) -> Result<MixxxWaveformData> {
   compute_mixxx_waveform_with_summary_rate(pcm_data, sample_rate, channels, target_visual_rate)
 }
+
+fn emit_mixxx_chunk(
+  callback: Option<&ThreadsafeFunction<MixxxWaveformChunk>>,
+  start_index: usize,
+  series: [MixxxBandSeries; 4],
+  meta: (u32, f64, f64, Option<f64>),
+  is_final: bool,
+) {
+  let Some(callback) = callback else {
+    return;
+  };
+  let (sample_rate, step, processed_sec, total_sec) = meta;
+  callback.call(
+    Ok(MixxxWaveformChunk {
+      start_index: start_index as u32,
+      sample_rate,
+      step,
+      processed_sec,
+      total_sec,
+      is_final,
+      bands: bands_from_series(series),
+    }),
+    ThreadsafeFunctionCallMode::Blocking,
+  );
+}
+
+/// 流式计算的可视采样率：总帧数未知时退回 441 点/秒
+fn file_visual_rate(
+  target_visual_rate: Option<f64>,
+  sample_rate: u32,
+  total_frames: Option<u64>,
+) -> f64 {
+  target_visual_rate
+    .filter(|rate| rate.is_finite() && *rate > 0.0)
+    .or_else(|| total_frames.map(|frames| mixxx_summary_visual_rate(sample_rate, frames as usize)))
+    .unwrap_or(MIXXX_WAVEFORM_POINTS_PER_SECOND)
+}
+
+/// 边解码边计算。可视采样率相同时逐点与 computeMixxxWaveformWithRate 一致；未指定可视采样率时
+/// 依赖解码器报告的总帧数，总帧数未知或只是估计值（如无 Xing 头的 VBR MP3）时可能与整段计算不同
+pub fn compute_mixxx_waveform_from_file(
+  file_path: &str,
+  options: MixxxWaveformFileOptions,
+  callback: Option<&ThreadsafeFunction<MixxxWaveformChunk>>,
+) -> Result<MixxxWaveformData> {
+  let mut stream = AudioDecodeStream::open(Path::new(file_path)).map_err(Error::from_reason)?;
+  let sample_rate = stream.sample_rate();
+  let channels = stream.channels().min(u8::MAX as u16) as u8;
+  let total_frames = stream.total_frames();
+  let visual_rate = file_visual_rate(options.target_visual_rate, sample_rate, total_frames);
+  let mut accumulator = MixxxWaveformAccumulator::new(sample_rate, channels, visual_rate)?;
+  let min_chunk_points = options.min_chunk_points.unwrap_or(0).max(1) as usize;
+  let total_sec = total_frames.map(|frames| frames as f64 / sample_rate as f64);
+  let meta = |accumulator: &MixxxWaveformAccumulator| {
+    (
+      sample_rate,
+      accumulator.step(),
+      accumulator.total_frames() as f64 / sample_rate as f64,
+      total_sec,
+    )
+  };
+
+  let mut emitted = 0usize;
+  loop {
+    let chunk = stream
+      .read_frames(MIXXX_FILE_READ_FRAMES)
+      .map_err(Error::from_reason)?;
+    if chunk.is_empty() {
+      break;
+    }
+    accumulator.push(&chunk);
+    if accumulator.len() >= emitted + min_chunk_points {
+      let series = accumulator.series_from(emitted);
+      emit_mixxx_chunk(callback, emitted, series, meta(&accumulator), false);
+      emitted = accumulator.len();
+    }
+  }
+  if accumulator.total_frames() == 0 {
+    return Err(Error::from_reason("Empty PCM buffer"));
+  }
+
+  let meta = meta(&accumulator);
+  let finished = accumulator.finish();
+  let tail = finished
+    .bands
+    .each_ref()
+    .map(|band| band.slice_from(emitted.min(band.len())));
+  emit_mixxx_chunk(callback, emitted, tail, meta, true);
+  Ok(finished.into_data())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn chunked_accumulation_matches_single_pass() {
+    let sample_rate = 44_100;
+    let pcm: Vec<f32> = (0..sample_rate as usize * 2)
+      .flat_map(|frame| {
+        let t = frame as f32 / sample_rate as f32;
+        let value = 0.6 * (std::f32::consts::TAU * 80.0 * t).sin()
+          + 0.3 * (std::f32::consts::TAU * 6_000.0 * t).sin();
+        [value, -value * 0.5]
+      })
+      .collect();
+    let whole = {
+      let mut accumulator = MixxxWaveformAccumulator::new(sample_rate, 2, 441.0).unwrap();
+      accumulator.push(&pcm);
+      accumulator.finish()
+    };
+    let mut chunked = MixxxWaveformAccumulator::new(sample_rate, 2, 441.0).unwrap();
+    let mut streamed: [MixxxBandSeries; 4] = Default::default();
+    let mut emitted = 0;
+    for part in pcm.chunks(2 * 3_001) {
+      chunked.push(part);
+      for (target, new) in streamed.iter_mut().zip(chunked.series_from(emitted)) {
+        target.left.extend(new.left);
+        target.right.extend(new.right);
+        target.peak_left.extend(new.peak_left);
+        target.peak_right.extend(new.peak_right);
+      }
+      emitted = chunked.len();
+    }
+    let finished = chunked.finish();
+
+    assert_eq!(finished.bands, whole.bands);
+    assert_eq!(whole.bands[0].len(), 883);
+    // 增量输出是最终结果的前缀（末尾补齐的点只在最后一块）
+    for (streamed, finished) in streamed.iter().zip(&finished.bands) {
+      assert_eq!(streamed.left[..], finished.left[..streamed.len()]);
+      assert_eq!(
+        streamed.peak_right[..],
+        finished.peak_right[..streamed.len()]
+      );
+    }
+    // 低频正弦落在 low 频段，高频落在 high 频段
+    assert!(whole.bands[0].peak_left[400] > whole.bands[1].peak_left[400]);
+    assert!(whole.bands[2].peak_left[400] > 0);
+  }
+
+  #[test]
+  fn summary_rate_caps_long_tracks() {
+    assert_eq!(mixxx_summary_visual_rate(44_100, 1_000), 44_100.0);
+    let rate = mixxx_summary_visual_rate(44_100, 44_100 * 600);
+    assert!((rate * 600.0 - 1920.0).abs() < 1e-6);
+  }
+
+  #[test]
+  fn file_visual_rate_falls_back_when_length_is_unknown() {
+    let frames = 44_100 * 600;
+    assert_eq!(
+      file_visual_rate(None, 44_100, Some(frames as u64)),
+      mixxx_summary_visual_rate(44_100, frames)
+    );
+    // 总帧数未知时无法按时长压缩，与整段计算的可视采样率不同
+    assert_eq!(
+      file_visual_rate(None, 44_100, None),
+      MIXXX_WAVEFORM_POINTS_PER_SECOND
+    );
+    assert_ne!(
+      file_visual_rate(None, 44_100, None),
+      mixxx_summary_visual_rate(44_100, frames)
+    );
+    assert_eq!(file_visual_rate(Some(200.0), 44_100, None), 200.0);
+    assert_eq!(
+      file_visual_rate(Some(f64::NAN), 44_100, None),
+      MIXXX_WAVEFORM_POINTS_PER_SECOND
+    );
+  }
+}
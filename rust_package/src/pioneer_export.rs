@@ -3,6 +3,7 @@ use std::fs::File;
 use std::path::Path;
 
 use binrw::BinRead;
+use napi::bindgen_prelude::Buffer;
 use rekordcrate::pdb::{
   Header as RekordcrateHeader, PlaylistTreeNode, PlaylistTreeNodeId, Row as RekordcrateRow,
 };
@@ -10,7 +11,8 @@ use rekordcrate::pdb::{
 #[path = "pioneer_export_anlz.rs"]
 mod pioneer_export_anlz;
 pub use pioneer_export_anlz::{
-  read_pioneer_beat_grid, read_pioneer_detail_waveform, read_pioneer_preview_waveform,
+  generate_pioneer_waveforms, generate_pioneer_waveforms_from_pcm, read_pioneer_beat_grid,
+  read_pioneer_detail_waveform, read_pioneer_preview_waveform, render_pioneer_detail_waveform,
 };
 
 /// Pioneer 旧 Device Library 调试输出
@@ -222,6 +224,43 @@ pub struct PioneerDetailWaveformDump {
   pub error: Option<String>,
 }
 
+/// FRKB 生成的 ANLZ 波形段，可原样写入对应的分析文件
+#[napi(object)]
+pub struct PioneerGeneratedAnlzSection {
+  /// 段类型（PWAV/PWV3/PWV4/PWV5/PWV6/PWV7）
+  pub kind: String,
+  /// 应写入的分析文件扩展名（DAT/EXT/2EX）
+  pub file_kind: String,
+  /// 12 字节通用段头之后的段头数据（大端）
+  pub header_data: Buffer,
+  pub content: Buffer,
+}
+
+/// 由 PCM 生成的 Pioneer 波形，各列按读取 ANLZ 的同一规则解码
+#[napi(object)]
+pub struct PioneerGeneratedWaveformDump {
+  pub file_path: Option<String>,
+  pub duration_sec: f64,
+  /// 细节列时间频率（Hz）
+  pub detail_rate: f64,
+  /// PWAV 蓝色概览（400 列）
+  pub blue_preview: Vec<PioneerPreviewWaveformColumn>,
+  pub blue_preview_max_height: u32,
+  /// PWV4 RGB 概览（1200 列）
+  pub rgb_preview: Vec<PioneerPreviewWaveformColumn>,
+  pub rgb_preview_max_height: u32,
+  /// PWV3 蓝色细节
+  pub blue_detail: Vec<PioneerDetailWaveformColumn>,
+  /// PWV5 RGB 细节
+  pub rgb_detail: Vec<PioneerDetailWaveformColumn>,
+  /// PWV6 三频概览（1200 列）
+  pub triband_preview: Vec<PioneerDetailWaveformColumn>,
+  /// PWV7 三频细节
+  pub triband_detail: Vec<PioneerDetailWaveformColumn>,
+  pub sections: Vec<PioneerGeneratedAnlzSection>,
+  pub error: Option<String>,
+}
+
 #[napi]
 pub fn dump_pioneer_export_debug(
   export_pdb_path: String,
@@ -2,11 +2,24 @@ use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::result::Result as StdResult;
 
+use bytemuck::try_cast_slice;
+use napi::bindgen_prelude::Buffer;
+
 use super::{
   PioneerBeatGridDump, PioneerBeatGridEntry, PioneerDetailWaveformColumn,
-  PioneerDetailWaveformDump, PioneerPreviewWaveformColumn, PioneerPreviewWaveformDump,
+  PioneerDetailWaveformDump, PioneerGeneratedAnlzSection, PioneerGeneratedWaveformDump,
+  PioneerPreviewWaveformColumn, PioneerPreviewWaveformDump,
 };
+use crate::audio_decode_stream::AudioDecodeStream;
 use crate::pioneer_anlz_raw;
+use crate::pioneer_waveform_gen::{
+  PioneerGeneratedWaveforms, PioneerWaveformGenerator, PIONEER_DETAIL_COLUMNS_PER_SECOND,
+};
+use crate::waveform_render::{
+  encode_render_image, render_waveform, RenderColumn, RenderSource, WaveformColorScheme,
+  WaveformImage, WaveformRenderOptions,
+};
+
 fn build_pioneer_preview_waveform_candidates(input_path: &Path) -> Vec<PathBuf> {
   let mut candidates = Vec::new();
   let mut seen = HashSet::new();
@@ -91,15 +104,13 @@ fn build_pioneer_rgb_waveform_column(
   }
 }
 
-fn read_pioneer_preview_waveform_from_file(
-  preview_path: &Path,
+fn parse_pioneer_preview_waveform_sections(
+  sections: &[pioneer_anlz_raw::RawAnlzSection],
 ) -> StdResult<(String, Vec<PioneerPreviewWaveformColumn>, u32), String> {
-  let sections = pioneer_anlz_raw::read_pioneer_anlz_sections(preview_path)
-    .map_err(|error| format!("parse preview file failed: {error}"))?;
   let mut blue_columns: Option<Vec<PioneerPreviewWaveformColumn>> = None;
 
   for section in sections {
-    if pioneer_anlz_raw::section_kind_eq(&section, b"PWV4") {
+    if pioneer_anlz_raw::section_kind_eq(section, b"PWV4") {
       if section.header_data.len() < 12 {
         continue;
       }
@@ -122,7 +133,7 @@ fn read_pioneer_preview_waveform_from_file(
       }
       return Ok(("rgb".to_string(), columns, max_height));
     }
-    if pioneer_anlz_raw::section_kind_eq(&section, b"PWAV") {
+    if pioneer_anlz_raw::section_kind_eq(section, b"PWAV") {
       if section.header_data.len() < 4 {
         continue;
       }
@@ -144,7 +155,7 @@ fn read_pioneer_preview_waveform_from_file(
       }
       continue;
     }
-    if pioneer_anlz_raw::section_kind_eq(&section, b"PWV2") {
+    if pioneer_anlz_raw::section_kind_eq(section, b"PWV2") {
       if section.header_data.len() < 4 {
         continue;
       }
@@ -180,6 +191,14 @@ fn read_pioneer_preview_waveform_from_file(
   Err("missing preview waveform section".to_string())
 }
 
+fn read_pioneer_preview_waveform_from_file(
+  preview_path: &Path,
+) -> StdResult<(String, Vec<PioneerPreviewWaveformColumn>, u32), String> {
+  let sections = pioneer_anlz_raw::read_pioneer_anlz_sections(preview_path)
+    .map_err(|error| format!("parse preview file failed: {error}"))?;
+  parse_pioneer_preview_waveform_sections(&sections)
+}
+
 #[napi]
 pub fn read_pioneer_preview_waveform(analyze_file_path: String) -> PioneerPreviewWaveformDump {
   fn build_empty(
@@ -311,8 +330,12 @@ fn parse_pioneer_pqtz_section(
 
 #[cfg(test)]
 mod pioneer_beat_grid_tests {
-  use super::{parse_pioneer_detail_waveform_sections, parse_pioneer_pqtz_section};
+  use super::{
+    parse_pioneer_detail_waveform_sections, parse_pioneer_pqtz_section,
+    parse_pioneer_preview_waveform_sections,
+  };
   use crate::pioneer_anlz_raw::RawAnlzSection;
+  use crate::pioneer_waveform_gen::PioneerWaveformGenerator;
 
   #[test]
   fn parses_full_pqtz_entries_in_timeline_milliseconds() {
@@ -407,6 +430,64 @@ mod pioneer_beat_grid_tests {
     assert_eq!(parsed.columns[0].band_mid, Some(8));
     assert_eq!(parsed.columns[0].band_high, Some(4));
   }
+
+  #[test]
+  fn parses_pwav_height_from_high_bits() {
+    let sections = [RawAnlzSection {
+      kind: *b"PWAV",
+      header_data: [0u8, 0, 0, 2, 0, 1, 0, 0].to_vec(),
+      content: vec![(20 << 3) | 6, (3 << 3) | 1],
+    }];
+
+    let (style, columns, max_height) =
+      parse_pioneer_preview_waveform_sections(&sections).expect("PWAV should parse");
+    assert_eq!(style, "blue");
+    assert_eq!(max_height, 20);
+    assert_eq!(columns[0].back_height, 20);
+    assert_eq!(columns[0].back_color_r, 116);
+    assert_eq!(columns[1].back_height, 3);
+    assert_eq!(columns[1].back_color_r, 43);
+  }
+
+  #[test]
+  fn generated_sections_decode_through_reader_rules() {
+    let sample_rate = 8_000u32;
+    let mut generator = PioneerWaveformGenerator::new(sample_rate, 1).unwrap();
+    let samples: Vec<f32> = (0..sample_rate * 2)
+      .map(|index| {
+        (0.8 * (2.0 * std::f64::consts::PI * 60.0 * index as f64 / 8_000.0).sin()) as f32
+      })
+      .collect();
+    generator.push(&samples);
+    let sections: Vec<RawAnlzSection> = generator
+      .finish()
+      .sections()
+      .into_iter()
+      .map(|item| item.section)
+      .collect();
+
+    let (style, columns, _) =
+      parse_pioneer_preview_waveform_sections(&sections).expect("PWV4 should parse");
+    assert_eq!(style, "rgb");
+    assert_eq!(columns.len(), 1200);
+    assert!(columns[600].back_color_r > columns[600].back_color_b);
+
+    let (style, columns, max_height) =
+      parse_pioneer_preview_waveform_sections(&sections[..1]).expect("PWAV should parse");
+    assert_eq!(style, "blue");
+    assert_eq!(columns.len(), 400);
+    assert!(max_height <= 31);
+    assert!(columns[200].back_height > 16);
+    assert_eq!(columns[200].back_color_r, 43);
+
+    let parsed = parse_pioneer_detail_waveform_sections(&sections).expect("PWV7 should parse");
+    assert_eq!(parsed.style, "triband-detail");
+    assert_eq!(parsed.detail_rate, Some(150.0));
+    assert_eq!(parsed.columns.len(), 300);
+    let rgb = parse_pioneer_detail_waveform_sections(&sections[3..4]).expect("PWV5 should parse");
+    assert_eq!(rgb.detail_rate, Some(150.0));
+    assert_eq!(rgb.columns[150].color_r, 255);
+  }
 }
 
 #[napi]
@@ -682,3 +763,168 @@ pub fn read_pioneer_detail_waveform(analyze_file_path: String) -> PioneerDetailW
     last_error.unwrap_or_else(|| "detail waveform file not found".to_string()),
   )
 }
+
+fn build_empty_generated_waveform(
+  file_path: Option<String>,
+  error: impl Into<String>,
+) -> PioneerGeneratedWaveformDump {
+  PioneerGeneratedWaveformDump {
+    file_path,
+    duration_sec: 0.0,
+    detail_rate: f64::from(PIONEER_DETAIL_COLUMNS_PER_SECOND),
+    blue_preview: Vec::new(),
+    blue_preview_max_height: 0,
+    rgb_preview: Vec::new(),
+    rgb_preview_max_height: 0,
+    blue_detail: Vec::new(),
+    rgb_detail: Vec::new(),
+    triband_preview: Vec::new(),
+    triband_detail: Vec::new(),
+    sections: Vec::new(),
+    error: Some(error.into()),
+  }
+}
+
+fn build_generated_waveform(
+  file_path: Option<String>,
+  generated: PioneerGeneratedWaveforms,
+) -> StdResult<PioneerGeneratedWaveformDump, String> {
+  let sections = generated.sections();
+  // 生成的段直接走读取 ANLZ 的解析路径，保证与 Rekordbox 分析结果的渲染规则一致。
+  let find = |kind: &[u8; 4]| {
+    sections
+      .iter()
+      .map(|item| &item.section)
+      .find(|section| pioneer_anlz_raw::section_kind_eq(section, kind))
+      .ok_or_else(|| {
+        format!(
+          "missing generated {} section",
+          String::from_utf8_lossy(kind)
+        )
+      })
+  };
+  let preview = |kind: &[u8; 4]| -> StdResult<(Vec<PioneerPreviewWaveformColumn>, u32), String> {
+    let (_, columns, max_height) =
+      parse_pioneer_preview_waveform_sections(std::slice::from_ref(find(kind)?))?;
+    Ok((columns, max_height))
+  };
+  let detail = |kind: &[u8; 4]| -> StdResult<Vec<PioneerDetailWaveformColumn>, String> {
+    Ok(parse_pioneer_detail_waveform_sections(std::slice::from_ref(find(kind)?))?.columns)
+  };
+
+  let (blue_preview, blue_preview_max_height) = preview(b"PWAV")?;
+  let (rgb_preview, rgb_preview_max_height) = preview(b"PWV4")?;
+  Ok(PioneerGeneratedWaveformDump {
+    file_path,
+    duration_sec: generated.duration_sec(),
+    detail_rate: f64::from(PIONEER_DETAIL_COLUMNS_PER_SECOND),
+    blue_preview,
+    blue_preview_max_height,
+    rgb_preview,
+    rgb_preview_max_height,
+    blue_detail: detail(b"PWV3")?,
+    rgb_detail: detail(b"PWV5")?,
+    triband_preview: detail(b"PWV6")?,
+    triband_detail: detail(b"PWV7")?,
+    sections: sections
+      .into_iter()
+      .map(|item| PioneerGeneratedAnlzSection {
+        kind: String::from_utf8_lossy(&item.section.kind).to_string(),
+        file_kind: item.file_kind.to_string(),
+        header_data: item.section.header_data.into(),
+        content: item.section.content.into(),
+      })
+      .collect(),
+    error: None,
+  })
+}
+
+/// 解码整轨并生成 Pioneer 蓝色 / RGB / 三频的概览与细节波形
+#[napi]
+pub fn generate_pioneer_waveforms(file_path: String) -> PioneerGeneratedWaveformDump {
+  let mut stream = match AudioDecodeStream::open(Path::new(&file_path)) {
+    Ok(stream) => stream,
+    Err(error) => return build_empty_generated_waveform(Some(file_path), error),
+  };
+  let mut generator = match PioneerWaveformGenerator::new(stream.sample_rate(), stream.channels()) {
+    Ok(generator) => generator,
+    Err(error) => return build_empty_generated_waveform(Some(file_path), error.reason),
+  };
+  loop {
+    match stream.read_frames(65_536) {
+      Ok(chunk) if chunk.is_empty() => break,
+      Ok(chunk) => generator.push(&chunk),
+      Err(error) => return build_empty_generated_waveform(Some(file_path), error),
+    }
+  }
+  match build_generated_waveform(Some(file_path.clone()), generator.finish()) {
+    Ok(dump) => dump,
+    Err(error) => build_empty_generated_waveform(Some(file_path), error),
+  }
+}
+
+/// 对已解码的交错 f32 PCM 生成 Pioneer 波形
+#[napi]
+pub fn generate_pioneer_waveforms_from_pcm(
+  pcm_data: Buffer,
+  sample_rate: u32,
+  channels: u8,
+) -> PioneerGeneratedWaveformDump {
+  let Ok(samples) = try_cast_slice::<u8, f32>(pcm_data.as_ref()) else {
+    return build_empty_generated_waveform(None, "PCM buffer length is not aligned");
+  };
+  if sample_rate == 0 || channels == 0 {
+    return build_empty_generated_waveform(None, "sample_rate/channels must be positive");
+  }
+  let mut generator = match PioneerWaveformGenerator::new(sample_rate, u16::from(channels)) {
+    Ok(generator) => generator,
+    Err(error) => return build_empty_generated_waveform(None, error.reason),
+  };
+  generator.push(samples);
+  build_generated_waveform(None, generator.finish())
+    .unwrap_or_else(|error| build_empty_generated_waveform(None, error))
+}
+
+fn pioneer_detail_render_source(waveform: &PioneerDetailWaveformDump) -> RenderSource {
+  let is_rgb = waveform.style == "rgb";
+  let columns = waveform
+    .columns
+    .iter()
+    .map(|column| {
+      let height = f32::from(column.height) / 255.0;
+      // PWV5 没有频段数据，用颜色通道按高度近似；PWV3 蓝色既无频段也无白度。
+      let band = |value: Option<u8>, color: u8| match value {
+        Some(value) => f32::from(value) / 255.0,
+        None if is_rgb => f32::from(color) / 255.0 * height,
+        None => 0.0,
+      };
+      let low = band(column.band_low, column.color_r);
+      let mid = band(column.band_mid, column.color_g);
+      let high = band(column.band_high, column.color_b);
+      RenderColumn {
+        bands: [[low; 2], [mid; 2], [high; 2], [height; 2]],
+        color: is_rgb.then_some([column.color_r, column.color_g, column.color_b]),
+      }
+    })
+    .collect();
+  RenderSource {
+    columns,
+    columns_per_sec: waveform.detail_rate,
+  }
+}
+
+/// 把 Pioneer 细节波形绘制为图像，默认配色随波形样式（rgb / blue / 三频）
+#[napi]
+pub fn render_pioneer_detail_waveform(
+  waveform: PioneerDetailWaveformDump,
+  options: WaveformRenderOptions,
+) -> napi::Result<WaveformImage> {
+  let default_scheme = match waveform.style.as_str() {
+    "rgb" => WaveformColorScheme::PioneerRgb,
+    "blue" => WaveformColorScheme::Blue,
+    _ => WaveformColorScheme::PioneerThreeBand,
+  };
+  let source = pioneer_detail_render_source(&waveform);
+  let image = render_waveform(&source, &options, default_scheme)?;
+  encode_render_image(image, options.format.as_deref())
+}
@@ -0,0 +1,406 @@
+//! 由 PCM 生成 Pioneer ANLZ 波形：多声道先混为单声道，经 Mixxx 三频分离后按 150 列/秒
+//! 取各频段峰值，再编码为 PWAV/PWV3（蓝色）、PWV4/PWV5（RGB）与 PWV6/PWV7（三频）段落。
+//! 概览段由细节列按固定列数取最大值；位布局与 `pioneer_export_anlz` 的读取规则一一对应。
+
+use napi::bindgen_prelude::*;
+
+use crate::mixxx_waveform::{scale_mixxx_value, BandKey, ColumnPeakAccumulator, MixxxBandSplitter};
+use crate::pioneer_anlz_raw::RawAnlzSection;
+
+/// 细节波形列频率（Hz），与官方 PWV3/PWV5/PWV7 一致
+pub(crate) const PIONEER_DETAIL_COLUMNS_PER_SECOND: u32 = 150;
+const BLUE_PREVIEW_COLUMNS: usize = 400;
+const RGB_PREVIEW_COLUMNS: usize = 1200;
+const TRIBAND_PREVIEW_COLUMNS: usize = 1200;
+/// PWV3/PWV7 头部第三个 u32：高 16 位为列频率
+const DETAIL_RATE_WORD: u32 = PIONEER_DETAIL_COLUMNS_PER_SECOND << 16;
+/// PWV5 低 16 位是格式标志，沿用官方文件的 0x0305
+const RGB_DETAIL_RATE_WORD: u32 = DETAIL_RATE_WORD | 0x0305;
+/// PWAV 头部第二个 u32，官方文件固定为 0x00010000
+const BLUE_PREVIEW_FLAGS: u32 = 0x0001_0000;
+const RGB_PREVIEW_MAX_HEIGHT: u16 = 127;
+
+/// 单列各频段电平（0..=255，量化同 Mixxx 波形）
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub(crate) struct PioneerWaveformLevels {
+  pub(crate) low: u8,
+  pub(crate) mid: u8,
+  pub(crate) high: u8,
+  pub(crate) all: u8,
+}
+
+impl PioneerWaveformLevels {
+  fn merge(columns: &[PioneerWaveformLevels]) -> PioneerWaveformLevels {
+    columns
+      .iter()
+      .fold(PioneerWaveformLevels::default(), |merged, column| {
+        PioneerWaveformLevels {
+          low: merged.low.max(column.low),
+          mid: merged.mid.max(column.mid),
+          high: merged.high.max(column.high),
+          all: merged.all.max(column.all),
+        }
+      })
+  }
+
+  fn band_max(&self) -> u8 {
+    self.low.max(self.mid).max(self.high)
+  }
+
+  /// 白度：高频相对最强频段的占比（0..=7）
+  fn whiteness(&self) -> u8 {
+    rescale_level(self.high, self.band_max(), 7)
+  }
+
+  /// PWV3：低 5 位高度，高 3 位白度
+  fn blue_entry(&self) -> u8 {
+    (self.whiteness() << 5) | rescale_level(self.all, 255, 31)
+  }
+
+  /// PWAV：高 5 位高度，低 3 位白度（位序与 PWV3 相反）
+  fn blue_preview_entry(&self) -> u8 {
+    (rescale_level(self.all, 255, 31) << 3) | self.whiteness()
+  }
+
+  /// PWV5：大端 u16，R/G/B 各 3 位取低/中/高频相对最强频段的比例，高度 5 位
+  fn rgb_detail_entry(&self) -> [u8; 2] {
+    let band_max = self.band_max();
+    let red = u16::from(rescale_level(self.low, band_max, 7));
+    let green = u16::from(rescale_level(self.mid, band_max, 7));
+    let blue = u16::from(rescale_level(self.high, band_max, 7));
+    let height = u16::from(rescale_level(self.all, 255, 31));
+    ((red << 13) | (green << 10) | (blue << 7) | (height << 2)).to_be_bytes()
+  }
+
+  /// PWV4：6 字节，前三字节含义未公开（置 0），后三字节依次为低/中/高频高度
+  fn rgb_preview_entry(&self) -> [u8; 6] {
+    [
+      0,
+      0,
+      0,
+      rescale_level(self.low, 255, RGB_PREVIEW_MAX_HEIGHT),
+      rescale_level(self.mid, 255, RGB_PREVIEW_MAX_HEIGHT),
+      rescale_level(self.high, 255, RGB_PREVIEW_MAX_HEIGHT),
+    ]
+  }
+
+  /// PWV6/PWV7：字节顺序为 mid, high, low
+  fn triband_entry(&self) -> [u8; 3] {
+    [self.mid, self.high, self.low]
+  }
+}
+
+fn rescale_level(value: u8, from_max: u8, to_max: u16) -> u8 {
+  if from_max == 0 {
+    return 0;
+  }
+  let from_max = u32::from(from_max);
+  let scaled = (u32::from(value).min(from_max) * u32::from(to_max) + from_max / 2) / from_max;
+  scaled as u8
+}
+
+/// 流式生成：逐块喂入交错 f32 PCM，结束时得到 150 列/秒的细节列
+pub(crate) struct PioneerWaveformGenerator {
+  sample_rate: u32,
+  channels: usize,
+  splitter: MixxxBandSplitter,
+  /// 低 / 中 / 高 / 全频段峰值
+  peaks: ColumnPeakAccumulator<4>,
+  columns: Vec<PioneerWaveformLevels>,
+}
+
+impl PioneerWaveformGenerator {
+  pub(crate) fn new(sample_rate: u32, channels: u16) -> Result<Self> {
+    if channels == 0 {
+      return Err(Error::from_reason("Missing channels for Pioneer waveform"));
+    }
+    if sample_rate == 0 {
+      return Err(Error::from_reason(
+        "Missing sample rate for Pioneer waveform",
+      ));
+    }
+    let step = sample_rate as f64 / PIONEER_DETAIL_COLUMNS_PER_SECOND as f64;
+    Ok(Self {
+      sample_rate,
+      channels: channels as usize,
+      splitter: MixxxBandSplitter::new(sample_rate as f64)?,
+      peaks: ColumnPeakAccumulator::new(step),
+      columns: Vec::new(),
+    })
+  }
+
+  pub(crate) fn push(&mut self, interleaved: &[f32]) {
+    let channels = self.channels;
+    for frame in interleaved.chunks_exact(channels) {
+      let mono = frame.iter().map(|value| *value as f64).sum::<f64>() / channels as f64;
+      let [low, mid, high] = self.splitter.process(mono);
+      if let Some(peaks) = self.peaks.push_frame([low, mid, high, mono]) {
+        self.store(peaks);
+      }
+    }
+  }
+
+  fn store(&mut self, [low, mid, high, all]: [f64; 4]) {
+    self.columns.push(PioneerWaveformLevels {
+      low: scale_mixxx_value(low, BandKey::Low),
+      mid: scale_mixxx_value(mid, BandKey::Mid),
+      high: scale_mixxx_value(high, BandKey::High),
+      all: scale_mixxx_value(all, BandKey::All),
+    });
+  }
+
+  pub(crate) fn finish(mut self) -> PioneerGeneratedWaveforms {
+    if let Some(peaks) = self.peaks.flush() {
+      self.store(peaks);
+    }
+    PioneerGeneratedWaveforms {
+      sample_rate: self.sample_rate,
+      total_frames: self.peaks.total_frames(),
+      detail: self.columns,
+    }
+  }
+}
+
+pub(crate) struct PioneerGeneratedWaveforms {
+  pub(crate) sample_rate: u32,
+  pub(crate) total_frames: u64,
+  pub(crate) detail: Vec<PioneerWaveformLevels>,
+}
+
+/// 生成的 ANLZ 段及其应写入的分析文件扩展名（DAT/EXT/2EX）
+pub(crate) struct PioneerGeneratedSection {
+  pub(crate) file_kind: &'static str,
+  pub(crate) section: RawAnlzSection,
+}
+
+fn build_section(kind: &[u8; 4], header: &[u32], content: Vec<u8>) -> RawAnlzSection {
+  RawAnlzSection {
+    kind: *kind,
+    header_data: header
+      .iter()
+      .flat_map(|value| value.to_be_bytes())
+      .collect(),
+    content,
+  }
+}
+
+impl PioneerGeneratedWaveforms {
+  pub(crate) fn duration_sec(&self) -> f64 {
+    self.total_frames as f64 / self.sample_rate as f64
+  }
+
+  /// 把细节列合并为固定列数的概览；曲目过短时重复最近的细节列
+  fn preview(&self, count: usize) -> Vec<PioneerWaveformLevels> {
+    let total = self.detail.len();
+    if total == 0 {
+      return vec![PioneerWaveformLevels::default(); count];
+    }
+    (0..count)
+      .map(|index| {
+        let start = index * total / count;
+        let end = ((index + 1) * total / count).max(start + 1);
+        PioneerWaveformLevels::merge(&self.detail[start..end])
+      })
+      .collect()
+  }
+
+  pub(crate) fn blue_preview(&self) -> Vec<u8> {
+    let columns = self.preview(BLUE_PREVIEW_COLUMNS);
+    columns
+      .iter()
+      .map(PioneerWaveformLevels::blue_preview_entry)
+      .collect()
+  }
+
+  pub(crate) fn blue_detail(&self) -> Vec<u8> {
+    self
+      .detail
+      .iter()
+      .map(PioneerWaveformLevels::blue_entry)
+      .collect()
+  }
+
+  pub(crate) fn rgb_preview(&self) -> Vec<u8> {
+    let columns = self.preview(RGB_PREVIEW_COLUMNS);
+    columns
+      .iter()
+      .flat_map(|column| column.rgb_preview_entry())
+      .collect()
+  }
+
+  pub(crate) fn rgb_detail(&self) -> Vec<u8> {
+    self
+      .detail
+      .iter()
+      .flat_map(|column| column.rgb_detail_entry())
+      .collect()
+  }
+
+  pub(crate) fn triband_preview(&self) -> Vec<u8> {
+    let columns = self.preview(TRIBAND_PREVIEW_COLUMNS);
+    columns
+      .iter()
+      .flat_map(|column| column.triband_entry())
+      .collect()
+  }
+
+  pub(crate) fn triband_detail(&self) -> Vec<u8> {
+    self
+      .detail
+      .iter()
+      .flat_map(|column| column.triband_entry())
+      .collect()
+  }
+
+  /// 按官方文件顺序生成全部波形段：DAT(PWAV)、EXT(PWV3/PWV4/PWV5)、2EX(PWV7/PWV6)
+  pub(crate) fn sections(&self) -> Vec<PioneerGeneratedSection> {
+    let detail_count = self.detail.len() as u32;
+    let section = |file_kind, section| PioneerGeneratedSection { file_kind, section };
+    vec![
+      section(
+        "DAT",
+        build_section(
+          b"PWAV",
+          &[BLUE_PREVIEW_COLUMNS as u32, BLUE_PREVIEW_FLAGS],
+          self.blue_preview(),
+        ),
+      ),
+      section(
+        "EXT",
+        build_section(
+          b"PWV3",
+          &[1, detail_count, DETAIL_RATE_WORD],
+          self.blue_detail(),
+        ),
+      ),
+      section(
+        "EXT",
+        build_section(
+          b"PWV4",
+          &[6, RGB_PREVIEW_COLUMNS as u32, 0],
+          self.rgb_preview(),
+        ),
+      ),
+      section(
+        "EXT",
+        build_section(
+          b"PWV5",
+          &[2, detail_count, RGB_DETAIL_RATE_WORD],
+          self.rgb_detail(),
+        ),
+      ),
+      section(
+        "2EX",
+        build_section(
+          b"PWV7",
+          &[3, detail_count, DETAIL_RATE_WORD],
+          self.triband_detail(),
+        ),
+      ),
+      section(
+        "2EX",
+        build_section(
+          b"PWV6",
+          &[3, TRIBAND_PREVIEW_COLUMNS as u32],
+          self.triband_preview(),
+        ),
+      ),
+    ]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::analysis_utils::sine_wave;
+
+  /// 双声道（左右相同）正弦
+  fn sine(frequency: f64, seconds: f64, sample_rate: u32) -> Vec<f32> {
+    let frames = (seconds * sample_rate as f64) as usize;
+    sine_wave(frequency, 0.8, sample_rate as f64, frames)
+      .into_iter()
+      .flat_map(|value| [value, value])
+      .collect()
+  }
+
+  #[test]
+  fn encodes_levels_with_reader_bit_layout() {
+    let column = PioneerWaveformLevels {
+      low: 255,
+      mid: 128,
+      high: 0,
+      all: 255,
+    };
+    assert_eq!(column.blue_entry(), 31);
+    assert_eq!(column.blue_preview_entry(), 31 << 3);
+    let value = u16::from_be_bytes(column.rgb_detail_entry());
+    assert_eq!((value >> 13) & 0x07, 7);
+    assert_eq!((value >> 10) & 0x07, 4);
+    assert_eq!((value >> 7) & 0x07, 0);
+    assert_eq!((value >> 2) & 0x1f, 31);
+    assert_eq!(column.rgb_preview_entry(), [0, 0, 0, 127, 64, 0]);
+    assert_eq!(column.triband_entry(), [128, 0, 255]);
+
+    let bright = PioneerWaveformLevels {
+      high: 255,
+      ..column
+    };
+    assert_eq!(bright.blue_entry() >> 5, 7);
+    assert_eq!(bright.blue_preview_entry() & 0x07, 7);
+  }
+
+  #[test]
+  fn bass_tone_is_red_and_low_dominant() {
+    let sample_rate = 44_100;
+    let mut generator = PioneerWaveformGenerator::new(sample_rate, 2).unwrap();
+    for chunk in sine(60.0, 2.0, sample_rate).chunks(4_096) {
+      generator.push(chunk);
+    }
+    let generated = generator.finish();
+    assert_eq!(generated.detail.len(), 300);
+    assert!((generated.duration_sec() - 2.0).abs() < 1e-9);
+
+    let column = generated.detail[150];
+    assert!(column.low > column.mid && column.low > column.high);
+    let value = u16::from_be_bytes(column.rgb_detail_entry());
+    assert_eq!((value >> 13) & 0x07, 7);
+    assert!((value >> 7) & 0x07 <= 1);
+    assert!(column.blue_entry() >> 5 <= 1);
+  }
+
+  #[test]
+  fn sections_carry_fixed_preview_sizes_and_detail_rate() {
+    let sample_rate = 8_000;
+    let mut generator = PioneerWaveformGenerator::new(sample_rate, 1).unwrap();
+    generator.push(&vec![0.5; sample_rate as usize]);
+    let generated = generator.finish();
+    let sections = generated.sections();
+    let kinds: Vec<&[u8; 4]> = sections.iter().map(|item| &item.section.kind).collect();
+    assert_eq!(
+      kinds,
+      [b"PWAV", b"PWV3", b"PWV4", b"PWV5", b"PWV7", b"PWV6"]
+    );
+
+    let header_word = |section: &RawAnlzSection, index: usize| {
+      u32::from_be_bytes(
+        section.header_data[index * 4..index * 4 + 4]
+          .try_into()
+          .unwrap(),
+      )
+    };
+    let pwav = &sections[0].section;
+    assert_eq!(header_word(pwav, 0), 400);
+    assert_eq!(pwav.content.len(), 400);
+    let pwv4 = &sections[2].section;
+    assert_eq!(header_word(pwv4, 1), 1200);
+    assert_eq!(pwv4.content.len(), 1200 * 6);
+    let pwv5 = &sections[3].section;
+    assert_eq!(sections[3].file_kind, "EXT");
+    assert_eq!(header_word(pwv5, 1), 150);
+    assert_eq!(header_word(pwv5, 2) >> 16, 150);
+    assert_eq!(pwv5.content.len(), 300);
+    let pwv6 = &sections[5].section;
+    assert_eq!(pwv6.header_data.len(), 8);
+    assert_eq!(pwv6.content.len(), 1200 * 3);
+  }
+}
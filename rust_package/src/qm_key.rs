@@ -18,12 +18,30 @@ extern "C" {
     channels: c_int,
   ) -> c_int;
   fn qm_key_finalize(handle: *mut QmKeyDetectorHandle) -> c_int;
+  fn qm_chroma_create(sample_rate: c_double) -> *mut QmChromaHandle;
+  fn qm_chroma_destroy(handle: *mut QmChromaHandle);
+  fn qm_chroma_process(
+    handle: *mut QmChromaHandle,
+    interleaved: *const f32,
+    frames: usize,
+    channels: c_int,
+  ) -> c_int;
+  fn qm_chroma_finalize(handle: *mut QmChromaHandle, out12: *mut c_double) -> c_int;
+}
+
+#[repr(C)]
+pub struct QmChromaHandle {
+  _private: [u8; 0],
 }
 
 pub struct KeyDetector {
   handle: *mut QmKeyDetectorHandle,
 }
 
+// SAFETY: 句柄由 KeyDetector 独占，所有 FFI 调用都经 &mut self（或 Drop）串行发生；
+// qm_key_wrapper 的检测器没有线程局部或全局可变状态，不依赖创建它的线程，可整体移交给其它线程
+unsafe impl Send for KeyDetector {}
+
 impl KeyDetector {
   pub fn new(sample_rate: u32) -> Result<Self, String> {
     let handle = unsafe { qm_key_create(sample_rate as f64) };
@@ -71,6 +89,46 @@ impl Drop for KeyDetector {
   }
 }
 
+/// QM 色度累加器：与调性检测相同的 Chromagram 前端，输出整轨平均 12 维色度（0 = C）
+pub struct ChromaAccumulator {
+  handle: *mut QmChromaHandle,
+}
+
+impl ChromaAccumulator {
+  pub fn new(sample_rate: u32) -> Result<Self, String> {
+    let handle = unsafe { qm_chroma_create(sample_rate as f64) };
+    if handle.is_null() {
+      return Err("qm_chroma_create failed".to_string());
+    }
+    Ok(ChromaAccumulator { handle })
+  }
+
+  /// 输入必须为立体声交错 PCM
+  pub fn process(&mut self, interleaved: &[f32], frames: usize) -> Result<(), String> {
+    let ok = unsafe { qm_chroma_process(self.handle, interleaved.as_ptr(), frames, 2) };
+    if ok == 0 {
+      return Err("qm_chroma_process failed".to_string());
+    }
+    Ok(())
+  }
+
+  /// 返回平均色度；没有完整分析窗时为 None
+  pub fn finalize(&mut self) -> Option<[f64; 12]> {
+    let mut chroma = [0.0f64; 12];
+    let windows = unsafe { qm_chroma_finalize(self.handle, chroma.as_mut_ptr()) };
+    (windows > 0).then_some(chroma)
+  }
+}
+
+impl Drop for ChromaAccumulator {
+  fn drop(&mut self) {
+    if !self.handle.is_null() {
+      unsafe { qm_chroma_destroy(self.handle) };
+      self.handle = std::ptr::null_mut();
+    }
+  }
+}
+
 pub fn analyze_key_id_from_pcm(
   pcm_data: &[f32],
   sample_rate: u32,
@@ -0,0 +1,335 @@
+//! 带限重采样：linear 供预览，sinc（加窗 sinc 查表）供导出与高质量回放
+
+use std::result::Result as StdResult;
+use std::sync::{Arc, OnceLock};
+
+use bytemuck::cast_slice;
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::mixxx_waveform::pcm_buffer_to_f32;
+
+/// 单位截止时每侧零点数
+const SINC_HALF_ZERO_CROSSINGS: f64 = 16.0;
+/// 每个输入采样间隔的查表精度
+const SINC_TABLE_RESOLUTION: usize = 512;
+/// 截止频率相对 Nyquist 的余量，留出过渡带
+const SINC_CUTOFF_MARGIN: f64 = 0.95;
+/// 回放核的截止比例按 1/64 档缓存
+const PLAYBACK_KERNEL_STEPS: usize = 64;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ResampleQuality {
+  #[default]
+  Linear,
+  Sinc,
+}
+
+impl ResampleQuality {
+  pub(crate) fn as_str(self) -> &'static str {
+    match self {
+      ResampleQuality::Linear => "linear",
+      ResampleQuality::Sinc => "sinc",
+    }
+  }
+}
+
+pub(crate) fn parse_resample_quality(raw: &str) -> Option<ResampleQuality> {
+  match raw.trim() {
+    "linear" => Some(ResampleQuality::Linear),
+    "sinc" => Some(ResampleQuality::Sinc),
+    _ => None,
+  }
+}
+
+/// Blackman 窗 sinc 核，按与中心的距离（输入采样数）查表
+pub(crate) struct SincKernel {
+  half_width: f64,
+  table: Vec<f32>,
+}
+
+impl SincKernel {
+  /// cutoff 为相对输入 Nyquist 的截止（0..1]
+  pub(crate) fn new(cutoff: f64) -> Self {
+    let cutoff = cutoff.clamp(0.01, 1.0);
+    let half_width = SINC_HALF_ZERO_CROSSINGS / cutoff;
+    let len = (half_width * SINC_TABLE_RESOLUTION as f64).ceil() as usize + 2;
+    let table = (0..len)
+      .map(|index| {
+        let x = index as f64 / SINC_TABLE_RESOLUTION as f64;
+        if x >= half_width {
+          return 0.0;
+        }
+        let arg = std::f64::consts::PI * cutoff * x;
+        let sinc = if arg.abs() < 1e-12 {
+          1.0
+        } else {
+          arg.sin() / arg
+        };
+        let u = x / half_width;
+        let window = 0.42
+          + 0.5 * (std::f64::consts::PI * u).cos()
+          + 0.08 * (2.0 * std::f64::consts::PI * u).cos();
+        (cutoff * sinc * window) as f32
+      })
+      .collect();
+    Self { half_width, table }
+  }
+
+  fn weight(&self, distance: f64) -> f32 {
+    let position = distance.abs() * SINC_TABLE_RESOLUTION as f64;
+    let index = position as usize;
+    if index + 1 >= self.table.len() {
+      return 0.0;
+    }
+    let frac = (position - index as f64) as f32;
+    self.table[index] + (self.table[index + 1] - self.table[index]) * frac
+  }
+
+  /// 在 source_frame 处做带限插值；越界部分按 0 处理并做权重归一
+  pub(crate) fn interpolate(
+    &self,
+    source_data: &[f32],
+    frame_count: usize,
+    source_channels: usize,
+    source_frame: f64,
+    channel: usize,
+  ) -> f32 {
+    if frame_count == 0 || source_channels == 0 || !source_frame.is_finite() {
+      return 0.0;
+    }
+    let source_channel = channel.min(source_channels - 1);
+    let first = (source_frame - self.half_width).ceil().max(0.0) as usize;
+    let last = ((source_frame + self.half_width).floor() as usize).min(frame_count - 1);
+    let mut sum = 0.0f32;
+    let mut weight_sum = 0.0f32;
+    for frame in first..=last {
+      let weight = self.weight(source_frame - frame as f64);
+      sum += weight * source_data[frame * source_channels + source_channel];
+      weight_sum += weight;
+    }
+    if weight_sum.abs() <= f32::EPSILON {
+      0.0
+    } else {
+      sum / weight_sum
+    }
+  }
+}
+
+/// 回放插值核表：每档截止一个核，进程内只构建一次；在控制线程取得后交给 deck，
+/// 音频线程只做查表
+pub(crate) struct PlaybackSincKernels {
+  kernels: Vec<SincKernel>,
+}
+
+impl PlaybackSincKernels {
+  /// 首次调用会构建全部档位，不得在音频线程调用
+  pub(crate) fn shared() -> Arc<Self> {
+    static SHARED: OnceLock<Arc<PlaybackSincKernels>> = OnceLock::new();
+    Arc::clone(SHARED.get_or_init(|| {
+      Arc::new(Self {
+        kernels: (1..=PLAYBACK_KERNEL_STEPS)
+          .map(|step| {
+            SincKernel::new(step as f64 / PLAYBACK_KERNEL_STEPS as f64 * SINC_CUTOFF_MARGIN)
+          })
+          .collect(),
+      })
+    }))
+  }
+
+  /// source_step 为每个输出帧推进的源帧数（速度 × 源/输出采样率）；
+  /// 截止按 min(1, 1/source_step) 向下取档，保证变速与降采样时都不混叠
+  pub(crate) fn select(&self, source_step: f64) -> &SincKernel {
+    let ratio = if source_step.is_finite() && source_step > 1.0 {
+      1.0 / source_step
+    } else {
+      1.0
+    };
+    let step =
+      ((ratio * PLAYBACK_KERNEL_STEPS as f64).floor() as usize).clamp(1, PLAYBACK_KERNEL_STEPS);
+    &self.kernels[step - 1]
+  }
+}
+
+fn linear_sample(data: &[f32], frame_count: usize, channels: usize, pos: f64, ch: usize) -> f32 {
+  let base = pos.floor().max(0.0) as usize;
+  if base >= frame_count {
+    return 0.0;
+  }
+  let next = (base + 1).min(frame_count - 1);
+  let frac = (pos - base as f64).clamp(0.0, 1.0) as f32;
+  let left = data[base * channels + ch];
+  left + (data[next * channels + ch] - left) * frac
+}
+
+/// 交错 PCM 整段重采样
+pub(crate) fn resample_interleaved(
+  samples: &[f32],
+  channels: usize,
+  from_sample_rate: u32,
+  to_sample_rate: u32,
+  quality: ResampleQuality,
+) -> StdResult<Vec<f32>, String> {
+  if channels == 0 || from_sample_rate == 0 || to_sample_rate == 0 {
+    return Err("重采样参数无效".to_string());
+  }
+  if !samples.len().is_multiple_of(channels) {
+    return Err("PCM buffer is not aligned to channel count".to_string());
+  }
+  if from_sample_rate == to_sample_rate {
+    return Ok(samples.to_vec());
+  }
+  let frame_count = samples.len() / channels;
+  let step = from_sample_rate as f64 / to_sample_rate as f64;
+  let output_frames = (frame_count as f64 / step).round() as usize;
+  let kernel = match quality {
+    ResampleQuality::Linear => None,
+    ResampleQuality::Sinc => {
+      let ratio = (to_sample_rate as f64 / from_sample_rate as f64).min(1.0);
+      Some(if ratio < 1.0 {
+        SincKernel::new(ratio * SINC_CUTOFF_MARGIN)
+      } else {
+        SincKernel::new(SINC_CUTOFF_MARGIN)
+      })
+    }
+  };
+  let mut output = Vec::with_capacity(output_frames * channels);
+  for frame in 0..output_frames {
+    let position = frame as f64 * step;
+    for channel in 0..channels {
+      output.push(match kernel.as_ref() {
+        Some(kernel) => kernel.interpolate(samples, frame_count, channels, position, channel),
+        None => linear_sample(samples, frame_count, channels, position, channel),
+      });
+    }
+  }
+  Ok(output)
+}
+
+/// 重采样结果
+#[napi(object)]
+pub struct ResamplePcmResult {
+  /// PCM 数据（Buffer，内部为 f32 小端序）
+  pub pcm_data: Buffer,
+  pub sample_rate: u32,
+  pub channels: u8,
+  pub total_frames: u32,
+  /// 错误描述（失败时）
+  pub error: Option<String>,
+}
+
+/// 对交错 f32 PCM 重采样；quality 为 linear（默认）或 sinc
+#[napi]
+pub fn resample_pcm(
+  pcm_data: Buffer,
+  channels: u8,
+  from_sample_rate: u32,
+  to_sample_rate: u32,
+  quality: Option<String>,
+) -> napi::Result<ResamplePcmResult> {
+  let quality = match quality.as_deref() {
+    Some(raw) => parse_resample_quality(raw)
+      .ok_or_else(|| napi::Error::from_reason(format!("unknown resample quality: {}", raw)))?,
+    None => ResampleQuality::default(),
+  };
+  let pcm_f32 = pcm_buffer_to_f32(&pcm_data);
+  let safe_channels = usize::from(channels.max(1));
+  Ok(
+    match resample_interleaved(
+      &pcm_f32,
+      safe_channels,
+      from_sample_rate,
+      to_sample_rate,
+      quality,
+    ) {
+      Ok(resampled) => ResamplePcmResult {
+        total_frames: (resampled.len() / safe_channels) as u32,
+        pcm_data: Buffer::from(cast_slice(&resampled).to_vec()),
+        sample_rate: to_sample_rate,
+        channels,
+        error: None,
+      },
+      Err(message) => ResamplePcmResult {
+        pcm_data: Buffer::from(Vec::<u8>::new()),
+        sample_rate: to_sample_rate,
+        channels,
+        total_frames: 0,
+        error: Some(message),
+      },
+    },
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::analysis_utils::sine_wave;
+
+  fn sine(frequency: f64, sample_rate: u32, frames: usize) -> Vec<f32> {
+    sine_wave(frequency, 1.0, sample_rate as f64, frames)
+  }
+
+  fn rms(samples: &[f32]) -> f64 {
+    (samples.iter().map(|v| (*v as f64).powi(2)).sum::<f64>() / samples.len() as f64).sqrt()
+  }
+
+  #[test]
+  fn sinc_preserves_passband_tone_when_upsampling() {
+    let input = sine(1_000.0, 44_100, 44_100);
+    let output = resample_interleaved(&input, 1, 44_100, 48_000, ResampleQuality::Sinc).unwrap();
+    assert_eq!(output.len(), 48_000);
+    let expected = sine(1_000.0, 48_000, 48_000);
+    let error: Vec<f32> = output[1_000..47_000]
+      .iter()
+      .zip(&expected[1_000..47_000])
+      .map(|(a, b)| a - b)
+      .collect();
+    assert!(rms(&error) < 1e-3, "error rms {}", rms(&error));
+  }
+
+  #[test]
+  fn sinc_rejects_content_above_target_nyquist() {
+    // 30 kHz 在 96k→44.1k 时应被滤除，线性插值会折叠到可听频段
+    let input = sine(30_000.0, 96_000, 96_000);
+    let sinc = resample_interleaved(&input, 1, 96_000, 44_100, ResampleQuality::Sinc).unwrap();
+    let linear = resample_interleaved(&input, 1, 96_000, 44_100, ResampleQuality::Linear).unwrap();
+    let sinc_rms = rms(&sinc[2_000..42_000]);
+    let linear_rms = rms(&linear[2_000..42_000]);
+    assert!(sinc_rms < 0.01, "sinc rms {}", sinc_rms);
+    assert!(linear_rms > 0.1, "linear rms {}", linear_rms);
+  }
+
+  #[test]
+  fn playback_kernel_cutoff_follows_effective_step() {
+    // 96k 源在 44.1k 输出上回放：30 kHz 须被滤除，同采样率回放保留全带宽
+    let kernels = PlaybackSincKernels::shared();
+    let input = sine(30_000.0, 96_000, 96_000);
+    let step = 96_000.0 / 44_100.0;
+    let kernel = kernels.select(step);
+    let output: Vec<f32> = (2_000..42_000)
+      .map(|frame| kernel.interpolate(&input, input.len(), 1, frame as f64 * step, 0))
+      .collect();
+    assert!(rms(&output) < 0.01, "rms {}", rms(&output));
+    // 同采样率下 2 倍速同样要降低截止，慢放不需要
+    let input = sine(15_000.0, 44_100, 44_100);
+    let kernel = kernels.select(2.0);
+    let output: Vec<f32> = (1_000..20_000)
+      .map(|frame| kernel.interpolate(&input, input.len(), 1, frame as f64 * 2.0, 0))
+      .collect();
+    assert!(rms(&output) < 0.01, "rms {}", rms(&output));
+    assert!(std::ptr::eq(kernels.select(1.0), kernels.select(0.5)));
+    assert!(!std::ptr::eq(kernels.select(1.0), kernels.select(2.0)));
+    assert!(Arc::ptr_eq(&kernels, &PlaybackSincKernels::shared()));
+  }
+
+  #[test]
+  fn same_rate_is_passthrough_and_quality_parses() {
+    let input = vec![0.25f32, -0.25, 0.5, -0.5];
+    let output = resample_interleaved(&input, 2, 48_000, 48_000, ResampleQuality::Sinc).unwrap();
+    assert_eq!(output, input);
+    assert_eq!(parse_resample_quality("sinc"), Some(ResampleQuality::Sinc));
+    assert_eq!(parse_resample_quality("cubic"), None);
+    assert_eq!(ResampleQuality::Linear.as_str(), "linear");
+    assert!(resample_interleaved(&input[..3], 2, 44_100, 48_000, ResampleQuality::Linear).is_err());
+  }
+}
@@ -0,0 +1,466 @@
+//! 内存最近邻索引：按曲目 id 存定长向量，支持增删、欧氏距离 top-k 查询与落盘；
+//! 句柄管理同流式解码（create/load 得到句柄，close 释放）
+
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use napi_derive::napi;
+use parking_lot::Mutex;
+
+const SIMILARITY_INDEX_MAGIC: &[u8; 8] = b"FRKBSIM1";
+const SIMILARITY_INDEX_HEADER_BYTES: usize = 16;
+const MAX_DIMENSION: u32 = 4096;
+
+/// 索引句柄信息
+#[napi(object)]
+pub struct SimilarityIndexInfo {
+  pub handle: u32,
+  pub dimension: u32,
+  pub size: u32,
+}
+
+/// 查询命中
+#[napi(object)]
+pub struct SimilarityMatch {
+  pub id: String,
+  /// 欧氏距离，越小越相似
+  pub distance: f64,
+}
+
+/// 向量按行连续存放；删除时与末行交换，保持紧凑（槽位顺序因此不等于插入顺序）
+pub(crate) struct SimilarityIndex {
+  dimension: usize,
+  ids: Vec<String>,
+  vectors: Vec<f32>,
+  /// 每个槽位条目的插入序号，距离相同时据此排序
+  sequences: Vec<u64>,
+  next_sequence: u64,
+  slots: HashMap<String, usize>,
+}
+
+/// 堆里按距离比较的候选（最大堆，堆顶为当前第 k 近）
+struct Candidate {
+  distance: f64,
+  sequence: u64,
+  slot: usize,
+}
+
+impl PartialEq for Candidate {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == CmpOrdering::Equal
+  }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+  fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Candidate {
+  fn cmp(&self, other: &Self) -> CmpOrdering {
+    self
+      .distance
+      .total_cmp(&other.distance)
+      .then(self.sequence.cmp(&other.sequence))
+  }
+}
+
+impl SimilarityIndex {
+  pub(crate) fn new(dimension: usize) -> Self {
+    Self {
+      dimension,
+      ids: Vec::new(),
+      vectors: Vec::new(),
+      sequences: Vec::new(),
+      next_sequence: 0,
+      slots: HashMap::new(),
+    }
+  }
+
+  pub(crate) fn len(&self) -> usize {
+    self.ids.len()
+  }
+
+  fn row(&self, slot: usize) -> &[f32] {
+    &self.vectors[slot * self.dimension..(slot + 1) * self.dimension]
+  }
+
+  fn check_vector(&self, vector: &[f64]) -> StdResult<(), String> {
+    if vector.len() != self.dimension {
+      return Err(format!(
+        "向量维度不匹配: 期望 {}，实际 {}",
+        self.dimension,
+        vector.len()
+      ));
+    }
+    if vector.iter().any(|value| !value.is_finite()) {
+      return Err("向量包含非有限值".to_string());
+    }
+    Ok(())
+  }
+
+  /// 新增或覆盖；返回是否覆盖了已有条目
+  pub(crate) fn insert(&mut self, id: &str, vector: &[f64]) -> StdResult<bool, String> {
+    self.check_vector(vector)?;
+    let values = vector.iter().map(|value| *value as f32);
+    if let Some(slot) = self.slots.get(id).copied() {
+      let start = slot * self.dimension;
+      for (target, value) in self.vectors[start..start + self.dimension]
+        .iter_mut()
+        .zip(values)
+      {
+        *target = value;
+      }
+      return Ok(true);
+    }
+    self.slots.insert(id.to_string(), self.ids.len());
+    self.ids.push(id.to_string());
+    self.vectors.extend(values);
+    self.sequences.push(self.next_sequence);
+    self.next_sequence += 1;
+    Ok(false)
+  }
+
+  pub(crate) fn remove(&mut self, id: &str) -> bool {
+    let Some(slot) = self.slots.remove(id) else {
+      return false;
+    };
+    let last = self.ids.len() - 1;
+    if slot != last {
+      let (head, tail) = self.vectors.split_at_mut(last * self.dimension);
+      head[slot * self.dimension..(slot + 1) * self.dimension].copy_from_slice(tail);
+      self.ids.swap(slot, last);
+      self.sequences.swap(slot, last);
+      self.slots.insert(self.ids[slot].clone(), slot);
+    }
+    self.ids.pop();
+    self.sequences.pop();
+    self.vectors.truncate(last * self.dimension);
+    true
+  }
+
+  pub(crate) fn vector_of(&self, id: &str) -> Option<Vec<f64>> {
+    self
+      .slots
+      .get(id)
+      .map(|slot| self.row(*slot).iter().map(|value| *value as f64).collect())
+  }
+
+  /// 精确 top-k（暴力扫描）；结果按距离升序，距离相同按插入顺序
+  pub(crate) fn query(
+    &self,
+    vector: &[f64],
+    k: usize,
+    exclude_id: Option<&str>,
+  ) -> StdResult<Vec<(String, f64)>, String> {
+    self.check_vector(vector)?;
+    let excluded = exclude_id.and_then(|id| self.slots.get(id).copied());
+    let mut heap: BinaryHeap<Candidate> = BinaryHeap::with_capacity(k + 1);
+    if k > 0 {
+      for slot in 0..self.len() {
+        if Some(slot) == excluded {
+          continue;
+        }
+        let squared = self
+          .row(slot)
+          .iter()
+          .zip(vector)
+          .map(|(stored, query)| {
+            let diff = *stored as f64 - query;
+            diff * diff
+          })
+          .sum::<f64>();
+        let candidate = Candidate {
+          distance: squared,
+          sequence: self.sequences[slot],
+          slot,
+        };
+        if heap.len() < k {
+          heap.push(candidate);
+        } else if heap.peek().is_some_and(|worst| candidate < *worst) {
+          heap.pop();
+          heap.push(candidate);
+        }
+      }
+    }
+    Ok(
+      heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|candidate| (self.ids[candidate.slot].clone(), candidate.distance.sqrt()))
+        .collect(),
+    )
+  }
+
+  /// 头部：魔数、维度、条目数；之后每条为 id 长度 + UTF-8 id + 维度个 f32（均为小端），
+  /// 条目按插入顺序写出，载入后距离相同时的先后不变
+  pub(crate) fn encode(&self) -> Vec<u8> {
+    let id_bytes: usize = self.ids.iter().map(|id| 4 + id.len()).sum();
+    let mut bytes =
+      Vec::with_capacity(SIMILARITY_INDEX_HEADER_BYTES + id_bytes + self.vectors.len() * 4);
+    bytes.extend_from_slice(SIMILARITY_INDEX_MAGIC);
+    bytes.extend_from_slice(&(self.dimension as u32).to_le_bytes());
+    bytes.extend_from_slice(&(self.len() as u32).to_le_bytes());
+    let mut slots: Vec<usize> = (0..self.len()).collect();
+    slots.sort_by_key(|slot| self.sequences[*slot]);
+    for slot in slots {
+      let id = &self.ids[slot];
+      bytes.extend_from_slice(&(id.len() as u32).to_le_bytes());
+      bytes.extend_from_slice(id.as_bytes());
+      for value in self.row(slot) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+      }
+    }
+    bytes
+  }
+
+  pub(crate) fn decode(bytes: &[u8]) -> StdResult<Self, String> {
+    let invalid = || "相似度索引文件无效".to_string();
+    if bytes.len() < SIMILARITY_INDEX_HEADER_BYTES || &bytes[0..8] != SIMILARITY_INDEX_MAGIC {
+      return Err(invalid());
+    }
+    let u32_at = |offset: usize| -> StdResult<u32, String> {
+      bytes
+        .get(offset..offset + 4)
+        .map(|slice| u32::from_le_bytes(slice.try_into().unwrap()))
+        .ok_or_else(invalid)
+    };
+    let dimension = u32_at(8)?;
+    if dimension == 0 || dimension > MAX_DIMENSION {
+      return Err(invalid());
+    }
+    let count = u32_at(12)? as usize;
+    let mut index = Self::new(dimension as usize);
+    let mut offset = SIMILARITY_INDEX_HEADER_BYTES;
+    let row_bytes = dimension as usize * 4;
+    for _ in 0..count {
+      let id_len = u32_at(offset)? as usize;
+      offset += 4;
+      let id = bytes
+        .get(offset..offset + id_len)
+        .and_then(|slice| std::str::from_utf8(slice).ok())
+        .ok_or_else(invalid)?
+        .to_string();
+      offset += id_len;
+      let row = bytes.get(offset..offset + row_bytes).ok_or_else(invalid)?;
+      offset += row_bytes;
+      let vector: Vec<f64> = row
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()) as f64)
+        .collect();
+      index.insert(&id, &vector)?;
+    }
+    Ok(index)
+  }
+}
+
+fn similarity_indexes() -> &'static Mutex<HashMap<u32, Arc<Mutex<SimilarityIndex>>>> {
+  static SIMILARITY_INDEXES: OnceLock<Mutex<HashMap<u32, Arc<Mutex<SimilarityIndex>>>>> =
+    OnceLock::new();
+  SIMILARITY_INDEXES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn next_index_handle() -> u32 {
+  static NEXT_INDEX_HANDLE: AtomicU32 = AtomicU32::new(1);
+  NEXT_INDEX_HANDLE.fetch_add(1, Ordering::Relaxed)
+}
+
+fn index_by_handle(handle: u32) -> napi::Result<Arc<Mutex<SimilarityIndex>>> {
+  similarity_indexes()
+    .lock()
+    .get(&handle)
+    .cloned()
+    .ok_or_else(|| napi::Error::from_reason(format!("无效的索引句柄: {}", handle)))
+}
+
+fn register_index(index: SimilarityIndex) -> SimilarityIndexInfo {
+  let handle = next_index_handle();
+  let info = SimilarityIndexInfo {
+    handle,
+    dimension: index.dimension as u32,
+    size: index.len() as u32,
+  };
+  similarity_indexes()
+    .lock()
+    .insert(handle, Arc::new(Mutex::new(index)));
+  info
+}
+
+fn to_matches(hits: Vec<(String, f64)>) -> Vec<SimilarityMatch> {
+  hits
+    .into_iter()
+    .map(|(id, distance)| SimilarityMatch { id, distance })
+    .collect()
+}
+
+/// 创建空索引
+#[napi]
+pub fn create_similarity_index(dimension: u32) -> napi::Result<SimilarityIndexInfo> {
+  if dimension == 0 || dimension > MAX_DIMENSION {
+    return Err(napi::Error::from_reason(format!(
+      "向量维度须在 1..={} 之间",
+      MAX_DIMENSION
+    )));
+  }
+  Ok(register_index(SimilarityIndex::new(dimension as usize)))
+}
+
+/// 从磁盘加载索引
+#[napi]
+pub fn load_similarity_index(file_path: String) -> napi::Result<SimilarityIndexInfo> {
+  let bytes = fs::read(&file_path).map_err(|error| napi::Error::from_reason(error.to_string()))?;
+  let index = SimilarityIndex::decode(&bytes).map_err(napi::Error::from_reason)?;
+  Ok(register_index(index))
+}
+
+/// 写入磁盘（先写临时文件再替换）
+#[napi]
+pub fn save_similarity_index(handle: u32, file_path: String) -> napi::Result<()> {
+  let bytes = index_by_handle(handle)?.lock().encode();
+  let path = Path::new(&file_path);
+  if let Some(parent) = path
+    .parent()
+    .filter(|parent| !parent.as_os_str().is_empty())
+  {
+    fs::create_dir_all(parent).map_err(|error| napi::Error::from_reason(error.to_string()))?;
+  }
+  let tmp_path = path.with_extension(format!("{}.{}", std::process::id(), "tmp"));
+  let written = File::create(&tmp_path).and_then(|mut file| {
+    file.write_all(&bytes)?;
+    file.sync_data()
+  });
+  if let Err(error) = written.and_then(|_| fs::rename(&tmp_path, path)) {
+    let _ = fs::remove_file(&tmp_path);
+    return Err(napi::Error::from_reason(error.to_string()));
+  }
+  Ok(())
+}
+
+/// 新增或覆盖条目；返回是否覆盖了已有 id
+#[napi]
+pub fn add_similarity_index_item(handle: u32, id: String, vector: Vec<f64>) -> napi::Result<bool> {
+  let index = index_by_handle(handle)?;
+  let mut index = index.lock();
+  index.insert(&id, &vector).map_err(napi::Error::from_reason)
+}
+
+/// 删除条目；返回是否存在
+#[napi]
+pub fn remove_similarity_index_item(handle: u32, id: String) -> napi::Result<bool> {
+  Ok(index_by_handle(handle)?.lock().remove(&id))
+}
+
+/// 条目数
+#[napi]
+pub fn similarity_index_size(handle: u32) -> napi::Result<u32> {
+  Ok(index_by_handle(handle)?.lock().len() as u32)
+}
+
+/// 按向量查询最近的 k 条；exclude_id 用于排除种子自身
+#[napi]
+pub fn query_similarity_index(
+  handle: u32,
+  vector: Vec<f64>,
+  k: u32,
+  exclude_id: Option<String>,
+) -> napi::Result<Vec<SimilarityMatch>> {
+  let index = index_by_handle(handle)?;
+  let index = index.lock();
+  index
+    .query(&vector, k as usize, exclude_id.as_deref())
+    .map(to_matches)
+    .map_err(napi::Error::from_reason)
+}
+
+/// 以索引内已有条目为种子查询最近的 k 条（不含自身）
+#[napi]
+pub fn query_similarity_index_by_id(
+  handle: u32,
+  id: String,
+  k: u32,
+) -> napi::Result<Vec<SimilarityMatch>> {
+  let index = index_by_handle(handle)?;
+  let index = index.lock();
+  let vector = index
+    .vector_of(&id)
+    .ok_or_else(|| napi::Error::from_reason(format!("索引中没有条目: {}", id)))?;
+  index
+    .query(&vector, k as usize, Some(&id))
+    .map(to_matches)
+    .map_err(napi::Error::from_reason)
+}
+
+/// 释放索引
+#[napi]
+pub fn close_similarity_index(handle: u32) -> bool {
+  similarity_indexes().lock().remove(&handle).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ids(hits: &[(String, f64)]) -> Vec<&str> {
+    hits.iter().map(|(id, _)| id.as_str()).collect()
+  }
+
+  #[test]
+  fn insert_remove_and_query_top_k() {
+    let mut index = SimilarityIndex::new(2);
+    for (id, x) in [("a", 0.0), ("b", 1.0), ("c", 2.0), ("d", 5.0)] {
+      assert!(!index.insert(id, &[x, 0.0]).unwrap());
+    }
+    assert!(index.insert("d", &[3.0, 0.0]).unwrap());
+    assert!(index.insert("e", &[1.0]).is_err());
+
+    let hits = index.query(&[0.9, 0.0], 3, None).unwrap();
+    assert_eq!(ids(&hits), vec!["b", "a", "c"]);
+    assert!((hits[0].1 - 0.1).abs() < 1e-6);
+
+    assert!(index.remove("a"));
+    assert!(!index.remove("a"));
+    assert_eq!(index.len(), 3);
+    let hits = index.query(&[0.0, 0.0], 10, Some("b")).unwrap();
+    assert_eq!(ids(&hits), vec!["c", "d"]);
+    assert_eq!(index.vector_of("d"), Some(vec![3.0, 0.0]));
+  }
+
+  #[test]
+  fn equal_distances_keep_insertion_order_after_removal() {
+    let mut index = SimilarityIndex::new(2);
+    for (id, x) in [("a", 1.0), ("b", -1.0), ("c", 1.0)] {
+      index.insert(id, &[x, 0.0]).unwrap();
+    }
+    assert!(index.remove("a"));
+    index.insert("d", &[0.0, 1.0]).unwrap();
+    let hits = index.query(&[0.0, 0.0], 3, None).unwrap();
+    assert_eq!(ids(&hits), vec!["b", "c", "d"]);
+
+    let restored = SimilarityIndex::decode(&index.encode()).unwrap();
+    let hits = restored.query(&[0.0, 0.0], 3, None).unwrap();
+    assert_eq!(ids(&hits), vec!["b", "c", "d"]);
+  }
+
+  #[test]
+  fn encode_decode_round_trip() {
+    let mut index = SimilarityIndex::new(3);
+    index.insert("曲目/一.flac", &[0.5, -1.0, 2.0]).unwrap();
+    index.insert("two.mp3", &[0.0, 0.25, 1.0]).unwrap();
+    let bytes = index.encode();
+    let restored = SimilarityIndex::decode(&bytes).unwrap();
+    assert_eq!(restored.dimension, 3);
+    assert_eq!(restored.ids, index.ids);
+    assert_eq!(restored.vectors, index.vectors);
+    assert!(SimilarityIndex::decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(SimilarityIndex::decode(b"FRKBPCM1\0\0\0\0\0\0\0\0").is_err());
+  }
+}
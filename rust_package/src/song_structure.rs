@@ -0,0 +1,860 @@
+//! 歌曲结构分段：按节拍网格投影到小节，融合能量、频谱 flux 与色度自相似矩阵的 Foote novelty，
+//! 输出对齐小节的段落边界并标注 intro / build / drop / breakdown / outro；
+//! 段落字段与 rekordbox PSSI（起止拍号 + 高情绪 phrase kind）对应。
+//! 小节网格由单一 BPM + 首拍推出，不读取分段网格（beatGridClips），变速曲目后段的小节边界会漂移
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::analysis_utils::{
+  analyze_file_in_chunks, analyze_pcm_buffer, hann_window, power_spectrum, AnalysisErrorResult,
+};
+
+const FFT_SIZE: usize = 2048;
+const HOP_SIZE: usize = 1024;
+const LOW_BAND_MAX_HZ: f64 = 150.0;
+const MID_BAND_MAX_HZ: f64 = 2_500.0;
+const CHROMA_MIN_HZ: f64 = 55.0;
+const CHROMA_MAX_HZ: f64 = 5_000.0;
+const DEFAULT_BEATS_PER_BAR: u32 = 4;
+/// Foote 核半宽（小节）
+const NOVELTY_HALF_WIDTH_BARS: usize = 4;
+/// 段落最短小节数
+const MIN_SEGMENT_BARS: usize = 4;
+/// 边界得分阈值（novelty 的稳健 z 值 + phrase 先验）
+const BOUNDARY_MIN_SCORE: f64 = 1.0;
+/// 4 / 8 小节对齐的先验加分
+const PHRASE_4_BONUS: f64 = 0.25;
+const PHRASE_8_BONUS: f64 = 0.5;
+/// 少于该小节数不分段
+const MIN_ANALYZED_BARS: usize = 8;
+const ENERGY_SCALE_FLOOR_DB: f64 = 1.0;
+/// 证据不足时退回标签的置信度
+const FALLBACK_LABEL_CONFIDENCE: f64 = 0.3;
+const READ_CHUNK_FRAMES: usize = 65_536;
+
+/// 结构分析选项（节拍网格必填）；只支持恒定 BPM，分段网格需由调用方取主段 BPM 与首拍
+#[napi(object)]
+pub struct SongStructureOptions {
+  pub bpm: f64,
+  pub first_beat_ms: f64,
+  /// 首拍之后第几拍是小节首拍（0..3），同网格输入
+  pub downbeat_beat_offset: Option<f64>,
+  /// 每小节拍数，默认 4
+  pub beats_per_bar: Option<u32>,
+}
+
+/// 结构段落
+#[napi(object)]
+pub struct SongStructurePhrase {
+  pub index: u32,
+  /// intro / build / drop / breakdown / outro
+  pub label: String,
+  /// PSSI 高情绪 phrase kind：1 Intro / 2 Up / 3 Down / 5 Chorus / 6 Outro
+  pub pssi_kind: u32,
+  pub start_sec: f64,
+  pub end_sec: f64,
+  /// 起始小节序号（0 为首个小节首拍所在小节）
+  pub start_bar: i32,
+  pub bar_count: u32,
+  /// 起始拍号（从 1 开始，同 PSSI beat）
+  pub start_beat: u32,
+  /// 结束拍号（不含）
+  pub end_beat: u32,
+  /// 标签置信度 0..1
+  pub confidence: f64,
+  /// 起始边界置信度 0..1（首段为 1）
+  pub boundary_confidence: f64,
+}
+
+/// 结构分析结果
+#[napi(object)]
+pub struct SongStructureResult {
+  pub file_path: Option<String>,
+  /// PSSI mood，目前固定为 high
+  pub mood: String,
+  pub bar_count: u32,
+  pub phrases: Vec<SongStructurePhrase>,
+  /// 每小节起点的边界 novelty（稳健 z 值）
+  pub novelty: Vec<f64>,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BarGrid {
+  beat_sec: f64,
+  first_beat_sec: f64,
+  beats_per_bar: u32,
+  /// 序号 0 小节的起点（可能早于 0 秒）
+  origin_sec: f64,
+  /// 序号 0 小节前还有多少个（部分）小节
+  leading_bars: i32,
+}
+
+impl BarGrid {
+  fn from_options(options: &SongStructureOptions) -> Option<Self> {
+    if !options.bpm.is_finite() || options.bpm <= 0.0 || !options.first_beat_ms.is_finite() {
+      return None;
+    }
+    let beat_sec = 60.0 / options.bpm;
+    let beats_per_bar = options
+      .beats_per_bar
+      .filter(|value| *value > 0)
+      .unwrap_or(DEFAULT_BEATS_PER_BAR);
+    let first_beat_sec = options.first_beat_ms / 1000.0;
+    let downbeat_offset = options
+      .downbeat_beat_offset
+      .filter(|value| value.is_finite())
+      .map(|value| value.round().rem_euclid(beats_per_bar as f64))
+      .unwrap_or(0.0);
+    let bar_sec = beat_sec * beats_per_bar as f64;
+    let origin_sec = first_beat_sec + downbeat_offset * beat_sec;
+    Some(Self {
+      beat_sec,
+      first_beat_sec,
+      beats_per_bar,
+      origin_sec,
+      leading_bars: (origin_sec / bar_sec).ceil().max(0.0) as i32,
+    })
+  }
+
+  fn bar_sec(&self) -> f64 {
+    self.beat_sec * self.beats_per_bar as f64
+  }
+
+  /// 第 slot 个分析小节（含前导部分小节）的起点
+  fn slot_start_sec(&self, slot: usize) -> f64 {
+    self.origin_sec + (slot as i32 - self.leading_bars) as f64 * self.bar_sec()
+  }
+
+  fn slot_for_sec(&self, sec: f64) -> Option<usize> {
+    let slot = ((sec - self.origin_sec) / self.bar_sec()).floor() as i64 + self.leading_bars as i64;
+    usize::try_from(slot).ok()
+  }
+
+  /// PSSI 拍号：首拍为 1
+  fn beat_number_at(&self, sec: f64) -> u32 {
+    (((sec - self.first_beat_sec) / self.beat_sec).round() + 1.0).max(1.0) as u32
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+struct BarAccumulator {
+  frames: u32,
+  power_all: f64,
+  power_low: f64,
+  power_mid: f64,
+  power_high: f64,
+  flux: f64,
+  chroma: [f64; 12],
+}
+
+/// 单小节特征（能量为 dB）
+#[derive(Clone, Debug, PartialEq)]
+struct BarFeatures {
+  energy: f64,
+  low: f64,
+  mid: f64,
+  high: f64,
+  flux: f64,
+  chroma: [f64; 12],
+}
+
+/// 增量分析器：喂入交错 f32 PCM，按网格累积小节特征
+pub(crate) struct StructureAnalyzer {
+  sample_rate: u32,
+  channels: usize,
+  grid: BarGrid,
+  window: Vec<f64>,
+  pending: Vec<f64>,
+  consumed_frames: u64,
+  previous_log_magnitude: Vec<f64>,
+  chroma_bins: Vec<Option<usize>>,
+  bars: Vec<BarAccumulator>,
+  duration_sec: f64,
+}
+
+fn power_to_db(power: f64) -> f64 {
+  10.0 * power.max(1e-12).log10()
+}
+
+fn median(values: &[f64]) -> f64 {
+  if values.is_empty() {
+    return 0.0;
+  }
+  let mut sorted = values.to_vec();
+  sorted.sort_by(|a, b| a.total_cmp(b));
+  let mid = sorted.len() / 2;
+  if sorted.len().is_multiple_of(2) {
+    (sorted[mid - 1] + sorted[mid]) / 2.0
+  } else {
+    sorted[mid]
+  }
+}
+
+/// 稳健标准化：(x - median) / (1.4826 × MAD + floor)，截断到 ±4
+fn robust_z(values: &[f64], scale_floor: f64) -> Vec<f64> {
+  let center = median(values);
+  let deviations: Vec<f64> = values.iter().map(|value| (value - center).abs()).collect();
+  let scale = 1.4826 * median(&deviations) + scale_floor;
+  values
+    .iter()
+    .map(|value| ((value - center) / scale).clamp(-4.0, 4.0))
+    .collect()
+}
+
+fn cosine(a: &[f64], b: &[f64]) -> f64 {
+  let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+  let norm = (a.iter().map(|x| x * x).sum::<f64>() * b.iter().map(|y| y * y).sum::<f64>()).sqrt();
+  if norm > 0.0 {
+    dot / norm
+  } else {
+    0.0
+  }
+}
+
+fn mean(values: &[f64]) -> f64 {
+  if values.is_empty() {
+    return 0.0;
+  }
+  values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// 最小二乘斜率（每小节）
+fn slope(values: &[f64]) -> f64 {
+  let n = values.len() as f64;
+  if values.len() < 2 {
+    return 0.0;
+  }
+  let x_mean = (n - 1.0) / 2.0;
+  let y_mean = mean(values);
+  let (num, den) = values
+    .iter()
+    .enumerate()
+    .fold((0.0, 0.0), |(num, den), (index, value)| {
+      let dx = index as f64 - x_mean;
+      (num + dx * (value - y_mean), den + dx * dx)
+    });
+  num / den
+}
+
+impl StructureAnalyzer {
+  pub(crate) fn new(
+    sample_rate: u32,
+    channels: u16,
+    options: &SongStructureOptions,
+  ) -> Option<Self> {
+    let grid = BarGrid::from_options(options)?;
+    let bin_hz = sample_rate as f64 / FFT_SIZE as f64;
+    let chroma_bins = (0..=FFT_SIZE / 2)
+      .map(|bin| {
+        let hz = bin as f64 * bin_hz;
+        (CHROMA_MIN_HZ..=CHROMA_MAX_HZ).contains(&hz).then(|| {
+          let midi = 69.0 + 12.0 * (hz / 440.0).log2();
+          (midi.round() as i64).rem_euclid(12) as usize
+        })
+      })
+      .collect();
+    Some(Self {
+      sample_rate,
+      channels: channels.max(1) as usize,
+      grid,
+      window: hann_window(FFT_SIZE),
+      pending: Vec::with_capacity(FFT_SIZE),
+      consumed_frames: 0,
+      previous_log_magnitude: vec![0.0; FFT_SIZE / 2 + 1],
+      chroma_bins,
+      bars: Vec::new(),
+      duration_sec: 0.0,
+    })
+  }
+
+  pub(crate) fn push(&mut self, interleaved: &[f32]) {
+    let channels = self.channels;
+    for frame in interleaved.chunks_exact(channels) {
+      self
+        .pending
+        .push(frame.iter().map(|sample| *sample as f64).sum::<f64>() / channels as f64);
+      if self.pending.len() == FFT_SIZE {
+        self.analyze_frame();
+        self.pending.drain(..HOP_SIZE);
+        self.consumed_frames += HOP_SIZE as u64;
+      }
+    }
+    self.duration_sec += (interleaved.len() / channels) as f64 / self.sample_rate as f64;
+  }
+
+  fn analyze_frame(&mut self) {
+    let center_sec =
+      (self.consumed_frames as f64 + FFT_SIZE as f64 / 2.0) / self.sample_rate as f64;
+    let Some(slot) = self.grid.slot_for_sec(center_sec) else {
+      return;
+    };
+    let power = power_spectrum(&self.pending, &self.window);
+    if self.bars.len() <= slot {
+      self.bars.resize(slot + 1, BarAccumulator::default());
+    }
+    let bin_hz = self.sample_rate as f64 / FFT_SIZE as f64;
+    let bar = &mut self.bars[slot];
+    let mut flux = 0.0;
+    for (bin, value) in power.iter().enumerate() {
+      let hz = bin as f64 * bin_hz;
+      bar.power_all += value;
+      match hz {
+        hz if hz < LOW_BAND_MAX_HZ => bar.power_low += value,
+        hz if hz < MID_BAND_MAX_HZ => bar.power_mid += value,
+        _ => bar.power_high += value,
+      }
+      if let Some(pitch_class) = self.chroma_bins[bin] {
+        bar.chroma[pitch_class] += value;
+      }
+      let log_magnitude = (1.0 + 1000.0 * value.sqrt()).ln();
+      flux += (log_magnitude - self.previous_log_magnitude[bin]).max(0.0);
+      self.previous_log_magnitude[bin] = log_magnitude;
+    }
+    bar.flux += flux;
+    bar.frames += 1;
+  }
+
+  fn bar_features(&self) -> Vec<BarFeatures> {
+    self
+      .bars
+      .iter()
+      .map(|bar| {
+        let frames = bar.frames.max(1) as f64;
+        let chroma_sum: f64 = bar.chroma.iter().sum();
+        let mut chroma = [0.0; 12];
+        if chroma_sum > 0.0 {
+          for (slot, value) in chroma.iter_mut().zip(bar.chroma.iter()) {
+            *slot = value / chroma_sum;
+          }
+        }
+        BarFeatures {
+          energy: power_to_db(bar.power_all / frames),
+          low: power_to_db(bar.power_low / frames),
+          mid: power_to_db(bar.power_mid / frames),
+          high: power_to_db(bar.power_high / frames),
+          flux: bar.flux / frames,
+          chroma,
+        }
+      })
+      .collect()
+  }
+
+  pub(crate) fn finish(self) -> StructureReport {
+    let bar_sec = self.grid.bar_sec();
+    // 丢弃尾部不足半小节的残片，避免噪声边界
+    let usable_bars = (0..self.bars.len())
+      .take_while(|slot| {
+        let start = self.grid.slot_start_sec(*slot).max(0.0);
+        self.duration_sec - start >= bar_sec * 0.5 || *slot == 0
+      })
+      .count();
+    let mut features = self.bar_features();
+    features.truncate(usable_bars);
+    let segmentation = segment_bars(&features);
+    let end_sec = self.duration_sec;
+    let phrases = segmentation
+      .segments
+      .iter()
+      .enumerate()
+      .map(|(index, segment)| {
+        let start_sec = if index == 0 {
+          0.0
+        } else {
+          self.grid.slot_start_sec(segment.start).max(0.0)
+        };
+        let segment_end_sec = if segment.end >= features.len() {
+          end_sec
+        } else {
+          self.grid.slot_start_sec(segment.end).min(end_sec)
+        };
+        PhraseReport {
+          label: segment.label,
+          start_sec,
+          end_sec: segment_end_sec,
+          start_bar: segment.start as i32 - self.grid.leading_bars,
+          bar_count: segment.end - segment.start,
+          start_beat: self.grid.beat_number_at(start_sec),
+          end_beat: self.grid.beat_number_at(segment_end_sec),
+          confidence: segment.confidence,
+          boundary_confidence: segment.boundary_confidence,
+        }
+      })
+      .collect();
+    StructureReport {
+      bar_count: features.len(),
+      phrases,
+      novelty: segmentation.novelty,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PhraseLabel {
+  Intro,
+  Build,
+  Drop,
+  Breakdown,
+  Outro,
+}
+
+impl PhraseLabel {
+  fn as_str(self) -> &'static str {
+    match self {
+      PhraseLabel::Intro => "intro",
+      PhraseLabel::Build => "build",
+      PhraseLabel::Drop => "drop",
+      PhraseLabel::Breakdown => "breakdown",
+      PhraseLabel::Outro => "outro",
+    }
+  }
+
+  fn pssi_kind(self) -> u32 {
+    match self {
+      PhraseLabel::Intro => 1,
+      PhraseLabel::Build => 2,
+      PhraseLabel::Breakdown => 3,
+      PhraseLabel::Drop => 5,
+      PhraseLabel::Outro => 6,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+  start: usize,
+  end: usize,
+  label: PhraseLabel,
+  confidence: f64,
+  boundary_confidence: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segmentation {
+  segments: Vec<Segment>,
+  novelty: Vec<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PhraseReport {
+  label: PhraseLabel,
+  start_sec: f64,
+  end_sec: f64,
+  start_bar: i32,
+  bar_count: usize,
+  start_beat: u32,
+  end_beat: u32,
+  confidence: f64,
+  boundary_confidence: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StructureReport {
+  bar_count: usize,
+  phrases: Vec<PhraseReport>,
+  novelty: Vec<f64>,
+}
+
+/// 带高斯衰减的棋盘核 Foote novelty；边界 b 位于第 b-1 与第 b 小节之间
+fn foote_novelty(similarity: &[Vec<f64>], half_width: usize) -> Vec<f64> {
+  let count = similarity.len();
+  let sigma = half_width as f64 / 2.0;
+  (0..count)
+    .map(|boundary| {
+      let mut score = 0.0;
+      let mut weight_sum = 0.0;
+      let first = boundary.saturating_sub(half_width);
+      let window = (boundary + half_width).min(count) - first;
+      for (i, row) in similarity.iter().enumerate().skip(first).take(window) {
+        for (j, value) in row.iter().enumerate().skip(first).take(window) {
+          let di = i as f64 - boundary as f64 + 0.5;
+          let dj = j as f64 - boundary as f64 + 0.5;
+          let weight = (-(di * di + dj * dj) / (2.0 * sigma * sigma)).exp();
+          let sign = if (i < boundary) == (j < boundary) {
+            1.0
+          } else {
+            -1.0
+          };
+          score += sign * weight * value;
+          weight_sum += weight;
+        }
+      }
+      if weight_sum > 0.0 {
+        score / weight_sum
+      } else {
+        0.0
+      }
+    })
+    .collect()
+}
+
+fn segment_bars(features: &[BarFeatures]) -> Segmentation {
+  let count = features.len();
+  if count < MIN_ANALYZED_BARS {
+    return Segmentation {
+      segments: if count == 0 {
+        Vec::new()
+      } else {
+        vec![Segment {
+          start: 0,
+          end: count,
+          label: PhraseLabel::Drop,
+          confidence: FALLBACK_LABEL_CONFIDENCE,
+          boundary_confidence: 1.0,
+        }]
+      },
+      novelty: vec![0.0; count],
+    };
+  }
+
+  let energy = robust_z(
+    &features.iter().map(|bar| bar.energy).collect::<Vec<_>>(),
+    ENERGY_SCALE_FLOOR_DB,
+  );
+  let low = robust_z(
+    &features.iter().map(|bar| bar.low).collect::<Vec<_>>(),
+    ENERGY_SCALE_FLOOR_DB,
+  );
+  let mid = robust_z(
+    &features.iter().map(|bar| bar.mid).collect::<Vec<_>>(),
+    ENERGY_SCALE_FLOOR_DB,
+  );
+  let high = robust_z(
+    &features.iter().map(|bar| bar.high).collect::<Vec<_>>(),
+    ENERGY_SCALE_FLOOR_DB,
+  );
+  let flux_values: Vec<f64> = features.iter().map(|bar| bar.flux).collect();
+  let flux = robust_z(&flux_values, median(&flux_values).abs() * 0.05 + 1e-9);
+
+  // 能量/音色视图与色度视图分别求相似度再融合，避免低频支配距离
+  let timbre: Vec<[f64; 5]> = (0..count)
+    .map(|bar| [energy[bar], low[bar], mid[bar], high[bar], flux[bar]])
+    .collect();
+  let similarity: Vec<Vec<f64>> = (0..count)
+    .map(|i| {
+      (0..count)
+        .map(|j| {
+          let timbre_distance = timbre[i]
+            .iter()
+            .zip(timbre[j].iter())
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f64>()
+            .sqrt();
+          0.5 * (-timbre_distance / 2.0).exp()
+            + 0.5 * cosine(&features[i].chroma, &features[j].chroma)
+        })
+        .collect()
+    })
+    .collect();
+  let novelty = robust_z(&foote_novelty(&similarity, NOVELTY_HALF_WIDTH_BARS), 1e-3);
+
+  // 候选：局部峰值 + phrase 对齐加分，按得分贪心并保证最短段长
+  let mut candidates: Vec<(usize, f64)> = (MIN_SEGMENT_BARS..=count - MIN_SEGMENT_BARS)
+    .filter(|bar| {
+      let lo = bar.saturating_sub(2);
+      let hi = (bar + 2).min(count - 1);
+      (lo..=hi).all(|other| novelty[other] <= novelty[*bar])
+    })
+    .map(|bar| {
+      let prior = if bar % 8 == 0 {
+        PHRASE_8_BONUS
+      } else if bar % 4 == 0 {
+        PHRASE_4_BONUS
+      } else {
+        0.0
+      };
+      (bar, novelty[bar] + prior)
+    })
+    .filter(|(_, score)| *score >= BOUNDARY_MIN_SCORE)
+    .collect();
+  candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+  let mut boundaries: Vec<(usize, f64)> = vec![(0, f64::INFINITY), (count, f64::INFINITY)];
+  for (bar, score) in candidates {
+    if boundaries
+      .iter()
+      .all(|(existing, _)| existing.abs_diff(bar) >= MIN_SEGMENT_BARS)
+    {
+      boundaries.push((bar, score));
+    }
+  }
+  boundaries.sort_by_key(|(bar, _)| *bar);
+
+  let stats: Vec<(f64, f64, f64)> = boundaries
+    .windows(2)
+    .map(|pair| {
+      let range = pair[0].0..pair[1].0;
+      let tension: Vec<f64> = range
+        .clone()
+        .map(|bar| (energy[bar] + mid[bar] + high[bar]) / 3.0)
+        .collect();
+      (
+        mean(&energy[range.clone()]),
+        mean(&low[range]),
+        slope(&tension),
+      )
+    })
+    .collect();
+  let labels = label_segments(&stats);
+  let segments = boundaries
+    .windows(2)
+    .zip(labels)
+    .map(|(pair, (label, confidence))| Segment {
+      start: pair[0].0,
+      end: pair[1].0,
+      label,
+      confidence,
+      boundary_confidence: if pair[0].1.is_finite() {
+        (0.3 + 0.7 * (pair[0].1 / 3.0)).clamp(0.0, 1.0)
+      } else {
+        1.0
+      },
+    })
+    .collect();
+  Segmentation { segments, novelty }
+}
+
+fn margin_confidence(margin: f64) -> f64 {
+  (0.5 + 0.45 * margin.max(0.0).tanh()).clamp(0.0, 1.0)
+}
+
+/// 证据不足时按能量高低归入 drop / breakdown 的低置信度标签
+fn fallback_label(energy: f64) -> (PhraseLabel, f64) {
+  if energy >= 0.0 {
+    (PhraseLabel::Drop, FALLBACK_LABEL_CONFIDENCE)
+  } else {
+    (PhraseLabel::Breakdown, FALLBACK_LABEL_CONFIDENCE)
+  }
+}
+
+/// 按段统计 (能量 z, 低频 z, 张力斜率) 解码标签；drop 精度优先，证据不足时退回 fallback_label
+fn label_segments(stats: &[(f64, f64, f64)]) -> Vec<(PhraseLabel, f64)> {
+  let count = stats.len();
+  let mut labels: Vec<(PhraseLabel, f64)> = Vec::with_capacity(count);
+  for (index, &(energy, low, tension_slope)) in stats.iter().enumerate() {
+    let others_energy = if count > 1 {
+      (stats.iter().map(|stat| stat.0).sum::<f64>() - energy) / (count - 1) as f64
+    } else {
+      energy
+    };
+    let previous = index
+      .checked_sub(1)
+      .map(|prev| (stats[prev], labels[prev].0));
+    let next_energy = stats.get(index + 1).map(|stat| stat.0);
+
+    let label = if count > 1 && index == 0 && energy < others_energy - 0.3 {
+      (
+        PhraseLabel::Intro,
+        margin_confidence(others_energy - energy - 0.3),
+      )
+    } else if count > 1 && index == count - 1 && energy < others_energy - 0.3 {
+      (
+        PhraseLabel::Outro,
+        margin_confidence(others_energy - energy - 0.3),
+      )
+    } else if low < -0.8 && energy < 0.0 {
+      (PhraseLabel::Breakdown, margin_confidence(-low - 0.8))
+    } else if tension_slope > 0.1 && next_energy.is_some_and(|next| next > energy + 0.3) {
+      (PhraseLabel::Build, margin_confidence(tension_slope * 5.0))
+    } else if let Some(((prev_energy, _, _), prev_label)) = previous {
+      let contrast = energy - prev_energy;
+      let required = if prev_label == PhraseLabel::Build {
+        0.2
+      } else {
+        0.5
+      };
+      if energy > 0.3 && low > 0.0 && contrast >= required {
+        (
+          PhraseLabel::Drop,
+          margin_confidence(contrast - required + low),
+        )
+      } else {
+        fallback_label(energy)
+      }
+    } else {
+      fallback_label(energy)
+    };
+    labels.push(label);
+  }
+  labels
+}
+
+fn to_result(file_path: Option<String>, report: StructureReport) -> SongStructureResult {
+  SongStructureResult {
+    file_path,
+    mood: "high".to_string(),
+    bar_count: report.bar_count as u32,
+    phrases: report
+      .phrases
+      .into_iter()
+      .enumerate()
+      .map(|(index, phrase)| SongStructurePhrase {
+        index: index as u32,
+        label: phrase.label.as_str().to_string(),
+        pssi_kind: phrase.label.pssi_kind(),
+        start_sec: phrase.start_sec,
+        end_sec: phrase.end_sec,
+        start_bar: phrase.start_bar,
+        bar_count: phrase.bar_count as u32,
+        start_beat: phrase.start_beat,
+        end_beat: phrase.end_beat,
+        confidence: phrase.confidence,
+        boundary_confidence: phrase.boundary_confidence,
+      })
+      .collect(),
+    novelty: report.novelty,
+    error: None,
+  }
+}
+
+impl AnalysisErrorResult for SongStructureResult {
+  fn error_result(file_path: Option<String>, error: String) -> Self {
+    SongStructureResult {
+      file_path,
+      mood: "high".to_string(),
+      bar_count: 0,
+      phrases: Vec::new(),
+      novelty: Vec::new(),
+      error: Some(error),
+    }
+  }
+}
+
+const INVALID_GRID_ERROR: &str = "invalid beat grid: bpm must be positive and firstBeatMs finite";
+
+fn new_analyzer(
+  sample_rate: u32,
+  channels: u16,
+  options: &SongStructureOptions,
+) -> Result<StructureAnalyzer, String> {
+  StructureAnalyzer::new(sample_rate, channels, options)
+    .ok_or_else(|| INVALID_GRID_ERROR.to_string())
+}
+
+/// 解码整轨做结构分段
+#[napi]
+pub fn analyze_song_structure(
+  file_path: String,
+  options: SongStructureOptions,
+) -> SongStructureResult {
+  analyze_file_in_chunks(
+    file_path,
+    READ_CHUNK_FRAMES,
+    |sample_rate, channels| new_analyzer(sample_rate, channels, &options),
+    StructureAnalyzer::push,
+    |analyzer, file_path, _, _| to_result(Some(file_path), analyzer.finish()),
+  )
+}
+
+/// 对已解码的交错 f32 PCM 做结构分段
+#[napi]
+pub fn analyze_song_structure_from_pcm(
+  pcm_data: Buffer,
+  sample_rate: u32,
+  channels: u8,
+  options: SongStructureOptions,
+) -> SongStructureResult {
+  analyze_pcm_buffer(
+    &pcm_data,
+    sample_rate,
+    channels,
+    |samples| match new_analyzer(sample_rate, channels as u16, &options) {
+      Ok(mut analyzer) => {
+        analyzer.push(samples);
+        to_result(None, analyzer.finish())
+      }
+      Err(error) => SongStructureResult::error_result(None, error),
+    },
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RATE: u32 = 8_000;
+
+  fn grid_options(first_beat_ms: f64, downbeat_beat_offset: Option<f64>) -> SongStructureOptions {
+    SongStructureOptions {
+      bpm: 120.0,
+      first_beat_ms,
+      downbeat_beat_offset,
+      beats_per_bar: None,
+    }
+  }
+
+  /// 120 BPM 下每小节 2 秒；sections 为 (小节数, 低频幅度, 中频幅度, 高频噪声幅度)
+  fn synth_track(sections: &[(usize, f32, f32, f32)]) -> Vec<f32> {
+    let mut seed = 0x2545_f491_u32;
+    let mut pcm = Vec::new();
+    for &(bars, low, mid, high) in sections {
+      for _ in 0..bars * 2 * RATE as usize {
+        let t = pcm.len() as f32 / RATE as f32;
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let noise = (seed as f32 / u32::MAX as f32) * 2.0 - 1.0;
+        pcm.push(
+          low * (std::f32::consts::TAU * 55.0 * t).sin()
+            + mid * (std::f32::consts::TAU * 440.0 * t).sin()
+            + high * noise,
+        );
+      }
+    }
+    pcm
+  }
+
+  #[test]
+  fn segments_synthetic_track_on_phrase_boundaries() {
+    let pcm = synth_track(&[
+      (16, 0.0, 0.05, 0.05),
+      (16, 0.5, 0.2, 0.1),
+      (8, 0.0, 0.1, 0.02),
+      (16, 0.5, 0.2, 0.1),
+      (8, 0.0, 0.05, 0.05),
+    ]);
+    let mut analyzer = StructureAnalyzer::new(RATE, 1, &grid_options(0.0, None)).unwrap();
+    analyzer.push(&pcm);
+    let report = analyzer.finish();
+
+    assert_eq!(report.bar_count, 64);
+    let starts: Vec<i32> = report
+      .phrases
+      .iter()
+      .map(|phrase| phrase.start_bar)
+      .collect();
+    assert_eq!(starts, vec![0, 16, 32, 40, 56]);
+    let labels: Vec<&str> = report
+      .phrases
+      .iter()
+      .map(|phrase| phrase.label.as_str())
+      .collect();
+    assert_eq!(labels, vec!["intro", "drop", "breakdown", "drop", "outro"]);
+    assert_eq!(report.phrases[1].start_beat, 65);
+    assert_eq!(report.phrases[1].end_beat, 129);
+    assert_eq!(report.phrases[1].label.pssi_kind(), 5);
+    assert!(report
+      .phrases
+      .iter()
+      .all(|phrase| (0.0..=1.0).contains(&phrase.confidence)));
+  }
+
+  #[test]
+  fn bar_grid_respects_downbeat_offset_and_leading_bars() {
+    // 首拍 0.5 秒且为小节第 2 拍：小节首拍在 0.5 + 3 × 0.5 = 2.0 秒
+    let grid = BarGrid::from_options(&grid_options(500.0, Some(3.0))).unwrap();
+    assert!((grid.origin_sec - 2.0).abs() < 1e-9);
+    assert_eq!(grid.leading_bars, 1);
+    assert_eq!(grid.slot_for_sec(0.1), Some(0));
+    assert_eq!(grid.slot_for_sec(2.1), Some(1));
+    assert!((grid.slot_start_sec(1) - 2.0).abs() < 1e-9);
+    assert_eq!(grid.beat_number_at(2.0), 4);
+    assert!(BarGrid::from_options(&SongStructureOptions {
+      bpm: 0.0,
+      first_beat_ms: 0.0,
+      downbeat_beat_offset: None,
+      beats_per_bar: None,
+    })
+    .is_none());
+  }
+}
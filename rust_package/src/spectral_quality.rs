@@ -0,0 +1,581 @@
+//! 频谱质量分析：由长时平均频谱检测有效带宽截止（16/19/20 kHz 等陡峭“架子”），
+//! 推断原始有损码率档位，并识别假无损（有损转无损）与升采样文件
+
+use std::path::Path;
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+use crate::analysis_utils::{
+  analyze_file_in_chunks, analyze_pcm_buffer, hann_window, power_spectrum, AnalysisErrorResult,
+};
+
+const FFT_SIZE: usize = 4096;
+/// 低于该 RMS 的帧视为静音，不参与统计（约 -80 dBFS）
+const SILENCE_RMS: f64 = 1e-4;
+/// dB 下限，避免 log(0)
+const FLOOR_DB: f64 = -140.0;
+/// 只在 10 kHz 以上搜索截止架子
+const SHELF_SEARCH_MIN_HZ: f64 = 10_000.0;
+/// 架子两侧的比较窗口宽度
+const SHELF_WINDOW_HZ: f64 = 400.0;
+/// 架子两侧平均电平差至少达到该值才判定为硬截止
+const SHELF_MIN_DROP_DB: f64 = 18.0;
+/// 架子落差达到该值时落差置信度为 1
+const SHELF_FULL_CONFIDENCE_DROP_DB: f64 = 48.0;
+/// 单帧内容判定：低于帧峰值 70 dB 或低于 -100 dBFS 视为无内容
+const FRAME_CONTENT_RANGE_DB: f64 = 70.0;
+const FRAME_CONTENT_MIN_DB: f64 = -100.0;
+/// 单帧截止高于全局架子超过该值，视为架子之上仍有内容
+const FRAME_CUTOFF_TOLERANCE_HZ: f64 = 300.0;
+/// 截止不低于该频率时视为全频带（44.1k 母带的抗混叠滤波通常在 21 kHz 以上）
+const FULL_BAND_MIN_HZ: f64 = 21_000.0;
+/// 高采样率文件的架子落在该区间内，说明源自 44.1/48 kHz
+const UPSAMPLED_SHELF_RANGE_HZ: (f64, f64) = (19_500.0, 24_500.0);
+const UPSAMPLED_MIN_SAMPLE_RATE: u32 = 88_200;
+/// 参考电平频段
+const REFERENCE_BAND_HZ: (f64, f64) = (1_000.0, 5_000.0);
+/// 参考电平过低（几乎静音）时不下结论
+const MIN_REFERENCE_DB: f64 = -90.0;
+/// 至少需要的有效分析帧数
+const MIN_ANALYZED_FRAMES: usize = 8;
+const SPECTROGRAM_WIDTH: usize = 512;
+const SPECTROGRAM_HEIGHT: usize = 128;
+/// 频谱图灰度映射的 dB 范围
+const SPECTROGRAM_MIN_DB: f64 = -120.0;
+const SPECTRUM_POINTS: usize = 256;
+/// 无损容器扩展名（m4a 可能是 ALAC 也可能是 AAC，不计入）
+const LOSSLESS_EXTENSIONS: [&str; 8] = ["flac", "wav", "aif", "aiff", "wv", "ape", "tta", "tak"];
+/// 哈希流程中每隔几个窗口分析一次，控制额外开销
+pub(crate) const HASH_ANALYSIS_STRIDE: usize = 4;
+
+/// 频谱质量分析结果
+#[napi(object)]
+pub struct SpectralQualityResult {
+  pub file_path: Option<String>,
+  /// lossless / fake-lossless / upsampled / full-band / 320k / 256k / 192k / 128k / sub-128k / unknown
+  pub quality_label: String,
+  /// 推断的原始码率档位（full-band / 320k / 256k / 192k / 128k / sub-128k / unknown）
+  pub bitrate_class: String,
+  /// 有效带宽截止（Hz）
+  pub cutoff_hz: f64,
+  /// 是否检测到有损编码特有的硬截止
+  pub shelf_detected: bool,
+  /// 截止两侧的平均电平差（dB）
+  pub shelf_drop_db: f64,
+  /// 无损容器但频谱来自有损编码
+  pub fake_lossless: bool,
+  /// 高采样率文件但频谱止于 44.1/48 kHz 的带宽
+  pub upsampled: bool,
+  /// 0..1
+  pub confidence: f64,
+  pub sample_rate: u32,
+  pub analyzed_frames: u32,
+  /// 长时平均频谱（dBFS，0..奈奎斯特等分）
+  pub spectrum_db: Vec<f64>,
+  /// 频谱图灰度图（u8，按行存储，首行为最高频）
+  pub spectrogram: Buffer,
+  pub spectrogram_width: u32,
+  pub spectrogram_height: u32,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SpectralReport {
+  pub(crate) quality_label: &'static str,
+  pub(crate) bitrate_class: &'static str,
+  pub(crate) cutoff_hz: f64,
+  pub(crate) shelf_detected: bool,
+  pub(crate) shelf_drop_db: f64,
+  pub(crate) fake_lossless: bool,
+  pub(crate) upsampled: bool,
+  pub(crate) confidence: f64,
+  pub(crate) analyzed_frames: usize,
+  pub(crate) spectrum_db: Vec<f64>,
+  pub(crate) spectrogram: Vec<u8>,
+  pub(crate) spectrogram_width: usize,
+}
+
+/// 增量频谱分析器：逐块喂入交错 PCM，结束时给出判定
+pub(crate) struct SpectralAnalyzer {
+  sample_rate: u32,
+  channels: usize,
+  stride: usize,
+  keep_spectrogram: bool,
+  window: Vec<f64>,
+  power_scale: f64,
+  pending: Vec<f64>,
+  window_index: usize,
+  ltas_sum: Vec<f64>,
+  frame_cutoff_bins: Vec<usize>,
+  spectrogram_columns: Vec<[f32; SPECTROGRAM_HEIGHT]>,
+}
+
+fn power_to_db(power: f64) -> f64 {
+  if power > 0.0 {
+    (10.0 * power.log10()).max(FLOOR_DB)
+  } else {
+    FLOOR_DB
+  }
+}
+
+/// 把频点功率按线性频率等分为 bands 段并取平均
+fn reduce_bands(power: &[f64], bands: usize) -> Vec<f64> {
+  let bins = power.len();
+  (0..bands)
+    .map(|band| {
+      let start = band * bins / bands;
+      let end = ((band + 1) * bins / bands).max(start + 1).min(bins);
+      power[start..end].iter().sum::<f64>() / (end - start) as f64
+    })
+    .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+  if values.is_empty() {
+    return FLOOR_DB;
+  }
+  values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn bitrate_class_for_cutoff(cutoff_hz: f64) -> &'static str {
+  match cutoff_hz {
+    hz if hz < 15_000.0 => "sub-128k",
+    hz if hz < 16_500.0 => "128k",
+    hz if hz < 18_500.0 => "192k",
+    hz if hz < 19_800.0 => "256k",
+    hz if hz < FULL_BAND_MIN_HZ => "320k",
+    _ => "full-band",
+  }
+}
+
+pub(crate) fn is_lossless_container(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(|ext| LOSSLESS_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+    .unwrap_or(false)
+}
+
+impl SpectralAnalyzer {
+  pub(crate) fn new(
+    sample_rate: u32,
+    channels: u16,
+    stride: usize,
+    keep_spectrogram: bool,
+  ) -> Self {
+    let window = hann_window(FFT_SIZE);
+    let window_sum: f64 = window.iter().sum();
+    Self {
+      sample_rate,
+      channels: channels.max(1) as usize,
+      stride: stride.max(1),
+      keep_spectrogram,
+      // 满幅正弦在对应频点约为 0 dBFS
+      power_scale: 4.0 / (window_sum * window_sum),
+      window,
+      pending: Vec::with_capacity(FFT_SIZE),
+      window_index: 0,
+      ltas_sum: vec![0.0; FFT_SIZE / 2 + 1],
+      frame_cutoff_bins: Vec::new(),
+      spectrogram_columns: Vec::new(),
+    }
+  }
+
+  pub(crate) fn push_f32(&mut self, interleaved: &[f32]) {
+    let channels = self.channels;
+    for frame in interleaved.chunks_exact(channels) {
+      let mono = frame.iter().map(|sample| *sample as f64).sum::<f64>() / channels as f64;
+      self.push_mono(mono);
+    }
+  }
+
+  pub(crate) fn push_i16(&mut self, interleaved: &[i16]) {
+    let channels = self.channels;
+    for frame in interleaved.chunks_exact(channels) {
+      let mono = frame.iter().map(|sample| *sample as f64).sum::<f64>() / channels as f64;
+      self.push_mono(mono / 32768.0);
+    }
+  }
+
+  fn push_mono(&mut self, sample: f64) {
+    self.pending.push(sample);
+    if self.pending.len() < FFT_SIZE {
+      return;
+    }
+    if self.window_index.is_multiple_of(self.stride) {
+      let frame = std::mem::take(&mut self.pending);
+      self.analyze_frame(&frame);
+      self.pending = frame;
+    }
+    self.pending.clear();
+    self.window_index += 1;
+  }
+
+  fn analyze_frame(&mut self, frame: &[f64]) {
+    let rms = (frame.iter().map(|sample| sample * sample).sum::<f64>() / frame.len() as f64).sqrt();
+    if rms < SILENCE_RMS {
+      return;
+    }
+    let power: Vec<f64> = power_spectrum(frame, &self.window)
+      .into_iter()
+      .map(|value| value * self.power_scale)
+      .collect();
+    for (sum, value) in self.ltas_sum.iter_mut().zip(power.iter()) {
+      *sum += value;
+    }
+
+    let peak_db = power_to_db(power.iter().copied().fold(0.0, f64::max));
+    let content_threshold_db = (peak_db - FRAME_CONTENT_RANGE_DB).max(FRAME_CONTENT_MIN_DB);
+    let cutoff_bin = power
+      .iter()
+      .rposition(|value| power_to_db(*value) > content_threshold_db)
+      .unwrap_or(0);
+    self.frame_cutoff_bins.push(cutoff_bin);
+
+    if self.keep_spectrogram {
+      let mut column = [0.0f32; SPECTROGRAM_HEIGHT];
+      for (slot, band) in column
+        .iter_mut()
+        .zip(reduce_bands(&power, SPECTROGRAM_HEIGHT))
+      {
+        *slot = power_to_db(band) as f32;
+      }
+      self.spectrogram_columns.push(column);
+    }
+  }
+
+  fn bin_hz(&self) -> f64 {
+    self.sample_rate as f64 / FFT_SIZE as f64
+  }
+
+  fn build_spectrogram(&self) -> (Vec<u8>, usize) {
+    let columns = self.spectrogram_columns.len();
+    if columns == 0 {
+      return (Vec::new(), 0);
+    }
+    let width = columns.min(SPECTROGRAM_WIDTH);
+    let mut image = vec![0u8; width * SPECTROGRAM_HEIGHT];
+    for x in 0..width {
+      let start = x * columns / width;
+      let end = ((x + 1) * columns / width).max(start + 1).min(columns);
+      for band in 0..SPECTROGRAM_HEIGHT {
+        let db = self.spectrogram_columns[start..end]
+          .iter()
+          .map(|column| column[band] as f64)
+          .sum::<f64>()
+          / (end - start) as f64;
+        let level = ((db - SPECTROGRAM_MIN_DB) / -SPECTROGRAM_MIN_DB).clamp(0.0, 1.0);
+        let y = SPECTROGRAM_HEIGHT - 1 - band;
+        image[y * width + x] = (level * 255.0).round() as u8;
+      }
+    }
+    (image, width)
+  }
+
+  /// 在长时频谱上找落差最大的位置，返回 (截止频点, 落差 dB)
+  fn find_shelf(&self, ltas_db: &[f64]) -> Option<(usize, f64)> {
+    let bin_hz = self.bin_hz();
+    let half_window = ((SHELF_WINDOW_HZ / bin_hz).ceil() as usize).max(2);
+    let first = ((SHELF_SEARCH_MIN_HZ / bin_hz).ceil() as usize).max(half_window);
+    let last = ltas_db.len().checked_sub(half_window + 1)?;
+    (first..=last)
+      .map(|bin| {
+        let below = mean(&ltas_db[bin + 1 - half_window..=bin]);
+        let above = mean(&ltas_db[bin + 1..=bin + half_window]);
+        (bin, below - above)
+      })
+      .max_by(|a, b| a.1.total_cmp(&b.1))
+  }
+
+  pub(crate) fn finish(mut self, lossless_container: bool) -> SpectralReport {
+    let analyzed_frames = self.frame_cutoff_bins.len();
+    let nyquist_hz = self.sample_rate as f64 / 2.0;
+    let ltas_db: Vec<f64> = self
+      .ltas_sum
+      .iter()
+      .map(|sum| power_to_db(sum / analyzed_frames.max(1) as f64))
+      .collect();
+    let spectrum_db = reduce_bands(
+      &self
+        .ltas_sum
+        .iter()
+        .map(|sum| sum / analyzed_frames.max(1) as f64)
+        .collect::<Vec<_>>(),
+      SPECTRUM_POINTS,
+    )
+    .into_iter()
+    .map(power_to_db)
+    .collect();
+    let (spectrogram, spectrogram_width) = self.build_spectrogram();
+    self.spectrogram_columns.clear();
+
+    let bin_hz = self.bin_hz();
+    let reference_bins = (REFERENCE_BAND_HZ.0 / bin_hz) as usize
+      ..((REFERENCE_BAND_HZ.1.min(nyquist_hz) / bin_hz) as usize).min(ltas_db.len());
+    let reference_db = if reference_bins.is_empty() {
+      FLOOR_DB
+    } else {
+      power_to_db(
+        self.ltas_sum[reference_bins.clone()].iter().sum::<f64>()
+          / (reference_bins.len() * analyzed_frames.max(1)) as f64,
+      )
+    };
+
+    let mut report = SpectralReport {
+      quality_label: "unknown",
+      bitrate_class: "unknown",
+      cutoff_hz: nyquist_hz,
+      shelf_detected: false,
+      shelf_drop_db: 0.0,
+      fake_lossless: false,
+      upsampled: false,
+      confidence: 0.0,
+      analyzed_frames,
+      spectrum_db,
+      spectrogram,
+      spectrogram_width,
+    };
+    if analyzed_frames < MIN_ANALYZED_FRAMES || reference_db < MIN_REFERENCE_DB {
+      return report;
+    }
+
+    let shelf = self
+      .find_shelf(&ltas_db)
+      .filter(|(_, drop_db)| *drop_db >= SHELF_MIN_DROP_DB);
+    let cutoff_bin = match shelf {
+      Some((bin, drop_db)) => {
+        report.shelf_detected = true;
+        report.shelf_drop_db = drop_db;
+        bin
+      }
+      // 无硬截止：取长时频谱中仍高于参考电平 80 dB 以内的最高频点
+      None => ltas_db
+        .iter()
+        .rposition(|db| *db > reference_db - 80.0)
+        .unwrap_or(ltas_db.len() - 1),
+    };
+    report.cutoff_hz = (cutoff_bin as f64 * bin_hz).min(nyquist_hz);
+
+    // 各帧内容都止于架子以下，说明截止来自编码器而非个别段落
+    let tolerance_bins = (FRAME_CUTOFF_TOLERANCE_HZ / bin_hz).ceil() as usize;
+    let frames_below_cutoff = self
+      .frame_cutoff_bins
+      .iter()
+      .filter(|bin| **bin <= cutoff_bin + tolerance_bins)
+      .count();
+    let consistency = frames_below_cutoff as f64 / analyzed_frames as f64;
+
+    let lossy_shelf = report.shelf_detected && report.cutoff_hz < FULL_BAND_MIN_HZ;
+    report.bitrate_class = if lossy_shelf {
+      bitrate_class_for_cutoff(report.cutoff_hz)
+    } else {
+      "full-band"
+    };
+    report.upsampled = report.shelf_detected
+      && !lossy_shelf
+      && self.sample_rate >= UPSAMPLED_MIN_SAMPLE_RATE
+      && (UPSAMPLED_SHELF_RANGE_HZ.0..=UPSAMPLED_SHELF_RANGE_HZ.1).contains(&report.cutoff_hz);
+    report.fake_lossless = lossless_container && lossy_shelf;
+    report.quality_label = match (lossless_container, lossy_shelf, report.upsampled) {
+      (true, true, _) => "fake-lossless",
+      (_, false, true) => "upsampled",
+      (true, false, false) => "lossless",
+      (false, _, _) => report.bitrate_class,
+    };
+
+    report.confidence = if report.shelf_detected {
+      let drop_confidence = ((report.shelf_drop_db - SHELF_MIN_DROP_DB)
+        / (SHELF_FULL_CONFIDENCE_DROP_DB - SHELF_MIN_DROP_DB))
+        .clamp(0.0, 1.0);
+      0.4 + 0.3 * drop_confidence + 0.3 * consistency
+    } else {
+      // 无架子时，越多帧延伸到高频越可信
+      0.5
+        + 0.5
+          * (1.0 - consistency).max(
+            self
+              .frame_cutoff_bins
+              .iter()
+              .filter(|bin| **bin as f64 * bin_hz >= FULL_BAND_MIN_HZ.min(nyquist_hz * 0.9))
+              .count() as f64
+              / analyzed_frames as f64,
+          )
+    }
+    .clamp(0.0, 1.0);
+    report
+  }
+}
+
+fn to_result(
+  file_path: Option<String>,
+  sample_rate: u32,
+  report: SpectralReport,
+) -> SpectralQualityResult {
+  SpectralQualityResult {
+    file_path,
+    quality_label: report.quality_label.to_string(),
+    bitrate_class: report.bitrate_class.to_string(),
+    cutoff_hz: report.cutoff_hz,
+    shelf_detected: report.shelf_detected,
+    shelf_drop_db: report.shelf_drop_db,
+    fake_lossless: report.fake_lossless,
+    upsampled: report.upsampled,
+    confidence: report.confidence,
+    sample_rate,
+    analyzed_frames: report.analyzed_frames as u32,
+    spectrum_db: report.spectrum_db,
+    spectrogram_width: report.spectrogram_width as u32,
+    spectrogram_height: if report.spectrogram_width > 0 {
+      SPECTROGRAM_HEIGHT as u32
+    } else {
+      0
+    },
+    spectrogram: Buffer::from(report.spectrogram),
+    error: None,
+  }
+}
+
+impl AnalysisErrorResult for SpectralQualityResult {
+  fn error_result(file_path: Option<String>, error: String) -> Self {
+    SpectralQualityResult {
+      file_path,
+      quality_label: "unknown".to_string(),
+      bitrate_class: "unknown".to_string(),
+      cutoff_hz: 0.0,
+      shelf_detected: false,
+      shelf_drop_db: 0.0,
+      fake_lossless: false,
+      upsampled: false,
+      confidence: 0.0,
+      sample_rate: 0,
+      analyzed_frames: 0,
+      spectrum_db: Vec::new(),
+      spectrogram: Buffer::from(Vec::<u8>::new()),
+      spectrogram_width: 0,
+      spectrogram_height: 0,
+      error: Some(error),
+    }
+  }
+}
+
+/// 解码整轨做频谱质量分析
+#[napi]
+pub fn analyze_spectral_quality(file_path: String) -> SpectralQualityResult {
+  analyze_file_in_chunks(
+    file_path,
+    FFT_SIZE * 16,
+    |sample_rate, channels| Ok(SpectralAnalyzer::new(sample_rate, channels, 1, true)),
+    SpectralAnalyzer::push_f32,
+    |analyzer, file_path, sample_rate, _| {
+      let report = analyzer.finish(is_lossless_container(Path::new(&file_path)));
+      to_result(Some(file_path), sample_rate, report)
+    },
+  )
+}
+
+/// 对已解码的交错 f32 PCM 做频谱质量分析；losslessContainer 表示来源为无损容器
+#[napi]
+pub fn analyze_spectral_quality_from_pcm(
+  pcm_data: Buffer,
+  sample_rate: u32,
+  channels: u8,
+  lossless_container: Option<bool>,
+) -> SpectralQualityResult {
+  analyze_pcm_buffer(&pcm_data, sample_rate, channels, |samples| {
+    let mut analyzer = SpectralAnalyzer::new(sample_rate, channels as u16, 1, true);
+    analyzer.push_f32(samples);
+    to_result(
+      None,
+      sample_rate,
+      analyzer.finish(lossless_container.unwrap_or(false)),
+    )
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// 伪随机白噪声，可选用砖墙低通（频域外的分量由多正弦叠加近似）
+  fn noise_with_cutoff(sample_rate: u32, seconds: f64, cutoff_hz: f64) -> Vec<f32> {
+    let frames = (sample_rate as f64 * seconds) as usize;
+    let partials: Vec<(f64, f64)> = (1..400)
+      .map(|index| {
+        let freq = index as f64 * cutoff_hz / 400.0;
+        (
+          freq,
+          (index as f64 * 2.399).rem_euclid(std::f64::consts::TAU),
+        )
+      })
+      .collect();
+    (0..frames)
+      .map(|frame| {
+        let t = frame as f64 / sample_rate as f64;
+        let value: f64 = partials
+          .iter()
+          .map(|(freq, phase)| (std::f64::consts::TAU * freq * t + phase).sin())
+          .sum::<f64>()
+          / partials.len() as f64;
+        (value * 4.0) as f32
+      })
+      .collect()
+  }
+
+  #[test]
+  fn detects_lossy_shelf_in_lossless_container() {
+    let pcm = noise_with_cutoff(44_100, 1.5, 16_000.0);
+    let mut analyzer = SpectralAnalyzer::new(44_100, 1, 1, true);
+    analyzer.push_f32(&pcm);
+    let report = analyzer.finish(true);
+
+    assert!(report.shelf_detected);
+    assert!(
+      (report.cutoff_hz - 16_000.0).abs() < 300.0,
+      "{}",
+      report.cutoff_hz
+    );
+    assert_eq!(report.bitrate_class, "128k");
+    assert_eq!(report.quality_label, "fake-lossless");
+    assert!(report.fake_lossless);
+    assert!(report.confidence > 0.6);
+    assert_eq!(
+      report.spectrogram.len(),
+      report.spectrogram_width * SPECTROGRAM_HEIGHT
+    );
+  }
+
+  #[test]
+  fn full_band_and_upsampled_and_silence_classification() {
+    let pcm = noise_with_cutoff(44_100, 1.5, 21_900.0);
+    let mut analyzer = SpectralAnalyzer::new(44_100, 1, 1, false);
+    analyzer.push_f32(&pcm);
+    let report = analyzer.finish(true);
+    assert_eq!(report.quality_label, "lossless");
+    assert_eq!(report.bitrate_class, "full-band");
+    assert!(report.spectrogram.is_empty());
+
+    let pcm = noise_with_cutoff(96_000, 0.8, 22_000.0);
+    let mut analyzer = SpectralAnalyzer::new(96_000, 1, 1, false);
+    analyzer.push_f32(&pcm);
+    let report = analyzer.finish(true);
+    assert!(report.upsampled);
+    assert_eq!(report.quality_label, "upsampled");
+
+    let mut analyzer = SpectralAnalyzer::new(44_100, 2, 1, false);
+    analyzer.push_i16(&vec![0i16; 44_100 * 2]);
+    let report = analyzer.finish(true);
+    assert_eq!(report.quality_label, "unknown");
+    assert_eq!(report.confidence, 0.0);
+  }
+
+  #[test]
+  fn bitrate_classes_follow_encoder_lowpass_defaults() {
+    assert_eq!(bitrate_class_for_cutoff(11_000.0), "sub-128k");
+    assert_eq!(bitrate_class_for_cutoff(16_000.0), "128k");
+    assert_eq!(bitrate_class_for_cutoff(19_000.0), "256k");
+    assert_eq!(bitrate_class_for_cutoff(20_000.0), "320k");
+    assert!(is_lossless_container(Path::new("/a/B.FLAC")));
+    assert!(!is_lossless_container(Path::new("/a/b.m4a")));
+  }
+}
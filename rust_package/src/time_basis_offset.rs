@@ -0,0 +1,229 @@
+//! 时间基偏移探测：rekordbox 的网格/cue 以未裁剪 priming 的时间线为准，
+//! 这里给出本地解码时间线（已按 gapless 裁剪）到 rekordbox 时间线的偏移
+
+use std::path::Path;
+use std::result::Result as StdResult;
+
+use napi_derive::napi;
+
+use crate::audio_decode_stream::AudioDecodeStream;
+use crate::gapless_info::{read_gapless_info, GaplessInfo, GaplessSource};
+
+/// 各后端比对时解码的时长
+const PROBE_DECODE_SEC: f64 = 4.0;
+/// 比对窗口与最大搜索偏移
+const PROBE_WINDOW_FRAMES: usize = 2048;
+const PROBE_MAX_LAG_SEC: f64 = 0.05;
+/// 起音判定阈值（相对窗口峰值）
+const PROBE_ONSET_RATIO: f32 = 0.1;
+/// 后端一致的判定：相关度与残余偏移
+const PROBE_MIN_CORRELATION: f64 = 0.9;
+const PROBE_MAX_RESIDUAL_MS: f64 = 0.1;
+/// 无 priming 的 PCM/无损格式
+const LOSSLESS_EXTENSIONS: [&str; 5] = ["wav", "flac", "aif", "aiff", "wv"];
+
+/// 时间基偏移探测结果
+#[napi(object)]
+pub struct TimeBasisOffsetProbeResult {
+  /// 叠加到本地时间线即得到 rekordbox 时间线的偏移（毫秒）
+  pub offset_ms: f64,
+  /// 置信度 0..1
+  pub confidence: f64,
+  /// 判定依据（lame / lavf / itunsmpb / lossless / none）
+  pub basis: String,
+  /// 解码时已裁掉的 priming 时长（毫秒）
+  pub priming_offset_ms: Option<f64>,
+  /// Symphonia 与 FFmpeg 解码结果的残余偏移（毫秒，正值表示 FFmpeg 滞后）
+  pub backend_residual_ms: Option<f64>,
+  /// 错误描述（失败时）
+  pub error: Option<String>,
+}
+
+/// 与旧版 ffprobe 探测保持一致：LAME 文件为 start_time + skip_samples（两倍 priming），
+/// Lavf/Lavc 写入的 Info 标签只计 start_time，MP4 与无 gapless 信息的文件为 0
+fn rekordbox_offset_ms(
+  info: Option<&GaplessInfo>,
+  sample_rate: u32,
+  lossless: bool,
+) -> (f64, &'static str, f64) {
+  let Some(info) = info else {
+    return if lossless {
+      (0.0, "lossless", 1.0)
+    } else {
+      (0.0, "none", 0.6)
+    };
+  };
+  let priming_ms = info.priming_offset_ms(sample_rate).unwrap_or(0.0);
+  match info.source {
+    GaplessSource::Lame if info.lame_encoder => (priming_ms * 2.0, "lame", 0.9),
+    GaplessSource::Lame => (priming_ms, "lavf", 0.85),
+    GaplessSource::ITunSmpb => (0.0, "itunsmpb", 0.85),
+  }
+}
+
+fn read_probe_mono(stream: &mut AudioDecodeStream) -> Option<Vec<f32>> {
+  let channels = stream.channels().max(1) as usize;
+  let frames = (PROBE_DECODE_SEC * stream.sample_rate() as f64) as usize;
+  let mut mono = Vec::with_capacity(frames);
+  while mono.len() < frames {
+    let chunk = stream.read_frames(frames - mono.len()).ok()?;
+    if chunk.is_empty() {
+      break;
+    }
+    mono.extend(
+      chunk
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+    );
+  }
+  Some(mono)
+}
+
+/// 以 reference 首个起音附近窗口，在 candidate 中搜索最佳对齐；返回 (偏移帧, 归一化相关度)
+fn measure_lag(reference: &[f32], candidate: &[f32], max_lag: usize) -> Option<(i64, f64)> {
+  let peak = reference.iter().fold(0.0f32, |acc, v| acc.max(v.abs()));
+  if peak <= f32::EPSILON {
+    return None;
+  }
+  let onset = reference
+    .iter()
+    .position(|v| v.abs() >= peak * PROBE_ONSET_RATIO)?;
+  let start = onset.saturating_sub(PROBE_WINDOW_FRAMES / 4).max(max_lag);
+  let window = reference.get(start..start + PROBE_WINDOW_FRAMES)?;
+  let window_energy: f64 = window.iter().map(|v| (*v as f64) * (*v as f64)).sum();
+  let mut best: Option<(i64, f64)> = None;
+  for lag in -(max_lag as i64)..=(max_lag as i64) {
+    let offset = start as i64 + lag;
+    if offset < 0 {
+      continue;
+    }
+    let Some(segment) = candidate.get(offset as usize..offset as usize + PROBE_WINDOW_FRAMES)
+    else {
+      continue;
+    };
+    let mut dot = 0.0f64;
+    let mut energy = 0.0f64;
+    for (a, b) in window.iter().zip(segment) {
+      dot += (*a as f64) * (*b as f64);
+      energy += (*b as f64) * (*b as f64);
+    }
+    let denom = (window_energy * energy).sqrt();
+    if denom <= f64::EPSILON {
+      continue;
+    }
+    let correlation = dot / denom;
+    if best.is_none_or(|(_, value)| correlation > value) {
+      best = Some((lag, correlation));
+    }
+  }
+  best
+}
+
+fn probe_backend_residual_ms(path: &Path) -> Option<(f64, f64)> {
+  let mut symphonia = AudioDecodeStream::open_symphonia(path).ok()?;
+  let mut ffmpeg = AudioDecodeStream::open_ffmpeg(path, "ffmpeg").ok()?;
+  if symphonia.sample_rate() != ffmpeg.sample_rate() {
+    return None;
+  }
+  let sample_rate = symphonia.sample_rate();
+  let reference = read_probe_mono(&mut symphonia)?;
+  let candidate = read_probe_mono(&mut ffmpeg)?;
+  let max_lag = (PROBE_MAX_LAG_SEC * sample_rate as f64) as usize;
+  let (lag, correlation) = measure_lag(&reference, &candidate, max_lag)?;
+  Some((lag as f64 * 1000.0 / sample_rate as f64, correlation))
+}
+
+fn probe_time_basis_offset_for_path(path: &Path) -> StdResult<TimeBasisOffsetProbeResult, String> {
+  let stream = AudioDecodeStream::open(path)?;
+  let sample_rate = stream.sample_rate();
+  drop(stream);
+  let gapless = read_gapless_info(path);
+  let lossless = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(|ext| LOSSLESS_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+    .unwrap_or(false);
+  let (offset_ms, basis, mut confidence) =
+    rekordbox_offset_ms(gapless.as_ref(), sample_rate, lossless);
+
+  let residual = if crate::is_ffmpeg_only_extension(path) {
+    None
+  } else {
+    probe_backend_residual_ms(path)
+  };
+  match residual {
+    Some((residual_ms, correlation))
+      if correlation >= PROBE_MIN_CORRELATION && residual_ms.abs() <= PROBE_MAX_RESIDUAL_MS => {}
+    Some((_, correlation)) => confidence *= 0.5 * correlation.clamp(0.0, 1.0),
+    // 只有单一后端可用（或首段静音）时无法交叉验证
+    None => confidence *= 0.8,
+  }
+
+  Ok(TimeBasisOffsetProbeResult {
+    offset_ms: (offset_ms * 1000.0).round() / 1000.0,
+    confidence,
+    basis: basis.to_string(),
+    priming_offset_ms: gapless.and_then(|info| info.priming_offset_ms(sample_rate)),
+    backend_residual_ms: residual.map(|(residual_ms, _)| residual_ms),
+    error: None,
+  })
+}
+
+/// 探测文件的时间基偏移（毫秒）及置信度，供导入的 Pioneer 网格与 cue 对齐
+#[napi]
+pub fn probe_time_basis_offset(file_path: String) -> TimeBasisOffsetProbeResult {
+  match probe_time_basis_offset_for_path(Path::new(&file_path)) {
+    Ok(result) => result,
+    Err(error) => TimeBasisOffsetProbeResult {
+      offset_ms: 0.0,
+      confidence: 0.0,
+      basis: "none".to_string(),
+      priming_offset_ms: None,
+      backend_residual_ms: None,
+      error: Some(error),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lame_info(lame_encoder: bool) -> GaplessInfo {
+    GaplessInfo {
+      source: GaplessSource::Lame,
+      delay_frames: 1105,
+      padding_frames: 0,
+      valid_frames: None,
+      sample_rate: Some(44_100),
+      lame_encoder,
+    }
+  }
+
+  #[test]
+  fn rekordbox_offset_matches_legacy_ffprobe_rules() {
+    let priming_ms = 1105.0 * 1000.0 / 44_100.0;
+    let (lame_ms, basis, _) = rekordbox_offset_ms(Some(&lame_info(true)), 44_100, false);
+    assert_eq!(basis, "lame");
+    assert!((lame_ms - priming_ms * 2.0).abs() < 1e-9);
+    let (lavf_ms, basis, _) = rekordbox_offset_ms(Some(&lame_info(false)), 44_100, false);
+    assert_eq!(basis, "lavf");
+    assert!((lavf_ms - priming_ms).abs() < 1e-9);
+    assert_eq!(rekordbox_offset_ms(None, 44_100, false).0, 0.0);
+    assert_eq!(rekordbox_offset_ms(None, 44_100, true).1, "lossless");
+  }
+
+  #[test]
+  fn measure_lag_finds_shifted_onset() {
+    let mut reference = vec![0.0f32; 8_000];
+    for (index, sample) in reference.iter_mut().enumerate().skip(3_000) {
+      *sample = ((index as f32) * 0.37).sin() * (1.0 + (index % 13) as f32 * 0.05);
+    }
+    let mut candidate = vec![0.0f32; 17];
+    candidate.extend_from_slice(&reference[..reference.len() - 17]);
+    let (lag, correlation) = measure_lag(&reference, &candidate, 200).expect("lag");
+    assert_eq!(lag, 17);
+    assert!(correlation > 0.999);
+    assert!(measure_lag(&[0.0; 4_000], &candidate, 200).is_none());
+  }
+}
@@ -0,0 +1,813 @@
+//! 批量曲目分析：每个文件只解码一次，把 PCM 分块并行分发给所选分析器
+//! （内容哈希 / 调性 / Mixxx 波形 / 响度），逐曲目流式回传结果。
+//! 声纹指纹例外：直接复用 generateChromaprintFingerprint 单独解码前 N 秒，
+//! 保证与单独生成的指纹逐字节一致（两者的解码器与下混方式不同）；
+//! 因需额外解码，不在缺省分析器之列
+
+use std::fs::File;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Instant;
+
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+use rayon::prelude::*;
+use symphonia::core::audio::{SampleBuffer, SignalSpec};
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{Limit, MetadataOptions};
+use symphonia::core::probe::Hint;
+use symphonia::default::get_probe;
+
+use crate::analysis_utils::{to_stereo, LoudnessAccumulator, K_FAST_ANALYSIS_SECONDS};
+use crate::cancel_registry::CancelRegistry;
+use crate::decode_error::{
+  check_source_file, join_decode_errors, primary_decode_error, DecodeBackend, DecodeError,
+  DecodeErrorInfo, DecodeErrorKind,
+};
+use crate::ffmpeg_decode::ffmpeg_decode_content_i16_cancellable;
+use crate::mixxx_waveform::{
+  mixxx_summary_visual_rate, MixxxWaveformAccumulator, MixxxWaveformData,
+  MIXXX_WAVEFORM_POINTS_PER_SECOND,
+};
+use crate::qm_key::KeyDetector;
+use crate::PcmContentHasher;
+
+const ANALYZER_HASH: &str = "hash";
+const ANALYZER_CHROMAPRINT: &str = "chromaprint";
+const ANALYZER_KEY: &str = "key";
+const ANALYZER_WAVEFORM: &str = "waveform";
+const ANALYZER_LOUDNESS: &str = "loudness";
+/// 攒够该帧数再整块分发给各分析器，摊薄并行调度开销
+const FAN_OUT_BLOCK_FRAMES: usize = 65_536;
+const DEFAULT_CHROMAPRINT_MAX_SECONDS: u32 = 120;
+
+/// 批量分析参数
+#[napi(object)]
+pub struct TrackAnalysisOptions {
+  /// hash / chromaprint / key / waveform / loudness，缺省为除 chromaprint 外的全部
+  /// chromaprint 不共用本次解码，会为前 N 秒额外解码一遍，需显式选择
+  pub analyzers: Option<Vec<String>>,
+  /// 指纹只取前 N 秒，缺省 120；语义同 generateChromaprintFingerprint 的 maxLengthSeconds
+  pub chromaprint_max_seconds: Option<u32>,
+  /// 调性只分析前 60 秒，缺省 false
+  pub fast_key_analysis: Option<bool>,
+  /// 波形总览采样率（点/秒），缺省按时长压到约 1920 点
+  pub waveform_visual_rate: Option<f64>,
+}
+
+/// 单个分析器的失败信息
+#[napi(object)]
+pub struct TrackAnalyzerError {
+  pub analyzer: String,
+  pub error: String,
+}
+
+#[napi(object)]
+pub struct TrackLoudnessResult {
+  pub integrated_db: f64,
+  pub peak_db: f64,
+}
+
+/// 单曲分析结果；未选中或失败的分析器对应字段为空
+#[napi(object)]
+pub struct TrackAnalysisResult {
+  /// 在输入 filePaths 中的下标
+  pub index: u32,
+  pub file_path: String,
+  pub decoder_backend: Option<String>,
+  pub sample_rate: u32,
+  pub channels: u32,
+  pub duration_sec: f64,
+  /// 与 calculateAudioHashes 相同的 PCM 内容哈希
+  pub sha256_hash: Option<String>,
+  pub quality_label: Option<String>,
+  pub chromaprint_fingerprint: Option<String>,
+  pub key_text: Option<String>,
+  pub waveform: Option<MixxxWaveformData>,
+  pub loudness: Option<TrackLoudnessResult>,
+  pub errors: Vec<TrackAnalyzerError>,
+  pub elapsed_ms: f64,
+  /// 解码失败时的整体错误
+  pub error: Option<String>,
+  pub error_code: Option<String>,
+  pub decode_errors: Option<Vec<DecodeErrorInfo>>,
+}
+
+/// 每完成一首回调一次
+#[napi(object)]
+pub struct TrackAnalysisProgress {
+  pub task_id: String,
+  pub processed: u32,
+  pub total: u32,
+  pub result: TrackAnalysisResult,
+}
+
+#[napi(object)]
+pub struct TrackAnalysisSummary {
+  pub task_id: String,
+  pub total: u32,
+  pub processed: u32,
+  /// 解码失败的曲目数（不含单个分析器失败）
+  pub failed: u32,
+  pub cancelled: bool,
+  pub elapsed_ms: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct AnalyzerSet {
+  hash: bool,
+  chromaprint: bool,
+  key: bool,
+  waveform: bool,
+  loudness: bool,
+}
+
+impl AnalyzerSet {
+  fn parse(names: Option<&[String]>) -> napi::Result<Self> {
+    let Some(names) = names else {
+      return Ok(Self {
+        hash: true,
+        chromaprint: false,
+        key: true,
+        waveform: true,
+        loudness: true,
+      });
+    };
+    if names.is_empty() {
+      return Err(napi::Error::from_reason("未选择任何分析器"));
+    }
+    let mut set = Self {
+      hash: false,
+      chromaprint: false,
+      key: false,
+      waveform: false,
+      loudness: false,
+    };
+    for name in names {
+      match name.trim().to_ascii_lowercase().as_str() {
+        ANALYZER_HASH => set.hash = true,
+        ANALYZER_CHROMAPRINT => set.chromaprint = true,
+        ANALYZER_KEY => set.key = true,
+        ANALYZER_WAVEFORM => set.waveform = true,
+        ANALYZER_LOUDNESS => set.loudness = true,
+        other => return Err(napi::Error::from_reason(format!("未知的分析器: {}", other))),
+      }
+    }
+    Ok(set)
+  }
+}
+
+#[derive(Clone, Copy)]
+struct TrackAnalysisConfig {
+  analyzers: AnalyzerSet,
+  chromaprint_max_seconds: u32,
+  fast_key_analysis: bool,
+  waveform_visual_rate: Option<f64>,
+}
+
+impl TrackAnalysisConfig {
+  fn from_options(options: Option<&TrackAnalysisOptions>) -> napi::Result<Self> {
+    Ok(Self {
+      analyzers: AnalyzerSet::parse(options.and_then(|o| o.analyzers.as_deref()))?,
+      chromaprint_max_seconds: options
+        .and_then(|o| o.chromaprint_max_seconds)
+        .unwrap_or(DEFAULT_CHROMAPRINT_MAX_SECONDS),
+      fast_key_analysis: options.and_then(|o| o.fast_key_analysis).unwrap_or(false),
+      waveform_visual_rate: options
+        .and_then(|o| o.waveform_visual_rate)
+        .filter(|rate| rate.is_finite() && *rate > 0.0),
+    })
+  }
+}
+
+struct KeyStage {
+  detector: KeyDetector,
+  remaining_frames: Option<usize>,
+  error: Option<String>,
+}
+
+impl KeyStage {
+  fn push(&mut self, samples: &[f32], channels: u16) {
+    if self.error.is_some() {
+      return;
+    }
+    let channel_count = channels.max(1) as usize;
+    let mut frames = samples.len() / channel_count;
+    if let Some(remaining) = self.remaining_frames.as_mut() {
+      frames = frames.min(*remaining);
+      *remaining -= frames;
+    }
+    if frames == 0 {
+      return;
+    }
+    let stereo = to_stereo(samples, channel_count, frames);
+    if let Err(error) = self.detector.process(&stereo, frames, 2) {
+      self.error = Some(error);
+    }
+  }
+
+  fn finish(mut self) -> StdResult<String, String> {
+    if let Some(error) = self.error {
+      return Err(error);
+    }
+    self.detector.finalize().map(crate::key_id_to_id3_text)
+  }
+}
+
+/// 单曲的分析器集合：解码端逐包推入，攒满一块后并行分发
+struct TrackSink {
+  config: TrackAnalysisConfig,
+  sample_rate: u32,
+  channels: u16,
+  total_frames: u64,
+  hasher: Option<PcmContentHasher>,
+  key: Option<KeyStage>,
+  waveform: Option<MixxxWaveformAccumulator>,
+  loudness: Option<LoudnessAccumulator>,
+  errors: Vec<TrackAnalyzerError>,
+  pending_i16: Vec<i16>,
+  pending_f32: Vec<f32>,
+}
+
+impl TrackSink {
+  /// total_frames_hint 用于推算缺省的波形总览采样率，未知时退回 441 点/秒
+  fn new(
+    config: TrackAnalysisConfig,
+    sample_rate: u32,
+    channels: u16,
+    total_frames_hint: Option<u64>,
+  ) -> Self {
+    let analyzers = config.analyzers;
+    let mut errors = Vec::new();
+    let key = if analyzers.key {
+      match KeyDetector::new(sample_rate) {
+        Ok(detector) => Some(KeyStage {
+          detector,
+          remaining_frames: config
+            .fast_key_analysis
+            .then(|| (sample_rate as usize).saturating_mul(K_FAST_ANALYSIS_SECONDS)),
+          error: None,
+        }),
+        Err(error) => {
+          errors.push(analyzer_error(ANALYZER_KEY, error));
+          None
+        }
+      }
+    } else {
+      None
+    };
+    let waveform = if analyzers.waveform {
+      let visual_rate = config.waveform_visual_rate.unwrap_or_else(|| {
+        total_frames_hint
+          .map(|frames| mixxx_summary_visual_rate(sample_rate, frames as usize))
+          .unwrap_or(MIXXX_WAVEFORM_POINTS_PER_SECOND)
+      });
+      let created = u8::try_from(channels)
+        .map_err(|_| format!("不支持的声道数: {}", channels))
+        .and_then(|channels| {
+          MixxxWaveformAccumulator::new(sample_rate, channels, visual_rate)
+            .map_err(|error| error.reason)
+        });
+      match created {
+        Ok(accumulator) => Some(accumulator),
+        Err(error) => {
+          errors.push(analyzer_error(ANALYZER_WAVEFORM, error));
+          None
+        }
+      }
+    } else {
+      None
+    };
+    Self {
+      config,
+      sample_rate,
+      channels,
+      total_frames: 0,
+      hasher: analyzers.hash.then(|| PcmContentHasher::new(true)),
+      key,
+      waveform,
+      loudness: analyzers.loudness.then(LoudnessAccumulator::default),
+      errors,
+      pending_i16: Vec::new(),
+      pending_f32: Vec::new(),
+    }
+  }
+
+  fn needs_i16(&self) -> bool {
+    self.hasher.is_some()
+  }
+
+  fn needs_f32(&self) -> bool {
+    self.key.is_some() || self.waveform.is_some() || self.loudness.is_some()
+  }
+
+  /// samples_f32 缺省时由 i16 换算
+  fn push(
+    &mut self,
+    samples_i16: &[i16],
+    samples_f32: Option<&[f32]>,
+    sample_rate: u32,
+    channels: u16,
+  ) {
+    if (sample_rate, channels) != (self.sample_rate, self.channels) {
+      self.handle_spec_change(sample_rate, channels);
+    }
+    if self.needs_i16() {
+      self.pending_i16.extend_from_slice(samples_i16);
+    }
+    if self.needs_f32() {
+      match samples_f32 {
+        Some(samples) => self.pending_f32.extend_from_slice(samples),
+        None => self
+          .pending_f32
+          .extend(samples_i16.iter().map(|&sample| sample as f32 / 32768.0)),
+      }
+    }
+    let frames = samples_i16.len() / channels.max(1) as usize;
+    self.total_frames += frames as u64;
+    let pending_frames =
+      self.pending_i16.len().max(self.pending_f32.len()) / channels.max(1) as usize;
+    if pending_frames >= FAN_OUT_BLOCK_FRAMES {
+      self.flush();
+    }
+  }
+
+  /// 中途 spec 变化：内容哈希照常延续（与 calculateAudioHashes 一致），其余分析器无法跨 spec 拼接，直接放弃
+  fn handle_spec_change(&mut self, sample_rate: u32, channels: u16) {
+    self.flush();
+    let message = format!(
+      "解码中途采样格式变化: {}Hz/{}ch -> {}Hz/{}ch",
+      self.sample_rate, self.channels, sample_rate, channels
+    );
+    let dropped = [
+      (ANALYZER_KEY, self.key.take().is_some()),
+      (ANALYZER_WAVEFORM, self.waveform.take().is_some()),
+      (ANALYZER_LOUDNESS, self.loudness.take().is_some()),
+    ];
+    for (analyzer, was_active) in dropped {
+      if was_active {
+        self.errors.push(analyzer_error(analyzer, message.clone()));
+      }
+    }
+    self.sample_rate = sample_rate;
+    self.channels = channels;
+  }
+
+  fn flush(&mut self) {
+    let (sample_rate, channels) = (self.sample_rate, self.channels);
+    let TrackSink {
+      hasher,
+      key,
+      waveform,
+      loudness,
+      pending_i16,
+      pending_f32,
+      ..
+    } = self;
+    let (samples_i16, samples_f32) = (&pending_i16[..], &pending_f32[..]);
+    rayon::scope(|scope| {
+      if let Some(hasher) = hasher.as_mut() {
+        scope.spawn(move |_| hasher.push_i16(samples_i16, sample_rate, channels));
+      }
+      if let Some(key) = key.as_mut() {
+        scope.spawn(move |_| key.push(samples_f32, channels));
+      }
+      if let Some(waveform) = waveform.as_mut() {
+        scope.spawn(move |_| waveform.push(samples_f32));
+      }
+      if let Some(loudness) = loudness.as_mut() {
+        scope.spawn(move |_| loudness.push(samples_f32, channels));
+      }
+    });
+    pending_i16.clear();
+    pending_f32.clear();
+  }
+
+  fn finish(mut self, path: &Path) -> TrackAnalysisOutputs {
+    self.flush();
+    let mut outputs = TrackAnalysisOutputs {
+      sample_rate: self.sample_rate,
+      channels: self.channels,
+      duration_sec: if self.sample_rate > 0 {
+        self.total_frames as f64 / self.sample_rate as f64
+      } else {
+        0.0
+      },
+      sha256_hash: None,
+      quality_label: None,
+      chromaprint_fingerprint: None,
+      key_text: None,
+      waveform: self.waveform,
+      loudness: None,
+      errors: self.errors,
+    };
+
+    if let Some(hasher) = self.hasher {
+      let (hash, quality_label) = hasher.finish(path);
+      outputs.sha256_hash = Some(hash);
+      outputs.quality_label = quality_label;
+    }
+    if self.config.analyzers.chromaprint {
+      match crate::fingerprint_audio_file(
+        &path.to_string_lossy(),
+        self.config.chromaprint_max_seconds,
+      ) {
+        Ok(result) => outputs.chromaprint_fingerprint = Some(result.fingerprint),
+        Err(error) => outputs
+          .errors
+          .push(analyzer_error(ANALYZER_CHROMAPRINT, error)),
+      }
+    }
+    if let Some(key) = self.key {
+      match key.finish() {
+        Ok(value) => outputs.key_text = Some(value),
+        Err(error) => outputs.errors.push(analyzer_error(ANALYZER_KEY, error)),
+      }
+    }
+    if let Some(loudness) = self.loudness {
+      match loudness.finish() {
+        Some((integrated_db, peak_db)) => {
+          outputs.loudness = Some(TrackLoudnessResult {
+            integrated_db,
+            peak_db,
+          })
+        }
+        None => outputs.errors.push(analyzer_error(
+          ANALYZER_LOUDNESS,
+          "没有可分析的音频帧".to_string(),
+        )),
+      }
+    }
+    outputs
+  }
+}
+
+/// 分析器产出；波形保持累加器形态，写入结果时才导出为 napi Buffer
+struct TrackAnalysisOutputs {
+  sample_rate: u32,
+  channels: u16,
+  duration_sec: f64,
+  sha256_hash: Option<String>,
+  quality_label: Option<String>,
+  chromaprint_fingerprint: Option<String>,
+  key_text: Option<String>,
+  waveform: Option<MixxxWaveformAccumulator>,
+  loudness: Option<TrackLoudnessResult>,
+  errors: Vec<TrackAnalyzerError>,
+}
+
+impl TrackAnalysisOutputs {
+  fn write_to(self, result: &mut TrackAnalysisResult) {
+    result.sample_rate = self.sample_rate;
+    result.channels = self.channels as u32;
+    result.duration_sec = self.duration_sec;
+    result.sha256_hash = self.sha256_hash;
+    result.quality_label = self.quality_label;
+    result.chromaprint_fingerprint = self.chromaprint_fingerprint;
+    result.key_text = self.key_text;
+    result.waveform = self.waveform.map(MixxxWaveformAccumulator::into_data);
+    result.loudness = self.loudness;
+    result.errors = self.errors;
+  }
+}
+
+fn analyzer_error(analyzer: &str, error: String) -> TrackAnalyzerError {
+  TrackAnalyzerError {
+    analyzer: analyzer.to_string(),
+    error,
+  }
+}
+
+impl TrackAnalysisResult {
+  fn with_path(index: u32, path: &Path) -> Self {
+    TrackAnalysisResult {
+      index,
+      file_path: path.to_string_lossy().to_string(),
+      decoder_backend: None,
+      sample_rate: 0,
+      channels: 0,
+      duration_sec: 0.0,
+      sha256_hash: None,
+      quality_label: None,
+      chromaprint_fingerprint: None,
+      key_text: None,
+      waveform: None,
+      loudness: None,
+      errors: Vec::new(),
+      elapsed_ms: 0.0,
+      error: None,
+      error_code: None,
+      decode_errors: None,
+    }
+  }
+
+  fn set_decode_failure(&mut self, errors: &[DecodeError]) {
+    self.error = Some(join_decode_errors(errors));
+    self.error_code = primary_decode_error(errors).map(|error| error.kind.as_str().to_string());
+    self.decode_errors = Some(errors.iter().map(DecodeError::to_info).collect());
+  }
+}
+
+/// 返回 None 表示被取消
+fn analyze_with_symphonia(
+  path: &Path,
+  config: TrackAnalysisConfig,
+  cancel_flag: &AtomicBool,
+) -> StdResult<Option<TrackSink>, DecodeError> {
+  let file = File::open(path).map_err(|e| DecodeError::from_io(DecodeBackend::Symphonia, &e))?;
+  let media_stream = MediaSourceStream::new(Box::new(file), Default::default());
+  let mut hint = Hint::new();
+  if let Some(ext) = path.extension().and_then(|os| os.to_str()) {
+    hint.with_extension(ext);
+  }
+  let metadata_opts = MetadataOptions {
+    limit_metadata_bytes: Limit::None,
+    limit_visual_bytes: Limit::None,
+  };
+  let probed = get_probe()
+    .format(
+      &hint,
+      media_stream,
+      &FormatOptions::default(),
+      &metadata_opts,
+    )
+    .map_err(|e| DecodeError::from_symphonia(&e).context("探测音频格式失败"))?;
+
+  let mut format = probed.format;
+  let total_frames_hint = crate::find_decode_track(&mut format)
+    .ok()
+    .and_then(|track| track.codec_params.n_frames);
+  let mut sink: Option<TrackSink> = None;
+  // f32 分析器用的转换缓冲；spec 变化或容量不足时重建
+  let mut f32_buffer: Option<(SampleBuffer<f32>, SignalSpec)> = None;
+  let mut cancelled = false;
+  let outcome = catch_unwind(AssertUnwindSafe(|| {
+    crate::decode_symphonia_i16(format, &mut |samples, spec, audio_buf| {
+      if cancel_flag.load(Ordering::Relaxed) {
+        cancelled = true;
+        return false;
+      }
+      let channels = spec.channels.count() as u16;
+      let sink =
+        sink.get_or_insert_with(|| TrackSink::new(config, spec.rate, channels, total_frames_hint));
+      let samples_f32 = if sink.needs_f32() {
+        let required = audio_buf.frames() * channels as usize;
+        let recreate = f32_buffer
+          .as_ref()
+          .map(|(buffer, buffer_spec)| buffer.capacity() < required || *buffer_spec != spec)
+          .unwrap_or(true);
+        if recreate {
+          f32_buffer = Some((
+            SampleBuffer::new(audio_buf.frames().next_power_of_two() as u64, spec),
+            spec,
+          ));
+        }
+        let (buffer, _) = f32_buffer.as_mut().unwrap();
+        buffer.copy_interleaved_ref(audio_buf);
+        Some(buffer.samples())
+      } else {
+        None
+      };
+      sink.push(samples, samples_f32, spec.rate, channels);
+      true
+    })
+  }));
+  match outcome {
+    Ok(Ok(())) => {}
+    Ok(Err(error)) => return Err(crate::symphonia_setup_error(error)),
+    Err(_) => {
+      return Err(DecodeError::new(
+        DecodeErrorKind::Other,
+        DecodeBackend::Symphonia,
+        "内部音频解码错误（panic）",
+      ))
+    }
+  }
+  if cancelled {
+    return Ok(None);
+  }
+  sink.map(Some).ok_or_else(|| {
+    DecodeError::new(
+      DecodeErrorKind::Other,
+      DecodeBackend::Symphonia,
+      "Symphonia 解码未产出 PCM",
+    )
+  })
+}
+
+/// 返回 None 表示被取消
+fn analyze_with_ffmpeg(
+  path: &Path,
+  config: TrackAnalysisConfig,
+  cancel_flag: &AtomicBool,
+) -> StdResult<Option<TrackSink>, DecodeError> {
+  let Some(pcm) =
+    ffmpeg_decode_content_i16_cancellable(path, || cancel_flag.load(Ordering::Relaxed))?
+  else {
+    return Ok(None);
+  };
+  let mut sink = TrackSink::new(
+    config,
+    pcm.sample_rate,
+    pcm.channels,
+    Some(pcm.total_frames),
+  );
+  let block_samples = FAN_OUT_BLOCK_FRAMES * pcm.channels.max(1) as usize;
+  for block in pcm.samples_i16.chunks(block_samples) {
+    if cancel_flag.load(Ordering::Relaxed) {
+      return Ok(None);
+    }
+    sink.push(block, None, pcm.sample_rate, pcm.channels);
+  }
+  Ok(Some(sink))
+}
+
+/// 先走 Symphonia，失败再回退 FFmpeg；返回 None 表示被取消
+fn analyze_track(
+  index: u32,
+  file_path: &str,
+  config: TrackAnalysisConfig,
+  cancel_flag: &AtomicBool,
+) -> Option<TrackAnalysisResult> {
+  let started_at = Instant::now();
+  let path = Path::new(file_path);
+  let mut result = TrackAnalysisResult::with_path(index, path);
+  let mut decode_errors = Vec::new();
+  let mut decoded = None;
+  if let Err(error) = check_source_file(path) {
+    decode_errors.push(error);
+  } else {
+    if !crate::is_ffmpeg_only_extension(path) {
+      match analyze_with_symphonia(path, config, cancel_flag) {
+        Ok(Some(sink)) => decoded = Some((sink, DecodeBackend::Symphonia)),
+        Ok(None) => return None,
+        Err(error) => decode_errors.push(error),
+      }
+    }
+    if decoded.is_none() {
+      match analyze_with_ffmpeg(path, config, cancel_flag) {
+        Ok(Some(sink)) => decoded = Some((sink, DecodeBackend::Ffmpeg)),
+        Ok(None) => return None,
+        Err(error) if error.kind == DecodeErrorKind::Cancelled => return None,
+        Err(error) => decode_errors.push(error),
+      }
+    }
+  }
+
+  match decoded {
+    Some((sink, backend)) => {
+      result.decoder_backend = Some(backend.as_str().to_string());
+      sink.finish(path).write_to(&mut result);
+    }
+    None => result.set_decode_failure(&decode_errors),
+  }
+  result.elapsed_ms = started_at.elapsed().as_secs_f64() * 1000.0;
+  Some(result)
+}
+
+static TRACK_ANALYSIS_TASKS: CancelRegistry = CancelRegistry::new();
+
+/// 批量分析曲目：每个文件只解码一次（chromaprint 除外），多文件并行；每完成一首通过 callback 回传结果。
+/// 同一 taskId 可通过 cancelTrackAnalysis 取消，已取消的曲目不再回调
+#[napi]
+pub async fn analyze_tracks(
+  task_id: String,
+  file_paths: Vec<String>,
+  options: Option<TrackAnalysisOptions>,
+  callback: ThreadsafeFunction<TrackAnalysisProgress>,
+) -> napi::Result<TrackAnalysisSummary> {
+  let config = TrackAnalysisConfig::from_options(options.as_ref())?;
+  let started_at = Instant::now();
+  let guard = TRACK_ANALYSIS_TASKS.register(&task_id);
+  let total = file_paths.len() as u32;
+  let processed = AtomicU32::new(0);
+  let failed = AtomicU32::new(0);
+
+  file_paths
+    .par_iter()
+    .enumerate()
+    .for_each(|(index, file_path)| {
+      if guard.is_cancelled() {
+        return;
+      }
+      let Some(result) = analyze_track(index as u32, file_path, config, guard.cancel_flag()) else {
+        return;
+      };
+      if result.error_code.is_some() {
+        failed.fetch_add(1, Ordering::Relaxed);
+      }
+      let current = processed.fetch_add(1, Ordering::SeqCst) + 1;
+      callback.call(
+        Ok(TrackAnalysisProgress {
+          task_id: task_id.clone(),
+          processed: current,
+          total,
+          result,
+        }),
+        ThreadsafeFunctionCallMode::Blocking,
+      );
+    });
+
+  let cancelled = guard.is_cancelled();
+  drop(guard);
+  Ok(TrackAnalysisSummary {
+    task_id,
+    total,
+    processed: processed.load(Ordering::SeqCst),
+    failed: failed.load(Ordering::Relaxed),
+    cancelled,
+    elapsed_ms: started_at.elapsed().as_secs_f64() * 1000.0,
+  })
+}
+
+/// 请求取消批量分析任务；任务不存在（已结束）时返回 false
+#[napi]
+pub fn cancel_track_analysis(task_id: String) -> bool {
+  TRACK_ANALYSIS_TASKS.cancel(&task_id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config(names: &[&str]) -> TrackAnalysisConfig {
+    let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+    TrackAnalysisConfig::from_options(Some(&TrackAnalysisOptions {
+      analyzers: Some(names),
+      chromaprint_max_seconds: None,
+      fast_key_analysis: None,
+      waveform_visual_rate: None,
+    }))
+    .unwrap()
+  }
+
+  fn sine_i16(frames: usize, channels: usize) -> Vec<i16> {
+    (0..frames)
+      .flat_map(|frame| {
+        let value = (frame as f64 * 440.0 * std::f64::consts::TAU / 44_100.0).sin();
+        std::iter::repeat_n((value * 16_000.0) as i16, channels)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn analyzer_set_defaults_to_all_and_rejects_unknown_or_empty() {
+    let all = TrackAnalysisConfig::from_options(None).unwrap().analyzers;
+    assert!(all.hash && all.key && all.waveform && all.loudness);
+    assert!(!all.chromaprint);
+    assert!(config(&["chromaprint"]).analyzers.chromaprint);
+    assert_eq!(
+      config(&[" Key", "loudness"]).analyzers,
+      AnalyzerSet::parse(Some(&["key".to_string(), "loudness".to_string()])).unwrap()
+    );
+    assert!(AnalyzerSet::parse(Some(&["bpm".to_string()])).is_err());
+    assert!(AnalyzerSet::parse(Some(&[])).is_err());
+  }
+
+  #[test]
+  fn sink_matches_single_pass_across_fan_out_blocks() {
+    let samples = sine_i16(FAN_OUT_BLOCK_FRAMES * 2 + 777, 2);
+    let mut sink = TrackSink::new(config(&["hash", "waveform", "loudness"]), 44_100, 2, None);
+    for packet in samples.chunks(1152 * 2) {
+      sink.push(packet, None, 44_100, 2);
+    }
+    let path = Path::new("a.flac");
+    let result = sink.finish(path);
+
+    let mut hasher = PcmContentHasher::new(true);
+    hasher.push_i16(&samples, 44_100, 2);
+    let (hash, quality_label) = hasher.finish(path);
+    let mut loudness = LoudnessAccumulator::default();
+    let samples_f32: Vec<f32> = samples.iter().map(|&s| s as f32 / 32768.0).collect();
+    loudness.push(&samples_f32, 2);
+    let (integrated_db, peak_db) = loudness.finish().unwrap();
+
+    assert_eq!(result.sha256_hash, Some(hash));
+    assert_eq!(result.quality_label, quality_label);
+    let measured = result.loudness.unwrap();
+    assert!((measured.integrated_db - integrated_db).abs() < 1e-9);
+    assert!((measured.peak_db - peak_db).abs() < 1e-9);
+    assert!((result.duration_sec - samples.len() as f64 / 2.0 / 44_100.0).abs() < 1e-9);
+    let waveform = result.waveform.unwrap();
+    assert_eq!(waveform.total_frames(), samples.len() / 2);
+    assert!(waveform.len() > 0);
+    assert!(result.errors.is_empty());
+  }
+
+  #[test]
+  fn spec_change_keeps_hash_and_reports_other_analyzers() {
+    let mut sink = TrackSink::new(config(&["hash", "loudness"]), 44_100, 2, None);
+    sink.push(&sine_i16(4096, 2), None, 44_100, 2);
+    sink.push(&sine_i16(4096, 1), None, 48_000, 1);
+    let result = sink.finish(Path::new("a.mp3"));
+
+    assert!(result.sha256_hash.is_some());
+    assert!(result.loudness.is_none());
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].analyzer, ANALYZER_LOUDNESS);
+    assert_eq!((result.sample_rate, result.channels), (48_000, 1));
+  }
+}
@@ -0,0 +1,720 @@
+//! Mixxx 波形金字塔：一次遍历 PCM 得到最细层（默认 441 点/秒），逐层 2:1 合并
+//! （均值取平均、峰值取最大）直到总览分辨率；缩放时按需读取某一层的区间，无需重新分析。
+//!
+//! 文件格式（小端）：
+//! - 头部 32 字节：魔数 `FRKBWPYR`、格式版本 u32、层数 u32、采样率 u32、保留 u32、总帧数 u64
+//! - 层表：每层 24 字节，步长（帧/点）f64、点数 u64、数据偏移 u64
+//! - 层数据：按点交错，每点 16 字节，依次为 low/mid/high/all 的 left/right/peakLeft/peakRight
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::mixxx_waveform::{
+  pcm_buffer_to_f32, scale_mixxx_value, BandKey, ColumnPeakAccumulator, MixxxBandSplitter,
+  MixxxWaveformBand, MixxxWaveformBands, MIXXX_WAVEFORM_POINTS_PER_SECOND,
+};
+
+const WAVEFORM_PYRAMID_MAGIC: &[u8; 8] = b"FRKBWPYR";
+/// 文件格式或数值定义变化时递增
+pub const WAVEFORM_PYRAMID_FORMAT_VERSION: u32 = 1;
+const HEADER_BYTES: usize = 32;
+const LEVEL_ENTRY_BYTES: usize = 24;
+const POINT_BYTES: usize = 16;
+const BANDS: [BandKey; 4] = [BandKey::Low, BandKey::Mid, BandKey::High, BandKey::All];
+/// 最细层上限（点/秒），避免误传采样率级别的请求
+const MAX_BASE_RATE: f64 = 8_820.0;
+const DEFAULT_MIN_POINTS: u32 = 512;
+const MAX_LEVELS: usize = 24;
+
+/// 金字塔生成选项
+#[napi(object)]
+#[derive(Default)]
+pub struct WaveformPyramidOptions {
+  /// 最细层点/秒，默认 441（同 Mixxx 主波形）
+  pub base_rate: Option<f64>,
+  /// 最粗层点数不超过该值即停止，默认 512
+  pub min_points: Option<u32>,
+}
+
+/// 金字塔某一层（或层内区间）
+#[napi(object)]
+pub struct WaveformPyramidLevel {
+  pub level: u32,
+  /// 每点覆盖的 PCM 帧数
+  pub step: f64,
+  /// 点/秒
+  pub rate: f64,
+  /// 该层总点数
+  pub length: u32,
+  /// 本次返回区间的起点（整层时为 0）
+  pub start_point: u32,
+  pub bands: MixxxWaveformBands,
+}
+
+#[napi(object)]
+pub struct WaveformPyramid {
+  pub version: u32,
+  pub duration: f64,
+  pub sample_rate: u32,
+  pub total_frames: f64,
+  /// 由细到粗
+  pub levels: Vec<WaveformPyramidLevel>,
+}
+
+/// 层描述（不含数据）
+#[napi(object)]
+pub struct WaveformPyramidLevelInfo {
+  pub level: u32,
+  pub step: f64,
+  pub rate: f64,
+  pub length: u32,
+}
+
+#[napi(object)]
+pub struct WaveformPyramidInfo {
+  pub version: u32,
+  pub duration: f64,
+  pub sample_rate: u32,
+  pub total_frames: f64,
+  pub levels: Vec<WaveformPyramidLevelInfo>,
+}
+
+/// 一层的点数据，每点 16 字节（布局同文件）
+pub(crate) struct PyramidLevelData {
+  pub(crate) step: f64,
+  pub(crate) points: Vec<u8>,
+}
+
+impl PyramidLevelData {
+  pub(crate) fn len(&self) -> usize {
+    self.points.len() / POINT_BYTES
+  }
+}
+
+pub(crate) struct PyramidData {
+  pub(crate) sample_rate: u32,
+  pub(crate) total_frames: u64,
+  pub(crate) levels: Vec<PyramidLevelData>,
+}
+
+/// 每点未量化的 [band][channel] 均值与峰值
+#[derive(Clone, Copy, Default)]
+struct RawPoint {
+  values: [[f32; 2]; 4],
+  peaks: [[f32; 2]; 4],
+}
+
+impl RawPoint {
+  fn quantize(&self, out: &mut Vec<u8>) {
+    for (band_index, band) in BANDS.iter().enumerate() {
+      let values = self.values[band_index];
+      let peaks = self.peaks[band_index];
+      out.push(scale_mixxx_value(values[0] as f64, *band));
+      out.push(scale_mixxx_value(values[1] as f64, *band));
+      out.push(scale_mixxx_value(peaks[0] as f64, *band));
+      out.push(scale_mixxx_value(peaks[1] as f64, *band));
+    }
+  }
+
+  fn merge(children: &[RawPoint]) -> RawPoint {
+    let mut merged = RawPoint::default();
+    let count = children.len() as f32;
+    for child in children {
+      for band in 0..4 {
+        for channel in 0..2 {
+          merged.values[band][channel] += child.values[band][channel] / count;
+          merged.peaks[band][channel] = merged.peaks[band][channel].max(child.peaks[band][channel]);
+        }
+      }
+    }
+    merged
+  }
+}
+
+/// 构建中的一层：已量化的点，加上等待与下一点配对合并到更粗一层的未量化点
+struct PendingLevel {
+  step: f64,
+  points: Vec<u8>,
+  carry: Option<RawPoint>,
+}
+
+/// 流式构建：逐块喂入交错 f32 PCM，每个点到达即量化并逐层向上合并，
+/// 每层只暂存一个未量化点
+pub(crate) struct WaveformPyramidBuilder {
+  sample_rate: u32,
+  channels: usize,
+  step: f64,
+  splitters: [MixxxBandSplitter; 2],
+  /// 按 band * 2 + channel 排列的峰值
+  columns: ColumnPeakAccumulator<8>,
+  levels: Vec<PendingLevel>,
+}
+
+impl WaveformPyramidBuilder {
+  pub(crate) fn new(sample_rate: u32, channels: u16, base_rate: Option<f64>) -> Result<Self> {
+    if channels == 0 {
+      return Err(Error::from_reason("Missing channels for Mixxx waveform"));
+    }
+    if sample_rate == 0 {
+      return Err(Error::from_reason("Missing sample rate for Mixxx waveform"));
+    }
+    let sample_rate_f = sample_rate as f64;
+    let base_rate = base_rate
+      .filter(|rate| rate.is_finite() && *rate > 0.0)
+      .unwrap_or(MIXXX_WAVEFORM_POINTS_PER_SECOND)
+      .min(MAX_BASE_RATE)
+      .min(sample_rate_f);
+    let step = sample_rate_f / base_rate;
+    Ok(Self {
+      sample_rate,
+      channels: channels as usize,
+      step,
+      splitters: [
+        MixxxBandSplitter::new(sample_rate_f)?,
+        MixxxBandSplitter::new(sample_rate_f)?,
+      ],
+      columns: ColumnPeakAccumulator::new(step),
+      levels: Vec::new(),
+    })
+  }
+
+  pub(crate) fn push(&mut self, interleaved: &[f32]) {
+    let channels = self.channels;
+    for frame in interleaved.chunks_exact(channels) {
+      let left = frame[0] as f64;
+      let right = if channels > 1 { frame[1] as f64 } else { left };
+      let [left_low, left_mid, left_high] = self.splitters[0].process(left);
+      let [right_low, right_mid, right_high] = self.splitters[1].process(right);
+      if let Some(peaks) = self.columns.push_frame([
+        left_low, right_low, left_mid, right_mid, left_high, right_high, left, right,
+      ]) {
+        self.store(peaks);
+      }
+    }
+  }
+
+  /// 最细层每点的均值即该点峰值（同 Mixxx 主波形的单步峰值）
+  fn store(&mut self, peaks: [f64; 8]) {
+    let mut point = RawPoint::default();
+    for band in 0..4 {
+      for channel in 0..2 {
+        let value = peaks[band * 2 + channel] as f32;
+        point.values[band][channel] = value;
+        point.peaks[band][channel] = value;
+      }
+    }
+    self.push_point(0, point);
+  }
+
+  /// 量化进 level 层；与该层暂存点凑成一对后合并，继续送往更粗一层
+  fn push_point(&mut self, mut level: usize, mut point: RawPoint) {
+    while level < MAX_LEVELS {
+      if level == self.levels.len() {
+        self.levels.push(PendingLevel {
+          step: self.step * 2f64.powi(level as i32),
+          points: Vec::new(),
+          carry: None,
+        });
+      }
+      let pending = &mut self.levels[level];
+      point.quantize(&mut pending.points);
+      match pending.carry.take() {
+        None => {
+          pending.carry = Some(point);
+          return;
+        }
+        Some(first) => {
+          point = RawPoint::merge(&[first, point]);
+          level += 1;
+        }
+      }
+    }
+  }
+
+  pub(crate) fn finish(mut self, min_points: Option<u32>) -> PyramidData {
+    if let Some(peaks) = self.columns.flush() {
+      self.store(peaks);
+    }
+    // 奇数点数的层把落单的点原样并入更粗一层
+    let mut level = 0;
+    while level < self.levels.len() {
+      if let Some(carry) = self.levels[level].carry.take() {
+        if level + 1 < self.levels.len() {
+          self.push_point(level + 1, carry);
+        }
+      }
+      level += 1;
+    }
+    let min_points = min_points
+      .filter(|value| *value > 0)
+      .unwrap_or(DEFAULT_MIN_POINTS) as usize;
+    let mut levels: Vec<PyramidLevelData> = Vec::new();
+    for pending in std::mem::take(&mut self.levels) {
+      levels.push(PyramidLevelData {
+        step: pending.step,
+        points: pending.points,
+      });
+      if levels[levels.len() - 1].len() <= min_points {
+        break;
+      }
+    }
+    if levels.is_empty() {
+      levels.push(PyramidLevelData {
+        step: self.step,
+        points: Vec::new(),
+      });
+    }
+    PyramidData {
+      sample_rate: self.sample_rate,
+      total_frames: self.columns.total_frames(),
+      levels,
+    }
+  }
+}
+
+fn level_to_napi(
+  level: u32,
+  step: f64,
+  sample_rate: u32,
+  length: usize,
+  start_point: usize,
+  points: &[u8],
+) -> WaveformPyramidLevel {
+  let count = points.len() / POINT_BYTES;
+  let series = |offset: usize| -> Buffer {
+    Buffer::from(
+      (0..count)
+        .map(|index| points[index * POINT_BYTES + offset])
+        .collect::<Vec<u8>>(),
+    )
+  };
+  let band = |band: usize| MixxxWaveformBand {
+    left: series(band * 4),
+    right: series(band * 4 + 1),
+    peak_left: series(band * 4 + 2),
+    peak_right: series(band * 4 + 3),
+  };
+  WaveformPyramidLevel {
+    level,
+    step,
+    rate: sample_rate as f64 / step,
+    length: length as u32,
+    start_point: start_point as u32,
+    bands: MixxxWaveformBands {
+      low: band(0),
+      mid: band(1),
+      high: band(2),
+      all: band(3),
+    },
+  }
+}
+
+fn band_from_napi(band: &MixxxWaveformBand) -> [&[u8]; 4] {
+  [
+    band.left.as_ref(),
+    band.right.as_ref(),
+    band.peak_left.as_ref(),
+    band.peak_right.as_ref(),
+  ]
+}
+
+fn level_from_napi(level: &WaveformPyramidLevel) -> Result<PyramidLevelData> {
+  let bands = &level.bands;
+  let series: Vec<&[u8]> = [&bands.low, &bands.mid, &bands.high, &bands.all]
+    .into_iter()
+    .flat_map(band_from_napi)
+    .collect();
+  let count = series[0].len();
+  if series.iter().any(|values| values.len() != count) {
+    return Err(Error::from_reason("波形金字塔各频段长度不一致"));
+  }
+  let mut points = Vec::with_capacity(count * POINT_BYTES);
+  for index in 0..count {
+    points.extend(series.iter().map(|values| values[index]));
+  }
+  Ok(PyramidLevelData {
+    step: level.step,
+    points,
+  })
+}
+
+impl PyramidData {
+  fn to_napi(&self) -> WaveformPyramid {
+    WaveformPyramid {
+      version: WAVEFORM_PYRAMID_FORMAT_VERSION,
+      duration: self.total_frames as f64 / self.sample_rate as f64,
+      sample_rate: self.sample_rate,
+      total_frames: self.total_frames as f64,
+      levels: self
+        .levels
+        .iter()
+        .enumerate()
+        .map(|(index, level)| {
+          level_to_napi(
+            index as u32,
+            level.step,
+            self.sample_rate,
+            level.len(),
+            0,
+            &level.points,
+          )
+        })
+        .collect(),
+    }
+  }
+
+  pub(crate) fn encode(&self) -> Vec<u8> {
+    let table_bytes = self.levels.len() * LEVEL_ENTRY_BYTES;
+    let data_bytes: usize = self.levels.iter().map(|level| level.points.len()).sum();
+    let mut bytes = Vec::with_capacity(HEADER_BYTES + table_bytes + data_bytes);
+    bytes.extend_from_slice(WAVEFORM_PYRAMID_MAGIC);
+    bytes.extend_from_slice(&WAVEFORM_PYRAMID_FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(self.levels.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&self.total_frames.to_le_bytes());
+    let mut offset = (HEADER_BYTES + table_bytes) as u64;
+    for level in &self.levels {
+      bytes.extend_from_slice(&level.step.to_le_bytes());
+      bytes.extend_from_slice(&(level.len() as u64).to_le_bytes());
+      bytes.extend_from_slice(&offset.to_le_bytes());
+      offset += level.points.len() as u64;
+    }
+    for level in &self.levels {
+      bytes.extend_from_slice(&level.points);
+    }
+    bytes
+  }
+}
+
+/// 文件头与层表
+struct PyramidHeader {
+  sample_rate: u32,
+  total_frames: u64,
+  /// (步长, 点数, 数据偏移)
+  levels: Vec<(f64, u64, u64)>,
+}
+
+fn read_header(file: &mut File) -> std::result::Result<PyramidHeader, String> {
+  let invalid = || "波形金字塔文件无效".to_string();
+  let mut head = [0u8; HEADER_BYTES];
+  file.read_exact(&mut head).map_err(|_| invalid())?;
+  if &head[0..8] != WAVEFORM_PYRAMID_MAGIC {
+    return Err(invalid());
+  }
+  let u32_at =
+    |bytes: &[u8], offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+  let u64_at =
+    |bytes: &[u8], offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+  let version = u32_at(&head, 8);
+  if version != WAVEFORM_PYRAMID_FORMAT_VERSION {
+    return Err(format!("不支持的波形金字塔版本: {}", version));
+  }
+  let level_count = u32_at(&head, 12) as usize;
+  let sample_rate = u32_at(&head, 16);
+  if level_count == 0 || level_count > MAX_LEVELS || sample_rate == 0 {
+    return Err(invalid());
+  }
+  let file_len = file.metadata().map_err(|error| error.to_string())?.len();
+  let mut table = vec![0u8; level_count * LEVEL_ENTRY_BYTES];
+  file.read_exact(&mut table).map_err(|_| invalid())?;
+  let levels: Vec<(f64, u64, u64)> = table
+    .chunks_exact(LEVEL_ENTRY_BYTES)
+    .map(|entry| {
+      (
+        f64::from_le_bytes(entry[0..8].try_into().unwrap()),
+        u64_at(entry, 8),
+        u64_at(entry, 16),
+      )
+    })
+    .collect();
+  let valid = levels.iter().all(|(step, length, offset)| {
+    step.is_finite()
+      && *step > 0.0
+      && offset
+        .checked_add(length.saturating_mul(POINT_BYTES as u64))
+        .is_some_and(|end| end <= file_len)
+  });
+  if !valid {
+    return Err(invalid());
+  }
+  Ok(PyramidHeader {
+    sample_rate,
+    total_frames: u64_at(&head, 24),
+    levels,
+  })
+}
+
+fn open_pyramid(file_path: &str) -> Result<(File, PyramidHeader)> {
+  let mut file = File::open(file_path).map_err(|error| Error::from_reason(error.to_string()))?;
+  let header = read_header(&mut file).map_err(Error::from_reason)?;
+  Ok((file, header))
+}
+
+/// 读取某层 [start, start + count) 的原始点数据，返回实际起点与数据
+fn read_level_points(
+  file: &mut File,
+  header: &PyramidHeader,
+  level: usize,
+  start_point: Option<u32>,
+  count: Option<u32>,
+) -> std::result::Result<(u64, Vec<u8>), String> {
+  let Some((_, length, offset)) = header.levels.get(level).copied() else {
+    return Err(format!("波形金字塔没有第 {} 层", level));
+  };
+  let start = (start_point.unwrap_or(0) as u64).min(length);
+  let end = count
+    .map(|count| start.saturating_add(count as u64).min(length))
+    .unwrap_or(length);
+  let mut points = vec![0u8; ((end - start) as usize) * POINT_BYTES];
+  file
+    .seek(SeekFrom::Start(offset + start * POINT_BYTES as u64))
+    .and_then(|_| file.read_exact(&mut points))
+    .map_err(|error| error.to_string())?;
+  Ok((start, points))
+}
+
+fn read_level_range(
+  file: &mut File,
+  header: &PyramidHeader,
+  level: usize,
+  start_point: Option<u32>,
+  count: Option<u32>,
+) -> Result<WaveformPyramidLevel> {
+  let (start, points) =
+    read_level_points(file, header, level, start_point, count).map_err(Error::from_reason)?;
+  let (step, length, _) = header.levels[level];
+  Ok(level_to_napi(
+    level as u32,
+    step,
+    header.sample_rate,
+    length as usize,
+    start as usize,
+    &points,
+  ))
+}
+
+/// 一次遍历 PCM 生成波形金字塔
+#[napi]
+pub fn compute_waveform_pyramid(
+  pcm_data: Buffer,
+  sample_rate: u32,
+  channels: u8,
+  options: Option<WaveformPyramidOptions>,
+) -> Result<WaveformPyramid> {
+  let options = options.unwrap_or_default();
+  let samples = pcm_buffer_to_f32(&pcm_data);
+  if samples.len() < channels.max(1) as usize {
+    return Err(Error::from_reason("Empty PCM buffer"));
+  }
+  let mut builder = WaveformPyramidBuilder::new(sample_rate, channels as u16, options.base_rate)?;
+  builder.push(&samples);
+  Ok(builder.finish(options.min_points).to_napi())
+}
+
+/// 把金字塔写入文件（先写临时文件再替换）
+#[napi]
+pub fn save_waveform_pyramid(file_path: String, pyramid: WaveformPyramid) -> Result<()> {
+  if pyramid.sample_rate == 0 || pyramid.levels.is_empty() || pyramid.levels.len() > MAX_LEVELS {
+    return Err(Error::from_reason("波形金字塔数据无效"));
+  }
+  let data = PyramidData {
+    sample_rate: pyramid.sample_rate,
+    total_frames: pyramid.total_frames.max(0.0) as u64,
+    levels: pyramid
+      .levels
+      .iter()
+      .map(level_from_napi)
+      .collect::<Result<Vec<_>>>()?,
+  };
+  let path = Path::new(&file_path);
+  if let Some(parent) = path
+    .parent()
+    .filter(|parent| !parent.as_os_str().is_empty())
+  {
+    fs::create_dir_all(parent).map_err(|error| Error::from_reason(error.to_string()))?;
+  }
+  let tmp_path = path.with_extension(format!("{}.{}", std::process::id(), "tmp"));
+  let written = File::create(&tmp_path).and_then(|mut file| {
+    file.write_all(&data.encode())?;
+    file.sync_data()
+  });
+  if let Err(error) = written.and_then(|_| fs::rename(&tmp_path, path)) {
+    let _ = fs::remove_file(&tmp_path);
+    return Err(Error::from_reason(error.to_string()));
+  }
+  Ok(())
+}
+
+/// 只读文件头与层表
+#[napi]
+pub fn read_waveform_pyramid_info(file_path: String) -> Result<WaveformPyramidInfo> {
+  let (_, header) = open_pyramid(&file_path)?;
+  Ok(WaveformPyramidInfo {
+    version: WAVEFORM_PYRAMID_FORMAT_VERSION,
+    duration: header.total_frames as f64 / header.sample_rate as f64,
+    sample_rate: header.sample_rate,
+    total_frames: header.total_frames as f64,
+    levels: header
+      .levels
+      .iter()
+      .enumerate()
+      .map(|(index, (step, length, _))| WaveformPyramidLevelInfo {
+        level: index as u32,
+        step: *step,
+        rate: header.sample_rate as f64 / step,
+        length: *length as u32,
+      })
+      .collect(),
+  })
+}
+
+/// 读取某一层的点区间；不传 startPoint/count 时读整层
+#[napi]
+pub fn read_waveform_pyramid_level(
+  file_path: String,
+  level: u32,
+  start_point: Option<u32>,
+  count: Option<u32>,
+) -> Result<WaveformPyramidLevel> {
+  let (mut file, header) = open_pyramid(&file_path)?;
+  read_level_range(&mut file, &header, level as usize, start_point, count)
+}
+
+/// 读取整个金字塔
+#[napi]
+pub fn load_waveform_pyramid(file_path: String) -> Result<WaveformPyramid> {
+  let (mut file, header) = open_pyramid(&file_path)?;
+  let levels = (0..header.levels.len())
+    .map(|level| read_level_range(&mut file, &header, level, None, None))
+    .collect::<Result<Vec<_>>>()?;
+  Ok(WaveformPyramid {
+    version: WAVEFORM_PYRAMID_FORMAT_VERSION,
+    duration: header.total_frames as f64 / header.sample_rate as f64,
+    sample_rate: header.sample_rate,
+    total_frames: header.total_frames as f64,
+    levels,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RATE: u32 = 44_100;
+
+  fn build(pcm: &[f32], channels: u16, chunk: usize) -> PyramidData {
+    let mut builder = WaveformPyramidBuilder::new(RATE, channels, None).unwrap();
+    for part in pcm.chunks(chunk * channels as usize) {
+      builder.push(part);
+    }
+    builder.finish(Some(64))
+  }
+
+  fn point(level: &PyramidLevelData, index: usize) -> &[u8] {
+    &level.points[index * POINT_BYTES..(index + 1) * POINT_BYTES]
+  }
+
+  #[test]
+  fn levels_halve_and_keep_peaks() {
+    // 左声道 3 秒静音后 1 秒满幅，右声道恒为 0.25
+    let frames = RATE as usize * 4;
+    let pcm: Vec<f32> = (0..frames)
+      .flat_map(|frame| [if frame >= RATE as usize * 3 { 1.0 } else { 0.0 }, 0.25])
+      .collect();
+    let pyramid = build(&pcm, 2, 10_000);
+    assert_eq!(pyramid.total_frames, frames as u64);
+    assert_eq!(pyramid.levels[0].len(), 1764);
+    for pair in pyramid.levels.windows(2) {
+      assert_eq!(pair[1].len(), pair[0].len().div_ceil(2));
+      assert_eq!(pair[1].step, pair[0].step * 2.0);
+    }
+    let coarse = pyramid.levels.last().unwrap();
+    assert!(coarse.len() <= 64);
+    // all 频段：右声道均值 / 峰值都约为 0.25，最后一点左声道峰值满幅
+    let last = point(coarse, coarse.len() - 1);
+    assert_eq!(last[12 + 2], 255);
+    assert_eq!(point(coarse, 0)[12], 0);
+    assert_eq!(point(coarse, 0)[12 + 1], 64);
+    assert_eq!(point(coarse, 0)[12 + 3], 64);
+
+    // 分块方式不影响结果
+    let whole = build(&pcm, 2, frames);
+    assert_eq!(whole.levels.len(), pyramid.levels.len());
+    assert!(whole
+      .levels
+      .iter()
+      .zip(&pyramid.levels)
+      .all(|(a, b)| a.points == b.points));
+  }
+
+  #[test]
+  fn incremental_levels_match_merging_unquantized_base() {
+    let mut builder = WaveformPyramidBuilder::new(RATE, 2, None).unwrap();
+    let mut base = Vec::new();
+    for index in 0..1_003 {
+      let peaks: [f64; 8] =
+        std::array::from_fn(|lane| ((index * 7 + lane * 13) % 97) as f64 / 97.0);
+      builder.store(peaks);
+      let mut point = RawPoint::default();
+      for band in 0..4 {
+        for channel in 0..2 {
+          point.values[band][channel] = peaks[band * 2 + channel] as f32;
+          point.peaks[band][channel] = peaks[band * 2 + channel] as f32;
+        }
+      }
+      base.push(point);
+    }
+    let pyramid = builder.finish(Some(10));
+
+    let mut raw = base;
+    for level in &pyramid.levels {
+      let mut expected = Vec::new();
+      for point in &raw {
+        point.quantize(&mut expected);
+      }
+      assert_eq!(level.points, expected);
+      raw = raw.chunks(2).map(RawPoint::merge).collect();
+    }
+    assert_eq!(pyramid.levels.len(), 8);
+    assert!(pyramid.levels[7].len() <= 10 && pyramid.levels[6].len() > 10);
+  }
+
+  #[test]
+  fn file_round_trip_and_range_reads() {
+    let pcm: Vec<f32> = (0..RATE as usize * 3)
+      .map(|frame| 0.5 * (std::f32::consts::TAU * 110.0 * frame as f32 / RATE as f32).sin())
+      .collect();
+    let pyramid = build(&pcm, 1, 4096);
+    let dir = std::env::temp_dir().join(format!("frkb-wpyr-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("track.wpyr");
+    fs::write(&path, pyramid.encode()).unwrap();
+
+    let mut file = File::open(&path).unwrap();
+    let header = read_header(&mut file).unwrap();
+    assert_eq!(header.levels.len(), pyramid.levels.len());
+    assert_eq!(header.total_frames, pcm.len() as u64);
+    let (start, points) = read_level_points(&mut file, &header, 1, Some(10), Some(5)).unwrap();
+    assert_eq!(start, 10);
+    assert_eq!(points.len(), 5 * POINT_BYTES);
+    assert_eq!(
+      points[..],
+      pyramid.levels[1].points[10 * POINT_BYTES..15 * POINT_BYTES]
+    );
+    let (start, points) = read_level_points(&mut file, &header, 0, Some(u32::MAX), None).unwrap();
+    assert_eq!(start as usize, pyramid.levels[0].len());
+    assert!(points.is_empty());
+    assert!(read_level_points(&mut file, &header, 99, None, None).is_err());
+
+    let mut corrupt = pyramid.encode();
+    corrupt.truncate(corrupt.len() - 1);
+    fs::write(&path, corrupt).unwrap();
+    assert!(read_header(&mut File::open(&path).unwrap()).is_err());
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,683 @@
+//! 波形栅格化：把 Mixxx / Pioneer 波形列按时间区间绘制成 RGBA 图像（可选 PNG），
+//! 可叠加节拍网格与 cue，供列表缩略图、导出与无界面测试使用。
+//!
+//! 各来源先转换为统一的 [`RenderColumn`]（各频段上/下半幅度 0..=1），再按配色方案逐像素列绘制。
+
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::mixxx_waveform::{MixxxWaveformBand, MixxxWaveformData};
+
+const MAX_IMAGE_SIDE: u32 = 16_384;
+/// 与 Pioneer 蓝色波形读取时的两档颜色一致，按白度插值
+const BLUE_COLOR: [u8; 3] = [43, 89, 255];
+const BLUE_INTENSE_COLOR: [u8; 3] = [116, 246, 244];
+/// 三频配色：低频蓝、中频橙、高频白（后画的覆盖先画的）
+const THREE_BAND_COLORS: [[u8; 3]; 3] = [[32, 83, 217], [242, 170, 60], [255, 255, 255]];
+const BEAT_LINE_COLOR: [u8; 4] = [255, 255, 255, 56];
+const BAR_LINE_COLOR: [u8; 4] = [255, 255, 255, 136];
+const DEFAULT_CUE_COLOR: [u8; 3] = [0x30, 0xd2, 0x6e];
+const LOOP_ALPHA: u8 = 48;
+const CUE_MARKER_ROWS: i64 = 4;
+
+/// 配色方案
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum WaveformColorScheme {
+  MixxxRgb,
+  PioneerRgb,
+  PioneerThreeBand,
+  Blue,
+}
+
+impl WaveformColorScheme {
+  pub(crate) fn parse(value: Option<&str>, default: WaveformColorScheme) -> Result<Self> {
+    match value.map(|value| value.trim().to_ascii_lowercase()) {
+      None => Ok(default),
+      Some(value) => match value.as_str() {
+        "" => Ok(default),
+        "mixxx-rgb" => Ok(Self::MixxxRgb),
+        "pioneer-rgb" => Ok(Self::PioneerRgb),
+        "pioneer-3band" => Ok(Self::PioneerThreeBand),
+        "blue" => Ok(Self::Blue),
+        _ => Err(Error::from_reason(format!("未知的波形配色: {}", value))),
+      },
+    }
+  }
+}
+
+/// 固定 BPM 网格
+#[napi(object)]
+pub struct WaveformRenderBeatGrid {
+  pub bpm: f64,
+  pub first_beat_ms: f64,
+  /// 每小节拍数，默认 4；小节线画得更亮
+  pub beats_per_bar: Option<u32>,
+}
+
+#[napi(object)]
+pub struct WaveformRenderCue {
+  pub time_ms: f64,
+  /// loop 终点；给出时画半透明区间
+  pub loop_end_ms: Option<f64>,
+  /// `#rrggbb`，默认 Rekordbox 绿色
+  pub color_hex: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct WaveformRenderOptions {
+  pub width: u32,
+  pub height: u32,
+  /// 起止时间（秒），默认整轨
+  pub start_sec: Option<f64>,
+  pub end_sec: Option<f64>,
+  /// mixxx-rgb / pioneer-rgb / pioneer-3band / blue，默认按数据来源
+  pub color_scheme: Option<String>,
+  /// `#rrggbb` 或 `#rrggbbaa`，默认透明
+  pub background_hex: Option<String>,
+  /// 来源没有列频率时（如 PWV6 概览）必须给出曲目时长
+  pub duration_sec: Option<f64>,
+  pub beat_grid: Option<WaveformRenderBeatGrid>,
+  pub cues: Option<Vec<WaveformRenderCue>>,
+  /// rgba（默认，逐行 RGBA8）或 png
+  pub format: Option<String>,
+}
+
+#[napi(object)]
+pub struct WaveformImage {
+  pub width: u32,
+  pub height: u32,
+  pub format: String,
+  pub data: Buffer,
+}
+
+/// 栅格化前的统一列：low/mid/high/all 的 [上半, 下半] 幅度（0..=1），可带来源自带的颜色
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub(crate) struct RenderColumn {
+  pub(crate) bands: [[f32; 2]; 4],
+  pub(crate) color: Option<[u8; 3]>,
+}
+
+pub(crate) struct RenderSource {
+  pub(crate) columns: Vec<RenderColumn>,
+  /// 列/秒；None 时由 `duration_sec` 推算
+  pub(crate) columns_per_sec: Option<f64>,
+}
+
+pub(crate) struct RenderImage {
+  pub(crate) width: u32,
+  pub(crate) height: u32,
+  pub(crate) pixels: Vec<u8>,
+}
+
+impl RenderImage {
+  fn new(width: u32, height: u32, background: [u8; 4]) -> Self {
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for _ in 0..width as usize * height as usize {
+      pixels.extend_from_slice(&background);
+    }
+    Self {
+      width,
+      height,
+      pixels,
+    }
+  }
+
+  /// 源色按 alpha 覆盖到目标像素（source-over）
+  fn blend(&mut self, x: i64, y: i64, color: [u8; 4]) {
+    if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+      return;
+    }
+    let offset = (y as usize * self.width as usize + x as usize) * 4;
+    let pixel = &mut self.pixels[offset..offset + 4];
+    let source_alpha = f32::from(color[3]) / 255.0;
+    let target_alpha = f32::from(pixel[3]) / 255.0;
+    let out_alpha = source_alpha + target_alpha * (1.0 - source_alpha);
+    if out_alpha <= 0.0 {
+      return;
+    }
+    for channel in 0..3 {
+      let value = (f32::from(color[channel]) * source_alpha
+        + f32::from(pixel[channel]) * target_alpha * (1.0 - source_alpha))
+        / out_alpha;
+      pixel[channel] = value.round().clamp(0.0, 255.0) as u8;
+    }
+    pixel[3] = (out_alpha * 255.0).round() as u8;
+  }
+
+  fn fill_column(&mut self, x: i64, top: i64, bottom: i64, color: [u8; 4]) {
+    for y in top.max(0)..=bottom.min(self.height as i64 - 1) {
+      self.blend(x, y, color);
+    }
+  }
+}
+
+pub(crate) fn parse_hex_color(value: &str) -> Option<[u8; 4]> {
+  let hex = value.trim().trim_start_matches('#');
+  if hex.len() != 6 && hex.len() != 8 {
+    return None;
+  }
+  let mut color = [0u8, 0, 0, 255];
+  for (index, slot) in color.iter_mut().enumerate().take(hex.len() / 2) {
+    *slot = u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok()?;
+  }
+  Some(color)
+}
+
+fn lerp_color(from: [u8; 3], to: [u8; 3], amount: f32) -> [u8; 3] {
+  let amount = amount.clamp(0.0, 1.0);
+  let mut color = [0u8; 3];
+  for channel in 0..3 {
+    let value =
+      f32::from(from[channel]) + (f32::from(to[channel]) - f32::from(from[channel])) * amount;
+    color[channel] = value.round() as u8;
+  }
+  color
+}
+
+/// 按低→红、中→绿、高→蓝混色并归一到最亮通道（同 Mixxx RGB 波形）
+fn band_ratio_color(low: f32, mid: f32, high: f32) -> [u8; 3] {
+  let max = low.max(mid).max(high);
+  if max <= 0.0 {
+    return [0, 0, 0];
+  }
+  [low, mid, high].map(|value| ((value / max) * 255.0).round() as u8)
+}
+
+fn merge_columns(columns: &[RenderColumn]) -> RenderColumn {
+  let mut merged = RenderColumn::default();
+  let mut loudest = -1.0f32;
+  for column in columns {
+    for band in 0..4 {
+      for side in 0..2 {
+        merged.bands[band][side] = merged.bands[band][side].max(column.bands[band][side]);
+      }
+    }
+    let level = column.bands[3][0].max(column.bands[3][1]);
+    if level > loudest {
+      loudest = level;
+      merged.color = column.color;
+    }
+  }
+  merged
+}
+
+fn draw_waveform_column(
+  image: &mut RenderImage,
+  x: i64,
+  column: &RenderColumn,
+  scheme: WaveformColorScheme,
+) {
+  let center = image.height as f32 / 2.0;
+  let span = |levels: [f32; 2]| -> Option<(i64, i64)> {
+    if levels[0] <= 0.0 && levels[1] <= 0.0 {
+      return None;
+    }
+    let top = (center - levels[0].clamp(0.0, 1.0) * center).floor() as i64;
+    let bottom = (center + levels[1].clamp(0.0, 1.0) * center).ceil() as i64 - 1;
+    Some((top, bottom.max(top)))
+  };
+  let [low, mid, high, all] = column.bands;
+  let peak = |levels: [f32; 2]| levels[0].max(levels[1]);
+  match scheme {
+    WaveformColorScheme::PioneerThreeBand => {
+      for (band, color) in [low, mid, high].into_iter().zip(THREE_BAND_COLORS) {
+        if let Some((top, bottom)) = span(band) {
+          image.fill_column(x, top, bottom, [color[0], color[1], color[2], 255]);
+        }
+      }
+    }
+    _ => {
+      let Some((top, bottom)) = span(all) else {
+        return;
+      };
+      let color = match scheme {
+        WaveformColorScheme::PioneerRgb => column
+          .color
+          .unwrap_or_else(|| band_ratio_color(peak(low), peak(mid), peak(high))),
+        WaveformColorScheme::Blue => {
+          let band_max = peak(low).max(peak(mid)).max(peak(high));
+          let whiteness = if band_max > 0.0 {
+            peak(high) / band_max
+          } else {
+            0.0
+          };
+          lerp_color(BLUE_COLOR, BLUE_INTENSE_COLOR, whiteness)
+        }
+        _ => band_ratio_color(peak(low), peak(mid), peak(high)),
+      };
+      image.fill_column(x, top, bottom, [color[0], color[1], color[2], 255]);
+    }
+  }
+}
+
+/// 按时间区间栅格化（不含格式编码）
+pub(crate) fn render_waveform(
+  source: &RenderSource,
+  options: &WaveformRenderOptions,
+  default_scheme: WaveformColorScheme,
+) -> Result<RenderImage> {
+  let (width, height) = (options.width, options.height);
+  if width == 0 || height == 0 || width > MAX_IMAGE_SIDE || height > MAX_IMAGE_SIDE {
+    return Err(Error::from_reason(format!(
+      "图像尺寸无效: {}x{}",
+      width, height
+    )));
+  }
+  let scheme = WaveformColorScheme::parse(options.color_scheme.as_deref(), default_scheme)?;
+  let background = match options.background_hex.as_deref() {
+    Some(value) => {
+      parse_hex_color(value).ok_or_else(|| Error::from_reason(format!("背景色无效: {}", value)))?
+    }
+    None => [0, 0, 0, 0],
+  };
+  let count = source.columns.len();
+  let columns_per_sec = match source.columns_per_sec {
+    Some(rate) if rate.is_finite() && rate > 0.0 => rate,
+    _ => match options.duration_sec {
+      Some(duration) if duration.is_finite() && duration > 0.0 => count as f64 / duration,
+      _ => return Err(Error::from_reason("波形缺少列频率，需提供 durationSec")),
+    },
+  };
+  let duration = count as f64 / columns_per_sec;
+  let start = options.start_sec.unwrap_or(0.0);
+  let end = options.end_sec.unwrap_or(duration);
+  if !start.is_finite() || !end.is_finite() || end <= start {
+    return Err(Error::from_reason("渲染时间区间无效"));
+  }
+
+  let mut image = RenderImage::new(width, height, background);
+  let sec_per_pixel = (end - start) / width as f64;
+  for x in 0..width {
+    let from = (start + x as f64 * sec_per_pixel) * columns_per_sec;
+    let to = (start + (x + 1) as f64 * sec_per_pixel) * columns_per_sec;
+    if to <= 0.0 {
+      continue;
+    }
+    let first = from.max(0.0).floor() as usize;
+    if first >= count {
+      break;
+    }
+    let last = (to.ceil() as usize).clamp(first + 1, count);
+    let column = merge_columns(&source.columns[first..last]);
+    draw_waveform_column(&mut image, x as i64, &column, scheme);
+  }
+
+  let time_to_x = |time_sec: f64| ((time_sec - start) / sec_per_pixel + 1e-9).floor() as i64;
+  let cues = options.cues.as_deref().unwrap_or_default();
+  for cue in cues {
+    let color = cue_color(cue);
+    if let Some(loop_end_ms) = cue.loop_end_ms.filter(|end_ms| *end_ms > cue.time_ms) {
+      let from = time_to_x(cue.time_ms / 1000.0).max(0);
+      let to = time_to_x(loop_end_ms / 1000.0).min(width as i64 - 1);
+      for x in from..=to {
+        image.fill_column(
+          x,
+          0,
+          height as i64 - 1,
+          [color[0], color[1], color[2], LOOP_ALPHA],
+        );
+      }
+    }
+  }
+  if let Some(grid) = options.beat_grid.as_ref() {
+    draw_beat_grid(&mut image, grid, start, end, time_to_x);
+  }
+  for cue in cues {
+    let color = cue_color(cue);
+    let x = time_to_x(cue.time_ms / 1000.0);
+    let opaque = [color[0], color[1], color[2], 255];
+    image.fill_column(x, 0, height as i64 - 1, opaque);
+    for row in 0..CUE_MARKER_ROWS {
+      for offset in 1..CUE_MARKER_ROWS - row {
+        image.blend(x - offset, row, opaque);
+        image.blend(x + offset, row, opaque);
+      }
+    }
+  }
+  Ok(image)
+}
+
+fn cue_color(cue: &WaveformRenderCue) -> [u8; 3] {
+  cue
+    .color_hex
+    .as_deref()
+    .and_then(parse_hex_color)
+    .map(|color| [color[0], color[1], color[2]])
+    .unwrap_or(DEFAULT_CUE_COLOR)
+}
+
+fn draw_beat_grid(
+  image: &mut RenderImage,
+  grid: &WaveformRenderBeatGrid,
+  start: f64,
+  end: f64,
+  time_to_x: impl Fn(f64) -> i64,
+) {
+  if !grid.bpm.is_finite() || grid.bpm <= 0.0 || !grid.first_beat_ms.is_finite() {
+    return;
+  }
+  let beat_sec = 60.0 / grid.bpm;
+  let first_beat = grid.first_beat_ms / 1000.0;
+  let beats_per_bar = i64::from(grid.beats_per_bar.unwrap_or(4).max(1));
+  // 每像素多于一条拍线时只画小节线，避免缩略图被网格糊满
+  let pixels_per_beat = beat_sec * image.width as f64 / (end - start);
+  let mut beat = ((start - first_beat) / beat_sec).ceil() as i64;
+  let bottom = image.height as i64 - 1;
+  loop {
+    let time = first_beat + beat as f64 * beat_sec;
+    if time > end {
+      break;
+    }
+    let is_bar = beat.rem_euclid(beats_per_bar) == 0;
+    if is_bar {
+      image.fill_column(time_to_x(time), 0, bottom, BAR_LINE_COLOR);
+    } else if pixels_per_beat >= 2.0 {
+      image.fill_column(time_to_x(time), 0, bottom, BEAT_LINE_COLOR);
+    }
+    beat += 1;
+  }
+}
+
+/// Mixxx 四频段左右均值序列转换为渲染列（上半左声道、下半右声道，同 Mixxx RGB 波形）
+pub(crate) fn mixxx_render_source(
+  sample_rate: u32,
+  step: f64,
+  duration: f64,
+  bands: [[&[u8]; 2]; 4],
+) -> RenderSource {
+  let count = bands
+    .iter()
+    .flat_map(|sides| sides.iter().map(|values| values.len()))
+    .min()
+    .unwrap_or(0);
+  let columns = (0..count)
+    .map(|index| RenderColumn {
+      bands: bands.map(|[left, right]| {
+        [
+          f32::from(left[index]) / 255.0,
+          f32::from(right[index]) / 255.0,
+        ]
+      }),
+      color: None,
+    })
+    .collect();
+  let columns_per_sec = if step.is_finite() && step > 0.0 && sample_rate > 0 {
+    Some(sample_rate as f64 / step)
+  } else if duration > 0.0 {
+    Some(count as f64 / duration)
+  } else {
+    None
+  };
+  RenderSource {
+    columns,
+    columns_per_sec,
+  }
+}
+
+/// 按 format 选项输出原始 RGBA 或 PNG
+pub(crate) fn encode_render_image(
+  image: RenderImage,
+  format: Option<&str>,
+) -> Result<WaveformImage> {
+  let format = format
+    .map(|value| value.trim().to_ascii_lowercase())
+    .filter(|value| !value.is_empty())
+    .unwrap_or_else(|| "rgba".to_string());
+  let data = match format.as_str() {
+    "rgba" => image.pixels,
+    "png" => encode_png(image.width, image.height, &image.pixels),
+    _ => return Err(Error::from_reason(format!("未知的图像格式: {}", format))),
+  };
+  Ok(WaveformImage {
+    width: image.width,
+    height: image.height,
+    format,
+    data: data.into(),
+  })
+}
+
+const fn build_crc32_table() -> [u32; 256] {
+  let mut table = [0u32; 256];
+  let mut index = 0;
+  while index < 256 {
+    let mut value = index as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      value = if value & 1 != 0 {
+        0xEDB8_8320 ^ (value >> 1)
+      } else {
+        value >> 1
+      };
+      bit += 1;
+    }
+    table[index] = value;
+    index += 1;
+  }
+  table
+}
+
+const CRC32_TABLE: [u32; 256] = build_crc32_table();
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+  let mut crc = 0xFFFF_FFFFu32;
+  for part in parts {
+    for byte in *part {
+      crc = CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8);
+    }
+  }
+  crc ^ 0xFFFF_FFFF
+}
+
+fn push_png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+  out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+  out.extend_from_slice(kind);
+  out.extend_from_slice(data);
+  out.extend_from_slice(&crc32(&[kind, data]).to_be_bytes());
+}
+
+/// 最小 PNG 编码：RGBA8，逐行 Sub 滤波后 zlib 压缩（波形图横向大段同色，压缩率高）
+pub(crate) fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+  let row_bytes = width as usize * 4;
+  let mut raw = Vec::with_capacity((row_bytes + 1) * height as usize);
+  for row in rgba.chunks_exact(row_bytes) {
+    raw.push(1);
+    for (index, byte) in row.iter().enumerate() {
+      let left = if index >= 4 { row[index - 4] } else { 0 };
+      raw.push(byte.wrapping_sub(left));
+    }
+  }
+
+  let mut encoder = ZlibEncoder::new(
+    Vec::with_capacity(raw.len() / 8 + 64),
+    Compression::default(),
+  );
+  encoder.write_all(&raw).expect("写入内存不会失败");
+  let zlib = encoder.finish().expect("写入内存不会失败");
+
+  let mut header = Vec::with_capacity(13);
+  header.extend_from_slice(&width.to_be_bytes());
+  header.extend_from_slice(&height.to_be_bytes());
+  header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+  let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+  push_png_chunk(&mut out, b"IHDR", &header);
+  push_png_chunk(&mut out, b"IDAT", &zlib);
+  push_png_chunk(&mut out, b"IEND", &[]);
+  out
+}
+
+fn band_slices(band: &MixxxWaveformBand) -> [&[u8]; 2] {
+  [band.left.as_ref(), band.right.as_ref()]
+}
+
+/// 把 Mixxx 波形绘制为图像，默认 mixxx-rgb 配色
+#[napi]
+pub fn render_mixxx_waveform(
+  waveform: MixxxWaveformData,
+  options: WaveformRenderOptions,
+) -> Result<WaveformImage> {
+  let bands = &waveform.bands;
+  let source = mixxx_render_source(
+    waveform.sample_rate,
+    waveform.step,
+    waveform.duration,
+    [
+      band_slices(&bands.low),
+      band_slices(&bands.mid),
+      band_slices(&bands.high),
+      band_slices(&bands.all),
+    ],
+  );
+  let image = render_waveform(&source, &options, WaveformColorScheme::MixxxRgb)?;
+  encode_render_image(image, options.format.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn options(width: u32, height: u32) -> WaveformRenderOptions {
+    WaveformRenderOptions {
+      width,
+      height,
+      ..Default::default()
+    }
+  }
+
+  impl RenderImage {
+    fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+      let offset = (y as usize * self.width as usize + x as usize) * 4;
+      self.pixels[offset..offset + 4].try_into().unwrap()
+    }
+  }
+
+  fn constant_source(column: RenderColumn, count: usize, rate: f64) -> RenderSource {
+    RenderSource {
+      columns: vec![column; count],
+      columns_per_sec: Some(rate),
+    }
+  }
+
+  #[test]
+  fn bass_heavy_column_renders_red_and_mirrored_height() {
+    let level = [0.5f32, 0.5];
+    let column = RenderColumn {
+      bands: [[1.0, 1.0], [0.0, 0.0], [0.0, 0.0], level],
+      color: None,
+    };
+    let source = mixxx_render_source(
+      100,
+      1.0,
+      1.0,
+      [
+        [&[255; 100], &[255; 100]],
+        [&[0; 100], &[0; 100]],
+        [&[0; 100], &[0; 100]],
+        [&[128; 100], &[128; 100]],
+      ],
+    );
+    assert_eq!(source.columns_per_sec, Some(100.0));
+    assert!((source.columns[0].bands[3][0] - level[0]).abs() < 0.01);
+
+    let image = render_waveform(
+      &constant_source(column, 100, 100.0),
+      &options(10, 40),
+      WaveformColorScheme::MixxxRgb,
+    )
+    .unwrap();
+    assert_eq!(image.pixels.len(), 10 * 40 * 4);
+    assert_eq!(image.pixel(5, 20), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(5, 10), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(5, 29), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(5, 9), [0, 0, 0, 0]);
+    assert_eq!(image.pixel(5, 30), [0, 0, 0, 0]);
+
+    let blue = render_waveform(
+      &constant_source(column, 100, 100.0),
+      &options(10, 40),
+      WaveformColorScheme::Blue,
+    )
+    .unwrap();
+    assert_eq!(blue.pixel(5, 20), [43, 89, 255, 255]);
+  }
+
+  #[test]
+  fn overlays_beat_grid_and_cues_at_time_positions() {
+    let mut render_options = WaveformRenderOptions {
+      background_hex: Some("#000000".to_string()),
+      color_scheme: Some("pioneer-3band".to_string()),
+      beat_grid: Some(WaveformRenderBeatGrid {
+        bpm: 120.0,
+        first_beat_ms: 0.0,
+        beats_per_bar: Some(4),
+      }),
+      cues: Some(vec![WaveformRenderCue {
+        time_ms: 1_250.0,
+        loop_end_ms: None,
+        color_hex: Some("#ff00ff".to_string()),
+      }]),
+      ..options(400, 20)
+    };
+    let source = constant_source(RenderColumn::default(), 400, 100.0);
+    let image = render_waveform(&source, &render_options, WaveformColorScheme::MixxxRgb).unwrap();
+    // 4 秒 400 像素：拍线每 50 像素，小节线每 200 像素
+    assert_eq!(image.pixel(200, 10), [136, 136, 136, 255]);
+    assert_eq!(image.pixel(50, 10), [56, 56, 56, 255]);
+    assert_eq!(image.pixel(51, 10), [0, 0, 0, 255]);
+    assert_eq!(image.pixel(125, 15), [255, 0, 255, 255]);
+    assert_eq!(image.pixel(127, 0), [255, 0, 255, 255]);
+
+    render_options.start_sec = Some(2.0);
+    render_options.end_sec = Some(1.0);
+    assert!(render_waveform(&source, &render_options, WaveformColorScheme::MixxxRgb).is_err());
+  }
+
+  #[test]
+  fn png_encoding_has_valid_chunks_and_checksums() {
+    let pixels = vec![255u8; 3 * 2 * 4];
+    let png = encode_png(3, 2, &pixels);
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 3);
+    assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 2);
+    assert_eq!(crc32(&[b"IEND"]), 0xAE42_6082);
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+  }
+
+  #[test]
+  fn png_idat_round_trips_and_compresses_flat_rows() {
+    use std::io::Read;
+
+    let (width, height) = (1_024u32, 64u32);
+    let pixels: Vec<u8> = (0..width * height)
+      .flat_map(|index| {
+        let x = (index % width) as u8;
+        [x, 0, 255 - x, 255]
+      })
+      .collect();
+    let png = encode_png(width, height, &pixels);
+    let idat_len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+    assert_eq!(&png[37..41], b"IDAT");
+    assert!(idat_len < pixels.len() / 20);
+
+    let mut raw = Vec::new();
+    flate2::read::ZlibDecoder::new(&png[41..41 + idat_len])
+      .read_to_end(&mut raw)
+      .unwrap();
+    let row_bytes = width as usize * 4;
+    assert_eq!(raw.len(), (row_bytes + 1) * height as usize);
+    let row = &raw[..=row_bytes];
+    assert_eq!(row[0], 1);
+    let mut decoded = vec![0u8; row_bytes];
+    for index in 0..row_bytes {
+      let left = if index >= 4 { decoded[index - 4] } else { 0 };
+      decoded[index] = row[index + 1].wrapping_add(left);
+    }
+    assert_eq!(decoded, pixels[..row_bytes]);
+  }
+}
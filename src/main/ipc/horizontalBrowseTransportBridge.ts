@@ -20,6 +20,9 @@ type HorizontalBrowseTransportDecodeDiagnostic = {
   startSec: number
   maxDurationSec?: number
   decoderBackend?: string
+  errorCode?: string
+  errorMessage?: string
+  errorPositionSec?: number
   queueWaitMs?: number
   totalMs: number
   ffmpegTotalMs?: number
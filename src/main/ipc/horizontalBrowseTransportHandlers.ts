@@ -98,7 +98,8 @@ const logSlowTransportOperation = (
 const flushTransportDecodeDiagnostics = () => {
   const diagnostics = horizontalBrowseTransportBridge.drainDecodeDiagnostics()
   for (const diagnostic of diagnostics) {
-    const message = `[HB-TRANSPORT-DECODE-SLOW] ${diagnostic.operation} ${JSON.stringify(diagnostic)}`
+    const tag = diagnostic.errorCode ? 'HB-TRANSPORT-DECODE-FAILED' : 'HB-TRANSPORT-DECODE-SLOW'
+    const message = `[${tag}] ${diagnostic.operation} ${JSON.stringify(diagnostic)}`
     log.warn(message)
   }
 }
@@ -52,6 +52,23 @@ export type HorizontalBrowseTransportStateInput = {
   bottom: HorizontalBrowseTransportDeckInput
 }
 
+export type HorizontalBrowseTransportStemKey = 'drums' | 'bass' | 'vocals' | 'other'
+
+export type HorizontalBrowseTransportStemsInput = {
+  drumsPath?: string
+  bassPath?: string
+  vocalsPath?: string
+  otherPath?: string
+}
+
+export type HorizontalBrowseTransportStemSnapshot = {
+  stem: HorizontalBrowseTransportStemKey
+  filePath?: string
+  status: 'empty' | 'loading' | 'ready' | 'failed'
+  gain: number
+  muted: boolean
+}
+
 export type HorizontalBrowseTransportDeckSnapshot = {
   deck: string
   label: string
@@ -87,13 +104,32 @@ export type HorizontalBrowseTransportDeckSnapshot = {
   autoGainEnabled: boolean
   autoGainStatus: 'off' | 'active' | 'master' | 'pending' | 'unavailable'
   autoGainValue: number
+  stemsActive: boolean
+  stems: HorizontalBrowseTransportStemSnapshot[]
+  motionMode: 'none' | 'brake' | 'spinback' | 'reverse' | 'censor'
+  motionRate: number
+  slipCurrentSec?: number
 }
 
+export type HorizontalBrowseTransportCrossfaderCurve =
+  | 'linear'
+  | 'constantPower'
+  | 'sharpCut'
+  | 'custom'
+
+export type HorizontalBrowseTransportResampleQuality = 'linear' | 'sinc'
+
 type HorizontalBrowseTransportOutputSnapshot = {
   crossfaderValue: number
   masterGain: number
   topDeckGain: number
   bottomDeckGain: number
+  topChannelFader: number
+  bottomChannelFader: number
+  crossfaderCurve: HorizontalBrowseTransportCrossfaderCurve
+  crossfaderSlope: number
+  crossfaderReverse: boolean
+  resampleQuality: HorizontalBrowseTransportResampleQuality
   limiterOverload: boolean
   limiterGainReductionDb: number
   sampleRate: number
@@ -118,6 +154,23 @@ export type HorizontalBrowseTransportVisualizerSnapshot = {
   preLimiterPeakRightDb: number
 }
 
+export type HorizontalBrowseTransportLevelMeterSnapshot = {
+  peakLeftDb: number
+  peakRightDb: number
+  rmsLeftDb: number
+  rmsRightDb: number
+  shortTermLufs: number
+}
+
+export type HorizontalBrowseTransportMeterSnapshot = {
+  sampleRate: number
+  top: HorizontalBrowseTransportLevelMeterSnapshot
+  bottom: HorizontalBrowseTransportLevelMeterSnapshot
+  master: HorizontalBrowseTransportLevelMeterSnapshot
+  spectrumDb: number[]
+  spectrumBandHz: number[]
+}
+
 type HorizontalBrowseRecordingState = 'idle' | 'armed' | 'recording' | 'error'
 
 export type HorizontalBrowseTransportRecordingStatus = {
@@ -170,7 +223,12 @@ const createEmptyHorizontalBrowseTransportDeckSnapshot = (
   cueMonitorEnabled: false,
   autoGainEnabled: true,
   autoGainStatus: 'pending',
-  autoGainValue: 1
+  autoGainValue: 1,
+  stemsActive: false,
+  stems: [],
+  motionMode: 'none',
+  motionRate: 1,
+  slipCurrentSec: undefined
 })
 
 export const createEmptyHorizontalBrowseTransportSnapshot =
@@ -186,6 +244,12 @@ export const createEmptyHorizontalBrowseTransportSnapshot =
       masterGain: 1,
       topDeckGain: 1,
       bottomDeckGain: 1,
+      topChannelFader: 1,
+      bottomChannelFader: 1,
+      crossfaderCurve: 'linear',
+      crossfaderSlope: 0.5,
+      crossfaderReverse: false,
+      resampleQuality: 'linear',
       limiterOverload: false,
       limiterGainReductionDb: 0,
       sampleRate: 44100,